@@ -10,6 +10,8 @@ use scale_typegen_description::scale_typegen::typegen::{
     settings::substitutes::path_segments,
     validation::{registry_contains_type_path, similar_type_paths_in_registry},
 };
+use std::path::PathBuf;
+use std::str::FromStr;
 use subxt_codegen::CodegenBuilder;
 use subxt_metadata::Metadata;
 
@@ -69,9 +71,49 @@ pub struct Opts {
     /// Defaults to `false` (default substitutions are provided).
     #[clap(long)]
     no_default_substitutions: bool,
+    /// Generate a `compatibility_report` function, which reports the items in the generated
+    /// code that have changed in the metadata of a live node. This embeds the metadata into
+    /// the generated code.
+    ///
+    /// Defaults to `false` (no report is generated).
+    #[clap(long)]
+    compatibility_report: bool,
     /// Allow insecure URLs e.g. URLs starting with ws:// or http:// without SSL encryption
     #[clap(long, short)]
     allow_insecure: bool,
+    /// The language to generate code for. Only `--no-docs` and `--runtime-types-only`
+    /// apply when generating TypeScript.
+    ///
+    /// Defaults to `rust`.
+    #[clap(long, value_enum, default_value_t = Lang::Rust)]
+    lang: Lang,
+    /// Only generate code for this pallet or runtime API (eg `Balances`), or for this item within
+    /// one (eg `Balances::transfer_keep_alive` or `System::Account`). Can be given multiple times.
+    #[clap(long = "allow-item")]
+    allowed_items: Vec<String>,
+    /// Don't generate code for this pallet, runtime API or item, given in the same form as
+    /// `--allow-item`. Can be given multiple times, and takes precedence over `--allow-item`.
+    #[clap(long = "deny-item")]
+    denied_items: Vec<String>,
+    /// Write the generated code to a tree of files in this directory rather than printing it,
+    /// with a `mod.rs` at the root and a file for each pallet and runtime type module.
+    ///
+    /// Example: `subxt codegen --output-dir src/api && rustfmt --edition=2021 src/api/mod.rs`
+    #[clap(long)]
+    output_dir: Option<PathBuf>,
+    /// Generate code for several runtimes at once, given as a module name and a metadata file
+    /// or URL. Types that are the same in each runtime are generated once, and shared by the
+    /// per-runtime modules. Can be given multiple times, and replaces `--file` and `--url`.
+    ///
+    /// Example: `--runtime v1=metadata_v1.scale --runtime v2=metadata_v2.scale`
+    #[clap(long = "runtime", value_parser = runtime_parser)]
+    runtimes: Vec<(String, FileOrUrl)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Lang {
+    Rust,
+    Ts,
 }
 
 #[derive(Debug, Clone)]
@@ -141,13 +183,33 @@ fn substitute_type_parser(src: &str) -> Result<(String, String), String> {
     Ok((from.to_string(), to.to_string()))
 }
 
-pub async fn run(opts: Opts, output: &mut impl std::io::Write) -> color_eyre::Result<()> {
-    validate_url_security(opts.file_or_url.url.as_ref(), opts.allow_insecure)?;
+fn runtime_parser(src: &str) -> Result<(String, FileOrUrl), String> {
+    let (name, source) = src
+        .split_once('=')
+        .ok_or_else(|| String::from("Invalid pattern for `runtime`. It should be `name=source`, where the source is a metadata file or URL, like `v1=metadata.scale`"))?;
+    let source = FileOrUrl::from_str(source)
+        .map_err(|e| format!("Invalid runtime source `{source}`: {e}"))?;
+
+    Ok((name.to_string(), source))
+}
 
-    let bytes = opts.file_or_url.fetch().await?;
+pub async fn run(opts: Opts, output: &mut impl std::io::Write) -> color_eyre::Result<()> {
+    let mut metadatas = Vec::new();
+    if opts.runtimes.is_empty() {
+        validate_url_security(opts.file_or_url.url.as_ref(), opts.allow_insecure)?;
+        metadatas.push((None, opts.file_or_url.fetch().await?));
+    } else {
+        if opts.file_or_url.file.is_some() || opts.file_or_url.url.is_some() {
+            color_eyre::eyre::bail!("`--runtime` cannot be combined with `--file` or `--url`");
+        }
+        for (name, file_or_url) in opts.runtimes {
+            validate_url_security(file_or_url.url.as_ref(), opts.allow_insecure)?;
+            metadatas.push((Some(name), file_or_url.fetch().await?));
+        }
+    }
 
     codegen(
-        &bytes,
+        metadatas,
         opts.derives,
         opts.attributes,
         opts.derives_for_type,
@@ -158,6 +220,11 @@ pub async fn run(opts: Opts, output: &mut impl std::io::Write) -> color_eyre::Re
         opts.runtime_types_only,
         opts.no_default_derives,
         opts.no_default_substitutions,
+        opts.compatibility_report,
+        opts.allowed_items,
+        opts.denied_items,
+        opts.lang,
+        opts.output_dir,
         output,
     )?;
     Ok(())
@@ -174,7 +241,7 @@ impl syn::parse::Parse for OuterAttribute {
 
 #[allow(clippy::too_many_arguments)]
 fn codegen(
-    metadata_bytes: &[u8],
+    metadata_bytes: Vec<(Option<String>, Vec<u8>)>,
     raw_derives: Vec<String>,
     raw_attributes: Vec<String>,
     derives_for_type: Vec<DeriveForType>,
@@ -185,10 +252,44 @@ fn codegen(
     runtime_types_only: bool,
     no_default_derives: bool,
     no_default_substitutions: bool,
+    compatibility_report: bool,
+    allowed_items: Vec<String>,
+    denied_items: Vec<String>,
+    lang: Lang,
+    output_dir: Option<PathBuf>,
     output: &mut impl std::io::Write,
 ) -> color_eyre::Result<()> {
     let mut codegen = CodegenBuilder::new();
 
+    if lang == Lang::Ts && output_dir.is_some() {
+        color_eyre::eyre::bail!("Writing to an output directory is only supported for Rust code");
+    }
+
+    // Runtimes are only named when generating code for several of them at once.
+    let is_multi = metadata_bytes.iter().any(|(name, _)| name.is_some());
+    if is_multi && (lang == Lang::Ts || output_dir.is_some()) {
+        color_eyre::eyre::bail!(
+            "`--runtime` cannot be combined with `--lang ts` or `--output-dir`"
+        );
+    }
+
+    if lang == Lang::Ts {
+        let rust_only = !raw_derives.is_empty()
+            || !raw_attributes.is_empty()
+            || !derives_for_type.is_empty()
+            || !attributes_for_type.is_empty()
+            || !substitute_types.is_empty()
+            || crate_path.is_some()
+            || no_default_derives
+            || no_default_substitutions
+            || compatibility_report;
+        if rust_only {
+            color_eyre::eyre::bail!(
+                "Derives, attributes, type substitutions, the crate path and the compatibility report only apply to Rust code"
+            );
+        }
+    }
+
     // Use the provided crate path:
     if let Some(crate_path) = crate_path {
         let crate_path =
@@ -206,12 +307,27 @@ fn codegen(
     if no_default_substitutions {
         codegen.disable_default_substitutes()
     }
+    if compatibility_report {
+        codegen.compatibility_report()
+    }
     if no_docs {
         codegen.no_docs()
     }
-
-    let metadata = subxt_metadata::Metadata::decode(&mut &*metadata_bytes)
-        .map_err(|e| eyre!("Cannot decode the provided metadata: {e}"))?;
+    codegen.set_allowed_items(allowed_items);
+    codegen.set_denied_items(denied_items);
+
+    let mut names = Vec::new();
+    let mut metadatas = Vec::new();
+    for (name, bytes) in metadata_bytes {
+        let metadata = subxt_metadata::Metadata::decode(&mut &*bytes)
+            .map_err(|e| eyre!("Cannot decode the provided metadata: {e}"))?;
+        if let Some(name) = name {
+            let name: syn::Ident = syn::parse_str(&name)
+                .map_err(|e| eyre!("Cannot parse runtime name {name}: {e}"))?;
+            names.push(name);
+        }
+        metadatas.push(metadata);
+    }
 
     // Configure derives:
     let global_derives = raw_derives
@@ -228,7 +344,7 @@ fn codegen(
         let derive = syn::parse_str(&d.trait_path)
             .map_err(|e| eyre!("Cannot parse derive for type {ty_str}: {e}"))?;
 
-        validate_path_with_metadata(&ty.path, &metadata)?;
+        validate_path_with_metadatas(&ty.path, &metadatas)?;
         // Note: recursive derives and attributes not supported in the CLI => recursive: false
         codegen.add_derives_for_type(ty, std::iter::once(derive), d.recursive);
     }
@@ -249,7 +365,7 @@ fn codegen(
         let attribute: OuterAttribute = syn::parse_str(&a.attribute)
             .map_err(|e| eyre!("Cannot parse attribute for type {ty_str}: {e}"))?;
 
-        validate_path_with_metadata(&ty.path, &metadata)?;
+        validate_path_with_metadatas(&ty.path, &metadatas)?;
         // Note: recursive derives and attributes not supported in the CLI => recursive: false
         codegen.add_attributes_for_type(ty, std::iter::once(attribute.0), a.recursive);
     }
@@ -261,10 +377,44 @@ fn codegen(
         let to: syn::Path = syn::parse_str(&to_str)
             .map_err(|e| eyre!("Cannot parse type substitution for path {from_str}: {e}"))?;
 
-        validate_path_with_metadata(&from, &metadata)?;
+        validate_path_with_metadatas(&from, &metadatas)?;
         codegen.set_type_substitute(from, to);
     }
 
+    if is_multi {
+        let code = codegen
+            .generate_multi(names.into_iter().zip(metadatas).collect())
+            .map_err(|e| eyre!("Cannot generate code: {e}"))?;
+        writeln!(output, "{code}")?;
+        return Ok(());
+    }
+
+    let metadata = metadatas
+        .pop()
+        .expect("one metadata is given when not generating code for several runtimes; qed");
+
+    if lang == Lang::Ts {
+        let code = codegen
+            .generate_typescript(metadata)
+            .map_err(|e| eyre!("Cannot generate code: {e}"))?;
+        write!(output, "{code}")?;
+        return Ok(());
+    }
+
+    if let Some(output_dir) = output_dir {
+        let files = codegen
+            .generate_files(metadata)
+            .map_err(|e| eyre!("Cannot generate code: {e}"))?;
+        for file in files {
+            let path = output_dir.join(file.path());
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, format!("{}\n", file.code()))?;
+        }
+        return Ok(());
+    }
+
     let code = codegen
         .generate(metadata)
         .map_err(|e| eyre!("Cannot generate code: {e}"))?;
@@ -273,6 +423,21 @@ fn codegen(
     Ok(())
 }
 
+/// Validates that the type path is part of at least one of the metadatas.
+fn validate_path_with_metadatas(
+    path: &syn::Path,
+    metadatas: &[Metadata],
+) -> color_eyre::Result<()> {
+    let mut results = metadatas
+        .iter()
+        .map(|metadata| validate_path_with_metadata(path, metadata));
+    let first = results.next().unwrap_or(Ok(()));
+    if first.is_err() && results.any(|res| res.is_ok()) {
+        return Ok(());
+    }
+    first
+}
+
 /// Validates that the type path is part of the metadata.
 fn validate_path_with_metadata(path: &syn::Path, metadata: &Metadata) -> color_eyre::Result<()> {
     fn pretty_path(path: &syn::Path) -> String {
@@ -367,4 +532,66 @@ mod tests {
             assert!(output.unwrap_err().to_string().contains(valid_type));
         }
     }
+
+    #[tokio::test]
+    async fn typescript_output() {
+        let output = run("--lang ts").await.unwrap();
+        assert!(output.contains("export const PALLETS = ["));
+        assert!(output.contains("export namespace runtime_types {"));
+
+        let output = run("--lang ts --derive serde::Serialize").await;
+        assert!(output.is_err());
+    }
+
+    #[tokio::test]
+    async fn filter_items() {
+        let output = run("--allow-item System::remark --allow-item Balances")
+            .await
+            .unwrap();
+        assert!(output.contains("pub fn remark"));
+        assert!(!output.contains("pub fn remark_with_event"));
+        assert!(output.contains("pub fn transfer_keep_alive"));
+
+        let output = run("--allow-item System::not_a_call").await;
+        assert!(output
+            .unwrap_err()
+            .to_string()
+            .contains("System::not_a_call"));
+    }
+
+    #[tokio::test]
+    async fn output_dir() {
+        let dir = std::env::temp_dir().join(format!("subxt-codegen-test-{}", std::process::id()));
+        let output = run(&format!("--output-dir {}", dir.display()))
+            .await
+            .unwrap();
+        assert!(output.is_empty());
+
+        let root = std::fs::read_to_string(dir.join("mod.rs")).unwrap();
+        assert!(root.contains("pub mod runtime_types ;"));
+        assert!(dir.join("system/calls.rs").exists());
+        assert!(dir.join("runtime_types/mod.rs").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn multiple_runtimes() {
+        let args = [
+            "codegen",
+            "--runtime=v1=../artifacts/polkadot_metadata_small.scale",
+            "--runtime=v2=../artifacts/polkadot_metadata_tiny.scale",
+        ];
+        let opts: super::Opts = clap::Parser::try_parse_from(args).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        super::run(opts, &mut output).await.unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("pub mod v1 {"));
+        assert!(output.contains("pub mod v2 {"));
+        assert_eq!(output.matches("pub mod runtime_types {").count(), 1);
+
+        // The runtimes replace `--file`.
+        let output = run("--runtime=v1=../artifacts/polkadot_metadata_small.scale").await;
+        assert!(output.is_err());
+    }
 }
@@ -4,8 +4,10 @@
 
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
+use scale_info::TypeDef;
 use scale_typegen::TypeGenerator;
-use subxt_metadata::PalletMetadata;
+use std::collections::{BTreeMap, BTreeSet};
+use subxt_metadata::{Metadata, PalletMetadata};
 
 use super::CodegenError;
 use scale_typegen::typegen::ir::ToTokensWithSettings;
@@ -34,3 +36,72 @@ pub fn generate_error_type_alias(
         pub type Error = #error_type;
     })
 }
+
+/// The pallets using some error type.
+struct ErrorTypeUsage<'a> {
+    path: TokenStream2,
+    type_id: u32,
+    pallets: BTreeSet<&'a str>,
+}
+
+/// Implement the `StaticError` trait for the error type of each pallet in the given metadatas.
+/// Instances of the same pallet share an error type, making it ambiguous which pallet the
+/// type belongs to, so we don't implement the trait for such types.
+pub fn generate_static_errors<'a>(
+    metadatas: impl IntoIterator<Item = &'a Metadata>,
+    type_gen: &TypeGenerator,
+    crate_path: &syn::Path,
+) -> Result<TokenStream2, CodegenError> {
+    let types_mod_prefix = format!("{} ::", type_gen.types_mod_ident());
+
+    // Error types are keyed by their generated path, since that's what we implement the
+    // trait for, and several pallets (or runtimes) can end up with the same one.
+    let mut usages: BTreeMap<String, ErrorTypeUsage> = BTreeMap::new();
+    for metadata in metadatas {
+        for pallet in metadata.pallets() {
+            let Some(error_ty) = pallet.error_ty_id() else {
+                continue;
+            };
+            let path = type_gen
+                .resolve_type_path(error_ty)?
+                .to_token_stream(type_gen.settings());
+            let key = path.to_string();
+            // Substituted types may live in other crates, so we can't implement traits for them.
+            if !key.starts_with(&types_mod_prefix) {
+                continue;
+            }
+            usages
+                .entry(key)
+                .or_insert_with(|| ErrorTypeUsage {
+                    path,
+                    type_id: error_ty,
+                    pallets: BTreeSet::new(),
+                })
+                .pallets
+                .insert(pallet.name());
+        }
+    }
+
+    let impls = usages
+        .into_values()
+        .filter(|usage| usage.pallets.len() == 1)
+        .map(|usage| {
+            let TypeDef::Variant(variant) = &type_gen.resolve_type(usage.type_id)?.type_def else {
+                return Err(CodegenError::InvalidType(usage.path.to_string()));
+            };
+            let variant_names = variant.variants.iter().map(|v| v.name.as_str());
+            let error_type = usage.path;
+            let pallet_name = usage.pallets.first().expect("filtered to one pallet; qed");
+            Ok(quote! {
+                impl #crate_path::error::StaticError for #error_type {
+                    const PALLET: &'static str = #pallet_name;
+                    const VARIANTS: &'static [&'static str] = &[ #( #variant_names, )* ];
+                }
+            })
+        })
+        .collect::<Result<Vec<_>, CodegenError>>()?;
+
+    Ok(quote! {
+        #( #impls )*
+    })
+}
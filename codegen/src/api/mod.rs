@@ -9,9 +9,14 @@ mod constants;
 mod custom_values;
 mod errors;
 mod events;
+mod multi;
 mod runtime_apis;
 mod storage;
+mod view_functions;
 
+pub use multi::MultiRuntimeGenerator;
+
+use codec::Encode;
 use scale_typegen::typegen::ir::type_ir::{CompositeFieldIR, CompositeIR, CompositeIRKind};
 use scale_typegen::typegen::ir::ToTokensWithSettings;
 use scale_typegen::typegen::type_params::TypeParameters;
@@ -31,6 +36,8 @@ use quote::{format_ident, quote};
 /// Create the API for interacting with a Substrate runtime.
 pub struct RuntimeGenerator {
     metadata: Metadata,
+    metadata_hash: [u8; 32],
+    compatibility_report: bool,
 }
 
 impl RuntimeGenerator {
@@ -47,7 +54,28 @@ impl RuntimeGenerator {
     pub fn new(mut metadata: Metadata) -> Self {
         scale_typegen::utils::ensure_unique_type_paths(metadata.types_mut())
             .expect("Duplicate type paths in metadata; this is bug please file an issue.");
-        RuntimeGenerator { metadata }
+        let metadata_hash = metadata.hasher().hash();
+        RuntimeGenerator {
+            metadata,
+            metadata_hash,
+            compatibility_report: false,
+        }
+    }
+
+    /// Use the given hash in the generated `is_codegen_valid_for` check rather than the hash of
+    /// the metadata that we were given. This is used when individual items have been removed
+    /// from the metadata, which would otherwise change the hash of the pallets they belong to.
+    pub fn with_metadata_hash(mut self, metadata_hash: [u8; 32]) -> Self {
+        self.metadata_hash = metadata_hash;
+        self
+    }
+
+    /// Generate a `compatibility_report` function, which compares the metadata of a live node
+    /// with the metadata that the code was generated from. This embeds the SCALE encoded
+    /// metadata into the generated code, and relies on the standard library.
+    pub fn with_compatibility_report(mut self) -> Self {
+        self.compatibility_report = true;
+        self
     }
 
     /// Generate the API for interacting with a Substrate runtime.
@@ -128,6 +156,44 @@ impl RuntimeGenerator {
         let types_mod = type_gen
             .generate_types_mod()?
             .to_token_stream(type_gen.settings());
+        let runtime_items = self.generate_runtime_items(&type_gen, &crate_path)?;
+        let static_errors =
+            errors::generate_static_errors([&self.metadata], &type_gen, &crate_path)?;
+        let mod_ident = &item_mod_ir.ident;
+        let rust_items = item_mod_ir.rust_items();
+
+        Ok(quote! {
+            #( #item_mod_attrs )*
+            #[allow(dead_code, unused_imports, non_camel_case_types, unreachable_patterns)]
+            #[allow(clippy::all)]
+            #[allow(rustdoc::broken_intra_doc_links)]
+            pub mod #mod_ident {
+                // Preserve any Rust items that were previously defined in the adorned module.
+                #( #rust_items ) *
+
+                // Make it easy to access the root items via `root_mod` at different levels
+                // without reaching out of this module.
+                #[allow(unused_imports)]
+                mod root_mod {
+                    pub use super::*;
+                }
+
+                #runtime_items
+                #static_errors
+                #types_mod
+            }
+        })
+    }
+
+    /// Generate everything in the runtime API module apart from the types module: the
+    /// calls, events, storage entries and so on for each pallet, the runtime APIs and the
+    /// functions for validating the generated code. The types referred to are expected to
+    /// be in scope via the types module identifier of the given type generator.
+    fn generate_runtime_items(
+        &self,
+        type_gen: &TypeGenerator,
+        crate_path: &syn::Path,
+    ) -> Result<TokenStream2, CodegenError> {
         let types_mod_ident = type_gen.types_mod_ident();
         let pallets_with_mod_names = self
             .metadata
@@ -157,20 +223,27 @@ impl RuntimeGenerator {
             .collect();
         let runtime_api_names_len = runtime_api_names.len();
 
-        let metadata_hash = self.metadata.hasher().hash();
+        let metadata_hash = self.metadata_hash;
+
+        let compatibility_report = self
+            .compatibility_report
+            .then(|| generate_compatibility_report(&self.metadata, crate_path));
 
         let modules = pallets_with_mod_names
             .iter()
             .map(|(pallet, mod_name)| {
-                let calls = calls::generate_calls(&type_gen, pallet, &crate_path)?;
+                let calls = calls::generate_calls(type_gen, pallet, crate_path)?;
+
+                let event = events::generate_events(type_gen, pallet, crate_path)?;
 
-                let event = events::generate_events(&type_gen, pallet, &crate_path)?;
+                let storage_mod = storage::generate_storage(type_gen, pallet, crate_path)?;
 
-                let storage_mod = storage::generate_storage(&type_gen, pallet, &crate_path)?;
+                let constants_mod = constants::generate_constants(type_gen, pallet, crate_path)?;
 
-                let constants_mod = constants::generate_constants(&type_gen, pallet, &crate_path)?;
+                let errors = errors::generate_error_type_alias(type_gen, pallet)?;
 
-                let errors = errors::generate_error_type_alias(&type_gen, pallet)?;
+                let view_functions_mod =
+                    view_functions::generate_view_functions(type_gen, pallet, crate_path)?;
 
                 Ok(quote! {
                     pub mod #mod_name {
@@ -181,12 +254,12 @@ impl RuntimeGenerator {
                         #event
                         #storage_mod
                         #constants_mod
+                        #view_functions_mod
                     }
                 })
             })
             .collect::<Result<Vec<_>, CodegenError>>()?;
 
-        let mod_ident = &item_mod_ir.ident;
         let pallets_with_constants: Vec<_> = pallets_with_mod_names
             .iter()
             .filter_map(|(pallet, pallet_mod_name)| {
@@ -208,13 +281,18 @@ impl RuntimeGenerator {
             .filter_map(|(pallet, pallet_mod_name)| pallet.call_ty_id().map(|_| pallet_mod_name))
             .collect();
 
-        let rust_items = item_mod_ir.rust_items();
+        let pallets_with_view_functions: Vec<_> = pallets_with_mod_names
+            .iter()
+            .filter_map(|(pallet, pallet_mod_name)| {
+                (pallet.view_functions().len() > 0).then_some(pallet_mod_name)
+            })
+            .collect();
 
         let apis_mod = runtime_apis::generate_runtime_apis(
             &self.metadata,
-            &type_gen,
+            type_gen,
             types_mod_ident,
-            &crate_path,
+            crate_path,
         )?;
 
         // Fetch the paths of the outer enums.
@@ -230,110 +308,132 @@ impl RuntimeGenerator {
             .resolve_type_path(self.metadata.outer_enums().error_enum_ty())?
             .to_token_stream(type_gen.settings());
 
-        let custom_values = generate_custom_values(&self.metadata, &type_gen, &crate_path);
+        let custom_values = generate_custom_values(&self.metadata, type_gen, crate_path);
 
         Ok(quote! {
-            #( #item_mod_attrs )*
-            #[allow(dead_code, unused_imports, non_camel_case_types, unreachable_patterns)]
-            #[allow(clippy::all)]
-            #[allow(rustdoc::broken_intra_doc_links)]
-            pub mod #mod_ident {
-                // Preserve any Rust items that were previously defined in the adorned module.
-                #( #rust_items ) *
-
-                // Make it easy to access the root items via `root_mod` at different levels
-                // without reaching out of this module.
-                #[allow(unused_imports)]
-                mod root_mod {
-                    pub use super::*;
-                }
+            // Identify the pallets composing the static metadata by name.
+            pub static PALLETS: [&str; #pallet_names_len] = [ #(#pallet_names,)* ];
 
-                // Identify the pallets composing the static metadata by name.
-                pub static PALLETS: [&str; #pallet_names_len] = [ #(#pallet_names,)* ];
+            // Runtime APIs in the metadata by name.
+            pub static RUNTIME_APIS: [&str; #runtime_api_names_len] = [ #(#runtime_api_names,)* ];
 
-                // Runtime APIs in the metadata by name.
-                pub static RUNTIME_APIS: [&str; #runtime_api_names_len] = [ #(#runtime_api_names,)* ];
+            /// The error type that is returned when there is a runtime issue.
+            pub type DispatchError = #types_mod_ident::sp_runtime::DispatchError;
 
-                /// The error type that is returned when there is a runtime issue.
-                pub type DispatchError = #types_mod_ident::sp_runtime::DispatchError;
+            /// The outer event enum.
+            pub type Event = #event_path;
 
-                /// The outer event enum.
-                pub type Event = #event_path;
+            /// The outer extrinsic enum.
+            pub type Call = #call_path;
 
-                /// The outer extrinsic enum.
-                pub type Call = #call_path;
+            /// The outer error enum represents the DispatchError's Module variant.
+            pub type Error = #error_path;
 
-                /// The outer error enum represents the DispatchError's Module variant.
-                pub type Error = #error_path;
+            pub fn constants() -> ConstantsApi {
+                ConstantsApi
+            }
 
-                pub fn constants() -> ConstantsApi {
-                    ConstantsApi
-                }
+            pub fn storage() -> StorageApi {
+                StorageApi
+            }
 
-                pub fn storage() -> StorageApi {
-                    StorageApi
-                }
+            pub fn tx() -> TransactionApi {
+                TransactionApi
+            }
 
-                pub fn tx() -> TransactionApi {
-                    TransactionApi
-                }
+            pub fn apis() -> runtime_apis::RuntimeApi {
+                runtime_apis::RuntimeApi
+            }
 
-                pub fn apis() -> runtime_apis::RuntimeApi {
-                    runtime_apis::RuntimeApi
-                }
+            #apis_mod
 
-                #apis_mod
+            pub fn view_functions() -> ViewFunctionsApi {
+                ViewFunctionsApi
+            }
 
-                pub fn custom() -> CustomValuesApi {
-                    CustomValuesApi
-                }
+            pub fn custom() -> CustomValuesApi {
+                CustomValuesApi
+            }
 
-                #custom_values
+            #custom_values
 
-                pub struct ConstantsApi;
-                impl ConstantsApi {
-                    #(
-                        pub fn #pallets_with_constants(&self) -> #pallets_with_constants::constants::ConstantsApi {
-                            #pallets_with_constants::constants::ConstantsApi
-                        }
-                    )*
-                }
+            pub struct ConstantsApi;
+            impl ConstantsApi {
+                #(
+                    pub fn #pallets_with_constants(&self) -> #pallets_with_constants::constants::ConstantsApi {
+                        #pallets_with_constants::constants::ConstantsApi
+                    }
+                )*
+            }
 
-                pub struct StorageApi;
-                impl StorageApi {
-                    #(
-                        pub fn #pallets_with_storage(&self) -> #pallets_with_storage::storage::StorageApi {
-                            #pallets_with_storage::storage::StorageApi
-                        }
-                    )*
-                }
+            pub struct StorageApi;
+            impl StorageApi {
+                #(
+                    pub fn #pallets_with_storage(&self) -> #pallets_with_storage::storage::StorageApi {
+                        #pallets_with_storage::storage::StorageApi
+                    }
+                )*
+            }
 
-                pub struct TransactionApi;
-                impl TransactionApi {
-                    #(
-                        pub fn #pallets_with_calls(&self) -> #pallets_with_calls::calls::TransactionApi {
-                            #pallets_with_calls::calls::TransactionApi
-                        }
-                    )*
-                }
+            pub struct TransactionApi;
+            impl TransactionApi {
+                #(
+                    pub fn #pallets_with_calls(&self) -> #pallets_with_calls::calls::TransactionApi {
+                        #pallets_with_calls::calls::TransactionApi
+                    }
+                )*
+            }
 
-                /// check whether the metadata provided is aligned with this statically generated code.
-                pub fn is_codegen_valid_for(metadata: &#crate_path::Metadata) -> bool {
-                    let runtime_metadata_hash = metadata
-                        .hasher()
-                        .only_these_pallets(&PALLETS)
-                        .only_these_runtime_apis(&RUNTIME_APIS)
-                        .hash();
-                    runtime_metadata_hash == [ #(#metadata_hash,)* ]
-                }
+            pub struct ViewFunctionsApi;
+            impl ViewFunctionsApi {
+                #(
+                    pub fn #pallets_with_view_functions(&self) -> #pallets_with_view_functions::view_functions::ViewFunctionsApi {
+                        #pallets_with_view_functions::view_functions::ViewFunctionsApi
+                    }
+                )*
+            }
 
-                #( #modules )*
-                #types_mod
+            /// check whether the metadata provided is aligned with this statically generated code.
+            pub fn is_codegen_valid_for(metadata: &#crate_path::Metadata) -> bool {
+                let runtime_metadata_hash = metadata
+                    .hasher()
+                    .only_these_pallets(&PALLETS)
+                    .only_these_runtime_apis(&RUNTIME_APIS)
+                    .hash();
+                runtime_metadata_hash == [ #(#metadata_hash,)* ]
             }
+
+            #compatibility_report
+
+            #( #modules )*
         })
     }
 }
 
+/// Generate a function to compare the metadata of a live node with the given metadata, which
+/// is embedded into the generated code so that the two can be compared item by item.
+fn generate_compatibility_report(metadata: &Metadata, crate_path: &syn::Path) -> TokenStream2 {
+    let metadata_bytes = proc_macro2::Literal::byte_string(&metadata.encode());
+    quote! {
+        /// Compare the metadata provided with the metadata that this code was generated from,
+        /// reporting every item that this code relies on which has been changed or removed.
+        ///
+        /// This function only exists because it was asked for, via
+        /// `CodegenBuilder::compatibility_report()`, the `compatibility_report` attribute of
+        /// the `#[subxt]` macro or the `--compatibility-report` flag of `subxt codegen`.
+        /// It requires the standard library.
+        pub fn compatibility_report(metadata: &#crate_path::Metadata) -> #crate_path::metadata::CompatibilityReport {
+            // The SCALE encoded metadata that this code was generated from, decoded on first use.
+            static CODEGEN_METADATA: ::std::sync::OnceLock<#crate_path::Metadata> = ::std::sync::OnceLock::new();
+            let codegen_metadata = CODEGEN_METADATA.get_or_init(|| {
+                #crate_path::ext::codec::Decode::decode(&mut &#metadata_bytes[..])
+                    .expect("the metadata that code was generated from is valid; qed")
+            });
+            codegen_metadata.compatibility_report(metadata)
+        }
+    }
+}
+
 /// Return a vector of tuples of variant names and corresponding struct definitions.
 pub fn generate_structs_from_variants<F>(
     type_gen: &TypeGenerator,
@@ -442,3 +542,31 @@ pub fn generate_type_alias_mod(
         #( #aliases )*
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::CodegenBuilder;
+    use codec::Decode;
+    use subxt_metadata::Metadata;
+
+    fn tiny_metadata() -> Metadata {
+        let bytes = std::fs::read("../artifacts/polkadot_metadata_tiny.scale")
+            .expect("Cannot read metadata blob");
+        Metadata::decode(&mut &*bytes).expect("Cannot decode metadata")
+    }
+
+    #[test]
+    fn compatibility_report_is_opt_in() {
+        let code = CodegenBuilder::new()
+            .generate(tiny_metadata())
+            .unwrap()
+            .to_string();
+        assert!(!code.contains("compatibility_report"));
+
+        let mut codegen = CodegenBuilder::new();
+        codegen.compatibility_report();
+        let code = codegen.generate(tiny_metadata()).unwrap().to_string();
+        assert_eq!(code.matches("pub fn compatibility_report").count(), 1);
+        assert_eq!(code.matches("static CODEGEN_METADATA").count(), 1);
+    }
+}
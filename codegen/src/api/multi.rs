@@ -0,0 +1,251 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Generate code for several runtimes at once, sharing the types that they have in common.
+
+use super::RuntimeGenerator;
+use crate::error::CodegenError;
+use crate::{ir, subxt_type_gen_settings};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use scale_typegen::typegen::ir::ToTokensWithSettings;
+use scale_typegen::TypeGenerator;
+use std::collections::HashSet;
+use subxt_metadata::Metadata;
+
+/// Create the API for interacting with several runtimes, typically different versions of the
+/// same chain. Types which are identical across the runtimes are generated once, in a shared
+/// types module, and each runtime gets its own module containing the usual calls, events,
+/// storage entries and so on, which refer to those shared types.
+pub struct MultiRuntimeGenerator {
+    runtimes: Vec<(syn::Ident, RuntimeGenerator)>,
+}
+
+impl MultiRuntimeGenerator {
+    /// Create a new generator from the name of the module to generate for each runtime, and a
+    /// generator for that runtime. Each runtime keeps the metadata hash of its generator, so
+    /// the generated `is_codegen_valid_for` functions are unaffected by the sharing of types.
+    pub fn new(runtimes: Vec<(syn::Ident, RuntimeGenerator)>) -> Result<Self, CodegenError> {
+        if runtimes.is_empty() {
+            return Err(CodegenError::InvalidRuntimes(
+                "at least one runtime must be given".to_owned(),
+            ));
+        }
+        // Each runtime would embed its metadata, including every one of the shared types.
+        if runtimes
+            .iter()
+            .any(|(_, generator)| generator.compatibility_report)
+        {
+            return Err(CodegenError::InvalidRuntimes(
+                "a compatibility report can't be generated for several runtimes at once".to_owned(),
+            ));
+        }
+        let mut seen = HashSet::new();
+        if let Some((name, _)) = runtimes.iter().find(|(name, _)| !seen.insert(name)) {
+            return Err(CodegenError::InvalidRuntimes(format!(
+                "the runtime name `{name}` is given more than once"
+            )));
+        }
+
+        let (names, generators): (Vec<_>, Vec<_>) = runtimes.into_iter().unzip();
+        let mut metadatas: Vec<Metadata> = generators.iter().map(|g| g.metadata.clone()).collect();
+        Metadata::share_types(&mut metadatas);
+
+        // Types from different runtimes may have the same path but be different, so we need
+        // to make the shared type paths unique again.
+        let mut types = metadatas[0].types().clone();
+        scale_typegen::utils::ensure_unique_type_paths(&mut types)
+            .expect("Duplicate type paths in metadata; this is bug please file an issue.");
+
+        let runtimes = names
+            .into_iter()
+            .zip(generators.into_iter().zip(metadatas))
+            .map(|(name, (mut generator, mut metadata))| {
+                *metadata.types_mut() = types.clone();
+                generator.metadata = metadata;
+                (name, generator)
+            })
+            .collect();
+        Ok(MultiRuntimeGenerator { runtimes })
+    }
+
+    /// Generate only the shared types module.
+    ///
+    /// # Arguments
+    ///
+    /// * `item_mod` - The module declaration for which the API is implemented.
+    /// * `derives` - Provide custom derives for the generated types.
+    /// * `type_substitutes` - Provide custom type substitutes.
+    /// * `crate_path` - Path to the `subxt` crate.
+    /// * `should_gen_docs` - True if the generated API contains the documentation from the metadata.
+    pub fn generate_runtime_types(
+        &self,
+        item_mod: syn::ItemMod,
+        derives: scale_typegen::DerivesRegistry,
+        type_substitutes: scale_typegen::TypeSubstitutes,
+        crate_path: syn::Path,
+        should_gen_docs: bool,
+    ) -> Result<TokenStream2, CodegenError> {
+        // All of the runtimes share the same types, so generating them from any one will do.
+        self.runtimes[0].1.generate_runtime_types(
+            item_mod,
+            derives,
+            type_substitutes,
+            crate_path,
+            should_gen_docs,
+        )
+    }
+
+    /// Generate the API for interacting with each of the runtimes.
+    ///
+    /// # Arguments
+    ///
+    /// * `item_mod` - The module declaration for which the API is implemented.
+    /// * `derives` - Provide custom derives for the generated types.
+    /// * `type_substitutes` - Provide custom type substitutes.
+    /// * `crate_path` - Path to the `subxt` crate.
+    /// * `should_gen_docs` - True if the generated API contains the documentation from the metadata.
+    pub fn generate_runtimes(
+        &self,
+        item_mod: syn::ItemMod,
+        derives: scale_typegen::DerivesRegistry,
+        type_substitutes: scale_typegen::TypeSubstitutes,
+        crate_path: syn::Path,
+        should_gen_docs: bool,
+    ) -> Result<TokenStream2, CodegenError> {
+        let item_mod_attrs = item_mod.attrs.clone();
+        let item_mod_ir = ir::ItemMod::try_from(item_mod)?;
+
+        let settings =
+            subxt_type_gen_settings(derives, type_substitutes, &crate_path, should_gen_docs);
+
+        let type_gen = TypeGenerator::new(self.runtimes[0].1.metadata.types(), &settings);
+        let types_mod = type_gen
+            .generate_types_mod()?
+            .to_token_stream(type_gen.settings());
+        let types_mod_ident = type_gen.types_mod_ident();
+
+        let runtime_mods = self
+            .runtimes
+            .iter()
+            .map(|(name, generator)| {
+                let runtime_items = generator.generate_runtime_items(&type_gen, &crate_path)?;
+                Ok(quote! {
+                    pub mod #name {
+                        use super::#types_mod_ident;
+
+                        // Each runtime has its own root, so that its pallets refer to the
+                        // items for that runtime.
+                        #[allow(unused_imports)]
+                        mod root_mod {
+                            pub use super::*;
+                        }
+
+                        #runtime_items
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, CodegenError>>()?;
+
+        // A pallet's error type is often shared by several runtimes, so we implement traits
+        // for them once here rather than in each runtime module.
+        let static_errors = super::errors::generate_static_errors(
+            self.runtimes
+                .iter()
+                .map(|(_, generator)| &generator.metadata),
+            &type_gen,
+            &crate_path,
+        )?;
+
+        let mod_ident = &item_mod_ir.ident;
+        let rust_items = item_mod_ir.rust_items();
+
+        Ok(quote! {
+            #( #item_mod_attrs )*
+            #[allow(dead_code, unused_imports, non_camel_case_types, unreachable_patterns)]
+            #[allow(clippy::all)]
+            #[allow(rustdoc::broken_intra_doc_links)]
+            pub mod #mod_ident {
+                // Preserve any Rust items that were previously defined in the adorned module.
+                #( #rust_items ) *
+
+                #[allow(unused_imports)]
+                mod root_mod {
+                    pub use super::*;
+                }
+
+                #( #runtime_mods )*
+                #static_errors
+                #types_mod
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CodegenBuilder;
+    use codec::Decode;
+    use subxt_metadata::Metadata;
+    use syn::parse_quote;
+
+    fn load_metadata(path: &str) -> Metadata {
+        let bytes = std::fs::read(path).expect("Cannot read metadata blob");
+        Metadata::decode(&mut &*bytes).expect("Cannot decode metadata")
+    }
+
+    #[test]
+    fn generates_shared_runtime_types() {
+        let runtimes = vec![
+            (
+                parse_quote!(full),
+                load_metadata("../artifacts/polkadot_metadata_full.scale"),
+            ),
+            (
+                parse_quote!(small),
+                load_metadata("../artifacts/polkadot_metadata_small.scale"),
+            ),
+        ];
+        let code = CodegenBuilder::new().generate_multi(runtimes).unwrap();
+        let api: syn::ItemMod = syn::parse2(code).unwrap();
+        let (_, items) = api.content.unwrap();
+
+        let mod_names: Vec<_> = items
+            .iter()
+            .filter_map(|item| match item {
+                syn::Item::Mod(m) => Some(m.ident.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(mod_names, ["root_mod", "full", "small", "runtime_types"]);
+
+        // Pallet error types shared by both runtimes are only given one trait impl.
+        let code = quote::quote!(#( #items )*).to_string();
+        let system_error_impl =
+            "StaticError for runtime_types :: frame_system :: pallet :: Error {";
+        assert_eq!(code.matches(system_error_impl).count(), 1);
+    }
+
+    #[test]
+    fn rejects_duplicate_runtime_names() {
+        let metadata = load_metadata("../artifacts/polkadot_metadata_tiny.scale");
+        let runtimes = vec![
+            (parse_quote!(v1), metadata.clone()),
+            (parse_quote!(v1), metadata),
+        ];
+        assert!(CodegenBuilder::new().generate_multi(runtimes).is_err());
+    }
+
+    #[test]
+    fn rejects_compatibility_report() {
+        let metadata = load_metadata("../artifacts/polkadot_metadata_tiny.scale");
+        let runtimes = vec![
+            (parse_quote!(v1), metadata.clone()),
+            (parse_quote!(v2), metadata),
+        ];
+        let mut codegen = CodegenBuilder::new();
+        codegen.compatibility_report();
+        assert!(codegen.generate_multi(runtimes).is_err());
+    }
+}
@@ -0,0 +1,356 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use std::collections::HashSet;
+
+use heck::ToUpperCamelCase as _;
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use scale_typegen::typegen::ir::ToTokensWithSettings;
+use scale_typegen::TypeGenerator;
+use subxt_metadata::PalletMetadata;
+
+use super::CodegenError;
+
+/// Generate view functions from the provided pallet's metadata.
+///
+/// The function creates a new module named `view_functions` under the pallet's module.
+/// ```ignore
+/// pub mod PalletName {
+///     pub mod view_functions {
+///     ...
+///     }
+/// }
+/// ```
+///
+/// The view functions are exposed via the `ViewFunctionsApi` wrapper, and each
+/// one returns a payload which can be executed via the `RuntimeViewFunction` runtime API.
+///
+/// # Arguments
+///
+/// - `type_gen` - [`scale_typegen::TypeGenerator`] that contains settings and all types from the runtime metadata.
+/// - `pallet` - Pallet metadata from which the view functions are generated.
+/// - `crate_path` - The crate path under which the `subxt-core` crate is located, e.g. `::subxt::ext::subxt_core` when using subxt as a dependency.
+pub fn generate_view_functions(
+    type_gen: &TypeGenerator,
+    pallet: &PalletMetadata,
+    crate_path: &syn::Path,
+) -> Result<TokenStream2, CodegenError> {
+    // Early return if the pallet has no view functions.
+    if pallet.view_functions().len() == 0 {
+        return Ok(quote!());
+    }
+
+    let pallet_name = pallet.name();
+    let types_mod_ident = type_gen.types_mod_ident();
+
+    let structs_and_fns: Vec<_> = pallet
+        .view_functions()
+        .map(|view_function| {
+            let fn_name = format_ident!("{}", view_function.name());
+            let fn_name_str = view_function.name();
+
+            let docs = view_function.docs();
+            let docs: TokenStream2 = type_gen
+                .settings()
+                .should_gen_docs
+                .then_some(quote! { #( #[doc = #docs ] )* })
+                .unwrap_or_default();
+
+            let mut unique_names = HashSet::new();
+            let mut unique_aliases = HashSet::new();
+
+            let inputs: Vec<_> = view_function
+                .inputs()
+                .enumerate()
+                .map(|(idx, input)| {
+                    // Parameter names can just be '_', but struct field names can't be
+                    // just an underscore, so fix any such names we find to work in structs.
+                    let mut name = input.name.trim_start_matches('_').to_string();
+                    if name.is_empty() {
+                        name = format!("_{}", idx);
+                    }
+                    while !unique_names.insert(name.clone()) {
+                        // Name is already used, append the index until it is unique.
+                        name = format!("{}_param{}", name, idx);
+                    }
+
+                    let mut alias = name.to_upper_camel_case();
+                    // Note: name is not empty.
+                    if alias.as_bytes()[0].is_ascii_digit() {
+                        alias = format!("Param{}", alias);
+                    }
+                    while !unique_aliases.insert(alias.clone()) {
+                        alias = format!("{}Param{}", alias, idx);
+                    }
+
+                    let (alias_name, name) = (format_ident!("{alias}"), format_ident!("{name}"));
+
+                    // Generate alias for the parameter type.
+                    let ty = type_gen
+                        .resolve_type_path(input.ty)?
+                        .to_token_stream(type_gen.settings());
+                    let aliased_param = quote!( pub type #alias_name = #ty; );
+
+                    // Structures are placed on the same level as the alias module.
+                    let struct_ty_path = quote!( #fn_name::#alias_name );
+                    let struct_param = quote!(#name: #struct_ty_path);
+
+                    // Function parameters must be indented by `types`.
+                    let fn_param = quote!(#name: types::#struct_ty_path);
+                    Ok((fn_param, struct_param, name, aliased_param))
+                })
+                .collect::<Result<_, CodegenError>>()?;
+
+            let fn_params = inputs.iter().map(|(fn_param, _, _, _)| fn_param);
+            let struct_params = inputs.iter().map(|(_, struct_param, _, _)| struct_param);
+            let param_names = inputs.iter().map(|(_, _, name, _)| name);
+            let type_aliases = inputs.iter().map(|(_, _, _, aliased_param)| aliased_param);
+
+            let output = type_gen
+                .resolve_type_path(view_function.output_ty())?
+                .to_token_stream(type_gen.settings());
+            let aliased_module = quote!(
+                pub mod #fn_name {
+                    use super::#types_mod_ident;
+
+                    #( #type_aliases )*
+
+                    // Guard the `Output` name against collisions by placing it in a dedicated module.
+                    pub mod output {
+                        use super::#types_mod_ident;
+                        pub type Output = #output;
+                    }
+                }
+            );
+
+            // From the view function metadata generate a structure that holds
+            // all parameter types. This structure is used with metadata
+            // to encode parameters to the call via `encode_as_fields_to`.
+            let derives = type_gen.settings().derives.default_derives();
+            let struct_name = format_ident!("{}", fn_name_str.to_upper_camel_case());
+            let struct_input = quote!(
+                #aliased_module
+
+                #derives
+                pub struct #struct_name {
+                    #( pub #struct_params, )*
+                }
+            );
+
+            let Some(view_function_hash) = pallet.view_function_hash(fn_name_str) else {
+                return Err(CodegenError::MissingViewFunctionMetadata(
+                    pallet_name.into(),
+                    fn_name_str.into(),
+                ));
+            };
+
+            let view_fn = quote!(
+                #docs
+                pub fn #fn_name(&self, #( #fn_params, )* ) -> #crate_path::view_functions::payload::StaticPayload<types::#struct_name, types::#fn_name::output::Output> {
+                    #crate_path::view_functions::payload::StaticPayload::new_static(
+                        #pallet_name,
+                        #fn_name_str,
+                        types::#struct_name { #( #param_names, )* },
+                        [#(#view_function_hash,)*],
+                    )
+                }
+            );
+
+            Ok((struct_input, view_fn))
+        })
+        .collect::<Result<_, CodegenError>>()?;
+
+    let structs = structs_and_fns.iter().map(|(struct_, _)| struct_);
+    let view_fns = structs_and_fns.iter().map(|(_, view_fn)| view_fn);
+
+    Ok(quote! {
+        pub mod view_functions {
+            use super::root_mod;
+            use super::#types_mod_ident;
+
+            pub struct ViewFunctionsApi;
+
+            impl ViewFunctionsApi {
+                #( #view_fns )*
+            }
+
+            pub mod types {
+                use super::#types_mod_ident;
+
+                #( #structs )*
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RuntimeGenerator;
+    use frame_metadata::v16::{
+        self, DeprecationStatus, PalletMetadata, PalletViewFunctionMetadata,
+        PalletViewFunctionParamMetadata,
+    };
+    use quote::quote;
+    use scale_info::meta_type;
+    use subxt_metadata::Metadata;
+
+    fn metadata_with_view_functions(view_functions: Vec<PalletViewFunctionMetadata>) -> Metadata {
+        let pallet = PalletMetadata {
+            name: "Test",
+            storage: None,
+            calls: None,
+            event: None,
+            constants: vec![],
+            error: None,
+            associated_types: vec![],
+            view_functions,
+            index: 0,
+            docs: vec![],
+            deprecation_info: DeprecationStatus::NotDeprecated,
+        };
+        let extrinsic_metadata = v16::ExtrinsicMetadata {
+            versions: vec![4],
+            address_ty: meta_type::<()>(),
+            signature_ty: meta_type::<()>(),
+            transaction_extensions_by_version: Default::default(),
+            transaction_extensions: vec![],
+        };
+
+        let metadata: Metadata = v16::RuntimeMetadataV16::new(
+            vec![pallet],
+            extrinsic_metadata,
+            vec![],
+            v16::OuterEnums {
+                call_enum_ty: meta_type::<()>(),
+                event_enum_ty: meta_type::<()>(),
+                error_enum_ty: meta_type::<()>(),
+            },
+            v16::CustomMetadata {
+                map: Default::default(),
+            },
+        )
+        .try_into()
+        .expect("can build valid metadata");
+        metadata
+    }
+
+    fn generate_code(metadata: Metadata) -> String {
+        let item_mod = syn::parse_quote!(
+            pub mod api {}
+        );
+        let generator = RuntimeGenerator::new(metadata);
+        let generated = generator
+            .generate_runtime(
+                item_mod,
+                Default::default(),
+                Default::default(),
+                syn::parse_str("::subxt_path").unwrap(),
+                false,
+            )
+            .expect("should be able to generate runtime");
+        generated.to_string()
+    }
+
+    fn view_function(
+        name: &'static str,
+        inputs: Vec<PalletViewFunctionParamMetadata>,
+    ) -> PalletViewFunctionMetadata {
+        PalletViewFunctionMetadata {
+            name,
+            id: [1; 32],
+            inputs,
+            output: meta_type::<u64>(),
+            docs: vec![],
+            deprecation_info: DeprecationStatus::NotDeprecated,
+        }
+    }
+
+    #[test]
+    fn generates_view_function_payloads() {
+        let metadata = metadata_with_view_functions(vec![view_function(
+            "get_value",
+            vec![PalletViewFunctionParamMetadata {
+                name: "key",
+                ty: meta_type::<u32>(),
+            }],
+        )]);
+        let hash = metadata
+            .pallet_by_name("Test")
+            .and_then(|pallet| pallet.view_function_hash("get_value"))
+            .unwrap();
+        let code = generate_code(metadata);
+
+        let structure = quote! {
+            pub struct GetValue {
+                pub key: get_value::Key,
+            }
+        };
+        let expected_alias = quote!(
+            pub mod get_value {
+                use super::runtime_types;
+                pub type Key = ::core::primitive::u32;
+                pub mod output {
+                    use super::runtime_types;
+                    pub type Output = ::core::primitive::u64;
+                }
+            }
+        );
+        let payload = quote!(
+            ::subxt_path::view_functions::payload::StaticPayload::new_static(
+                "Test",
+                "get_value",
+                types::GetValue { key, },
+                [#(#hash,)*],
+            )
+        );
+        let accessor = quote!(
+            pub fn test(&self) -> test::view_functions::ViewFunctionsApi {
+                test::view_functions::ViewFunctionsApi
+            }
+        );
+        assert!(code.contains(&structure.to_string()));
+        assert!(code.contains(&expected_alias.to_string()));
+        assert!(code.contains(&payload.to_string()));
+        assert!(code.contains(&accessor.to_string()));
+    }
+
+    #[test]
+    fn unique_param_names() {
+        let code = generate_code(metadata_with_view_functions(vec![view_function(
+            "get_value",
+            vec![
+                PalletViewFunctionParamMetadata {
+                    name: "_",
+                    ty: meta_type::<bool>(),
+                },
+                PalletViewFunctionParamMetadata {
+                    name: "foo",
+                    ty: meta_type::<bool>(),
+                },
+                PalletViewFunctionParamMetadata {
+                    name: "_foo",
+                    ty: meta_type::<bool>(),
+                },
+            ],
+        )]));
+
+        let structure = quote! {
+            pub struct GetValue {
+                pub _0: get_value::Param0,
+                pub foo: get_value::Foo,
+                pub foo_param2: get_value::FooParam2,
+            }
+        };
+        assert!(code.contains(&structure.to_string()));
+    }
+
+    #[test]
+    fn no_view_functions_generates_nothing() {
+        let code = generate_code(metadata_with_view_functions(vec![]));
+        assert!(!code.contains("pub mod view_functions"));
+        assert!(!code.contains("pub fn test (& self)"));
+    }
+}
@@ -32,6 +32,9 @@ pub enum CodegenError {
     /// Metadata for call could not be found.
     #[error("Metadata for runtime API entry {0}_{1} could not be found. Make sure you are providing a valid substrate-based metadata")]
     MissingRuntimeApiMetadata(String, String),
+    /// Metadata for view function could not be found.
+    #[error("Metadata for view function {0}_{1} could not be found. Make sure you are providing a valid substrate-based metadata")]
+    MissingViewFunctionMetadata(String, String),
     /// Call variant must have all named fields.
     #[error("Call variant for type {0} must have all named fields. Make sure you are providing a valid substrate-based metadata")]
     InvalidCallVariant(u32),
@@ -57,6 +60,15 @@ pub enum CodegenError {
     /// Error when generating metadata from Wasm-runtime
     #[error("Failed to generate metadata from wasm file. reason: {0}")]
     Wasm(String),
+    /// An item given to filter codegen by is not valid.
+    #[error("Invalid item filter `{0}`; expected a pallet or runtime API name like `Balances`, optionally followed by an item name like `Balances::transfer_keep_alive`")]
+    InvalidItemFilter(String),
+    /// An item given to filter codegen by does not exist in the metadata.
+    #[error("Item filter `{0}` does not match any pallet, runtime API or item in the metadata")]
+    UnknownFilterItem(String),
+    /// The runtimes given to generate code for together are not valid.
+    #[error("Invalid runtimes: {0}")]
+    InvalidRuntimes(String),
     /// Other error.
     #[error("Other error: {0}")]
     Other(String),
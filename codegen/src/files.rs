@@ -0,0 +1,188 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Split generated code into a tree of files, one per module.
+
+use crate::error::CodegenError;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use std::path::{Path, PathBuf};
+use syn::ext::IdentExt;
+
+/// A single file of generated code. See [`crate::CodegenBuilder::generate_files()`].
+#[derive(Debug, Clone)]
+pub struct GeneratedFile {
+    path: PathBuf,
+    code: TokenStream2,
+}
+
+impl GeneratedFile {
+    /// The path of this file, relative to the directory that the code is written to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The code in this file.
+    pub fn code(&self) -> &TokenStream2 {
+        &self.code
+    }
+}
+
+/// Split the generated module into files. The root module becomes `mod.rs`, and is given
+/// the attributes of the module as inner attributes. Pallet modules and their `calls`,
+/// `events`, `storage` (etc) modules, as well as every module in `runtime_types`, are moved
+/// into their own files.
+pub fn split_into_files(
+    code: TokenStream2,
+    types_mod_ident: &syn::Ident,
+) -> Result<Vec<GeneratedFile>, CodegenError> {
+    let root: syn::ItemMod = syn::parse2(code)
+        .map_err(|e| CodegenError::Other(format!("Generated code is not a module: {e}")))?;
+    let Some((_, items)) = root.content else {
+        return Err(CodegenError::InvalidModule(root.ident.span()));
+    };
+    let attrs = root.attrs.into_iter().map(|mut attr| {
+        attr.style = syn::AttrStyle::Inner(Default::default());
+        attr
+    });
+
+    let mut files = Vec::new();
+    let code = split_items(Path::new(""), items, &[], types_mod_ident, &mut files);
+    files.push(GeneratedFile {
+        path: PathBuf::from("mod.rs"),
+        code: quote! {
+            #( #attrs )*
+            #code
+        },
+    });
+    // Put the root first, and the rest in a stable order.
+    files.reverse();
+    files[1..].sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Move any modules that should be in their own files out of `items`, adding them to `files`,
+/// and return the code that remains.
+fn split_items(
+    dir: &Path,
+    items: Vec<syn::Item>,
+    mod_path: &[String],
+    types_mod_ident: &syn::Ident,
+    files: &mut Vec<GeneratedFile>,
+) -> TokenStream2 {
+    let items = items.into_iter().map(|item| {
+        let syn::Item::Mod(mut item_mod) = item else {
+            return item;
+        };
+        if !should_split(&item_mod, mod_path, types_mod_ident) {
+            return syn::Item::Mod(item_mod);
+        }
+
+        let name = item_mod.ident.unraw().to_string();
+        let mut child_path = mod_path.to_vec();
+        child_path.push(item_mod.ident.to_string());
+        let (_, child_items) = item_mod
+            .content
+            .take()
+            .expect("checked in should_split; qed");
+
+        let has_split_children = child_items.iter().any(|item| match item {
+            syn::Item::Mod(m) => should_split(m, &child_path, types_mod_ident),
+            _ => false,
+        });
+        let (child_dir, path) = if has_split_children {
+            (dir.join(&name), dir.join(&name).join("mod.rs"))
+        } else {
+            (dir.to_owned(), dir.join(format!("{name}.rs")))
+        };
+
+        let code = split_items(&child_dir, child_items, &child_path, types_mod_ident, files);
+        files.push(GeneratedFile { path, code });
+
+        item_mod.semi = Some(Default::default());
+        syn::Item::Mod(item_mod)
+    });
+    let items: Vec<_> = items.collect();
+    quote!( #( #items )* )
+}
+
+/// Modules directly in the root or in a pallet module, and any module in `runtime_types`,
+/// get their own files unless they contain nothing but `use` statements.
+fn should_split(
+    item_mod: &syn::ItemMod,
+    mod_path: &[String],
+    types_mod_ident: &syn::Ident,
+) -> bool {
+    let Some((_, items)) = &item_mod.content else {
+        return false;
+    };
+    if items.iter().all(|item| matches!(item, syn::Item::Use(_))) {
+        return false;
+    }
+    mod_path.len() <= 1 || *types_mod_ident == mod_path[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn splits_modules_into_files() {
+        let code = quote! {
+            #[allow(dead_code)]
+            pub mod api {
+                mod root_mod {
+                    pub use super::*;
+                }
+                pub mod system {
+                    use super::root_mod;
+                    pub mod calls {
+                        pub mod types {
+                            pub type Foo = u8;
+                        }
+                    }
+                }
+                pub mod runtime_types {
+                    pub mod sp_core {
+                        pub mod crypto {
+                            pub struct AccountId32(pub [u8; 32]);
+                        }
+                    }
+                }
+            }
+        };
+
+        let files = split_into_files(code, &parse_quote!(runtime_types)).unwrap();
+        let paths: Vec<_> = files.iter().map(|f| f.path().to_owned()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("mod.rs"),
+                PathBuf::from("runtime_types/mod.rs"),
+                PathBuf::from("runtime_types/sp_core/crypto.rs"),
+                PathBuf::from("runtime_types/sp_core/mod.rs"),
+                PathBuf::from("system/calls.rs"),
+                PathBuf::from("system/mod.rs"),
+            ]
+        );
+
+        let root = quote! {
+            #![allow(dead_code)]
+            mod root_mod {
+                pub use super::*;
+            }
+            pub mod system;
+            pub mod runtime_types;
+        };
+        assert_eq!(files[0].code().to_string(), root.to_string());
+
+        let calls = quote! {
+            pub mod types {
+                pub type Foo = u8;
+            }
+        };
+        assert_eq!(files[4].code().to_string(), calls.to_string());
+    }
+}
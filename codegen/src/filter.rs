@@ -0,0 +1,199 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Restrict codegen to a subset of the pallets, runtime APIs and items in the metadata.
+
+use crate::error::CodegenError;
+use scale_info::form::PortableForm;
+use subxt_metadata::{Metadata, MetadataItem};
+
+/// Lists of items to generate code for, and items not to generate code for. Each item is either
+/// the name of a pallet or runtime API (eg `Balances`), or the name of an item within one
+/// (eg `Balances::transfer_keep_alive` or `System::Account`).
+#[derive(Debug, Clone, Default)]
+pub struct ItemFilter {
+    allow: Vec<ItemPattern>,
+    deny: Vec<ItemPattern>,
+}
+
+#[derive(Debug, Clone)]
+struct ItemPattern {
+    original: String,
+    container: String,
+    item: Option<String>,
+}
+
+impl ItemPattern {
+    fn parse(s: &str) -> Result<Self, CodegenError> {
+        let invalid = || CodegenError::InvalidItemFilter(s.to_owned());
+        let (container, item) = match s.split_once("::") {
+            Some((container, item)) => (container.trim(), Some(item.trim())),
+            None => (s.trim(), None),
+        };
+        let is_valid =
+            |name: &str| !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if !is_valid(container) || !item.map_or(true, is_valid) {
+            return Err(invalid());
+        }
+        Ok(ItemPattern {
+            original: s.to_owned(),
+            container: container.to_owned(),
+            item: item.map(ToOwned::to_owned),
+        })
+    }
+
+    fn matches_container(&self, container: &str) -> bool {
+        self.container == container
+    }
+
+    fn matches_item(&self, container: &str, item: &str) -> bool {
+        self.container == container && self.item.as_ref().map_or(true, |i| i == item)
+    }
+}
+
+impl ItemFilter {
+    /// Build a filter from lists of allowed and denied items. If no items are allowed then
+    /// everything that isn't denied is kept.
+    pub fn new(allow: &[String], deny: &[String]) -> Result<Self, CodegenError> {
+        let parse = |items: &[String]| {
+            items
+                .iter()
+                .map(|s| ItemPattern::parse(s))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(ItemFilter {
+            allow: parse(allow)?,
+            deny: parse(deny)?,
+        })
+    }
+
+    /// Does this filter keep everything?
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Should we keep the pallet or runtime API with the given name?
+    pub fn keeps_container(&self, name: &str) -> bool {
+        let allowed = self.allow.is_empty() || self.allow.iter().any(|p| p.matches_container(name));
+        let denied = self
+            .deny
+            .iter()
+            .any(|p| p.item.is_none() && p.matches_container(name));
+        allowed && !denied
+    }
+
+    /// Should we keep the given item?
+    pub fn keeps_item(&self, item: &MetadataItem) -> bool {
+        let (container, name) = match item {
+            MetadataItem::Call { pallet, name }
+            | MetadataItem::Event { pallet, name }
+            | MetadataItem::StorageEntry { pallet, name }
+            | MetadataItem::Constant { pallet, name }
+            | MetadataItem::ViewFunction { pallet, name } => (pallet, name),
+            MetadataItem::RuntimeApiMethod { trait_name, name } => (trait_name, name),
+            // Custom values don't belong to a pallet, so we always keep them.
+            MetadataItem::CustomValue { .. } => return true,
+        };
+        let allowed =
+            self.allow.is_empty() || self.allow.iter().any(|p| p.matches_item(container, name));
+        let denied = self.deny.iter().any(|p| p.matches_item(container, name));
+        allowed && !denied
+    }
+
+    /// Remove everything that this filter doesn't keep from the metadata, along with any types
+    /// that are no longer needed. Returns an error if some item in the filter doesn't match
+    /// anything in the metadata, since that's most likely a typo.
+    pub fn apply(&self, metadata: &mut Metadata) -> Result<(), CodegenError> {
+        for pattern in self.allow.iter().chain(&self.deny) {
+            if !exists_in(metadata, pattern) {
+                return Err(CodegenError::UnknownFilterItem(pattern.original.clone()));
+            }
+        }
+
+        metadata.retain_items(
+            |pallet| self.keeps_container(pallet),
+            |api| self.keeps_container(api),
+            |item| self.keeps_item(item),
+        );
+        Ok(())
+    }
+}
+
+/// Does some pattern match a pallet, runtime API or item in the metadata?
+fn exists_in(metadata: &Metadata, pattern: &ItemPattern) -> bool {
+    let container = pattern.container.as_str();
+    if let Some(pallet) = metadata.pallet_by_name(container) {
+        let Some(item) = &pattern.item else {
+            return true;
+        };
+        let item = item.as_str();
+        let in_variants = |variants: Option<&[scale_info::Variant<PortableForm>]>| {
+            variants.is_some_and(|vs| vs.iter().any(|v| v.name == item))
+        };
+        if in_variants(pallet.call_variants())
+            || in_variants(pallet.event_variants())
+            || pallet
+                .storage()
+                .is_some_and(|s| s.entry_by_name(item).is_some())
+            || pallet.constant_by_name(item).is_some()
+            || pallet.view_function_by_name(item).is_some()
+        {
+            return true;
+        }
+    }
+    if let Some(api) = metadata.runtime_api_trait_by_name(container) {
+        return match &pattern.item {
+            Some(item) => api.method_by_name(item).is_some(),
+            None => true,
+        };
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(allow: &[&str], deny: &[&str]) -> ItemFilter {
+        let to_strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        ItemFilter::new(&to_strings(allow), &to_strings(deny)).unwrap()
+    }
+
+    fn call(pallet: &str, name: &str) -> MetadataItem {
+        MetadataItem::Call {
+            pallet: pallet.to_owned(),
+            name: name.to_owned(),
+        }
+    }
+
+    #[test]
+    fn allow_and_deny_items() {
+        let f = filter(
+            &["Balances::transfer_keep_alive", "System"],
+            &["System::remark"],
+        );
+        assert!(f.keeps_container("Balances"));
+        assert!(f.keeps_container("System"));
+        assert!(!f.keeps_container("Staking"));
+        assert!(f.keeps_item(&call("Balances", "transfer_keep_alive")));
+        assert!(!f.keeps_item(&call("Balances", "transfer_all")));
+        assert!(f.keeps_item(&call("System", "set_code")));
+        assert!(!f.keeps_item(&call("System", "remark")));
+
+        let f = filter(&[], &["Staking"]);
+        assert!(f.keeps_container("Balances"));
+        assert!(!f.keeps_container("Staking"));
+        assert!(f.keeps_item(&call("Balances", "transfer_all")));
+    }
+
+    #[test]
+    fn invalid_items() {
+        for item in ["", "::foo", "Balances::", "Balances::foo::bar", "Bal ances"] {
+            assert!(
+                ItemFilter::new(&[item.to_owned()], &[]).is_err(),
+                "item: {item}"
+            );
+        }
+    }
+}
@@ -11,12 +11,16 @@
 
 mod api;
 pub mod error;
+mod files;
+mod filter;
 mod ir;
+mod typescript;
 
 #[cfg(feature = "web")]
 use getrandom as _;
 
-use api::RuntimeGenerator;
+use api::{MultiRuntimeGenerator, RuntimeGenerator};
+use filter::ItemFilter;
 use proc_macro2::TokenStream as TokenStream2;
 use scale_typegen::typegen::settings::AllocCratePath;
 use scale_typegen::{
@@ -25,9 +29,11 @@ use scale_typegen::{
 };
 use std::collections::HashMap;
 use syn::parse_quote;
+use typescript::TypeScriptGenerator;
 
 // Part of the public interface, so expose:
 pub use error::CodegenError;
+pub use files::GeneratedFile;
 pub use subxt_metadata::Metadata;
 pub use syn;
 
@@ -58,6 +64,7 @@ pub struct CodegenBuilder {
     use_default_substitutions: bool,
     generate_docs: bool,
     runtime_types_only: bool,
+    compatibility_report: bool,
     item_mod: syn::ItemMod,
     extra_global_derives: Vec<syn::Path>,
     extra_global_attributes: Vec<syn::Attribute>,
@@ -66,6 +73,8 @@ pub struct CodegenBuilder {
     attributes_for_type: HashMap<syn::TypePath, Vec<syn::Attribute>>,
     derives_for_type_recursive: HashMap<syn::TypePath, Vec<syn::Path>>,
     attributes_for_type_recursive: HashMap<syn::TypePath, Vec<syn::Attribute>>,
+    allowed_items: Vec<String>,
+    denied_items: Vec<String>,
 }
 
 impl Default for CodegenBuilder {
@@ -76,6 +85,7 @@ impl Default for CodegenBuilder {
             use_default_substitutions: true,
             generate_docs: true,
             runtime_types_only: false,
+            compatibility_report: false,
             item_mod: syn::parse_quote!(
                 pub mod api {}
             ),
@@ -86,6 +96,8 @@ impl Default for CodegenBuilder {
             attributes_for_type: HashMap::new(),
             derives_for_type_recursive: HashMap::new(),
             attributes_for_type_recursive: HashMap::new(),
+            allowed_items: Vec::new(),
+            denied_items: Vec::new(),
         }
     }
 }
@@ -130,6 +142,14 @@ impl CodegenBuilder {
         self.runtime_types_only = true;
     }
 
+    /// Generate a `compatibility_report` function, which reports every item in the generated
+    /// code that has been changed or removed in the metadata of some live node. This embeds
+    /// the metadata that the code was generated from into the generated code, and the
+    /// function relies on the standard library.
+    pub fn compatibility_report(&mut self) {
+        self.compatibility_report = true;
+    }
+
     /// Set the additional derives that will be applied to all types. By default,
     /// a set of derives required for Subxt are automatically added for all types.
     ///
@@ -208,6 +228,24 @@ impl CodegenBuilder {
         self.type_substitutes.insert(ty, with);
     }
 
+    /// Only generate code for the given pallets, runtime APIs and items. Each entry is either the
+    /// name of a pallet or runtime API, like `Balances`, which keeps everything in it, or the name
+    /// of a call, event, storage entry, constant, view function or runtime API method within one,
+    /// like `Balances::transfer_keep_alive` or `System::Account`.
+    ///
+    /// By default, everything is allowed. Any runtime types which are no longer needed by the
+    /// remaining items are not generated.
+    pub fn set_allowed_items(&mut self, items: Vec<String>) {
+        self.allowed_items = items;
+    }
+
+    /// Don't generate code for the given pallets, runtime APIs and items, which are given in the
+    /// same form as for [`CodegenBuilder::set_allowed_items()`]. This takes precedence over the
+    /// allowed items.
+    pub fn set_denied_items(&mut self, items: Vec<String>) {
+        self.denied_items = items;
+    }
+
     /// By default, all of the code is generated inside a module `pub mod api {}`. We decorate
     /// this module with a few attributes to reduce compile warnings and things. You can provide a
     /// target module here, allowing you to add additional attributes or inner code items (with the
@@ -234,45 +272,20 @@ impl CodegenBuilder {
     /// If the `subxt` crate is not available as a top level dependency, use `generate` and provide
     /// a valid path to the `subxt¦ crate.
     pub fn generate(self, metadata: Metadata) -> Result<TokenStream2, CodegenError> {
+        let (derives_registry, type_substitutes) = self.derives_and_substitutes()?;
         let crate_path = self.crate_path;
 
-        let mut derives_registry: DerivesRegistry = if self.use_default_derives {
-            default_derives(&crate_path)
-        } else {
-            DerivesRegistry::new()
-        };
-
-        derives_registry.add_derives_for_all(self.extra_global_derives);
-        derives_registry.add_attributes_for_all(self.extra_global_attributes);
+        let (metadata, metadata_hash) =
+            filter_metadata(metadata, &self.allowed_items, &self.denied_items)?;
 
-        for (ty, derives) in self.derives_for_type {
-            derives_registry.add_derives_for(ty, derives, false);
-        }
-        for (ty, derives) in self.derives_for_type_recursive {
-            derives_registry.add_derives_for(ty, derives, true);
-        }
-        for (ty, attributes) in self.attributes_for_type {
-            derives_registry.add_attributes_for(ty, attributes, false);
+        let item_mod = self.item_mod;
+        let mut generator = RuntimeGenerator::new(metadata);
+        if let Some(metadata_hash) = metadata_hash {
+            generator = generator.with_metadata_hash(metadata_hash);
         }
-        for (ty, attributes) in self.attributes_for_type_recursive {
-            derives_registry.add_attributes_for(ty, attributes, true);
+        if self.compatibility_report {
+            generator = generator.with_compatibility_report();
         }
-
-        let mut type_substitutes: TypeSubstitutes = if self.use_default_substitutions {
-            default_substitutes(&crate_path)
-        } else {
-            TypeSubstitutes::new()
-        };
-
-        for (from, with) in self.type_substitutes {
-            let abs_path = absolute_path(with).map_err(TypegenError::from)?;
-            type_substitutes
-                .insert(from, abs_path)
-                .map_err(TypegenError::from)?;
-        }
-
-        let item_mod = self.item_mod;
-        let generator = RuntimeGenerator::new(metadata);
         let should_gen_docs = self.generate_docs;
 
         if self.runtime_types_only {
@@ -293,6 +306,167 @@ impl CodegenBuilder {
             )
         }
     }
+
+    /// Generate an interface for several runtimes at once, typically different versions of the
+    /// same chain. Each runtime is given as the name of the module to generate for it, along
+    /// with its metadata.
+    ///
+    /// Types which are identical in every way that affects their encoding are generated once,
+    /// in a `runtime_types` module shared by all of the runtimes. Each runtime gets its own
+    /// module (eg `api::v1`) containing its calls, events, storage entries, constants and so
+    /// on, which refer to those shared types. Any allowed or denied items (see
+    /// [`CodegenBuilder::set_allowed_items()`]) apply to each of the runtimes. A compatibility
+    /// report (see [`CodegenBuilder::compatibility_report()`]) can't be generated in this mode,
+    /// since each runtime would embed a copy of the shared types.
+    pub fn generate_multi(
+        self,
+        runtimes: Vec<(syn::Ident, Metadata)>,
+    ) -> Result<TokenStream2, CodegenError> {
+        let (derives_registry, type_substitutes) = self.derives_and_substitutes()?;
+        let crate_path = self.crate_path;
+
+        let runtimes = runtimes
+            .into_iter()
+            .map(|(name, metadata)| {
+                let (metadata, metadata_hash) =
+                    filter_metadata(metadata, &self.allowed_items, &self.denied_items)?;
+                let mut generator = RuntimeGenerator::new(metadata);
+                if let Some(metadata_hash) = metadata_hash {
+                    generator = generator.with_metadata_hash(metadata_hash);
+                }
+                if self.compatibility_report {
+                    generator = generator.with_compatibility_report();
+                }
+                Ok((name, generator))
+            })
+            .collect::<Result<Vec<_>, CodegenError>>()?;
+        let generator = MultiRuntimeGenerator::new(runtimes)?;
+
+        if self.runtime_types_only {
+            generator.generate_runtime_types(
+                self.item_mod,
+                derives_registry,
+                type_substitutes,
+                crate_path,
+                self.generate_docs,
+            )
+        } else {
+            generator.generate_runtimes(
+                self.item_mod,
+                derives_registry,
+                type_substitutes,
+                crate_path,
+                self.generate_docs,
+            )
+        }
+    }
+
+    /// Build the derives and type substitutes to generate types with.
+    fn derives_and_substitutes(&self) -> Result<(DerivesRegistry, TypeSubstitutes), CodegenError> {
+        let crate_path = &self.crate_path;
+
+        let mut derives_registry: DerivesRegistry = if self.use_default_derives {
+            default_derives(crate_path)
+        } else {
+            DerivesRegistry::new()
+        };
+
+        derives_registry.add_derives_for_all(self.extra_global_derives.clone());
+        derives_registry.add_attributes_for_all(self.extra_global_attributes.clone());
+
+        for (ty, derives) in &self.derives_for_type {
+            derives_registry.add_derives_for(ty.clone(), derives.clone(), false);
+        }
+        for (ty, derives) in &self.derives_for_type_recursive {
+            derives_registry.add_derives_for(ty.clone(), derives.clone(), true);
+        }
+        for (ty, attributes) in &self.attributes_for_type {
+            derives_registry.add_attributes_for(ty.clone(), attributes.clone(), false);
+        }
+        for (ty, attributes) in &self.attributes_for_type_recursive {
+            derives_registry.add_attributes_for(ty.clone(), attributes.clone(), true);
+        }
+
+        let mut type_substitutes: TypeSubstitutes = if self.use_default_substitutions {
+            default_substitutes(crate_path)
+        } else {
+            TypeSubstitutes::new()
+        };
+
+        for (from, with) in &self.type_substitutes {
+            let abs_path = absolute_path(with.clone()).map_err(TypegenError::from)?;
+            type_substitutes
+                .insert(from.clone(), abs_path)
+                .map_err(TypegenError::from)?;
+        }
+
+        Ok((derives_registry, type_substitutes))
+    }
+
+    /// Generate an interface like [`CodegenBuilder::generate()`], but split it into a tree of
+    /// files rather than one large module. The root module is placed in `mod.rs`, each pallet
+    /// gets a directory with a file for each of its calls, events, storage entries and
+    /// constants, and each module in `runtime_types` gets its own file.
+    ///
+    /// The attributes given to the target module (see [`CodegenBuilder::set_target_module()`])
+    /// become inner attributes of `mod.rs`, so the files can be written to some directory and
+    /// then included with a plain `mod api;`.
+    pub fn generate_files(self, metadata: Metadata) -> Result<Vec<GeneratedFile>, CodegenError> {
+        let code = self.generate(metadata)?;
+        files::split_into_files(code, &parse_quote!(runtime_types))
+    }
+
+    /// Generate TypeScript bindings rather than Rust code. These contain a `runtime_types`
+    /// namespace with a type for every type in the metadata, and one namespace per pallet
+    /// containing descriptors (including validation hashes) for each call, event, storage
+    /// entry and constant, laid out like the Rust interface.
+    ///
+    /// Only [`CodegenBuilder::no_docs()`] and [`CodegenBuilder::runtime_types_only()`] apply
+    /// here; the other settings only make sense for Rust code and are ignored.
+    pub fn generate_typescript(self, metadata: Metadata) -> Result<String, CodegenError> {
+        let (metadata, metadata_hash) =
+            filter_metadata(metadata, &self.allowed_items, &self.denied_items)?;
+        let mut generator = TypeScriptGenerator::new(metadata);
+        if let Some(metadata_hash) = metadata_hash {
+            generator = generator.with_metadata_hash(metadata_hash);
+        }
+        generator.generate(self.generate_docs, self.runtime_types_only)
+    }
+}
+
+/// Strip the metadata down to the allowed items. If anything is removed, we also return the
+/// hash of the pallets and runtime APIs that are kept, as they were before any of their items
+/// were removed, so that generated code can be validated against a node's metadata.
+fn filter_metadata(
+    mut metadata: Metadata,
+    allowed_items: &[String],
+    denied_items: &[String],
+) -> Result<(Metadata, Option<[u8; 32]>), CodegenError> {
+    let filter = ItemFilter::new(allowed_items, denied_items)?;
+    if filter.is_empty() {
+        return Ok((metadata, None));
+    }
+
+    let pallets: Vec<_> = metadata
+        .pallets()
+        .map(|p| p.name())
+        .filter(|name| filter.keeps_container(name))
+        .map(ToOwned::to_owned)
+        .collect();
+    let runtime_apis: Vec<_> = metadata
+        .runtime_api_traits()
+        .map(|api| api.name())
+        .filter(|name| filter.keeps_container(name))
+        .map(ToOwned::to_owned)
+        .collect();
+    let metadata_hash = metadata
+        .hasher()
+        .only_these_pallets(&pallets)
+        .only_these_runtime_apis(&runtime_apis)
+        .hash();
+
+    filter.apply(&mut metadata)?;
+    Ok((metadata, Some(metadata_hash)))
 }
 
 /// The default [`scale_typegen::TypeGeneratorSettings`], subxt is using for generating code.
@@ -0,0 +1,564 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Generate TypeScript types and descriptors for a Substrate runtime, laid out like the
+//! Rust interface so that the two can be kept in sync.
+
+mod types;
+
+use crate::error::CodegenError;
+use heck::{ToSnakeCase as _, ToUpperCamelCase as _};
+use subxt_metadata::{json_string, Metadata, PalletMetadata, StorageEntryType};
+use types::TypeScriptTypes;
+
+/// Definitions shared by all of the generated descriptors. The `__` fields are never set;
+/// they exist so that the TypeScript compiler can carry the types of each item around.
+const DESCRIPTORS: &str = r#"/** A call that can be submitted in a transaction. */
+export interface CallDescriptor<Args> {
+    readonly pallet: string;
+    readonly name: string;
+    readonly hash: string;
+    readonly __args?: Args;
+}
+
+/** An event that can be emitted by a pallet. */
+export interface EventDescriptor<Fields> {
+    readonly pallet: string;
+    readonly name: string;
+    readonly hash: string;
+    readonly __fields?: Fields;
+}
+
+/** A storage entry, along with the types of its keys and value. */
+export interface StorageDescriptor<Keys extends Array<unknown>, Value> {
+    readonly pallet: string;
+    readonly name: string;
+    readonly hash: string;
+    readonly __keys?: Keys;
+    readonly __value?: Value;
+}
+
+/** A constant exposed by a pallet. */
+export interface ConstantDescriptor<Value> {
+    readonly pallet: string;
+    readonly name: string;
+    readonly hash: string;
+    readonly __value?: Value;
+}"#;
+
+/// Create the TypeScript bindings for a Substrate runtime.
+pub struct TypeScriptGenerator {
+    metadata: Metadata,
+    metadata_hash: [u8; 32],
+}
+
+impl TypeScriptGenerator {
+    /// Create a new TypeScript generator from the provided metadata.
+    pub fn new(mut metadata: Metadata) -> Self {
+        scale_typegen::utils::ensure_unique_type_paths(metadata.types_mut())
+            .expect("Duplicate type paths in metadata; this is bug please file an issue.");
+        let metadata_hash = metadata.hasher().hash();
+        TypeScriptGenerator {
+            metadata,
+            metadata_hash,
+        }
+    }
+
+    /// Use the given hash for `METADATA_HASH` rather than the hash of the metadata that we
+    /// were given. See `RuntimeGenerator::with_metadata_hash()`.
+    pub fn with_metadata_hash(mut self, metadata_hash: [u8; 32]) -> Self {
+        self.metadata_hash = metadata_hash;
+        self
+    }
+
+    /// Generate the TypeScript bindings.
+    ///
+    /// # Arguments
+    ///
+    /// * `should_gen_docs` - True if the bindings contain the documentation from the metadata.
+    /// * `runtime_types_only` - True if only the `runtime_types` should be generated.
+    pub fn generate(
+        &self,
+        should_gen_docs: bool,
+        runtime_types_only: bool,
+    ) -> Result<String, CodegenError> {
+        let types = TypeScriptTypes::new(self.metadata.types());
+        let mut out = Writer::default();
+        out.line(
+            "// This file was generated from runtime metadata by subxt-codegen; do not edit it.",
+        );
+        out.line("//");
+        out.line(
+            "// Types describe the JSON representation of SCALE values used by `scale_value`.",
+        );
+
+        if !runtime_types_only {
+            self.write_api(&mut out, &types, should_gen_docs)?;
+        }
+
+        out.blank();
+        types.write_runtime_types(&mut out, should_gen_docs);
+        Ok(out.finish())
+    }
+
+    fn write_api(
+        &self,
+        out: &mut Writer,
+        types: &TypeScriptTypes,
+        should_gen_docs: bool,
+    ) -> Result<(), CodegenError> {
+        let pallet_names: Vec<_> = self.metadata.pallets().map(|p| p.name()).collect();
+        let runtime_api_names: Vec<_> = self
+            .metadata
+            .runtime_api_traits()
+            .map(|api| api.name())
+            .collect();
+
+        out.blank();
+        out.line("/** The pallets that this code was generated from. */");
+        out.line(&format!(
+            "export const PALLETS = {} as const;",
+            string_array(&pallet_names)
+        ));
+        out.blank();
+        out.line("/** The runtime APIs that this code was generated from. */");
+        out.line(&format!(
+            "export const RUNTIME_APIS = {} as const;",
+            string_array(&runtime_api_names)
+        ));
+        out.blank();
+        out.line("/**");
+        out.line(
+            " * The hash of the metadata that this code was generated from. A node's metadata is",
+        );
+        out.line(" * compatible if hashing just `PALLETS` and `RUNTIME_APIS` from it gives the same result.");
+        out.line(" */");
+        out.line(&format!(
+            "export const METADATA_HASH = {};",
+            json_string(&hex(&self.metadata_hash))
+        ));
+        out.blank();
+        out.lines(DESCRIPTORS);
+
+        let mut tx = Vec::new();
+        let mut events = Vec::new();
+        let mut storage = Vec::new();
+        let mut constants = Vec::new();
+        for pallet in self.metadata.pallets() {
+            let mod_name = ident(&pallet.name().to_snake_case());
+            out.blank();
+            out.open(&format!("export namespace {mod_name}"));
+            if write_calls(out, types, &pallet, should_gen_docs)? {
+                tx.push(mod_name.clone());
+            }
+            if write_events(out, types, &pallet, should_gen_docs)? {
+                events.push(mod_name.clone());
+            }
+            if write_storage(out, types, &pallet, should_gen_docs)? {
+                storage.push(mod_name.clone());
+            }
+            if write_constants(out, types, &pallet, should_gen_docs)? {
+                constants.push(mod_name.clone());
+            }
+            out.close();
+        }
+
+        // Mirror `tx()`, `storage()` and `constants()` from the Rust interface, and group the
+        // events in the same way.
+        for (name, mods, inner) in [
+            ("tx", tx, "calls"),
+            ("events", events, "events"),
+            ("storage", storage, "storage"),
+            ("constants", constants, "constants"),
+        ] {
+            out.blank();
+            out.open(&format!("export const {name} ="));
+            for mod_name in mods {
+                out.line(&format!("{mod_name}: {mod_name}.{inner},"));
+            }
+            out.close();
+            out.append_to_last_line(";");
+        }
+        Ok(())
+    }
+}
+
+/// Write the calls of some pallet, returning false if it has none.
+fn write_calls(
+    out: &mut Writer,
+    types: &TypeScriptTypes,
+    pallet: &PalletMetadata,
+    should_gen_docs: bool,
+) -> Result<bool, CodegenError> {
+    let Some(variants) = pallet.call_variants() else {
+        return Ok(false);
+    };
+
+    out.open("export namespace calls");
+    out.open("export namespace types");
+    for variant in variants {
+        let type_name = ident(&variant.name.to_upper_camel_case());
+        out.line(&format!(
+            "export type {type_name} = {};",
+            types.fields(&variant.fields)
+        ));
+    }
+    out.close();
+    for variant in variants {
+        let Some(hash) = pallet.call_hash(&variant.name) else {
+            return Err(CodegenError::MissingCallMetadata(
+                pallet.name().into(),
+                variant.name.clone(),
+            ));
+        };
+        let type_name = ident(&variant.name.to_upper_camel_case());
+        if should_gen_docs {
+            docs(out, &variant.docs);
+        }
+        out.line(&format!(
+            "export const {}: CallDescriptor<types.{type_name}> = {};",
+            ident(&variant.name.to_snake_case()),
+            descriptor(pallet.name(), &variant.name, &hash)
+        ));
+    }
+    out.close();
+    Ok(true)
+}
+
+/// Write the events of some pallet, returning false if it has none.
+fn write_events(
+    out: &mut Writer,
+    types: &TypeScriptTypes,
+    pallet: &PalletMetadata,
+    should_gen_docs: bool,
+) -> Result<bool, CodegenError> {
+    let Some(variants) = pallet.event_variants() else {
+        return Ok(false);
+    };
+
+    out.open("export namespace events");
+    for variant in variants {
+        let Some(hash) = pallet.event_hash(&variant.name) else {
+            return Err(CodegenError::Other(format!(
+                "Metadata for event {}_{} could not be found",
+                pallet.name(),
+                variant.name
+            )));
+        };
+        let name = ident(&variant.name);
+        if should_gen_docs {
+            docs(out, &variant.docs);
+        }
+        out.line(&format!(
+            "export type {name} = {};",
+            types.fields(&variant.fields)
+        ));
+        out.line(&format!(
+            "export const {name}: EventDescriptor<{name}> = {};",
+            descriptor(pallet.name(), &variant.name, &hash)
+        ));
+    }
+    out.close();
+    Ok(true)
+}
+
+/// Write the storage entries of some pallet, returning false if it has none.
+fn write_storage(
+    out: &mut Writer,
+    types: &TypeScriptTypes,
+    pallet: &PalletMetadata,
+    should_gen_docs: bool,
+) -> Result<bool, CodegenError> {
+    let Some(storage) = pallet.storage() else {
+        return Ok(false);
+    };
+
+    out.open("export namespace storage");
+    for entry in storage.entries() {
+        let Some(hash) = pallet.storage_hash(entry.name()) else {
+            return Err(CodegenError::MissingStorageMetadata(
+                pallet.name().into(),
+                entry.name().into(),
+            ));
+        };
+        let keys: Vec<String> = match entry.entry_type() {
+            StorageEntryType::Plain(_) => Vec::new(),
+            StorageEntryType::Map { key_ty, .. } => types.key_refs(*key_ty),
+        };
+        if should_gen_docs {
+            docs(out, entry.docs());
+        }
+        out.line(&format!(
+            "export const {}: StorageDescriptor<[{}], {}> = {};",
+            ident(&entry.name().to_snake_case()),
+            keys.join(", "),
+            types.type_ref(entry.entry_type().value_ty()),
+            descriptor(pallet.name(), entry.name(), &hash)
+        ));
+    }
+    out.close();
+    Ok(true)
+}
+
+/// Write the constants of some pallet, returning false if it has none.
+fn write_constants(
+    out: &mut Writer,
+    types: &TypeScriptTypes,
+    pallet: &PalletMetadata,
+    should_gen_docs: bool,
+) -> Result<bool, CodegenError> {
+    if pallet.constants().next().is_none() {
+        return Ok(false);
+    }
+
+    out.open("export namespace constants");
+    for constant in pallet.constants() {
+        let Some(hash) = pallet.constant_hash(constant.name()) else {
+            return Err(CodegenError::MissingConstantMetadata(
+                pallet.name().into(),
+                constant.name().into(),
+            ));
+        };
+        if should_gen_docs {
+            docs(out, constant.docs());
+        }
+        out.line(&format!(
+            "export const {}: ConstantDescriptor<{}> = {};",
+            ident(&constant.name().to_snake_case()),
+            types.type_ref(constant.ty()),
+            descriptor(pallet.name(), constant.name(), &hash)
+        ));
+    }
+    out.close();
+    Ok(true)
+}
+
+fn descriptor(pallet_name: &str, name: &str, hash: &[u8]) -> String {
+    format!(
+        "{{ pallet: {}, name: {}, hash: {} }}",
+        json_string(pallet_name),
+        json_string(name),
+        json_string(&hex(hash))
+    )
+}
+
+fn string_array(items: &[&str]) -> String {
+    let items: Vec<_> = items.iter().map(|item| json_string(item)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    let mut out = String::from("0x");
+    for byte in bytes {
+        let _ = write!(out, "{byte:02x}");
+    }
+    out
+}
+
+/// Write some documentation as a doc comment.
+fn docs(out: &mut Writer, docs: &[String]) {
+    if docs.is_empty() {
+        return;
+    }
+    out.line("/**");
+    for line in docs {
+        let line = line.replace("*/", "*\\/");
+        out.line(format!(" *{}", line).trim_end());
+    }
+    out.line(" */");
+}
+
+/// Make some name usable as a TypeScript identifier.
+fn ident(name: &str) -> String {
+    const RESERVED: &[&str] = &[
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "debugger",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "enum",
+        "export",
+        "extends",
+        "false",
+        "finally",
+        "for",
+        "function",
+        "if",
+        "import",
+        "in",
+        "instanceof",
+        "new",
+        "null",
+        "return",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "typeof",
+        "var",
+        "void",
+        "while",
+        "with",
+    ];
+    if RESERVED.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_owned()
+    }
+}
+
+/// Accumulates indented lines of TypeScript.
+#[derive(Default)]
+struct Writer {
+    lines: Vec<String>,
+    indent: usize,
+}
+
+impl Writer {
+    fn line(&mut self, line: &str) {
+        self.lines
+            .push(format!("{}{line}", "    ".repeat(self.indent)));
+    }
+
+    /// Write some lines which are already indented relative to the current indentation.
+    fn lines(&mut self, lines: &str) {
+        for line in lines.lines() {
+            if line.is_empty() {
+                self.blank();
+            } else {
+                self.line(line);
+            }
+        }
+    }
+
+    fn blank(&mut self) {
+        self.lines.push(String::new());
+    }
+
+    fn open(&mut self, header: &str) {
+        self.line(&format!("{header} {{"));
+        self.indent += 1;
+    }
+
+    fn close(&mut self) {
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    fn append_to_last_line(&mut self, s: &str) {
+        if let Some(line) = self.lines.last_mut() {
+            line.push_str(s);
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut out = self.lines.join("\n");
+        out.push('\n');
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use frame_metadata::v15;
+    use scale_info::meta_type;
+
+    fn metadata() -> Metadata {
+        let storage = v15::PalletStorageMetadata {
+            prefix: "Test",
+            entries: vec![v15::StorageEntryMetadata {
+                name: "Values",
+                modifier: v15::StorageEntryModifier::Optional,
+                ty: v15::StorageEntryType::Map {
+                    hashers: vec![v15::StorageHasher::Blake2_128Concat],
+                    key: meta_type::<(u32, bool)>(),
+                    value: meta_type::<Vec<u8>>(),
+                },
+                default: vec![0],
+                docs: vec![],
+            }],
+        };
+        let pallet = v15::PalletMetadata {
+            name: "Test",
+            storage: Some(storage),
+            calls: Some(v15::PalletCallMetadata {
+                ty: meta_type::<Option<u64>>(),
+            }),
+            event: None,
+            constants: vec![],
+            error: None,
+            index: 0,
+            docs: vec![],
+        };
+
+        v15::RuntimeMetadataV15::new(
+            vec![pallet],
+            v15::ExtrinsicMetadata {
+                version: 0,
+                signed_extensions: vec![],
+                address_ty: meta_type::<()>(),
+                call_ty: meta_type::<()>(),
+                signature_ty: meta_type::<()>(),
+                extra_ty: meta_type::<()>(),
+            },
+            meta_type::<()>(),
+            vec![],
+            v15::OuterEnums {
+                call_enum_ty: meta_type::<()>(),
+                event_enum_ty: meta_type::<()>(),
+                error_enum_ty: meta_type::<()>(),
+            },
+            v15::CustomMetadata {
+                map: Default::default(),
+            },
+        )
+        .try_into()
+        .expect("can build valid metadata")
+    }
+
+    #[test]
+    fn generates_descriptors_and_types() {
+        let metadata = metadata();
+        let call_hash = hex(&metadata
+            .pallet_by_name("Test")
+            .unwrap()
+            .call_hash("Some")
+            .unwrap());
+        let code = TypeScriptGenerator::new(metadata)
+            .generate(false, false)
+            .expect("should be able to generate bindings");
+
+        assert!(code.contains("export const PALLETS = [\"Test\"] as const;"));
+        assert!(code.contains("export type Some = [number];"));
+        assert!(code.contains(&format!(
+            "export const some: CallDescriptor<types.Some> = {{ pallet: \"Test\", name: \"Some\", hash: \"{call_hash}\" }};"
+        )));
+        assert!(code
+            .contains("export const values: StorageDescriptor<[number, boolean], Array<number>>"));
+        assert!(code.contains("tx = {\n    test: test.calls,\n};"));
+        assert!(code.contains(
+            "export namespace runtime_types {\n    export type Option<T> =\n        | { name: \"None\"; values: [] }\n        | { name: \"Some\"; values: [T] };\n}"
+        ));
+    }
+
+    #[test]
+    fn only_runtime_types() {
+        let code = TypeScriptGenerator::new(metadata())
+            .generate(false, true)
+            .expect("should be able to generate bindings");
+
+        assert!(!code.contains("PALLETS"));
+        assert!(code.contains("export namespace runtime_types"));
+    }
+}
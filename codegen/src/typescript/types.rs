@@ -0,0 +1,117 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::{docs, ident, Writer};
+use scale_info::{form::PortableForm, Field, PortableRegistry, Type, TypeDef};
+use std::collections::BTreeMap;
+use subxt_metadata::TypeScriptRenderer;
+
+/// Renders references to, and definitions of, the types in a registry.
+///
+/// Types are rendered by the same [`TypeScriptRenderer`] that backs
+/// [`subxt_metadata::Metadata::render_type()`], with named types referred to by their path
+/// within `runtime_types`.
+pub struct TypeScriptTypes<'a> {
+    types: &'a PortableRegistry,
+    renderer: TypeScriptRenderer<'a>,
+}
+
+impl<'a> TypeScriptTypes<'a> {
+    pub fn new(types: &'a PortableRegistry) -> Self {
+        let renderer = TypeScriptRenderer::new(types, |_, ty| {
+            if !is_named_def(ty) {
+                return None;
+            }
+            let path: Vec<_> = ty.path.segments.iter().map(|s| ident(s)).collect();
+            Some(format!("runtime_types.{}", path.join(".")))
+        });
+        TypeScriptTypes { types, renderer }
+    }
+
+    /// A reference to some type, relative to the root of the generated file.
+    pub fn type_ref(&self, id: u32) -> String {
+        self.renderer.expr(id)
+    }
+
+    /// References to the keys of some storage map. As in the Rust interface, a map keyed by a
+    /// tuple is treated as having one key per tuple field.
+    pub fn key_refs(&self, key_ty: u32) -> Vec<String> {
+        let ty = self
+            .types
+            .resolve(key_ty)
+            .expect("type IDs in the metadata are valid; qed");
+        match &ty.type_def {
+            TypeDef::Tuple(tuple) => tuple.fields.iter().map(|f| self.type_ref(f.id)).collect(),
+            _ => vec![self.type_ref(key_ty)],
+        }
+    }
+
+    /// The fields of some struct or enum variant: an object if all fields are named, and an
+    /// array otherwise.
+    pub fn fields(&self, fields: &[Field<PortableForm>]) -> String {
+        self.renderer.fields(fields)
+    }
+
+    /// Write out a `runtime_types` namespace containing one definition for every named type.
+    pub fn write_runtime_types(&self, out: &mut Writer, should_gen_docs: bool) {
+        // Types are grouped into nested namespaces by their path. Generic types appear once per
+        // instance in the registry but only need defining once, which `ensure_unique_type_paths`
+        // makes sure is safe to do.
+        let mut root = Namespace::default();
+        for ty in self.types.types.iter() {
+            let ty = &ty.ty;
+            if !is_named_def(ty) {
+                continue;
+            }
+            let (name, namespaces) = ty
+                .path
+                .segments
+                .split_last()
+                .expect("named types have a path; qed");
+            let namespace = namespaces.iter().fold(&mut root, |ns, segment| {
+                ns.children.entry(ident(segment)).or_default()
+            });
+            let name = ident(name);
+            if namespace.types.contains_key(&name) {
+                continue;
+            }
+            let mut def = Writer::default();
+            if should_gen_docs {
+                docs(&mut def, &ty.docs);
+            }
+            def.lines(&self.renderer.definition(&name, ty));
+            namespace.types.insert(name, def.finish());
+        }
+
+        out.open("export namespace runtime_types");
+        root.write(out);
+        out.close();
+    }
+}
+
+/// A namespace in the generated `runtime_types`.
+#[derive(Default)]
+struct Namespace {
+    children: BTreeMap<String, Namespace>,
+    types: BTreeMap<String, String>,
+}
+
+impl Namespace {
+    fn write(&self, out: &mut Writer) {
+        for (name, child) in &self.children {
+            out.open(&format!("export namespace {name}"));
+            child.write(out);
+            out.close();
+        }
+        for def in self.types.values() {
+            out.lines(def);
+        }
+    }
+}
+
+/// Named structs and enums get a definition in `runtime_types`; everything else is inlined.
+fn is_named_def(ty: &Type<PortableForm>) -> bool {
+    !ty.path.segments.is_empty()
+        && matches!(ty.type_def, TypeDef::Composite(_) | TypeDef::Variant(_))
+}
@@ -7,6 +7,7 @@ use crate::blocks::extrinsic_signed_extensions::ExtrinsicSignedExtensions;
 use crate::{
     config::{Config, Hasher},
     error::{Error, MetadataError},
+    utils::strip_compact_prefix,
     Metadata,
 };
 use alloc::sync::Arc;
@@ -163,22 +164,43 @@ where
         T::Hasher::hash(self.bytes())
     }
 
-    /// Is the extrinsic signed?
+    /// Is the extrinsic signed? This is only true for version 4 signed extrinsics; version 5
+    /// "general" extrinsics carry any signature in their transaction extensions instead.
     pub fn is_signed(&self) -> bool {
         self.decoded_info().is_signed()
     }
 
+    /// The version of the extrinsic format that this extrinsic is encoded in (4 or 5).
+    pub fn version(&self) -> u8 {
+        self.preamble()[0] & 0b0011_1111
+    }
+
+    /// Is the extrinsic a version 5 "general" extrinsic? These carry transaction extensions
+    /// (see [`Self::signed_extensions()`]) but, unlike version 4 signed extrinsics, no address
+    /// or signature of their own.
+    pub fn is_general(&self) -> bool {
+        self.preamble()[0] & 0b1100_0000 == 0b0100_0000
+    }
+
+    /// The version of the transaction extensions used in this extrinsic, if it's a version 5
+    /// "general" extrinsic.
+    pub fn transaction_extensions_version(&self) -> Option<u8> {
+        self.is_general().then(|| self.preamble()[1])
+    }
+
     /// The index of the extrinsic in the block.
     pub fn index(&self) -> u32 {
         self.index
     }
 
     /// Return _all_ of the bytes representing this extrinsic, which include, in order:
-    /// - First byte: abbbbbbb (a = 0 for unsigned, 1 for signed, b = version)
+    /// - Compact encoded length of the rest of the bytes
+    /// - First byte: aabbbbbb (a = 00 for unsigned/bare, 10 for signed, 01 for general, b = version)
     /// - SignatureType (if the payload is signed)
     ///   - Address
     ///   - Signature
     ///   - Extra fields
+    /// - Transaction extensions version and extra fields (if the payload is general)
     /// - Extrinsic call bytes
     pub fn bytes(&self) -> &[u8] {
         &self.ext.1
@@ -231,14 +253,14 @@ where
     /// These bytes are the scale encoded `extra` fields of each signed extension in order of the signed extensions.
     /// They do *not* include the `additional` signed bytes that are used as part of the payload that is signed.
     ///
-    /// Note: Returns `None` if the extrinsic is not signed.
+    /// Note: Returns `None` if the extrinsic is neither signed nor general.
     pub fn signed_extensions_bytes(&self) -> Option<&[u8]> {
         self.decoded_info()
             .transaction_extension_payload()
             .map(|t| &self.bytes()[t.range()])
     }
 
-    /// Returns `None` if the extrinsic is not signed.
+    /// Returns `None` if the extrinsic is neither signed nor general.
     pub fn signed_extensions(&self) -> Option<ExtrinsicSignedExtensions<'_, T>> {
         self.decoded_info()
             .transaction_extension_payload()
@@ -267,7 +289,9 @@ where
 
     /// Fetch the metadata for this extrinsic.
     pub fn extrinsic_metadata(&self) -> Result<ExtrinsicMetadataDetails, Error> {
-        let pallet = self.metadata.pallet_by_index_err(self.pallet_index())?;
+        let pallet = self
+            .metadata
+            .pallet_by_call_index_err(self.pallet_index())?;
         let variant = pallet
             .call_variant_by_index(self.variant_index())
             .ok_or_else(|| MetadataError::VariantIndexNotFound(self.variant_index()))?;
@@ -328,6 +352,14 @@ where
     fn decoded_info(&self) -> &Extrinsic<'static, u32> {
         &self.ext.0
     }
+
+    // The bytes following the compact encoded length, which begin with the byte denoting
+    // the extrinsic version and type.
+    fn preamble(&self) -> &[u8] {
+        // Note: this cannot panic because we checked that the extrinsic bytes decode.
+        let (_, bytes) = strip_compact_prefix(self.bytes()).expect("extrinsic has a length prefix");
+        bytes
+    }
 }
 
 /// A Static Extrinsic found in a block coupled with it's details.
@@ -351,7 +383,7 @@ mod tests {
     use super::*;
     use crate::config::SubstrateConfig;
     use assert_matches::assert_matches;
-    use codec::{Decode, Encode};
+    use codec::{Compact, Decode, Encode};
     use frame_metadata::v15::{CustomMetadata, OuterEnums};
     use frame_metadata::{
         v15::{ExtrinsicMetadata, PalletCallMetadata, PalletMetadata, RuntimeMetadataV15},
@@ -436,6 +468,11 @@ mod tests {
 
     /// Build fake metadata consisting the types needed to represent an extrinsic.
     fn metadata() -> Metadata {
+        metadata_with_extrinsic_version(4)
+    }
+
+    /// Build fake metadata which advertises the given extrinsic version.
+    fn metadata_with_extrinsic_version(version: u8) -> Metadata {
         let pallets = vec![PalletMetadata {
             name: "Test",
             storage: None,
@@ -450,7 +487,7 @@ mod tests {
         }];
 
         let extrinsic = ExtrinsicMetadata {
-            version: 4,
+            version,
             signed_extensions: vec![],
             address_ty: meta_type::<()>(),
             call_ty: meta_type::<RuntimeCall>(),
@@ -562,6 +599,58 @@ mod tests {
         assert_eq!(tx_encoded.hash(), extrinsic.hash(), "hashes should eq");
     }
 
+    #[test]
+    fn decode_extrinsics_with_legacy_metadata() {
+        use crate::test_utils::{legacy_metadata, LEGACY_SIGNED_EXTENSIONS};
+
+        // Before V12 metadata, pallets are indexed by their position amongst pallets with calls.
+        for (version, balances_index) in [(11, 0), (12, 5)] {
+            let metadata = legacy_metadata(version);
+
+            // Balances.transfer(dest, value):
+            let mut call = vec![balances_index, 0];
+            ([2u8; 32], Compact(1000u128)).encode_to(&mut call);
+
+            let mut unsigned = vec![4];
+            unsigned.extend(&call);
+            // Signed by [1; 32] with an Sr25519 signature, an immortal era, a nonce of 3 and no tip.
+            let mut signed = vec![0x84];
+            [1u8; 32].encode_to(&mut signed);
+            signed.push(1);
+            signed.extend([0u8; 64]);
+            (0u8, Compact(3u32), Compact(0u128)).encode_to(&mut signed);
+            signed.extend(&call);
+
+            let extrinsics = Extrinsics::<SubstrateConfig>::decode_from(
+                vec![unsigned.encode(), signed.encode()],
+                metadata,
+            )
+            .expect("can decode extrinsics");
+
+            for extrinsic in extrinsics.iter() {
+                assert_eq!(extrinsic.pallet_index(), balances_index);
+                assert_eq!(extrinsic.pallet_name().unwrap(), "Balances");
+                assert_eq!(extrinsic.variant_name().unwrap(), "transfer");
+                let fields: Vec<_> = extrinsic
+                    .field_values()
+                    .unwrap()
+                    .into_values()
+                    .map(|value| value.remove_context())
+                    .collect();
+                assert_eq!(fields, [Value::from_bytes([2u8; 32]), Value::u128(1000)]);
+            }
+
+            let signed = extrinsics.iter().nth(1).unwrap();
+            assert!(signed.is_signed());
+            assert_eq!(signed.address_bytes(), Some(&[1u8; 32][..]));
+            let extensions = signed.signed_extensions().unwrap();
+            let names: Vec<_> = extensions.iter().map(|e| e.name()).collect();
+            assert_eq!(names, LEGACY_SIGNED_EXTENSIONS);
+            assert_eq!(extensions.nonce(), Some(3));
+            assert_eq!(extensions.tip(), Some(0));
+        }
+    }
+
     #[test]
     fn statically_decode_extrinsic() {
         let metadata = metadata();
@@ -637,4 +726,65 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn v5_bare_extrinsic() {
+        let metadata = metadata_with_extrinsic_version(5);
+
+        let tx = crate::dynamic::tx(
+            "Test",
+            "TestCall",
+            vec![
+                Value::u128(10),
+                Value::bool(true),
+                Value::string("SomeValue"),
+            ],
+        );
+        let tx_encoded = crate::tx::create_unsigned::<SubstrateConfig, _>(&tx, &metadata)
+            .expect("Valid dynamic parameters are provided");
+
+        let extrinsics = Extrinsics::<SubstrateConfig>::decode_from(
+            vec![tx_encoded.encoded().to_owned()],
+            metadata,
+        )
+        .expect("Valid extrinsic");
+
+        let extrinsic = extrinsics.iter().next().unwrap();
+
+        assert_eq!(extrinsic.version(), 5);
+        assert!(!extrinsic.is_signed());
+        assert!(!extrinsic.is_general());
+        assert_eq!(extrinsic.transaction_extensions_version(), None);
+        assert_eq!(extrinsic.variant_name().unwrap(), "TestCall");
+    }
+
+    #[test]
+    fn v5_general_extrinsic() {
+        let metadata = metadata_with_extrinsic_version(5);
+
+        let call = RuntimeCall::Test(Pallet::TestCall {
+            value: 10,
+            signed: true,
+            name: "SomeValue".into(),
+        });
+
+        // "is general" + version 5, then transaction extensions version 0 (and no extensions).
+        let mut inner = vec![0b01000101u8, 0u8];
+        call.encode_to(&mut inner);
+        let mut bytes = Vec::new();
+        Compact(inner.len() as u32).encode_to(&mut bytes);
+        bytes.extend(inner);
+
+        let extrinsics = Extrinsics::<SubstrateConfig>::decode_from(vec![bytes], metadata)
+            .expect("Valid extrinsic");
+
+        let extrinsic = extrinsics.iter().next().unwrap();
+
+        assert_eq!(extrinsic.version(), 5);
+        assert!(!extrinsic.is_signed());
+        assert!(extrinsic.is_general());
+        assert_eq!(extrinsic.transaction_extensions_version(), Some(0));
+        assert_eq!(extrinsic.signed_extensions_bytes(), Some(&[][..]));
+        assert_eq!(extrinsic.as_root_extrinsic::<RuntimeCall>().unwrap(), call);
+    }
 }
@@ -2,25 +2,32 @@
 // This file is dual-licensed as Apache-2.0 or GPL-3.0.
 // see LICENSE for license details.
 
-use super::signed_extensions::CheckNonceParams;
+use super::signed_extensions::{CheckMetadataHashParams, CheckNonceParams};
 use super::{signed_extensions, ExtrinsicParams};
 use super::{Config, Header};
+use alloc::string::String;
 
 /// The default [`super::ExtrinsicParams`] implementation understands common signed extensions
 /// and how to apply them to a given chain.
-pub type DefaultExtrinsicParams<T> = signed_extensions::AnyOf<
-    T,
-    (
-        signed_extensions::CheckSpecVersion,
-        signed_extensions::CheckTxVersion,
-        signed_extensions::CheckNonce,
-        signed_extensions::CheckGenesis<T>,
-        signed_extensions::CheckMortality<T>,
-        signed_extensions::ChargeAssetTxPayment<T>,
-        signed_extensions::ChargeTransactionPayment,
-        signed_extensions::CheckMetadataHash,
-    ),
->;
+pub type DefaultExtrinsicParams<T> = signed_extensions::AnyOf<T, DefaultSignedExtensions<T>>;
+
+/// Like [`DefaultExtrinsicParams`], but any signed extensions that it doesn't know about are
+/// encoded from values provided via [`signed_extensions::DynamicParams::extension()`] instead
+/// of causing an error. See [`signed_extensions::DynamicExtrinsicParams`].
+pub type DynamicDefaultExtrinsicParams<T> =
+    signed_extensions::DynamicExtrinsicParams<T, DefaultSignedExtensions<T>>;
+
+type DefaultSignedExtensions<T> = (
+    signed_extensions::CheckSpecVersion,
+    signed_extensions::CheckTxVersion,
+    signed_extensions::CheckNonce,
+    signed_extensions::CheckGenesis<T>,
+    signed_extensions::CheckMortality<T>,
+    signed_extensions::ChargeAssetTxPayment<T>,
+    signed_extensions::ChargeTransactionPayment,
+    signed_extensions::CheckMetadataHash,
+    signed_extensions::VerifySignature,
+);
 
 /// A builder that outputs the set of [`super::ExtrinsicParams::Params`] required for
 /// [`DefaultExtrinsicParams`]. This may expose methods that aren't applicable to the current
@@ -34,6 +41,8 @@ pub struct DefaultExtrinsicParamsBuilder<T: Config> {
     tip_of_asset_id: Option<T::AssetId>,
     tip: u128,
     tip_of: u128,
+    /// By default, no metadata hash is provided.
+    metadata_hash: CheckMetadataHashParams,
 }
 
 struct Mortality<Hash> {
@@ -54,6 +63,7 @@ impl<T: Config> Default for DefaultExtrinsicParamsBuilder<T> {
             tip_of: 0,
             tip_of_asset_id: None,
             nonce: None,
+            metadata_hash: CheckMetadataHashParams::disabled(),
         }
     }
 }
@@ -121,6 +131,22 @@ impl<T: Config> DefaultExtrinsicParamsBuilder<T> {
         self
     }
 
+    /// Provide the RFC-0078 metadata hash via the `CheckMetadataHash` signed extension, which is
+    /// required by some hardware wallets. The hash is computed from the metadata and spec version
+    /// of the client, along with the given decimals and symbol of the chain's native token. This is
+    /// not applicable on chains which don't use the `CheckMetadataHash` signed extension.
+    pub fn metadata_hash(mut self, decimals: u8, token_symbol: impl Into<String>) -> Self {
+        self.metadata_hash = CheckMetadataHashParams::enabled(decimals, token_symbol);
+        self
+    }
+
+    /// Provide a precomputed RFC-0078 metadata hash via the `CheckMetadataHash` signed extension.
+    /// See [`DefaultExtrinsicParamsBuilder::metadata_hash()`].
+    pub fn metadata_hash_of(mut self, hash: [u8; 32]) -> Self {
+        self.metadata_hash = CheckMetadataHashParams::hash(hash);
+        self
+    }
+
     /// Build the extrinsic parameters.
     pub fn build(self) -> <DefaultExtrinsicParams<T> as ExtrinsicParams<T>>::Params {
         let check_mortality_params = if let Some(mortality) = self.mortality {
@@ -152,6 +178,7 @@ impl<T: Config> DefaultExtrinsicParamsBuilder<T> {
             check_mortality_params,
             charge_asset_tx_params,
             charge_transaction_params,
+            self.metadata_hash,
             (),
         )
     }
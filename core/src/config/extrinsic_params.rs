@@ -25,8 +25,12 @@ pub trait ExtrinsicParams<T: Config>: ExtrinsicParamsEncoder + Sized + Send + 's
 }
 
 /// This trait is expected to be implemented for any [`ExtrinsicParams`], and
-/// defines how to encode the "additional" and "extra" params. Both functions
-/// are optional and will encode nothing by default.
+/// defines how to encode the "additional" and "extra" params. All functions
+/// are optional; `encode_extra_to` and `encode_additional_to` encode nothing by
+/// default, and the rest defer to them.
+///
+/// Signed extensions are known as "transaction extensions" in version 5 extrinsics, and
+/// the same implementations are used to encode both.
 pub trait ExtrinsicParamsEncoder: 'static {
     /// This is expected to SCALE encode the "signed extra" parameters
     /// to some buffer that has been provided. These are the parameters
@@ -39,4 +43,33 @@ pub trait ExtrinsicParamsEncoder: 'static {
     /// sent along with the transaction, but are taken into account when
     /// signing it, meaning the client and node must agree on their values.
     fn encode_additional_to(&self, _v: &mut Vec<u8>) {}
+
+    /// This is expected to SCALE encode the "signed extra" parameters which are
+    /// taken into account when signing a version 5 "general" transaction. Only the
+    /// extensions which come after `VerifySignature` are signed, and so a collection of
+    /// extensions should encode just those here. `VerifySignature` itself encodes nothing.
+    fn encode_signer_payload_extra_to(&self, v: &mut Vec<u8>) {
+        self.encode_extra_to(v)
+    }
+
+    /// This is expected to SCALE encode the "additional" parameters which are taken
+    /// into account when signing a version 5 "general" transaction. As with
+    /// [`ExtrinsicParamsEncoder::encode_signer_payload_extra_to()`], only the extensions
+    /// which come after `VerifySignature` are signed.
+    fn encode_signer_payload_additional_to(&self, v: &mut Vec<u8>) {
+        self.encode_additional_to(v)
+    }
+
+    /// This is expected to SCALE encode the "signed extra" parameters which are sent
+    /// along with a version 5 "general" transaction, given the SCALE encoded account ID
+    /// of the signer and the signature that they produced. Extensions which carry the
+    /// signature (like `VerifySignature`) include them here.
+    fn encode_extra_with_signature_to(
+        &self,
+        _account_id: &[u8],
+        _signature: &[u8],
+        v: &mut Vec<u8>,
+    ) {
+        self.encode_extra_to(v)
+    }
 }
@@ -23,7 +23,9 @@ use scale_decode::DecodeAsType;
 use scale_encode::EncodeAsType;
 use serde::{de::DeserializeOwned, Serialize};
 
-pub use default_extrinsic_params::{DefaultExtrinsicParams, DefaultExtrinsicParamsBuilder};
+pub use default_extrinsic_params::{
+    DefaultExtrinsicParams, DefaultExtrinsicParamsBuilder, DynamicDefaultExtrinsicParams,
+};
 pub use extrinsic_params::{ExtrinsicParams, ExtrinsicParamsEncoder};
 pub use polkadot::{PolkadotConfig, PolkadotExtrinsicParams, PolkadotExtrinsicParamsBuilder};
 pub use refine_params::{RefineParams, RefineParamsData};
@@ -122,6 +124,9 @@ pub trait Header: Sized + Encode + Decode {
     /// Return the block number of this header.
     fn number(&self) -> Self::Number;
 
+    /// Return the hash of the parent block.
+    fn parent_hash(&self) -> <Self::Hasher as Hasher>::Output;
+
     /// Hash this header.
     fn hash(&self) -> <Self::Hasher as Hasher>::Output {
         Self::Hasher::hash_of(self)
@@ -144,6 +149,10 @@ cfg_substrate_compat! {
             fn number(&self) -> Self::Number {
                 *self.number()
             }
+
+            fn parent_hash(&self) -> <Self::Hasher as Hasher>::Output {
+                *self.parent_hash()
+            }
         }
 
         impl<T: sp_runtime::traits::Hash> Hasher for T {
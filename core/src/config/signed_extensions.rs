@@ -17,13 +17,18 @@ use crate::utils::Era;
 use crate::Config;
 use alloc::borrow::ToOwned;
 use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::vec::Vec;
 use codec::{Compact, Encode};
 use core::fmt::Debug;
 use derive_where::derive_where;
 use hashbrown::HashMap;
 use scale_decode::DecodeAsType;
+use scale_encode::EncodeAsType;
 use scale_info::PortableRegistry;
+use scale_value::Value;
+use subxt_metadata::{MetadataDigestInfo, SignedExtensionMetadata};
 
 /// A single [`SignedExtension`] has a unique name, but is otherwise the
 /// same as [`ExtrinsicParams`] in describing how to encode the extra and
@@ -42,30 +47,91 @@ pub trait SignedExtension<T: Config>: ExtrinsicParams<T> {
 
 /// The [`CheckMetadataHash`] signed extension.
 pub struct CheckMetadataHash {
-    // Eventually we might provide or calculate the metadata hash here,
-    // but for now we never provide a hash and so this is empty.
+    // `None` means that no hash is provided, and the chain won't check it.
+    hash: Option<[u8; 32]>,
 }
 
 impl<T: Config> ExtrinsicParams<T> for CheckMetadataHash {
-    type Params = ();
+    type Params = CheckMetadataHashParams;
 
-    fn new(_client: &ClientState<T>, _params: Self::Params) -> Result<Self, ExtrinsicParamsError> {
-        Ok(CheckMetadataHash {})
+    fn new(client: &ClientState<T>, params: Self::Params) -> Result<Self, ExtrinsicParamsError> {
+        let hash = match params.0 {
+            CheckMetadataHashParamsInner::Disabled => None,
+            CheckMetadataHashParamsInner::Hash(hash) => Some(hash),
+            CheckMetadataHashParamsInner::Compute {
+                decimals,
+                token_symbol,
+            } => {
+                let metadata = &client.metadata;
+                let hash = MetadataDigestInfo::from_metadata(
+                    metadata,
+                    client.runtime_version.spec_version,
+                    decimals,
+                    token_symbol,
+                )
+                .and_then(|info| metadata.metadata_digest_hash(&info))
+                .map_err(|e| ExtrinsicParamsError::Custom(Box::new(e)))?;
+                Some(hash)
+            }
+        };
+        Ok(CheckMetadataHash { hash })
     }
 }
 
 impl ExtrinsicParamsEncoder for CheckMetadataHash {
     fn encode_extra_to(&self, v: &mut Vec<u8>) {
-        // A single 0 byte in the TX payload indicates that the chain should
-        // _not_ expect any metadata hash to exist in the signer payload.
-        0u8.encode_to(v);
+        // A 0 byte in the TX payload indicates that the chain should _not_ expect
+        // any metadata hash to exist in the signer payload, and a 1 byte that it should.
+        let mode = if self.hash.is_some() { 1u8 } else { 0u8 };
+        mode.encode_to(v);
     }
     fn encode_additional_to(&self, v: &mut Vec<u8>) {
-        // We provide no metadata hash in the signer payload to align with the above.
-        None::<()>.encode_to(v);
+        // Provide the metadata hash in the signer payload (if any) to align with the above.
+        self.hash.encode_to(v);
+    }
+}
+
+/// Parameters to configure the [`CheckMetadataHash`] signed extension. By default,
+/// no metadata hash is provided and the chain will not check it.
+#[derive(Debug, Clone, Default)]
+pub struct CheckMetadataHashParams(CheckMetadataHashParamsInner);
+
+#[derive(Debug, Clone, Default)]
+enum CheckMetadataHashParamsInner {
+    #[default]
+    Disabled,
+    Hash([u8; 32]),
+    Compute {
+        decimals: u8,
+        token_symbol: String,
+    },
+}
+
+impl CheckMetadataHashParams {
+    /// Don't provide a metadata hash.
+    pub fn disabled() -> Self {
+        CheckMetadataHashParams(CheckMetadataHashParamsInner::Disabled)
+    }
+    /// Provide the RFC-0078 metadata hash, computing it from the client's metadata and spec
+    /// version along with the decimals and symbol of the chain's native token.
+    ///
+    /// Computing the hash involves merkleizing the metadata, which is relatively expensive. If
+    /// many transactions are being constructed, prefer to compute it once using
+    /// [`crate::Metadata::metadata_digest_hash()`] and provide it via [`Self::hash()`].
+    pub fn enabled(decimals: u8, token_symbol: impl Into<String>) -> Self {
+        CheckMetadataHashParams(CheckMetadataHashParamsInner::Compute {
+            decimals,
+            token_symbol: token_symbol.into(),
+        })
+    }
+    /// Provide a precomputed RFC-0078 metadata hash.
+    pub fn hash(hash: [u8; 32]) -> Self {
+        CheckMetadataHashParams(CheckMetadataHashParamsInner::Hash(hash))
     }
 }
 
+impl<T: Config> RefineParams<T> for CheckMetadataHashParams {}
+
 impl<T: Config> SignedExtension<T> for CheckMetadataHash {
     type Decoded = CheckMetadataHashMode;
     fn matches(identifier: &str, _type_id: u32, _types: &PortableRegistry) -> bool {
@@ -431,14 +497,191 @@ impl<T: Config> SignedExtension<T> for ChargeTransactionPayment {
     }
 }
 
+/// The [`VerifySignature`] transaction extension. This carries the signature of version 5
+/// "general" transactions, and is disabled in any other transactions.
+pub struct VerifySignature;
+
+impl<T: Config> ExtrinsicParams<T> for VerifySignature {
+    type Params = ();
+
+    fn new(_client: &ClientState<T>, _params: Self::Params) -> Result<Self, ExtrinsicParamsError> {
+        Ok(VerifySignature)
+    }
+}
+
+impl ExtrinsicParamsEncoder for VerifySignature {
+    fn encode_extra_to(&self, v: &mut Vec<u8>) {
+        // The index of the `VerifySignature::Disabled` variant.
+        1u8.encode_to(v);
+    }
+    fn encode_signer_payload_extra_to(&self, _v: &mut Vec<u8>) {
+        // The signature can't sign itself, so this is left out of the signer payload.
+    }
+    fn encode_signer_payload_additional_to(&self, _v: &mut Vec<u8>) {}
+    fn encode_extra_with_signature_to(&self, account_id: &[u8], signature: &[u8], v: &mut Vec<u8>) {
+        // The index of the `VerifySignature::Signed { signature, account }` variant.
+        0u8.encode_to(v);
+        v.extend_from_slice(signature);
+        v.extend_from_slice(account_id);
+    }
+}
+
+impl<T: Config> SignedExtension<T> for VerifySignature {
+    type Decoded = Value;
+    fn matches(identifier: &str, _type_id: u32, _types: &PortableRegistry) -> bool {
+        identifier == "VerifySignature"
+    }
+}
+
 /// This accepts a tuple of [`SignedExtension`]s, and will dynamically make use of whichever
 /// ones are actually required for the chain in the correct order, ignoring the rest. This
 /// is a sensible default, and allows for a single configuration to work across multiple chains.
 pub struct AnyOf<T, Params> {
     params: Vec<Box<dyn ExtrinsicParamsEncoder + Send + 'static>>,
+    // The position in `params` of the first extension after `VerifySignature`.
+    signer_payload_start: usize,
     _marker: core::marker::PhantomData<(T, Params)>,
 }
 
+/// This is like [`AnyOf`], except that any signed extensions required by the chain which
+/// are not in the tuple of known [`SignedExtension`]s are handled dynamically, using values
+/// provided via [`DynamicParams::extension()`]. If no values are provided for such an
+/// extension, then its types must be empty (ie encode to 0 bytes), else an
+/// [`ExtrinsicParamsError::UnknownSignedExtension`] error is returned.
+///
+/// This allows transactions to be signed for chains with custom signed extensions without
+/// needing to implement [`SignedExtension`] for them.
+pub struct DynamicExtrinsicParams<T, Params> {
+    params: Vec<Box<dyn ExtrinsicParamsEncoder + Send + 'static>>,
+    // The position in `params` of the first extension after `VerifySignature`.
+    signer_payload_start: usize,
+    _marker: core::marker::PhantomData<(T, Params)>,
+}
+
+/// The parameters for [`DynamicExtrinsicParams`]. These wrap the parameters for the known
+/// signed extensions, and optionally provide values for any other signed extensions.
+#[derive(Debug, Clone, Default)]
+pub struct DynamicParams<Params> {
+    known: Params,
+    values: BTreeMap<String, DynamicSignedExtensionValues>,
+}
+
+#[derive(Debug, Clone)]
+struct DynamicSignedExtensionValues {
+    extra: Value,
+    additional: Value,
+}
+
+impl<Params> DynamicParams<Params> {
+    /// Construct some new parameters, given the parameters for the known signed extensions.
+    pub fn new(known: Params) -> Self {
+        DynamicParams {
+            known,
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// Provide the `extra` and `additional` values to encode for the signed extension with
+    /// the given identifier. These are encoded according to the types given in the metadata.
+    /// Values provided here take precedence over any known [`SignedExtension`] implementation.
+    pub fn extension(
+        mut self,
+        identifier: impl Into<String>,
+        extra: Value,
+        additional: Value,
+    ) -> Self {
+        self.values.insert(
+            identifier.into(),
+            DynamicSignedExtensionValues { extra, additional },
+        );
+        self
+    }
+}
+
+impl<Params> From<Params> for DynamicParams<Params> {
+    fn from(known: Params) -> Self {
+        DynamicParams::new(known)
+    }
+}
+
+impl<T: Config, Params: RefineParams<T>> RefineParams<T> for DynamicParams<Params> {
+    fn refine(&mut self, data: &RefineParamsData<T>) {
+        self.known.refine(data)
+    }
+}
+
+/// A signed extension whose extra and additional bytes have been encoded from
+/// user provided values.
+struct DynamicSignedExtension {
+    extra: Vec<u8>,
+    additional: Vec<u8>,
+}
+
+impl DynamicSignedExtension {
+    fn new(
+        ext: &SignedExtensionMetadata,
+        values: Option<&DynamicSignedExtensionValues>,
+        types: &PortableRegistry,
+    ) -> Result<Self, ExtrinsicParamsError> {
+        let encode =
+            |value: Option<&Value>, type_id: u32| -> Result<Vec<u8>, ExtrinsicParamsError> {
+                let mut bytes = Vec::new();
+                match value {
+                    Some(value) => value
+                        .encode_as_type_to(type_id, types, &mut bytes)
+                        .map_err(|e| ExtrinsicParamsError::Custom(Box::new(e)))?,
+                    None if is_type_empty(type_id, types) => {}
+                    None => {
+                        return Err(ExtrinsicParamsError::UnknownSignedExtension(
+                            ext.identifier().to_owned(),
+                        ))
+                    }
+                }
+                Ok(bytes)
+            };
+
+        Ok(DynamicSignedExtension {
+            extra: encode(values.map(|v| &v.extra), ext.extra_ty())?,
+            additional: encode(values.map(|v| &v.additional), ext.additional_ty())?,
+        })
+    }
+}
+
+impl ExtrinsicParamsEncoder for DynamicSignedExtension {
+    fn encode_extra_to(&self, v: &mut Vec<u8>) {
+        v.extend_from_slice(&self.extra);
+    }
+    fn encode_additional_to(&self, v: &mut Vec<u8>) {
+        v.extend_from_slice(&self.additional);
+    }
+}
+
+// For each signed extension in the tuple, find the matching index in the metadata, if
+// there is one, and add it to a map with that index as the key.
+macro_rules! match_signed_extensions {
+    ($client:ident, $params:ident, $($ident:ident $index:tt),+) => {{
+        let metadata = &$client.metadata;
+        let types = metadata.types();
+        let mut exts_by_index = HashMap::new();
+        $({
+            for (idx, e) in metadata.extrinsic().signed_extensions().iter().enumerate() {
+                // Skip over any exts that have a match already:
+                if exts_by_index.contains_key(&idx) {
+                    continue
+                }
+                // Break and record as soon as we find a match:
+                if $ident::matches(e.identifier(), e.extra_ty(), types) {
+                    let ext = $ident::new($client, $params.$index)?;
+                    let boxed_ext: Box<dyn ExtrinsicParamsEncoder + Send + 'static> = Box::new(ext);
+                    exts_by_index.insert(idx, boxed_ext);
+                    break
+                }
+            }
+        })+
+        exts_by_index
+    }}
+}
+
 macro_rules! impl_tuples {
     ($($ident:ident $index:tt),+) => {
         // We do some magic when the tuple is wrapped in AnyOf. We
@@ -457,28 +700,11 @@ macro_rules! impl_tuples {
             ) -> Result<Self, ExtrinsicParamsError> {
                 let metadata = &client.metadata;
                 let types = metadata.types();
-
-                // For each signed extension in the tuple, find the matching index in the metadata, if
-                // there is one, and add it to a map with that index as the key.
-                let mut exts_by_index = HashMap::new();
-                $({
-                    for (idx, e) in metadata.extrinsic().signed_extensions().iter().enumerate() {
-                        // Skip over any exts that have a match already:
-                        if exts_by_index.contains_key(&idx) {
-                            continue
-                        }
-                        // Break and record as soon as we find a match:
-                        if $ident::matches(e.identifier(), e.extra_ty(), types) {
-                            let ext = $ident::new(client, params.$index)?;
-                            let boxed_ext: Box<dyn ExtrinsicParamsEncoder + Send + 'static> = Box::new(ext);
-                            exts_by_index.insert(idx, boxed_ext);
-                            break
-                        }
-                    }
-                })+
+                let mut exts_by_index = match_signed_extensions!(client, params, $($ident $index),+);
 
                 // Next, turn these into an ordered vec, erroring if we haven't matched on any exts yet.
                 let mut params = Vec::new();
+                let mut signer_payload_start = 0;
                 for (idx, e) in metadata.extrinsic().signed_extensions().iter().enumerate() {
                     let Some(ext) = exts_by_index.remove(&idx) else {
                         if is_type_empty(e.extra_ty(), types) {
@@ -487,11 +713,15 @@ macro_rules! impl_tuples {
                             return Err(ExtrinsicParamsError::UnknownSignedExtension(e.identifier().to_owned()));
                         }
                     };
+                    if e.identifier() == "VerifySignature" {
+                        signer_payload_start = params.len() + 1;
+                    }
                     params.push(ext);
                 }
 
                 Ok(AnyOf {
                     params,
+                    signer_payload_start,
                     _marker: core::marker::PhantomData
                 })
             }
@@ -512,6 +742,96 @@ macro_rules! impl_tuples {
                     ext.encode_additional_to(v);
                 }
             }
+            fn encode_signer_payload_extra_to(&self, v: &mut Vec<u8>) {
+                for ext in &self.params[self.signer_payload_start..] {
+                    ext.encode_signer_payload_extra_to(v);
+                }
+            }
+            fn encode_signer_payload_additional_to(&self, v: &mut Vec<u8>) {
+                for ext in &self.params[self.signer_payload_start..] {
+                    ext.encode_signer_payload_additional_to(v);
+                }
+            }
+            fn encode_extra_with_signature_to(&self, account_id: &[u8], signature: &[u8], v: &mut Vec<u8>) {
+                for ext in &self.params {
+                    ext.encode_extra_with_signature_to(account_id, signature, v);
+                }
+            }
+        }
+
+        // DynamicExtrinsicParams selects extensions in the same way as AnyOf, but falls
+        // back to encoding user provided values for any extensions it doesn't know about.
+        impl <T, $($ident),+> ExtrinsicParams<T> for DynamicExtrinsicParams<T, ($($ident,)+)>
+        where
+            T: Config,
+            $($ident: SignedExtension<T>,)+
+        {
+            type Params = DynamicParams<($($ident::Params,)+)>;
+
+            fn new(
+                client: &ClientState<T>,
+                params: Self::Params,
+            ) -> Result<Self, ExtrinsicParamsError> {
+                let metadata = &client.metadata;
+                let types = metadata.types();
+                let DynamicParams { known, values } = params;
+                let mut exts_by_index = match_signed_extensions!(client, known, $($ident $index),+);
+
+                // Next, turn these into an ordered vec, using any provided values in preference
+                // to the known extensions, and encoding the rest dynamically.
+                let mut params = Vec::new();
+                let mut signer_payload_start = 0;
+                for (idx, e) in metadata.extrinsic().signed_extensions().iter().enumerate() {
+                    let known_ext = exts_by_index.remove(&idx);
+                    let ext_values = values.get(e.identifier());
+                    let ext: Box<dyn ExtrinsicParamsEncoder + Send + 'static> = match (known_ext, ext_values) {
+                        (Some(ext), None) => ext,
+                        (_, ext_values) => Box::new(DynamicSignedExtension::new(e, ext_values, types)?),
+                    };
+                    if e.identifier() == "VerifySignature" {
+                        signer_payload_start = params.len() + 1;
+                    }
+                    params.push(ext);
+                }
+
+                Ok(DynamicExtrinsicParams {
+                    params,
+                    signer_payload_start,
+                    _marker: core::marker::PhantomData
+                })
+            }
+        }
+
+        impl <T, $($ident),+> ExtrinsicParamsEncoder for DynamicExtrinsicParams<T, ($($ident,)+)>
+        where
+            T: Config,
+            $($ident: SignedExtension<T>,)+
+        {
+            fn encode_extra_to(&self, v: &mut Vec<u8>) {
+                for ext in &self.params {
+                    ext.encode_extra_to(v);
+                }
+            }
+            fn encode_additional_to(&self, v: &mut Vec<u8>) {
+                for ext in &self.params {
+                    ext.encode_additional_to(v);
+                }
+            }
+            fn encode_signer_payload_extra_to(&self, v: &mut Vec<u8>) {
+                for ext in &self.params[self.signer_payload_start..] {
+                    ext.encode_signer_payload_extra_to(v);
+                }
+            }
+            fn encode_signer_payload_additional_to(&self, v: &mut Vec<u8>) {
+                for ext in &self.params[self.signer_payload_start..] {
+                    ext.encode_signer_payload_additional_to(v);
+                }
+            }
+            fn encode_extra_with_signature_to(&self, account_id: &[u8], signature: &[u8], v: &mut Vec<u8>) {
+                for ext in &self.params {
+                    ext.encode_extra_with_signature_to(account_id, signature, v);
+                }
+            }
         }
     }
 }
@@ -562,3 +882,207 @@ fn is_type_empty(type_id: u32, types: &scale_info::PortableRegistry) -> bool {
         | TypeDef::Primitive(_) => false,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{
+        DefaultExtrinsicParams, DefaultExtrinsicParamsBuilder, DynamicDefaultExtrinsicParams,
+        PolkadotConfig,
+    };
+    use crate::test_utils::{client_state, metadata_with_pallets};
+    use crate::tx;
+    use frame_metadata::v15;
+    use scale_info::{meta_type, MetaType, TypeInfo};
+    use scale_value::Value;
+
+    fn encode(ext: &CheckMetadataHash) -> (Vec<u8>, Vec<u8>) {
+        let (mut extra, mut additional) = (Vec::new(), Vec::new());
+        ext.encode_extra_to(&mut extra);
+        ext.encode_additional_to(&mut additional);
+        (extra, additional)
+    }
+
+    #[test]
+    fn check_metadata_hash_encodes_mode_and_hash() {
+        let state = client_state();
+
+        let disabled = <CheckMetadataHash as ExtrinsicParams<PolkadotConfig>>::new(
+            &state,
+            CheckMetadataHashParams::default(),
+        )
+        .unwrap();
+        assert_eq!(encode(&disabled), (vec![0], vec![0]));
+
+        let enabled = <CheckMetadataHash as ExtrinsicParams<PolkadotConfig>>::new(
+            &state,
+            CheckMetadataHashParams::enabled(10, "DOT"),
+        )
+        .unwrap();
+        let info = MetadataDigestInfo::from_metadata(&state.metadata, 1, 10, "DOT").unwrap();
+        let hash = state.metadata.metadata_digest_hash(&info).unwrap();
+        assert_eq!(encode(&enabled), (vec![1], Some(hash).encode()));
+    }
+
+    #[test]
+    fn metadata_proof_can_be_generated_for_transaction() {
+        let state = client_state();
+        let call = tx::payload::dynamic("System", "remark", vec![Value::from_bytes("Hello")]);
+        let params = DefaultExtrinsicParamsBuilder::<PolkadotConfig>::new()
+            .metadata_hash(10, "DOT")
+            .build();
+        let partial = tx::create_partial_signed(&call, &state, params).unwrap();
+
+        // The metadata hash is signed, as part of the additional params of `CheckMetadataHash`.
+        let info = MetadataDigestInfo::from_metadata(&state.metadata, 1, 10, "DOT").unwrap();
+        let hash = state.metadata.metadata_digest_hash(&info).unwrap();
+        assert_eq!(
+            hex::encode(hash),
+            "9394b5339c47068383ee4a3fcdebdad817fc67accb3b3795b601cfd90f7f6522"
+        );
+        let signer_payload = partial.signer_payload();
+        let signed_hash = Some(hash).encode();
+        assert!(signer_payload
+            .windows(signed_hash.len())
+            .any(|bytes| bytes == signed_hash));
+
+        let proof = partial.metadata_proof(&state.metadata).unwrap();
+        assert!(!proof.leaves.is_empty());
+        assert_eq!(proof.leaves.len(), proof.leaf_indices.len());
+    }
+
+    fn encode_params<E: ExtrinsicParamsEncoder>(ext: &E) -> (Vec<u8>, Vec<u8>) {
+        let (mut extra, mut additional) = (Vec::new(), Vec::new());
+        ext.encode_extra_to(&mut extra);
+        ext.encode_additional_to(&mut additional);
+        (extra, additional)
+    }
+
+    #[test]
+    fn dynamic_extrinsic_params_match_default_when_all_known() {
+        let state = client_state();
+        let params = || {
+            DefaultExtrinsicParamsBuilder::<PolkadotConfig>::new()
+                .tip(1)
+                .build()
+        };
+
+        let default = DefaultExtrinsicParams::<PolkadotConfig>::new(&state, params()).unwrap();
+        let dynamic =
+            DynamicDefaultExtrinsicParams::<PolkadotConfig>::new(&state, params().into()).unwrap();
+        assert_eq!(encode_params(&default), encode_params(&dynamic));
+    }
+
+    #[test]
+    fn dynamic_extrinsic_params_encode_provided_values() {
+        let state = client_state();
+        let params = || DefaultExtrinsicParamsBuilder::<PolkadotConfig>::new().build();
+
+        // Provide values for an extension in place of the known implementation:
+        let dynamic = DynamicDefaultExtrinsicParams::<PolkadotConfig>::new(
+            &state,
+            DynamicParams::new(params()).extension(
+                "CheckSpecVersion",
+                Value::unnamed_composite([]),
+                Value::u128(1234),
+            ),
+        )
+        .unwrap();
+        let default = DefaultExtrinsicParams::<PolkadotConfig>::new(&state, params()).unwrap();
+
+        let (dynamic_extra, dynamic_additional) = encode_params(&dynamic);
+        let (default_extra, default_additional) = encode_params(&default);
+        assert_eq!(dynamic_extra, default_extra);
+        assert_ne!(dynamic_additional, default_additional);
+        assert!(dynamic_additional.starts_with(&1234u32.encode()));
+    }
+
+    #[allow(unused)]
+    #[derive(TypeInfo)]
+    enum RuntimeCall {
+        Test(Call),
+    }
+
+    #[allow(unused, non_camel_case_types)]
+    #[derive(TypeInfo)]
+    enum Call {
+        remark { remark: Vec<u8> },
+    }
+
+    // A chain whose only signed extensions are the given custom ones, each of which is
+    // described by its identifier and its extra and additional types.
+    fn custom_client_state(
+        signed_extensions: Vec<(&'static str, MetaType, MetaType)>,
+    ) -> ClientState<PolkadotConfig> {
+        let signed_extensions = signed_extensions
+            .into_iter()
+            .map(
+                |(identifier, ty, additional_signed)| v15::SignedExtensionMetadata {
+                    identifier,
+                    ty,
+                    additional_signed,
+                },
+            )
+            .collect();
+        ClientState {
+            metadata: metadata_with_pallets::<RuntimeCall>(
+                vec![("Test", meta_type::<Call>())],
+                4,
+                signed_extensions,
+            ),
+            ..client_state()
+        }
+    }
+
+    fn dynamic_params(
+        state: &ClientState<PolkadotConfig>,
+        params: DynamicParams<
+            <DefaultExtrinsicParams<PolkadotConfig> as ExtrinsicParams<PolkadotConfig>>::Params,
+        >,
+    ) -> Result<DynamicDefaultExtrinsicParams<PolkadotConfig>, ExtrinsicParamsError> {
+        DynamicDefaultExtrinsicParams::<PolkadotConfig>::new(state, params)
+    }
+
+    #[test]
+    fn dynamic_extrinsic_params_encode_custom_extension_values() {
+        let state = custom_client_state(vec![
+            ("CheckFoo", meta_type::<u32>(), meta_type::<(bool, u16)>()),
+            ("CheckBar", meta_type::<()>(), meta_type::<()>()),
+        ]);
+        let params = DynamicParams::new(DefaultExtrinsicParamsBuilder::new().build()).extension(
+            "CheckFoo",
+            Value::u128(7),
+            Value::unnamed_composite([Value::bool(true), Value::u128(258)]),
+        );
+
+        let dynamic = dynamic_params(&state, params).unwrap();
+        assert_eq!(encode_params(&dynamic), (vec![7, 0, 0, 0], vec![1, 2, 1]));
+    }
+
+    #[test]
+    fn dynamic_extrinsic_params_encode_nothing_for_empty_custom_extensions() {
+        let state = custom_client_state(vec![(
+            "CheckBar",
+            meta_type::<()>(),
+            meta_type::<((), [u8; 0])>(),
+        )]);
+        let params = DefaultExtrinsicParamsBuilder::new().build().into();
+
+        let dynamic = dynamic_params(&state, params).unwrap();
+        assert_eq!(encode_params(&dynamic), (vec![], vec![]));
+    }
+
+    #[test]
+    fn dynamic_extrinsic_params_need_values_for_non_empty_custom_extensions() {
+        let state = custom_client_state(vec![
+            ("CheckBar", meta_type::<()>(), meta_type::<()>()),
+            ("CheckFoo", meta_type::<()>(), meta_type::<u64>()),
+        ]);
+        let params = DefaultExtrinsicParamsBuilder::new().build().into();
+
+        assert!(matches!(
+            dynamic_params(&state, params),
+            Err(ExtrinsicParamsError::UnknownSignedExtension(name)) if name == "CheckFoo"
+        ));
+    }
+}
@@ -78,6 +78,7 @@ impl<N, H> Header for SubstrateHeader<N, H>
 where
     N: Copy + Into<u64> + Into<U256> + TryFrom<U256> + Encode,
     H: Hasher + Encode,
+    H::Output: Copy,
     SubstrateHeader<N, H>: Encode + Decode,
 {
     type Number = N;
@@ -85,6 +86,9 @@ where
     fn number(&self) -> Self::Number {
         self.number
     }
+    fn parent_hash(&self) -> <Self::Hasher as Hasher>::Output {
+        self.parent_hash
+    }
 }
 
 /// Generic header digest. From `sp_runtime::generic::digest`.
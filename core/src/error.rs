@@ -8,6 +8,7 @@ use core::fmt::Display;
 
 use alloc::boxed::Box;
 use alloc::string::String;
+use alloc::vec::Vec;
 use subxt_metadata::StorageHasher;
 
 /// The error emitted when something goes wrong.
@@ -27,6 +28,10 @@ pub enum Error {
     ExtrinsicParams(ExtrinsicParamsError),
     /// Block body error.
     Block(BlockError),
+    /// Transaction envelope error.
+    Envelope(EnvelopeError),
+    /// Pallet view function error.
+    ViewFunction(ViewFunctionError),
 }
 
 impl core::fmt::Display for Error {
@@ -39,6 +44,8 @@ impl core::fmt::Display for Error {
             Error::Encode(e) => write!(f, "Error encoding from dynamic value: {e}"),
             Error::ExtrinsicParams(e) => write!(f, "Extrinsic params error: {e}"),
             Error::Block(e) => write!(f, "Error working with block_body: {}", e),
+            Error::Envelope(e) => write!(f, "Transaction envelope error: {e}"),
+            Error::ViewFunction(e) => write!(f, "View function error: {e}"),
         }
     }
 }
@@ -48,6 +55,8 @@ impl std::error::Error for Error {}
 
 impl_from!(ExtrinsicParamsError => Error::ExtrinsicParams);
 impl_from!(BlockError => Error::Block);
+impl_from!(EnvelopeError => Error::Envelope);
+impl_from!(ViewFunctionError => Error::ViewFunction);
 impl_from!(MetadataError => Error::Metadata);
 impl_from!(scale_decode::Error => Error::Decode);
 impl_from!(scale_decode::visitor::DecodeError => Error::Decode);
@@ -55,6 +64,24 @@ impl_from!(scale_encode::Error => Error::Encode);
 impl_from!(StorageAddressError => Error::StorageAddress);
 impl_from!(codec::Error => Error::Codec);
 
+/// Trait to uniquely identify a pallet's error type from the runtime metadata.
+///
+/// The `Error` type of each pallet in the generated API implements this trait, so that the
+/// error in a `DispatchError::Module` can be decoded into it. Pallets which are instances of
+/// the same pallet (eg two instances of `pallet_collective`) share one error type, so can't
+/// implement this trait, and their errors must be decoded into the outer `Error` enum instead.
+pub trait StaticError: scale_decode::DecodeAsType {
+    /// Pallet name.
+    const PALLET: &'static str;
+    /// The names of each of the error variants.
+    const VARIANTS: &'static [&'static str];
+
+    /// Returns true if the given pallet and error names match this error type.
+    fn is_error(pallet: &str, error: &str) -> bool {
+        Self::PALLET == pallet && Self::VARIANTS.contains(&error)
+    }
+}
+
 /// Block error
 #[derive(Debug)]
 pub enum BlockError {
@@ -124,6 +151,8 @@ pub enum MetadataError {
     RuntimeTraitNotFound(String),
     /// Runtime method not found.
     RuntimeMethodNotFound(String),
+    /// View function not found.
+    ViewFunctionNotFound(String),
     /// Call type not found in metadata.
     CallTypeNotFoundInPallet(u8),
     /// Event type not found in metadata.
@@ -136,6 +165,12 @@ pub enum MetadataError {
     IncompatibleCodegen,
     /// Custom value not found.
     CustomValueNameNotFound(String),
+    /// Something went wrong computing the merkleized metadata digest or proofs.
+    MerkleizedMetadata(subxt_metadata::MerkleizedMetadataError),
+    /// None of the extrinsic versions supported by the node are supported by Subxt.
+    UnsupportedExtrinsicVersions(Vec<u8>),
+    /// Signed extension not found.
+    SignedExtensionNotFound(String),
 }
 impl Display for MetadataError {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
@@ -155,6 +190,9 @@ impl Display for MetadataError {
             MetadataError::RuntimeMethodNotFound(e) => {
                 write!(f, "Runtime method with name {e} not found")
             }
+            MetadataError::ViewFunctionNotFound(e) => {
+                write!(f, "View function with name {e} not found")
+            }
             MetadataError::CallTypeNotFoundInPallet(e) => {
                 write!(f, "Call type not found in pallet with index {e}")
             }
@@ -171,6 +209,13 @@ impl Display for MetadataError {
             MetadataError::CustomValueNameNotFound(e) => {
                 write!(f, "Custom value with name {e} not found")
             }
+            MetadataError::MerkleizedMetadata(e) => write!(f, "{e}"),
+            MetadataError::UnsupportedExtrinsicVersions(e) => {
+                write!(f, "None of the extrinsic versions supported by the node ({e:?}) are supported by Subxt")
+            }
+            MetadataError::SignedExtensionNotFound(e) => {
+                write!(f, "Signed extension with name {e} not found")
+            }
         }
     }
 }
@@ -178,6 +223,102 @@ impl Display for MetadataError {
 #[cfg(feature = "std")]
 impl std::error::Error for MetadataError {}
 
+/// Something went wrong working with a [`crate::tx::envelope::TransactionEnvelope`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum EnvelopeError {
+    /// The genesis hash in the envelope does not match the one we expected.
+    GenesisHashMismatch,
+    /// The spec version in the envelope does not match the one we expected.
+    SpecVersionMismatch {
+        /// The spec version we expected.
+        expected: u32,
+        /// The spec version found in the envelope.
+        found: u32,
+    },
+    /// The transaction version in the envelope does not match the one we expected.
+    TransactionVersionMismatch {
+        /// The transaction version we expected.
+        expected: u32,
+        /// The transaction version found in the envelope.
+        found: u32,
+    },
+    /// The metadata hash in the envelope does not match the hash of the metadata we have.
+    MetadataHashMismatch,
+    /// The call data could not be fully decoded using the metadata we have.
+    InvalidCallData,
+    /// The human readable call description does not match the call data.
+    CallDescriptionMismatch,
+    /// The signed extension data could not be fully decoded using the metadata we have.
+    InvalidSignedExtensionData,
+    /// The named signed extension signs a different value to the one given in the envelope.
+    SignedExtensionMismatch(String),
+    /// The envelope could not be serialized to or deserialized from JSON.
+    Json(String),
+}
+
+impl Display for EnvelopeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            EnvelopeError::GenesisHashMismatch => {
+                write!(f, "The genesis hash in the envelope does not match")
+            }
+            EnvelopeError::SpecVersionMismatch { expected, found } => write!(
+                f,
+                "Expected spec version {expected} but the envelope has spec version {found}"
+            ),
+            EnvelopeError::TransactionVersionMismatch { expected, found } => write!(
+                f,
+                "Expected transaction version {expected} but the envelope has transaction version {found}"
+            ),
+            EnvelopeError::MetadataHashMismatch => {
+                write!(f, "The metadata hash in the envelope does not match")
+            }
+            EnvelopeError::InvalidCallData => {
+                write!(f, "The call data in the envelope could not be decoded")
+            }
+            EnvelopeError::CallDescriptionMismatch => write!(
+                f,
+                "The call description in the envelope does not match the call data"
+            ),
+            EnvelopeError::InvalidSignedExtensionData => write!(
+                f,
+                "The signed extension data in the envelope could not be decoded"
+            ),
+            EnvelopeError::SignedExtensionMismatch(name) => write!(
+                f,
+                "The {name} signed extension data does not match the envelope"
+            ),
+            EnvelopeError::Json(e) => write!(f, "Envelope JSON error: {e}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EnvelopeError {}
+
+/// Something went wrong executing a pallet view function.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ViewFunctionError {
+    /// The runtime failed to execute the view function. This contains a description
+    /// of the error that it returned.
+    ExecutionFailed(String),
+}
+
+impl Display for ViewFunctionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ViewFunctionError::ExecutionFailed(e) => {
+                write!(f, "The view function failed to execute: {e}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ViewFunctionError {}
+
 /// Something went wrong trying to encode or decode a storage address.
 #[derive(Clone, Debug)]
 #[non_exhaustive]
@@ -137,6 +137,11 @@ impl<T: Config> Events<T> {
         &self.event_bytes
     }
 
+    /// Return the metadata used to decode these events.
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
     /// Iterate over all of the events, using metadata to dynamically
     /// decode them as we go, and returning the raw bytes and other associated
     /// details. If an error occurs, all subsequent iterations return `None`.
@@ -708,6 +713,61 @@ mod tests {
         assert!(event_details.next().is_none());
     }
 
+    #[test]
+    fn dynamically_decode_events_with_legacy_metadata() {
+        // Before V12 metadata, pallets are indexed by their position amongst pallets with events.
+        for (version, balances_index) in [(11, 1), (12, 5)] {
+            let metadata = crate::test_utils::legacy_metadata(version);
+
+            // System.ExtrinsicSuccess(DispatchInfo { weight, class, pays_fee }):
+            let mut event_bytes = Phase::ApplyExtrinsic(0).encode();
+            event_bytes.extend([0, 0]);
+            (10u64, 0u8, 0u8).encode_to(&mut event_bytes);
+            Vec::<H256>::new().encode_to(&mut event_bytes);
+            // Balances.Transfer(from, to, amount):
+            Phase::ApplyExtrinsic(1).encode_to(&mut event_bytes);
+            event_bytes.extend([balances_index, 0]);
+            ([1u8; 32], [2u8; 32], 1000u128).encode_to(&mut event_bytes);
+            Vec::<H256>::new().encode_to(&mut event_bytes);
+
+            let events = events_raw(metadata, event_bytes, 2);
+            let mut event_details = events.iter();
+            assert_raw_events_match(
+                event_details.next().unwrap().unwrap(),
+                TestRawEventDetails {
+                    phase: Phase::ApplyExtrinsic(0),
+                    index: 0,
+                    pallet: "System".to_string(),
+                    pallet_index: 0,
+                    variant: "ExtrinsicSuccess".to_string(),
+                    variant_index: 0,
+                    fields: vec![Value::named_composite([
+                        ("weight", Value::u128(10)),
+                        ("class", Value::unnamed_variant("Normal", [])),
+                        ("pays_fee", Value::unnamed_variant("Yes", [])),
+                    ])],
+                },
+            );
+            assert_raw_events_match(
+                event_details.next().unwrap().unwrap(),
+                TestRawEventDetails {
+                    phase: Phase::ApplyExtrinsic(1),
+                    index: 1,
+                    pallet: "Balances".to_string(),
+                    pallet_index: balances_index,
+                    variant: "Transfer".to_string(),
+                    variant_index: 0,
+                    fields: vec![
+                        Value::from_bytes([1u8; 32]),
+                        Value::from_bytes([2u8; 32]),
+                        Value::u128(1000),
+                    ],
+                },
+            );
+            assert!(event_details.next().is_none());
+        }
+    }
+
     #[test]
     fn dynamically_decode_multiple_events() {
         #[derive(Clone, Copy, Debug, PartialEq, Decode, Encode, TypeInfo)]
@@ -16,6 +16,7 @@
 //! - [`storage`]: construct storage request payloads and decode the results you'd get back.
 //! - [`tx`]: construct and sign transactions (extrinsics).
 //! - [`runtime_api`]: construct runtime API request payloads and decode the results you'd get back.
+//! - [`view_functions`]: construct pallet view function payloads and decode the results you'd get back.
 //! - [`events`]: decode and explore events.
 //!
 
@@ -39,6 +40,10 @@ pub mod runtime_api;
 pub mod storage;
 pub mod tx;
 pub mod utils;
+pub mod view_functions;
+
+#[cfg(test)]
+mod test_utils;
 
 pub use config::Config;
 pub use error::Error;
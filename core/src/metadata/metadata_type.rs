@@ -39,6 +39,15 @@ impl Metadata {
             .ok_or(MetadataError::PalletIndexNotFound(index))
     }
 
+    /// Identical to `metadata.pallet_by_call_index()`, but returns an error if the pallet is not found.
+    pub fn pallet_by_call_index_err(
+        &self,
+        index: u8,
+    ) -> Result<subxt_metadata::PalletMetadata, MetadataError> {
+        self.pallet_by_call_index(index)
+            .ok_or(MetadataError::PalletIndexNotFound(index))
+    }
+
     /// Identical to `metadata.runtime_api_trait_by_name()`, but returns an error if the trait is not found.
     pub fn runtime_api_trait_by_name_err(
         &self,
@@ -23,6 +23,7 @@ use codec::Decode;
 
 pub use decode_encode_traits::{DecodeWithMetadata, EncodeWithMetadata};
 pub use metadata_type::Metadata;
+pub use subxt_metadata::CompatibilityReport;
 
 /// Attempt to decode some bytes into [`Metadata`], returning an error
 /// if decoding fails.
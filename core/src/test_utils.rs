@@ -0,0 +1,151 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Fixtures shared by the tests in this crate.
+
+use crate::client::{ClientState, RuntimeVersion};
+use crate::config::PolkadotConfig;
+use crate::utils::H256;
+use crate::Metadata;
+use alloc::string::String;
+use alloc::vec::Vec;
+use codec::Encode;
+use frame_metadata::v15::{
+    CustomMetadata, ExtrinsicMetadata, OuterEnums, PalletCallMetadata, PalletMetadata,
+    RuntimeMetadataV15, SignedExtensionMetadata,
+};
+use frame_metadata::RuntimeMetadataPrefixed;
+use scale_info::{meta_type, MetaType, TypeInfo};
+use subxt_metadata::LegacyTypes;
+
+/// The (small) Polkadot metadata that most tests are run against.
+pub(crate) fn metadata() -> Metadata {
+    let metadata_bytes = include_bytes!("../../artifacts/polkadot_metadata_small.scale");
+    crate::metadata::decode_from(&metadata_bytes[..]).unwrap()
+}
+
+/// The client state of a chain with [`metadata()`], for constructing transactions against.
+pub(crate) fn client_state() -> ClientState<PolkadotConfig> {
+    ClientState {
+        metadata: metadata(),
+        genesis_hash: H256::repeat_byte(1),
+        runtime_version: RuntimeVersion {
+            spec_version: 1,
+            transaction_version: 2,
+        },
+    }
+}
+
+/// Build metadata for a runtime with the given pallets, each of which is given as a name and
+/// the type of its calls. `RuntimeCall` is the outer call enum, whose variants must wrap the
+/// pallet calls in the same order. Transactions have the given version and signed extensions.
+pub(crate) fn metadata_with_pallets<RuntimeCall: TypeInfo + 'static>(
+    pallets: Vec<(&'static str, MetaType)>,
+    extrinsic_version: u8,
+    signed_extensions: Vec<SignedExtensionMetadata>,
+) -> Metadata {
+    let pallets = pallets
+        .into_iter()
+        .enumerate()
+        .map(|(index, (name, calls))| PalletMetadata {
+            name,
+            storage: None,
+            calls: Some(PalletCallMetadata { ty: calls }),
+            event: None,
+            constants: vec![],
+            error: None,
+            index: index as u8,
+            docs: vec![],
+        })
+        .collect();
+
+    let extrinsic = ExtrinsicMetadata {
+        version: extrinsic_version,
+        signed_extensions,
+        address_ty: meta_type::<()>(),
+        call_ty: meta_type::<RuntimeCall>(),
+        signature_ty: meta_type::<()>(),
+        extra_ty: meta_type::<()>(),
+    };
+
+    let meta = RuntimeMetadataV15::new(
+        pallets,
+        extrinsic,
+        meta_type::<()>(),
+        vec![],
+        OuterEnums {
+            call_enum_ty: meta_type::<RuntimeCall>(),
+            event_enum_ty: meta_type::<()>(),
+            error_enum_ty: meta_type::<()>(),
+        },
+        CustomMetadata {
+            map: Default::default(),
+        },
+    );
+    let runtime_metadata: RuntimeMetadataPrefixed = meta.into();
+    let metadata: subxt_metadata::Metadata = runtime_metadata.try_into().unwrap();
+    metadata.into()
+}
+
+/// The signed extensions of the transactions described by [`legacy_metadata()`].
+pub(crate) const LEGACY_SIGNED_EXTENSIONS: [&str; 5] = [
+    "CheckSpecVersion",
+    "CheckGenesis",
+    "CheckMortality",
+    "CheckNonce",
+    "ChargeTransactionPayment",
+];
+
+/// Decode legacy metadata of the given version (V11 or later) using the Substrate type
+/// definitions. It contains a "System" pallet with an `ExtrinsicSuccess(DispatchInfo)` event,
+/// and a "Balances" pallet with a `transfer(dest, value)` call and a
+/// `Transfer(AccountId, AccountId, Balance)` event. From V12 these pallets are at indexes
+/// 0 and 5, and before that at the indexes given by their positions.
+pub(crate) fn legacy_metadata(version: u8) -> Metadata {
+    type Calls = Option<Vec<(&'static str, Vec<(&'static str, &'static str)>, Vec<String>)>>;
+    type Events = Option<Vec<(&'static str, Vec<&'static str>, Vec<String>)>>;
+
+    let system_events: Events = Some(vec![("ExtrinsicSuccess", vec!["DispatchInfo"], vec![])]);
+    let balances_calls: Calls = Some(vec![(
+        "transfer",
+        vec![
+            ("dest", "<T::Lookup as StaticLookup>::Source"),
+            ("value", "Compact<T::Balance>"),
+        ],
+        vec![],
+    )]);
+    let balances_events: Events = Some(vec![(
+        "Transfer",
+        vec!["AccountId", "AccountId", "Balance"],
+        vec![],
+    )]);
+    let modules = [
+        ("System", None, system_events, 0u8),
+        ("Balances", balances_calls, balances_events, 5u8),
+    ];
+
+    let mut bytes = b"meta".to_vec();
+    bytes.push(version);
+    codec::Compact(modules.len() as u32).encode_to(&mut bytes);
+    for (name, calls, events, index) in modules {
+        // The module name, storage, calls, events, constants and errors.
+        (
+            name,
+            None::<()>,
+            calls,
+            events,
+            Vec::<()>::new(),
+            Vec::<()>::new(),
+        )
+            .encode_to(&mut bytes);
+        if version >= 12 {
+            index.encode_to(&mut bytes);
+        }
+    }
+    (4u8, &LEGACY_SIGNED_EXTENSIONS[..]).encode_to(&mut bytes);
+
+    let metadata = subxt_metadata::Metadata::decode_legacy(&bytes, &LegacyTypes::substrate())
+        .expect("can decode legacy metadata");
+    metadata.into()
+}
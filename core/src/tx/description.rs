@@ -0,0 +1,312 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! This module contains a [`TransactionDescription`], which is a decoded, human readable
+//! view of everything that is signed as part of a transaction. This is useful to show to
+//! a user before asking them to approve a transaction. Obtain one via
+//! [`super::PartialTransaction::describe()`].
+
+use super::PartialTransaction;
+use crate::config::signed_extensions::{
+    ChargeAssetTxPayment, ChargeTransactionPayment, CheckGenesis, CheckMortality, CheckNonce,
+    CheckSpecVersion, CheckTxVersion,
+};
+use crate::config::{Config, ExtrinsicParamsEncoder, SignedExtension};
+use crate::dynamic::DecodedValue;
+use crate::error::{Error, MetadataError};
+use crate::metadata::Metadata;
+use crate::utils::Era;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use codec::Decode;
+use derive_where::derive_where;
+use scale_decode::DecodeAsType;
+use scale_value::Composite;
+use serde::Serialize;
+
+/// A decoded description of a transaction, containing the call and the details of
+/// each signed extension that will be signed along with it. This can be rendered as text
+/// via its [`core::fmt::Display`] implementation, or as JSON via its [`Serialize`] implementation.
+#[derive(Serialize)]
+#[derive_where(Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", bound = "")]
+pub struct TransactionDescription<T: Config> {
+    /// The name of the pallet that the call belongs to.
+    pub pallet_name: String,
+    /// The name of the call.
+    pub call_name: String,
+    /// The decoded call arguments.
+    pub args: Composite<u32>,
+    /// The tip, if a `ChargeTransactionPayment` or `ChargeAssetTxPayment` signed extension is in use.
+    pub tip: Option<u128>,
+    /// The account nonce, if a `CheckNonce` signed extension is in use.
+    pub nonce: Option<u64>,
+    /// The mortality of the transaction, if a `CheckMortality` signed extension is in use.
+    pub mortality: Option<Era>,
+    /// The spec version, if a `CheckSpecVersion` signed extension is in use.
+    pub spec_version: Option<u32>,
+    /// The transaction version, if a `CheckTxVersion` signed extension is in use.
+    pub transaction_version: Option<u32>,
+    /// The genesis hash, if a `CheckGenesis` signed extension is in use.
+    pub genesis_hash: Option<T::Hash>,
+    /// Each of the signed extensions, in the order that they appear in the metadata. For
+    /// version 5 transactions, this is just the extensions after `VerifySignature`.
+    pub signed_extensions: Vec<SignedExtensionDescription>,
+}
+
+/// A decoded description of a single signed extension in a [`TransactionDescription`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedExtensionDescription {
+    /// The name of the signed extension.
+    pub name: String,
+    /// The decoded data which is sent along with the transaction.
+    pub extra: DecodedValue,
+    /// The decoded data which is only included in the signer payload.
+    pub additional: DecodedValue,
+}
+
+impl<T: Config> TransactionDescription<T> {
+    /// Find the description of the signed extension with the given name, if it exists.
+    pub fn signed_extension(&self, name: &str) -> Option<&SignedExtensionDescription> {
+        self.signed_extensions.iter().find(|s| s.name == name)
+    }
+}
+
+impl<T: Config> core::fmt::Display for TransactionDescription<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "{}.{}{}", self.pallet_name, self.call_name, self.args)?;
+        if let Some(tip) = self.tip {
+            writeln!(f, "  tip: {tip}")?;
+        }
+        if let Some(nonce) = self.nonce {
+            writeln!(f, "  nonce: {nonce}")?;
+        }
+        match self.mortality {
+            Some(Era::Immortal) => writeln!(f, "  mortality: immortal")?,
+            Some(Era::Mortal { period, phase }) => {
+                writeln!(f, "  mortality: mortal (period {period}, phase {phase})")?
+            }
+            None => {}
+        }
+        if let Some(spec_version) = self.spec_version {
+            writeln!(f, "  spec version: {spec_version}")?;
+        }
+        if let Some(transaction_version) = self.transaction_version {
+            writeln!(f, "  transaction version: {transaction_version}")?;
+        }
+        if let Some(genesis_hash) = &self.genesis_hash {
+            writeln!(
+                f,
+                "  genesis hash: 0x{}",
+                hex::encode(genesis_hash.as_ref())
+            )?;
+        }
+        writeln!(f, "  signed extensions:")?;
+        for ext in &self.signed_extensions {
+            writeln!(
+                f,
+                "    {}: extra {}, additional {}",
+                ext.name, ext.extra, ext.additional
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Config> PartialTransaction<T> {
+    /// Decode the call data and the signed extension data of this transaction using the
+    /// given metadata, returning a [`TransactionDescription`] of everything that will be
+    /// signed. This is useful to show to a user before asking them to approve a transaction.
+    ///
+    /// Version 5 transactions only sign the signed extensions after `VerifySignature`, which
+    /// itself carries the signature once the transaction is signed, and so only those
+    /// extensions are described.
+    pub fn describe(&self, metadata: &Metadata) -> Result<TransactionDescription<T>, Error> {
+        let mut extra = Vec::new();
+        let mut additional = Vec::new();
+        if self.version == 4 {
+            self.additional_and_extra_params.encode_extra_to(&mut extra);
+            self.additional_and_extra_params
+                .encode_additional_to(&mut additional);
+            return describe_transaction(&self.call_data, &extra, &additional, metadata, 0);
+        }
+
+        self.additional_and_extra_params
+            .encode_signer_payload_extra_to(&mut extra);
+        self.additional_and_extra_params
+            .encode_signer_payload_additional_to(&mut additional);
+        let first_signed = metadata
+            .extrinsic()
+            .signed_extensions()
+            .iter()
+            .position(|e| e.identifier() == "VerifySignature")
+            .map_or(0, |idx| idx + 1);
+        describe_transaction(&self.call_data, &extra, &additional, metadata, first_signed)
+    }
+}
+
+// Decode some call data and the signed extension "extra" and "additional" data which
+// will be signed along with it, returning a description of the transaction. The data
+// is for the signed extensions in the metadata from index `first_extension` onwards.
+pub(crate) fn describe_transaction<T: Config>(
+    call_data: &[u8],
+    mut extra: &[u8],
+    mut additional: &[u8],
+    metadata: &Metadata,
+    first_extension: usize,
+) -> Result<TransactionDescription<T>, Error> {
+    let (pallet_name, call_name, args) = decode_call(call_data, metadata)?;
+
+    let mut description = TransactionDescription {
+        pallet_name,
+        call_name,
+        args,
+        tip: None,
+        nonce: None,
+        mortality: None,
+        spec_version: None,
+        transaction_version: None,
+        genesis_hash: None,
+        signed_extensions: Vec::new(),
+    };
+
+    // The extra and additional data are encoded in the order that the
+    // signed extensions are listed in the metadata, so decode them in turn.
+    let types = metadata.types();
+    for ext in &metadata.extrinsic().signed_extensions()[first_extension..] {
+        let name = ext.identifier();
+        let (extra_ty, additional_ty) = (ext.extra_ty(), ext.additional_ty());
+
+        // Keep hold of the bytes for this extension so that we can also decode
+        // the ones we know about into more useful types.
+        let (mut ext_extra, mut ext_additional) = (extra, additional);
+        let extra_value = scale_value::scale::decode_as_type(&mut extra, extra_ty, types)?;
+        let additional_value =
+            scale_value::scale::decode_as_type(&mut additional, additional_ty, types)?;
+
+        if <CheckNonce as SignedExtension<T>>::matches(name, extra_ty, types) {
+            description.nonce = Some(u64::decode_as_type(&mut ext_extra, extra_ty, types)?);
+        } else if <CheckMortality<T> as SignedExtension<T>>::matches(name, extra_ty, types) {
+            description.mortality = Some(Era::decode_as_type(&mut ext_extra, extra_ty, types)?);
+        } else if <ChargeTransactionPayment as SignedExtension<T>>::matches(name, extra_ty, types) {
+            let payment =
+                ChargeTransactionPayment::decode_as_type(&mut ext_extra, extra_ty, types)?;
+            description.tip = Some(payment.tip());
+        } else if <ChargeAssetTxPayment<T> as SignedExtension<T>>::matches(name, extra_ty, types) {
+            let payment =
+                ChargeAssetTxPayment::<T>::decode_as_type(&mut ext_extra, extra_ty, types)?;
+            description.tip = Some(payment.tip());
+        } else if <CheckSpecVersion as SignedExtension<T>>::matches(name, extra_ty, types) {
+            description.spec_version = Some(u32::decode_as_type(
+                &mut ext_additional,
+                additional_ty,
+                types,
+            )?);
+        } else if <CheckTxVersion as SignedExtension<T>>::matches(name, extra_ty, types) {
+            description.transaction_version = Some(u32::decode_as_type(
+                &mut ext_additional,
+                additional_ty,
+                types,
+            )?);
+        } else if <CheckGenesis<T> as SignedExtension<T>>::matches(name, extra_ty, types) {
+            description.genesis_hash = Some(T::Hash::decode(&mut ext_additional)?);
+        }
+
+        description
+            .signed_extensions
+            .push(SignedExtensionDescription {
+                name: name.to_string(),
+                extra: extra_value,
+                additional: additional_value,
+            });
+    }
+
+    if !extra.is_empty() || !additional.is_empty() {
+        return Err(
+            codec::Error::from("Leftover bytes after decoding the signed extension data").into(),
+        );
+    }
+
+    Ok(description)
+}
+
+// Decode some call data using the metadata, returning the pallet name, call name
+// and decoded arguments.
+pub(crate) fn decode_call(
+    call_data: &[u8],
+    metadata: &Metadata,
+) -> Result<(String, String, Composite<u32>), Error> {
+    let cursor = &mut &*call_data;
+    let pallet_index = u8::decode(cursor)?;
+    let call_index = u8::decode(cursor)?;
+
+    let pallet = metadata.pallet_by_call_index_err(pallet_index)?;
+    let call = pallet
+        .call_variant_by_index(call_index)
+        .ok_or(MetadataError::VariantIndexNotFound(call_index))?;
+
+    let mut fields = call
+        .fields
+        .iter()
+        .map(|f| scale_decode::Field::new(f.ty.id, f.name.as_deref()));
+    let args = scale_value::scale::decode_as_fields(cursor, &mut fields, metadata.types())?;
+    if !cursor.is_empty() {
+        return Err(codec::Error::from("Leftover bytes after decoding the call data").into());
+    }
+
+    Ok((pallet.name().to_string(), call.name.clone(), args))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{DefaultExtrinsicParamsBuilder, PolkadotConfig};
+    use crate::dynamic::Value;
+    use crate::test_utils::client_state;
+    use crate::tx;
+    use crate::utils::H256;
+
+    #[test]
+    fn describe_decodes_call_and_signed_extensions() {
+        let state = client_state();
+        let call = tx::payload::dynamic("System", "remark", vec![Value::from_bytes("Hello")]);
+        let params = DefaultExtrinsicParamsBuilder::<PolkadotConfig>::new()
+            .tip(1234)
+            .nonce(5)
+            .build();
+        let partial = tx::create_partial_signed(&call, &state, params).unwrap();
+        let description = partial.describe(&state.metadata).unwrap();
+
+        assert_eq!(description.pallet_name, "System");
+        assert_eq!(description.call_name, "remark");
+        assert_eq!(description.tip, Some(1234));
+        assert_eq!(description.nonce, Some(5));
+        assert_eq!(description.mortality, Some(Era::Immortal));
+        assert_eq!(description.spec_version, Some(1));
+        assert_eq!(description.transaction_version, Some(2));
+        assert_eq!(description.genesis_hash, Some(H256::repeat_byte(1)));
+
+        let names: Vec<_> = description
+            .signed_extensions
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        let expected: Vec<_> = state
+            .metadata
+            .extrinsic()
+            .signed_extensions()
+            .iter()
+            .map(|s| s.identifier())
+            .collect();
+        assert_eq!(names, expected);
+        assert!(description.signed_extension("CheckNonce").is_some());
+
+        // It can be rendered as text and as JSON:
+        assert!(description.to_string().starts_with("System.remark"));
+        let json = serde_json::to_value(&description).unwrap();
+        assert_eq!(json["callName"], "remark");
+        assert_eq!(json["nonce"], 5);
+    }
+}
@@ -0,0 +1,521 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! This module contains a portable [`TransactionEnvelope`], which carries everything needed
+//! to sign a transaction from an online machine (which knows the current nonce, block
+//! and so on) to an offline, air-gapped one (which holds the keys), and an
+//! [`EnvelopeSignature`] which carries the resulting signature back again.
+//!
+//! The flow looks something like:
+//!
+//! 1. On the online machine, build a [`super::PartialTransaction`] and call
+//!    [`super::PartialTransaction::to_envelope()`], exporting it with
+//!    [`TransactionEnvelope::to_json()`] or [`TransactionEnvelope::to_bytes()`].
+//! 2. On the offline machine, import the envelope and call [`TransactionEnvelope::verify()`]
+//!    to check it against the metadata and chain details that you trust, inspect
+//!    [`TransactionEnvelope::describe()`] to see what is being signed, and then call
+//!    [`TransactionEnvelope::sign()`] to obtain an [`EnvelopeSignature`].
+//! 3. Back on the online machine, import the [`EnvelopeSignature`] and call
+//!    [`TransactionEnvelope::to_transaction()`] to build the transaction to submit.
+
+use super::{
+    description::TransactionDescription, encode_signed_extrinsic, signer::Signer as SignerT,
+    MetadataProof, PartialTransaction, Transaction,
+};
+use crate::client::ClientState;
+use crate::config::{Config, ExtrinsicParamsEncoder};
+use crate::error::{EnvelopeError, Error, MetadataError};
+use crate::metadata::Metadata;
+use crate::utils::H256;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+/// An unsigned transaction, along with everything needed to verify and sign it
+/// on a machine that is not connected to the network.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionEnvelope {
+    /// The SCALE encoded call data.
+    #[serde(with = "impl_serde::serialize")]
+    call_data: Vec<u8>,
+    /// The SCALE encoded signed extension "extra" params, which are sent along with the transaction.
+    #[serde(with = "impl_serde::serialize")]
+    extra: Vec<u8>,
+    /// The SCALE encoded signed extension "additional" params, which are only signed.
+    #[serde(with = "impl_serde::serialize")]
+    additional: Vec<u8>,
+    /// The genesis hash of the chain that this transaction is for.
+    #[serde(with = "impl_serde::serialize")]
+    genesis_hash: Vec<u8>,
+    /// The spec version of the runtime that this transaction was built against.
+    spec_version: u32,
+    /// The transaction version of the runtime that this transaction was built against.
+    transaction_version: u32,
+    /// The hash of the metadata that this transaction was built against.
+    metadata_hash: H256,
+    /// A human readable description of the call.
+    call: CallDescription,
+}
+
+/// A human readable description of the call in a [`TransactionEnvelope`].
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallDescription {
+    /// The name of the pallet that the call belongs to.
+    pub pallet_name: String,
+    /// The name of the call.
+    pub call_name: String,
+    /// The call arguments, decoded and rendered as a string.
+    pub args: String,
+}
+
+impl core::fmt::Display for CallDescription {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}.{}{}", self.pallet_name, self.call_name, self.args)
+    }
+}
+
+impl<T: Config> PartialTransaction<T> {
+    /// Export this [`PartialTransaction`] into a [`TransactionEnvelope`] that can be
+    /// handed to an offline machine to be verified and signed. The `client_state` should
+    /// be the same as was used to construct this [`PartialTransaction`].
+    ///
+    /// Envelopes are turned into version 4 signed transactions, and so this returns an error
+    /// if the node doesn't support them.
+    pub fn to_envelope(&self, client_state: &ClientState<T>) -> Result<TransactionEnvelope, Error> {
+        if self.version != 4 {
+            let versions = client_state.metadata.extrinsic().supported_versions();
+            return Err(MetadataError::UnsupportedExtrinsicVersions(versions.to_vec()).into());
+        }
+
+        let mut extra = Vec::new();
+        self.additional_and_extra_params.encode_extra_to(&mut extra);
+        let mut additional = Vec::new();
+        self.additional_and_extra_params
+            .encode_additional_to(&mut additional);
+
+        let metadata = &client_state.metadata;
+        let call = describe_call(&self.call_data, metadata)?;
+
+        Ok(TransactionEnvelope {
+            call_data: self.call_data.clone(),
+            extra,
+            additional,
+            genesis_hash: client_state.genesis_hash.as_ref().to_vec(),
+            spec_version: client_state.runtime_version.spec_version,
+            transaction_version: client_state.runtime_version.transaction_version,
+            metadata_hash: H256(metadata.hasher().hash()),
+            call,
+        })
+    }
+}
+
+impl TransactionEnvelope {
+    /// The SCALE encoded call data.
+    pub fn call_data(&self) -> &[u8] {
+        &self.call_data
+    }
+
+    /// The SCALE encoded signed extension "extra" params.
+    pub fn extra(&self) -> &[u8] {
+        &self.extra
+    }
+
+    /// The SCALE encoded signed extension "additional" params.
+    pub fn additional(&self) -> &[u8] {
+        &self.additional
+    }
+
+    /// The genesis hash of the chain that this transaction is for.
+    pub fn genesis_hash(&self) -> &[u8] {
+        &self.genesis_hash
+    }
+
+    /// The spec version of the runtime that this transaction was built against.
+    pub fn spec_version(&self) -> u32 {
+        self.spec_version
+    }
+
+    /// The transaction version of the runtime that this transaction was built against.
+    pub fn transaction_version(&self) -> u32 {
+        self.transaction_version
+    }
+
+    /// The hash of the metadata that this transaction was built against.
+    pub fn metadata_hash(&self) -> H256 {
+        self.metadata_hash
+    }
+
+    /// A human readable description of the call. This is not trusted until
+    /// [`TransactionEnvelope::verify()`] has been called.
+    pub fn call(&self) -> &CallDescription {
+        &self.call
+    }
+
+    /// Serialize this envelope to a JSON string.
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string(self).map_err(|e| EnvelopeError::Json(e.to_string()).into())
+    }
+
+    /// Deserialize an envelope from a JSON string.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|e| EnvelopeError::Json(e.to_string()).into())
+    }
+
+    /// Serialize this envelope to a compact (SCALE encoded) binary representation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encode()
+    }
+
+    /// Deserialize an envelope from the binary representation given by
+    /// [`TransactionEnvelope::to_bytes()`].
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::decode(&mut bytes)?)
+    }
+
+    /// Decode the call data and the signed extension "extra" and "additional" data in this
+    /// envelope using the given metadata, returning a [`TransactionDescription`] of everything
+    /// that will be signed. This is not trusted until [`TransactionEnvelope::verify()`] has been
+    /// called with the same metadata.
+    pub fn describe<T: Config>(
+        &self,
+        metadata: &Metadata,
+    ) -> Result<TransactionDescription<T>, Error> {
+        // Check the call data on its own first so that we can tell apart bad call
+        // data from bad signed extension data.
+        describe_call(&self.call_data, metadata)?;
+        super::description::describe_transaction(
+            &self.call_data,
+            &self.extra,
+            &self.additional,
+            metadata,
+            0,
+        )
+        .map_err(|e| match e {
+            Error::Codec(_) | Error::Decode(_) => EnvelopeError::InvalidSignedExtensionData.into(),
+            e => e,
+        })
+    }
+
+    /// Check that this envelope lines up with the given client state, which is expected
+    /// to come from a trusted source (eg an `OfflineClient` constructed on the signing machine).
+    /// This checks the genesis hash, runtime versions and metadata hash, and then decodes the call
+    /// data and signed extension data using the metadata to check that the call description is
+    /// accurate and that the genesis hash and runtime versions being signed are the ones given
+    /// in the envelope.
+    pub fn verify<T: Config>(&self, client_state: &ClientState<T>) -> Result<(), Error> {
+        if self.genesis_hash != client_state.genesis_hash.as_ref() {
+            return Err(EnvelopeError::GenesisHashMismatch.into());
+        }
+
+        let runtime_version = &client_state.runtime_version;
+        if self.spec_version != runtime_version.spec_version {
+            return Err(EnvelopeError::SpecVersionMismatch {
+                expected: runtime_version.spec_version,
+                found: self.spec_version,
+            }
+            .into());
+        }
+        if self.transaction_version != runtime_version.transaction_version {
+            return Err(EnvelopeError::TransactionVersionMismatch {
+                expected: runtime_version.transaction_version,
+                found: self.transaction_version,
+            }
+            .into());
+        }
+
+        let metadata = &client_state.metadata;
+        if self.metadata_hash.0 != metadata.hasher().hash() {
+            return Err(EnvelopeError::MetadataHashMismatch.into());
+        }
+
+        if describe_call(&self.call_data, metadata)? != self.call {
+            return Err(EnvelopeError::CallDescriptionMismatch.into());
+        }
+
+        let description = self.describe::<T>(metadata)?;
+        if let Some(genesis_hash) = description.genesis_hash {
+            if genesis_hash.as_ref() != self.genesis_hash {
+                return Err(EnvelopeError::SignedExtensionMismatch("CheckGenesis".into()).into());
+            }
+        }
+        if let Some(spec_version) = description.spec_version {
+            if spec_version != self.spec_version {
+                return Err(
+                    EnvelopeError::SignedExtensionMismatch("CheckSpecVersion".into()).into(),
+                );
+            }
+        }
+        if let Some(transaction_version) = description.transaction_version {
+            if transaction_version != self.transaction_version {
+                return Err(EnvelopeError::SignedExtensionMismatch("CheckTxVersion".into()).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Return the signer payload for this envelope. These are the bytes that must
+    /// be signed in order to produce a valid signature for the transaction.
+    pub fn signer_payload(&self) -> Vec<u8> {
+        let mut bytes = self.call_data.clone();
+        bytes.extend(&self.extra);
+        bytes.extend(&self.additional);
+        super::with_hashed_signer_payload(bytes, |bytes| bytes.to_vec())
+    }
+
+    /// Generate an RFC-0078 metadata proof for the transaction in this envelope. See
+    /// [`PartialTransaction::metadata_proof()`].
+    pub fn metadata_proof(&self, metadata: &Metadata) -> Result<MetadataProof, Error> {
+        metadata
+            .metadata_proof(&self.call_data, Some((&self.extra, &self.additional)))
+            .map_err(|e| MetadataError::MerkleizedMetadata(e).into())
+    }
+
+    /// Verify this envelope against the given client state (see [`TransactionEnvelope::verify()`])
+    /// and then sign it, returning an [`EnvelopeSignature`] that can be handed back to be
+    /// turned into a transaction via [`TransactionEnvelope::to_transaction()`].
+    pub fn sign<T, Signer>(
+        &self,
+        client_state: &ClientState<T>,
+        signer: &Signer,
+    ) -> Result<EnvelopeSignature, Error>
+    where
+        T: Config,
+        Signer: SignerT<T>,
+    {
+        self.verify(client_state)?;
+        let signature = signer.sign(&self.signer_payload());
+        Ok(EnvelopeSignature::new::<T>(&signer.address(), &signature))
+    }
+
+    /// Build a [`Transaction`], ready to submit, from this envelope and the given signature.
+    pub fn to_transaction<T: Config>(&self, signature: &EnvelopeSignature) -> Transaction<T> {
+        Transaction::from_bytes(encode_signed_extrinsic(
+            &signature.address,
+            &signature.signature,
+            &self.extra,
+            &self.call_data,
+        ))
+    }
+}
+
+/// The address and signature produced by signing a [`TransactionEnvelope`].
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct EnvelopeSignature {
+    /// The SCALE encoded address of the signer.
+    #[serde(with = "impl_serde::serialize")]
+    address: Vec<u8>,
+    /// The SCALE encoded signature.
+    #[serde(with = "impl_serde::serialize")]
+    signature: Vec<u8>,
+}
+
+impl EnvelopeSignature {
+    /// Create an [`EnvelopeSignature`] from an address and a signature which was
+    /// produced by signing [`TransactionEnvelope::signer_payload()`].
+    pub fn new<T: Config>(address: &T::Address, signature: &T::Signature) -> Self {
+        EnvelopeSignature {
+            address: address.encode(),
+            signature: signature.encode(),
+        }
+    }
+
+    /// The SCALE encoded address of the signer.
+    pub fn address(&self) -> &[u8] {
+        &self.address
+    }
+
+    /// The SCALE encoded signature.
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// Serialize this signature to a JSON string.
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string(self).map_err(|e| EnvelopeError::Json(e.to_string()).into())
+    }
+
+    /// Deserialize a signature from a JSON string.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|e| EnvelopeError::Json(e.to_string()).into())
+    }
+
+    /// Serialize this signature to a compact (SCALE encoded) binary representation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encode()
+    }
+
+    /// Deserialize a signature from the binary representation given by
+    /// [`EnvelopeSignature::to_bytes()`].
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::decode(&mut bytes)?)
+    }
+}
+
+// Decode some call data using the metadata, returning a description of it.
+fn describe_call(call_data: &[u8], metadata: &Metadata) -> Result<CallDescription, Error> {
+    let (pallet_name, call_name, args) = super::description::decode_call(call_data, metadata)
+        .map_err(|e| match e {
+            Error::Codec(_) | Error::Decode(_) => EnvelopeError::InvalidCallData.into(),
+            e => e,
+        })?;
+
+    Ok(CallDescription {
+        pallet_name,
+        call_name,
+        args: args.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::PolkadotConfig;
+    use crate::test_utils::client_state;
+    use crate::tx;
+    use crate::utils::{AccountId32, MultiAddress, MultiSignature};
+    use scale_value::Value;
+
+    // A signer which hands back a signature derived from the payload, so that
+    // signing the same payload twice produces the same transaction.
+    struct TestSigner;
+
+    impl SignerT<PolkadotConfig> for TestSigner {
+        fn account_id(&self) -> AccountId32 {
+            AccountId32([1; 32])
+        }
+        fn address(&self) -> MultiAddress<AccountId32, ()> {
+            self.account_id().into()
+        }
+        fn sign(&self, signer_payload: &[u8]) -> MultiSignature {
+            let mut signature = [0; 64];
+            signature[..32]
+                .copy_from_slice(&polkadot_sdk::sp_crypto_hashing::blake2_256(signer_payload));
+            MultiSignature::Sr25519(signature)
+        }
+    }
+
+    fn envelope(state: &ClientState<PolkadotConfig>) -> TransactionEnvelope {
+        let call = tx::payload::dynamic("System", "remark", vec![Value::from_bytes("Hello there")]);
+        tx::create_partial_signed(&call, state, Default::default())
+            .unwrap()
+            .to_envelope(state)
+            .unwrap()
+    }
+
+    #[test]
+    fn envelope_roundtrips_via_json_and_bytes() {
+        let state = client_state();
+        let envelope = envelope(&state);
+
+        assert_eq!(envelope.call().pallet_name, "System");
+        assert_eq!(envelope.call().call_name, "remark");
+
+        let json = envelope.to_json().unwrap();
+        assert_eq!(TransactionEnvelope::from_json(&json).unwrap(), envelope);
+
+        let bytes = envelope.to_bytes();
+        assert_eq!(TransactionEnvelope::from_bytes(&bytes).unwrap(), envelope);
+    }
+
+    #[test]
+    fn envelope_verification_catches_mismatches() {
+        let state = client_state();
+        let envelope = envelope(&state);
+        envelope.verify(&state).unwrap();
+
+        let mut other_state = state.clone();
+        other_state.runtime_version.spec_version = 100;
+        assert!(matches!(
+            envelope.verify(&other_state),
+            Err(Error::Envelope(EnvelopeError::SpecVersionMismatch {
+                expected: 100,
+                found: 1
+            }))
+        ));
+
+        let mut other_state = state.clone();
+        other_state.genesis_hash = H256::repeat_byte(2);
+        assert!(matches!(
+            envelope.verify(&other_state),
+            Err(Error::Envelope(EnvelopeError::GenesisHashMismatch))
+        ));
+
+        let mut tampered = envelope.clone();
+        tampered.call.args = "(remark: \"something else\")".into();
+        assert!(matches!(
+            tampered.verify(&state),
+            Err(Error::Envelope(EnvelopeError::CallDescriptionMismatch))
+        ));
+    }
+
+    #[test]
+    fn envelope_describes_signed_extensions() {
+        let state = client_state();
+        let envelope = envelope(&state);
+
+        let description = envelope
+            .describe::<PolkadotConfig>(&state.metadata)
+            .unwrap();
+        assert_eq!(description.call_name, "remark");
+        assert_eq!(description.genesis_hash, Some(state.genesis_hash));
+        assert_eq!(description.spec_version, Some(envelope.spec_version()));
+        assert_eq!(
+            description.transaction_version,
+            Some(envelope.transaction_version())
+        );
+    }
+
+    #[test]
+    fn envelope_verification_catches_mismatched_signed_extensions() {
+        let state = client_state();
+
+        // Sign a different genesis hash to the one that the envelope claims:
+        let mut other_state = state.clone();
+        other_state.genesis_hash = H256::repeat_byte(2);
+        let mut tampered = envelope(&state);
+        tampered.additional = envelope(&other_state).additional;
+        assert!(matches!(
+            tampered.verify(&state),
+            Err(Error::Envelope(EnvelopeError::SignedExtensionMismatch(name))) if name == "CheckGenesis"
+        ));
+
+        // Sign a different spec version to the one that the envelope claims:
+        let mut other_state = state.clone();
+        other_state.runtime_version.spec_version = 100;
+        let mut tampered = envelope(&state);
+        tampered.additional = envelope(&other_state).additional;
+        assert!(matches!(
+            tampered.sign(&state, &TestSigner),
+            Err(Error::Envelope(EnvelopeError::SignedExtensionMismatch(name))) if name == "CheckSpecVersion"
+        ));
+
+        // Extension data that can't be decoded is rejected too:
+        let mut tampered = envelope(&state);
+        tampered.extra.push(0);
+        assert!(matches!(
+            tampered.verify(&state),
+            Err(Error::Envelope(EnvelopeError::InvalidSignedExtensionData))
+        ));
+    }
+
+    #[test]
+    fn envelope_signing_matches_partial_transaction() {
+        let state = client_state();
+        let call = tx::payload::dynamic("System", "remark", vec![Value::from_bytes("Hello there")]);
+        let partial = tx::create_partial_signed(&call, &state, Default::default()).unwrap();
+        let envelope = partial.to_envelope(&state).unwrap();
+        assert_eq!(envelope.signer_payload(), partial.signer_payload());
+
+        let signature = envelope.sign(&state, &TestSigner).unwrap();
+        let signature = EnvelopeSignature::from_json(&signature.to_json().unwrap()).unwrap();
+
+        let from_envelope = envelope.to_transaction::<PolkadotConfig>(&signature);
+        let from_partial = partial.sign(&TestSigner);
+        assert_eq!(from_envelope.encoded(), from_partial.encoded());
+    }
+}
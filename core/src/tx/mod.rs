@@ -54,6 +54,8 @@
 //! println!("Tx: 0x{}", hex::encode(signed_call.encoded()));
 //! ```
 
+pub mod description;
+pub mod envelope;
 pub mod payload;
 pub mod signer;
 
@@ -70,6 +72,7 @@ use signer::Signer as SignerT;
 
 // Expose these here since we expect them in some calls below.
 pub use crate::client::{ClientState, RuntimeVersion};
+pub use subxt_metadata::MetadataProof;
 
 /// Run the validation logic against some extrinsic you'd like to submit. Returns `Ok(())`
 /// if the call is valid (or if it's not possible to check since the call has no validation hash).
@@ -96,7 +99,24 @@ pub fn call_data<Call: Payload>(call: &Call, metadata: &Metadata) -> Result<Vec<
     Ok(bytes)
 }
 
-/// Creates an unsigned extrinsic without submitting it.
+/// Return the version of the extrinsic format that transactions for the node with the given
+/// metadata will be constructed with. This is 4 if the node supports it, and otherwise 5.
+///
+/// We prefer version 4 because it is the most widely supported, and because signed version 5
+/// ("general") transactions rely on the `VerifySignature` transaction extension being present.
+pub fn extrinsic_version(metadata: &Metadata) -> Result<u8, Error> {
+    let extrinsic = metadata.extrinsic();
+    [4, 5]
+        .into_iter()
+        .find(|&version| extrinsic.supports_version(version))
+        .ok_or_else(|| {
+            MetadataError::UnsupportedExtrinsicVersions(extrinsic.supported_versions().to_vec())
+                .into()
+        })
+}
+
+/// Creates an unsigned extrinsic without submitting it. This is a version 4 unsigned or a
+/// version 5 "bare" extrinsic, depending on [`extrinsic_version()`].
 pub fn create_unsigned<T: Config, Call: Payload>(
     call: &Call,
     metadata: &Metadata,
@@ -106,27 +126,23 @@ pub fn create_unsigned<T: Config, Call: Payload>(
     validate(call, metadata)?;
 
     // 2. Encode extrinsic
+    let version = extrinsic_version(metadata)?;
     let extrinsic = {
         let mut encoded_inner = Vec::new();
-        // transaction protocol version (4) (is not signed, so no 1 bit at the front).
-        4u8.encode_to(&mut encoded_inner);
+        // transaction protocol version (no bits set at the front, since it's unsigned/bare).
+        version.encode_to(&mut encoded_inner);
         // encode call data after this byte.
         call.encode_call_data_to(metadata, &mut encoded_inner)?;
         // now, prefix byte length:
-        let len = Compact(
-            u32::try_from(encoded_inner.len()).expect("extrinsic size expected to be <4GB"),
-        );
-        let mut encoded = Vec::new();
-        len.encode_to(&mut encoded);
-        encoded.extend(encoded_inner);
-        encoded
+        with_length_prefix(encoded_inner)
     };
 
     // Wrap in Encoded to ensure that any more "encode" calls leave it in the right state.
     Ok(Transaction::from_bytes(extrinsic))
 }
 
-/// Create a partial extrinsic.
+/// Create a partial extrinsic. Once signed, this will be a version 4 signed or a version 5
+/// "general" extrinsic, depending on [`extrinsic_version()`].
 ///
 /// Note: if not provided, the default account nonce will be set to 0 and the default mortality will be _immortal_.
 /// This is because this method runs offline, and so is unable to fetch the data needed for more appropriate values.
@@ -139,15 +155,30 @@ pub fn create_partial_signed<T: Config, Call: Payload>(
     // with a hash allowing us to do so.
     validate(call, &client_state.metadata)?;
 
-    // 2. SCALE encode call data to bytes (pallet u8, call u8, call params).
+    // 2. Version 5 transactions are signed via the `VerifySignature` extension, so we
+    // can't sign them for a node which doesn't have it.
+    let extrinsic = client_state.metadata.extrinsic();
+    let version = extrinsic_version(&client_state.metadata)?;
+    if version == 5
+        && !extrinsic
+            .signed_extensions()
+            .iter()
+            .any(|e| e.identifier() == "VerifySignature")
+    {
+        return Err(MetadataError::SignedExtensionNotFound("VerifySignature".to_owned()).into());
+    }
+
+    // 3. SCALE encode call data to bytes (pallet u8, call u8, call params).
     let call_data = call_data(call, &client_state.metadata)?;
 
-    // 3. Construct our custom additional/extra params.
+    // 4. Construct our custom additional/extra params.
     let additional_and_extra_params =
         <T::ExtrinsicParams as ExtrinsicParams<T>>::new(client_state, params)?;
 
     // Return these details, ready to construct a signed extrinsic from.
     Ok(PartialTransaction {
+        version,
+        transaction_extensions_version: extrinsic.transaction_extensions_version(),
         call_data,
         additional_and_extra_params,
     })
@@ -186,6 +217,8 @@ where
 /// [`PartialTransaction::sign_with_address_and_signature()`] to apply an existing signature and address
 /// to the transaction.
 pub struct PartialTransaction<T: Config> {
+    version: u8,
+    transaction_extensions_version: u8,
     call_data: Vec<u8>,
     additional_and_extra_params: T::ExtrinsicParams,
 }
@@ -198,17 +231,33 @@ impl<T: Config> PartialTransaction<T> {
     where
         F: for<'a> FnOnce(Cow<'a, [u8]>) -> R,
     {
-        let mut bytes = self.call_data.clone();
-        self.additional_and_extra_params.encode_extra_to(&mut bytes);
-        self.additional_and_extra_params
-            .encode_additional_to(&mut bytes);
-        if bytes.len() > 256 {
-            f(Cow::Borrowed(blake2_256(&bytes).as_ref()))
+        if self.version == 4 {
+            let mut bytes = self.call_data.clone();
+            self.additional_and_extra_params.encode_extra_to(&mut bytes);
+            self.additional_and_extra_params
+                .encode_additional_to(&mut bytes);
+            with_hashed_signer_payload(bytes, f)
         } else {
-            f(Cow::Owned(bytes))
+            // Version 5 signer payloads are the transaction extensions version, the call data
+            // and the extra and additional params of the extensions after `VerifySignature`,
+            // and are always hashed.
+            let mut bytes = Vec::new();
+            self.transaction_extensions_version.encode_to(&mut bytes);
+            bytes.extend_from_slice(&self.call_data);
+            self.additional_and_extra_params
+                .encode_signer_payload_extra_to(&mut bytes);
+            self.additional_and_extra_params
+                .encode_signer_payload_additional_to(&mut bytes);
+            f(Cow::Borrowed(blake2_256(&bytes).as_ref()))
         }
     }
 
+    /// The version of the extrinsic format that this transaction will be encoded with
+    /// once it's signed. See [`extrinsic_version()`].
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
     /// Return the signer payload for this extrinsic. These are the bytes that must
     /// be signed in order to produce a valid signature for the extrinsic.
     pub fn signer_payload(&self) -> Vec<u8> {
@@ -221,6 +270,20 @@ impl<T: Config> PartialTransaction<T> {
         &self.call_data
     }
 
+    /// Generate an RFC-0078 metadata proof for this transaction, containing the type information
+    /// that a hardware signer needs in order to decode and display it. This is intended to be used
+    /// alongside the metadata hash provided via the `CheckMetadataHash` signed extension.
+    pub fn metadata_proof(&self, metadata: &Metadata) -> Result<MetadataProof, Error> {
+        let mut extra = Vec::new();
+        let mut additional = Vec::new();
+        self.additional_and_extra_params.encode_extra_to(&mut extra);
+        self.additional_and_extra_params
+            .encode_additional_to(&mut additional);
+        metadata
+            .metadata_proof(&self.call_data, Some((&extra, &additional)))
+            .map_err(|e| MetadataError::MerkleizedMetadata(e).into())
+    }
+
     /// Convert this [`PartialTransaction`] into a [`Transaction`], ready to submit.
     /// The provided `signer` is responsible for providing the "from" address for the transaction,
     /// as well as providing a signature to attach to it.
@@ -230,48 +293,129 @@ impl<T: Config> PartialTransaction<T> {
     {
         // Given our signer, we can sign the payload representing this extrinsic.
         let signature = self.with_signer_payload(|bytes| signer.sign(&bytes));
-        // Now, use the signature and "from" address to build the extrinsic.
-        self.sign_with_address_and_signature(&signer.address(), &signature)
+        // Now, use the signature and "from" address (or account for version 5
+        // transactions) to build the extrinsic.
+        if self.version == 4 {
+            self.sign_with_address_and_signature(&signer.address(), &signature)
+        } else {
+            self.sign_with_account_and_signature(&signer.account_id(), &signature)
+        }
     }
 
     /// Convert this [`PartialTransaction`] into a [`Transaction`], ready to submit.
     /// An address, and something representing a signature that can be SCALE encoded, are both
     /// needed in order to construct it. If you have a `Signer` to hand, you can use
     /// [`PartialTransaction::sign()`] instead.
+    ///
+    /// This always produces a version 4 signed transaction. If [`PartialTransaction::version()`]
+    /// is 5, use [`PartialTransaction::sign_with_account_and_signature()`] instead.
     pub fn sign_with_address_and_signature(
         &self,
         address: &T::Address,
         signature: &T::Signature,
     ) -> Transaction<T> {
-        // Encode the extrinsic (into the format expected by protocol version 4)
-        let extrinsic = {
-            let mut encoded_inner = Vec::new();
-            // "is signed" + transaction protocol version (4)
-            (0b10000000 + 4u8).encode_to(&mut encoded_inner);
-            // from address for signature
-            address.encode_to(&mut encoded_inner);
-            // the signature
-            signature.encode_to(&mut encoded_inner);
-            // attach custom extra params
-            self.additional_and_extra_params
-                .encode_extra_to(&mut encoded_inner);
-            // and now, call data (remembering that it's been encoded already and just needs appending)
-            encoded_inner.extend(&self.call_data);
-            // now, prefix byte length:
-            let len = Compact(
-                u32::try_from(encoded_inner.len()).expect("extrinsic size expected to be <4GB"),
-            );
-            let mut encoded = Vec::new();
-            len.encode_to(&mut encoded);
-            encoded.extend(encoded_inner);
-            encoded
-        };
+        let mut extra = Vec::new();
+        self.additional_and_extra_params.encode_extra_to(&mut extra);
+
+        // Return an extrinsic ready to be submitted.
+        Transaction::from_bytes(encode_signed_extrinsic(
+            &address.encode(),
+            &signature.encode(),
+            &extra,
+            &self.call_data,
+        ))
+    }
+
+    /// Convert this [`PartialTransaction`] into a version 5 "general" [`Transaction`], ready to
+    /// submit. The account ID and signature are handed to the transaction extensions (namely
+    /// `VerifySignature`) to be encoded. If you have a `Signer` to hand, you can use
+    /// [`PartialTransaction::sign()`] instead.
+    ///
+    /// [`create_partial_signed()`] returns an error if the node only supports version 5
+    /// transactions but has no `VerifySignature` extension, so the signature is always
+    /// carried by that extension here.
+    pub fn sign_with_account_and_signature(
+        &self,
+        account_id: &T::AccountId,
+        signature: &T::Signature,
+    ) -> Transaction<T> {
+        let mut extra = Vec::new();
+        self.additional_and_extra_params
+            .encode_extra_with_signature_to(&account_id.encode(), &signature.encode(), &mut extra);
 
         // Return an extrinsic ready to be submitted.
-        Transaction::from_bytes(extrinsic)
+        Transaction::from_bytes(encode_general_extrinsic(
+            self.transaction_extensions_version,
+            &extra,
+            &self.call_data,
+        ))
+    }
+}
+
+// The signer payload is the call data followed by the extra and additional params. If this
+// is longer than 256 bytes then it is hashed, and the hash is what's handed to `f` to be signed.
+fn with_hashed_signer_payload<F, R>(bytes: Vec<u8>, f: F) -> R
+where
+    F: for<'a> FnOnce(Cow<'a, [u8]>) -> R,
+{
+    if bytes.len() > 256 {
+        f(Cow::Borrowed(blake2_256(&bytes).as_ref()))
+    } else {
+        f(Cow::Owned(bytes))
     }
 }
 
+// Encode a signed extrinsic (into the format expected by protocol version 4) given the
+// already SCALE encoded address, signature, signed extension extra params and call data.
+fn encode_signed_extrinsic(
+    address: &[u8],
+    signature: &[u8],
+    extra: &[u8],
+    call_data: &[u8],
+) -> Vec<u8> {
+    let mut encoded_inner = Vec::new();
+    // "is signed" + transaction protocol version (4)
+    (0b10000000 + 4u8).encode_to(&mut encoded_inner);
+    // from address for signature
+    encoded_inner.extend(address);
+    // the signature
+    encoded_inner.extend(signature);
+    // attach custom extra params
+    encoded_inner.extend(extra);
+    // and now, call data (remembering that it's been encoded already and just needs appending)
+    encoded_inner.extend(call_data);
+    with_length_prefix(encoded_inner)
+}
+
+// Encode a "general" extrinsic (into the format expected by protocol version 5) given the
+// transaction extensions version, the already SCALE encoded extension extra params and call data.
+fn encode_general_extrinsic(
+    transaction_extensions_version: u8,
+    extra: &[u8],
+    call_data: &[u8],
+) -> Vec<u8> {
+    let mut encoded_inner = Vec::new();
+    // "is general" + transaction protocol version (5)
+    (0b01000000 + 5u8).encode_to(&mut encoded_inner);
+    // the version of the transaction extensions that follow
+    transaction_extensions_version.encode_to(&mut encoded_inner);
+    // attach custom extra params
+    encoded_inner.extend(extra);
+    // and now, call data (remembering that it's been encoded already and just needs appending)
+    encoded_inner.extend(call_data);
+    with_length_prefix(encoded_inner)
+}
+
+// Prefix the given encoded extrinsic with its compact encoded length.
+fn with_length_prefix(encoded_inner: Vec<u8>) -> Vec<u8> {
+    let len =
+        Compact(u32::try_from(encoded_inner.len()).expect("extrinsic size expected to be <4GB"));
+    let mut encoded = Vec::new();
+    len.encode_to(&mut encoded);
+    encoded.extend(encoded_inner);
+    encoded
+}
+
 /// This represents a signed transaction that's ready to be submitted.
 /// Use [`Transaction::encoded()`] or [`Transaction::into_encoded()`] to
 /// get the bytes for it, or [`Transaction::hash()`] to get the hash.
@@ -306,3 +450,166 @@ impl<T: Config> Transaction<T> {
         self.encoded.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DefaultExtrinsicParamsBuilder, SubstrateConfig};
+    use crate::test_utils::metadata_with_pallets;
+    use crate::utils::{AccountId32, MultiAddress, MultiSignature, H256};
+    use frame_metadata::v15::SignedExtensionMetadata;
+    use scale_info::{meta_type, TypeInfo};
+    use scale_value::Value;
+
+    #[allow(unused)]
+    #[derive(TypeInfo)]
+    enum RuntimeCall {
+        Test(Call),
+    }
+
+    #[allow(unused, non_camel_case_types)]
+    #[derive(TypeInfo)]
+    enum Call {
+        remark { remark: Vec<u8> },
+    }
+
+    // A signer which always produces the same signature, so that the extrinsic is predictable.
+    struct FakeSigner;
+
+    impl SignerT<SubstrateConfig> for FakeSigner {
+        fn account_id(&self) -> AccountId32 {
+            AccountId32([1; 32])
+        }
+        fn address(&self) -> MultiAddress<AccountId32, u32> {
+            self.account_id().into()
+        }
+        fn sign(&self, _signer_payload: &[u8]) -> MultiSignature {
+            MultiSignature::Sr25519([2; 64])
+        }
+    }
+
+    /// Build a client for a node which only supports version 5 extrinsics, and has the
+    /// given transaction extensions.
+    fn v5_client_state(extensions: &[&'static str]) -> ClientState<SubstrateConfig> {
+        let signed_extensions = extensions
+            .iter()
+            .map(|&identifier| SignedExtensionMetadata {
+                identifier,
+                ty: match identifier {
+                    "CheckNonce" => meta_type::<Compact<u64>>(),
+                    _ => meta_type::<()>(),
+                },
+                additional_signed: match identifier {
+                    "CheckSpecVersion" => meta_type::<u32>(),
+                    "CheckGenesis" => meta_type::<H256>(),
+                    _ => meta_type::<()>(),
+                },
+            })
+            .collect();
+
+        ClientState {
+            metadata: metadata_with_pallets::<RuntimeCall>(
+                vec![("Test", meta_type::<Call>())],
+                5,
+                signed_extensions,
+            ),
+            genesis_hash: H256::repeat_byte(9),
+            runtime_version: RuntimeVersion {
+                spec_version: 1,
+                transaction_version: 2,
+            },
+        }
+    }
+
+    #[test]
+    fn v5_general_extrinsic_is_signed_via_verify_signature() {
+        let state = v5_client_state(&[
+            "CheckSpecVersion",
+            "VerifySignature",
+            "CheckNonce",
+            "CheckGenesis",
+        ]);
+        let call = payload::dynamic("Test", "remark", vec![Value::from_bytes("Hello")]);
+        let params = DefaultExtrinsicParamsBuilder::<SubstrateConfig>::new()
+            .nonce(3)
+            .build();
+        let partial = create_partial_signed(&call, &state, params).unwrap();
+        assert_eq!(partial.version(), 5);
+
+        // Pallet index, call index and the compact encoded remark.
+        let call_data = [&[0u8, 0, 20][..], b"Hello"].concat();
+        assert_eq!(partial.call_data(), &call_data);
+
+        // The extensions version, call data, and then the explicit and implicit params of the
+        // extensions after `VerifySignature` (so the nonce and genesis hash, but not the spec
+        // version), hashed.
+        let payload = [&[0u8][..], &call_data, &[12], &[9; 32]].concat();
+        assert_eq!(partial.signer_payload(), blake2_256(&payload));
+        assert_eq!(
+            hex::encode(partial.signer_payload()),
+            "34b23389ae2aec255239511577c006190f72235a679f26fd05d144147d37c611"
+        );
+
+        // "is general" + version 5, the extensions version, then the extensions: nothing for
+        // `CheckSpecVersion`, the `VerifySignature::Signed` variant with the signature and
+        // account, the nonce and nothing for `CheckGenesis`. The call data comes last.
+        let tx = partial.sign(&FakeSigner);
+        let inner = [
+            &[0b01000101u8, 0, 0][..],
+            &[1],
+            &[2; 64],
+            &[1; 32],
+            &[12],
+            &call_data,
+        ]
+        .concat();
+        let expected = [&Compact(inner.len() as u32).encode()[..], &inner].concat();
+        assert_eq!(tx.encoded(), &expected);
+    }
+
+    #[test]
+    fn v5_description_covers_the_signed_extensions() {
+        let state = v5_client_state(&[
+            "CheckSpecVersion",
+            "VerifySignature",
+            "CheckNonce",
+            "CheckGenesis",
+        ]);
+        let call = payload::dynamic("Test", "remark", vec![Value::from_bytes("Hello")]);
+        let params = DefaultExtrinsicParamsBuilder::<SubstrateConfig>::new()
+            .nonce(3)
+            .build();
+        let partial = create_partial_signed(&call, &state, params).unwrap();
+        let description = partial.describe(&state.metadata).unwrap();
+
+        // Only the extensions after `VerifySignature` are signed, so the spec version and
+        // the (not yet known) signature aren't described.
+        let names: Vec<_> = description
+            .signed_extensions
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, ["CheckNonce", "CheckGenesis"]);
+        assert_eq!(description.nonce, Some(3));
+        assert_eq!(description.genesis_hash, Some(H256::repeat_byte(9)));
+        assert_eq!(description.spec_version, None);
+    }
+
+    #[test]
+    fn v5_signing_requires_verify_signature() {
+        let state = v5_client_state(&["CheckNonce", "CheckGenesis"]);
+        let call = payload::dynamic("Test", "remark", vec![Value::from_bytes("Hello")]);
+        let params = DefaultExtrinsicParamsBuilder::<SubstrateConfig>::new().build();
+
+        let Err(err) = create_partial_signed(&call, &state, params) else {
+            panic!("a v5 transaction can't be signed without VerifySignature");
+        };
+        assert!(matches!(
+            err,
+            Error::Metadata(MetadataError::SignedExtensionNotFound(name)) if name == "VerifySignature"
+        ));
+
+        // Unsigned transactions don't need it.
+        create_unsigned::<SubstrateConfig, _>(&call, &state.metadata).unwrap();
+    }
+}
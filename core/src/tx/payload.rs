@@ -10,11 +10,12 @@ use crate::metadata::Metadata;
 use crate::Error;
 use alloc::borrow::{Cow, ToOwned};
 use alloc::boxed::Box;
+use alloc::format;
 use alloc::string::String;
 
 use alloc::vec::Vec;
-use codec::Encode;
-use scale_encode::EncodeAsFields;
+use codec::{Compact, Encode};
+use scale_encode::{EncodeAsFields, EncodeAsType};
 use scale_value::{Composite, Value, ValueDef, Variant};
 
 /// This represents a transaction payload that can be submitted
@@ -37,6 +38,58 @@ pub trait Payload {
     fn validation_details(&self) -> Option<ValidationDetails<'_>> {
         None
     }
+
+    /// Returns true if this payload wraps some inner call in a `Proxy.proxy` or `Sudo.sudo`
+    /// call. These succeed even if the inner call fails, and instead report the result of the
+    /// inner call in a `Proxy.ProxyExecuted` or `Sudo.Sudid` event.
+    fn reports_inner_result(&self) -> bool {
+        false
+    }
+
+    /// Wrap this payload in a `Proxy.proxy` call, so that it is dispatched on behalf of the
+    /// `real` account (usually a `MultiAddress`). Optionally, `force_proxy_type` can name the
+    /// variant of the chain's `ProxyType` enum that the proxy must have.
+    fn via_proxy<Real>(
+        self,
+        real: Real,
+        force_proxy_type: Option<&str>,
+    ) -> WrappedPayload<Self, Real>
+    where
+        Self: Sized,
+        Real: EncodeAsType,
+    {
+        WrappedPayload {
+            inner: self,
+            wrapper: Wrapper::Proxy {
+                real,
+                force_proxy_type: force_proxy_type.map(ToOwned::to_owned),
+            },
+        }
+    }
+
+    /// Wrap this payload in a `Utility.as_derivative` call, so that it is dispatched
+    /// from the derivative account with the given index.
+    #[allow(clippy::wrong_self_convention)]
+    fn as_derivative(self, index: u16) -> WrappedPayload<Self>
+    where
+        Self: Sized,
+    {
+        WrappedPayload {
+            inner: self,
+            wrapper: Wrapper::AsDerivative { index },
+        }
+    }
+
+    /// Wrap this payload in a `Sudo.sudo` call, so that it is dispatched with root origin.
+    fn sudo(self) -> WrappedPayload<Self>
+    where
+        Self: Sized,
+    {
+        WrappedPayload {
+            inner: self,
+            wrapper: Wrapper::Sudo,
+        }
+    }
 }
 
 macro_rules! boxed_payload {
@@ -55,6 +108,9 @@ macro_rules! boxed_payload {
             fn validation_details(&self) -> Option<ValidationDetails<'_>> {
                 self.as_ref().validation_details()
             }
+            fn reports_inner_result(&self) -> bool {
+                self.as_ref().reports_inner_result()
+            }
         }
     };
 }
@@ -170,7 +226,7 @@ impl<CallData: EncodeAsFields> Payload for DefaultPayload<CallData> {
             .call_variant_by_name(&self.call_name)
             .ok_or_else(|| MetadataError::CallNameNotFound((*self.call_name).to_owned()))?;
 
-        let pallet_index = pallet.index();
+        let pallet_index = pallet.call_index();
         let call_index = call.index;
 
         pallet_index.encode_to(out);
@@ -196,6 +252,100 @@ impl<CallData: EncodeAsFields> Payload for DefaultPayload<CallData> {
     }
 }
 
+/// A transaction payload which wraps another payload in an outer call. This is constructed via
+/// [`Payload::via_proxy()`], [`Payload::as_derivative()`] or [`Payload::sudo()`], and the outer
+/// call is built dynamically against the metadata, so wrappers can be nested and used with
+/// any runtime.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WrappedPayload<Inner, Real = ()> {
+    inner: Inner,
+    wrapper: Wrapper<Real>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Wrapper<Real> {
+    Proxy {
+        real: Real,
+        force_proxy_type: Option<String>,
+    },
+    AsDerivative {
+        index: u16,
+    },
+    Sudo,
+}
+
+impl<Inner, Real> WrappedPayload<Inner, Real> {
+    /// Returns the payload that is being wrapped.
+    pub fn inner(&self) -> &Inner {
+        &self.inner
+    }
+}
+
+impl<Inner: Payload, Real: EncodeAsType> Payload for WrappedPayload<Inner, Real> {
+    fn reports_inner_result(&self) -> bool {
+        match self.wrapper {
+            Wrapper::Proxy { .. } | Wrapper::Sudo => true,
+            Wrapper::AsDerivative { .. } => self.inner.reports_inner_result(),
+        }
+    }
+
+    fn encode_call_data_to(&self, metadata: &Metadata, out: &mut Vec<u8>) -> Result<(), Error> {
+        // The outer call isn't validated, so validate the inner one here instead.
+        super::validate(&self.inner, metadata)?;
+
+        let (pallet_name, call_name) = match &self.wrapper {
+            Wrapper::Proxy { .. } => ("Proxy", "proxy"),
+            Wrapper::AsDerivative { .. } => ("Utility", "as_derivative"),
+            Wrapper::Sudo => ("Sudo", "sudo"),
+        };
+        let pallet = metadata.pallet_by_name_err(pallet_name)?;
+        let call = pallet
+            .call_variant_by_name(call_name)
+            .ok_or_else(|| MetadataError::CallNameNotFound(call_name.to_owned()))?;
+
+        pallet.call_index().encode_to(out);
+        call.index.encode_to(out);
+
+        let types = metadata.types();
+        for field in &call.fields {
+            let ty = field.ty.id;
+            match (&self.wrapper, field.name.as_deref()) {
+                // The inner call is a `Box<RuntimeCall>`, which is encoded
+                // identically to the call data of the inner payload.
+                (_, Some("call")) => self.inner.encode_call_data_to(metadata, out)?,
+                (Wrapper::Proxy { real, .. }, Some("real")) => {
+                    real.encode_as_type_to(ty, types, out)?
+                }
+                (
+                    Wrapper::Proxy {
+                        force_proxy_type, ..
+                    },
+                    Some("force_proxy_type"),
+                ) => {
+                    let proxy_type = match force_proxy_type {
+                        Some(name) => Value::unnamed_variant(
+                            "Some",
+                            [Value::unnamed_variant(name.as_str(), [])],
+                        ),
+                        None => Value::unnamed_variant("None", []),
+                    };
+                    proxy_type.encode_as_type_to(ty, types, out)?
+                }
+                (Wrapper::AsDerivative { index }, Some("index")) => {
+                    index.encode_as_type_to(ty, types, out)?
+                }
+                (_, name) => {
+                    return Err(scale_encode::Error::custom_string(format!(
+                        "Unexpected field {name:?} in {pallet_name}.{call_name}"
+                    ))
+                    .into())
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Construct a transaction at runtime; essentially an alias to [`DefaultPayload::new()`]
 /// which provides a [`Composite`] value for the call data.
 pub fn dynamic(
@@ -205,3 +355,263 @@ pub fn dynamic(
 ) -> DynamicPayload {
     DefaultPayload::new(pallet_name, call_name, call_data.into())
 }
+
+/// The `Utility` pallet call used to combine payloads into a [`BatchPayload`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BatchMode {
+    /// `Utility.batch`: dispatch the calls in order, stopping at the first one which fails.
+    /// A `Utility.BatchInterrupted` event is emitted if this happens.
+    Batch,
+    /// `Utility.batch_all`: dispatch the calls in order, reverting all of them if any one fails.
+    BatchAll,
+    /// `Utility.force_batch`: dispatch every call, carrying on even if some of them fail.
+    ForceBatch,
+}
+
+impl BatchMode {
+    /// The name of the `Utility` pallet call that this mode corresponds to.
+    pub fn call_name(&self) -> &'static str {
+        match self {
+            BatchMode::Batch => "batch",
+            BatchMode::BatchAll => "batch_all",
+            BatchMode::ForceBatch => "force_batch",
+        }
+    }
+}
+
+/// A transaction payload which combines several other payloads into a single
+/// `Utility` pallet batch call. Construct one via [`batch()`].
+///
+/// Each payload is encoded into the outer call enum when the [`BatchPayload`] is constructed,
+/// so it can only be submitted to a chain with the same metadata as was used to construct it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BatchPayload {
+    mode: BatchMode,
+    calls: Vec<Vec<u8>>,
+}
+
+impl BatchPayload {
+    /// The `Utility` pallet call that will be used to batch the payloads.
+    pub fn mode(&self) -> BatchMode {
+        self.mode
+    }
+
+    /// The encoded call data of each of the batched payloads, in the order that they
+    /// were provided. The index of a call here lines up with the index reported in any
+    /// `Utility.BatchInterrupted` event.
+    pub fn calls(&self) -> &[Vec<u8>] {
+        &self.calls
+    }
+}
+
+impl Payload for BatchPayload {
+    fn encode_call_data_to(&self, metadata: &Metadata, out: &mut Vec<u8>) -> Result<(), Error> {
+        let pallet = metadata.pallet_by_name_err("Utility")?;
+        let call_name = self.mode.call_name();
+        let call = pallet
+            .call_variant_by_name(call_name)
+            .ok_or_else(|| MetadataError::CallNameNotFound(call_name.to_owned()))?;
+
+        pallet.call_index().encode_to(out);
+        call.index.encode_to(out);
+
+        // The calls are a `Vec<RuntimeCall>`, and each one is already encoded.
+        Compact(self.calls.len() as u32).encode_to(out);
+        for call in &self.calls {
+            out.extend(call);
+        }
+        Ok(())
+    }
+}
+
+/// Combine several payloads (which may be static or dynamic, and may come from different
+/// codegen modules) into a single `Utility` pallet batch call using the given [`BatchMode`].
+/// Each payload is validated against the metadata and then encoded into the outer call enum.
+pub fn batch<P: Payload>(
+    mode: BatchMode,
+    payloads: impl IntoIterator<Item = P>,
+    metadata: &Metadata,
+) -> Result<BatchPayload, Error> {
+    // Check up front that the chain supports the batch call we'll be making.
+    let call_name = mode.call_name();
+    metadata
+        .pallet_by_name_err("Utility")?
+        .call_variant_by_name(call_name)
+        .ok_or_else(|| MetadataError::CallNameNotFound(call_name.to_owned()))?;
+
+    let calls = payloads
+        .into_iter()
+        .map(|payload| {
+            super::validate(&payload, metadata)?;
+            payload.encode_call_data(metadata)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(BatchPayload { mode, calls })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::metadata_with_pallets;
+    use crate::utils::{AccountId32, MultiAddress};
+    use scale_info::{meta_type, TypeInfo};
+
+    // The calls of a runtime with the pallets needed to wrap and batch calls.
+    #[allow(unused)]
+    #[derive(TypeInfo)]
+    enum RuntimeCall {
+        System(SystemCall),
+        Utility(UtilityCall),
+        Sudo(SudoCall),
+        Proxy(ProxyCall),
+    }
+
+    #[allow(unused, non_camel_case_types)]
+    #[derive(TypeInfo)]
+    enum SystemCall {
+        remark { remark: Vec<u8> },
+    }
+
+    #[allow(unused, non_camel_case_types)]
+    #[derive(TypeInfo)]
+    enum UtilityCall {
+        batch { calls: Vec<RuntimeCall> },
+        batch_all { calls: Vec<RuntimeCall> },
+        force_batch { calls: Vec<RuntimeCall> },
+        as_derivative { index: u16, call: Box<RuntimeCall> },
+    }
+
+    #[allow(unused, non_camel_case_types)]
+    #[derive(TypeInfo)]
+    enum SudoCall {
+        sudo { call: Box<RuntimeCall> },
+    }
+
+    #[allow(unused, non_camel_case_types)]
+    #[derive(TypeInfo)]
+    enum ProxyCall {
+        proxy {
+            real: MultiAddress<AccountId32, ()>,
+            force_proxy_type: Option<ProxyType>,
+            call: Box<RuntimeCall>,
+        },
+    }
+
+    #[allow(unused)]
+    #[derive(TypeInfo)]
+    enum ProxyType {
+        Any,
+        NonTransfer,
+    }
+
+    fn metadata() -> Metadata {
+        metadata_with_pallets::<RuntimeCall>(
+            vec![
+                ("System", meta_type::<SystemCall>()),
+                ("Utility", meta_type::<UtilityCall>()),
+                ("Sudo", meta_type::<SudoCall>()),
+                ("Proxy", meta_type::<ProxyCall>()),
+            ],
+            4,
+            vec![],
+        )
+    }
+
+    #[test]
+    fn batch_encodes_calls_into_utility_call() {
+        let metadata = metadata();
+        let payloads: Vec<Box<dyn Payload>> = vec![
+            Box::new(dynamic("System", "remark", vec![Value::from_bytes("a")])),
+            Box::new(dynamic("System", "remark", vec![Value::from_bytes("b")])),
+        ];
+        let remarks: Vec<_> = payloads
+            .iter()
+            .map(|p| p.encode_call_data(&metadata).unwrap())
+            .collect();
+
+        let batch = batch(BatchMode::BatchAll, payloads, &metadata).unwrap();
+        assert_eq!(batch.calls(), &remarks[..]);
+
+        // This should be identical to building the nested call dynamically:
+        let nested = dynamic(
+            "Utility",
+            "batch_all",
+            vec![Value::unnamed_composite([
+                dynamic("System", "remark", vec![Value::from_bytes("a")]).into_value(),
+                dynamic("System", "remark", vec![Value::from_bytes("b")]).into_value(),
+            ])],
+        );
+        assert_eq!(
+            batch.encode_call_data(&metadata).unwrap(),
+            nested.encode_call_data(&metadata).unwrap()
+        );
+    }
+
+    #[test]
+    fn wrapped_payloads_are_encoded_like_nested_calls() {
+        let metadata = metadata();
+        let remark = || dynamic("System", "remark", vec![Value::from_bytes("a")]);
+
+        let sudo = dynamic("Sudo", "sudo", vec![remark().into_value()]);
+        assert_eq!(
+            remark().sudo().encode_call_data(&metadata).unwrap(),
+            sudo.encode_call_data(&metadata).unwrap()
+        );
+
+        let derivative = dynamic(
+            "Utility",
+            "as_derivative",
+            vec![Value::u128(3), remark().into_value()],
+        );
+        assert_eq!(
+            remark()
+                .as_derivative(3)
+                .encode_call_data(&metadata)
+                .unwrap(),
+            derivative.encode_call_data(&metadata).unwrap()
+        );
+    }
+
+    #[test]
+    fn only_proxy_and_sudo_wrappers_report_inner_results() {
+        let remark = || dynamic("System", "remark", vec![Value::from_bytes("a")]);
+        assert!(!remark().reports_inner_result());
+        assert!(!remark().as_derivative(3).reports_inner_result());
+        assert!(remark().sudo().reports_inner_result());
+        assert!(remark().sudo().as_derivative(3).reports_inner_result());
+        assert!(remark().via_proxy((), None).reports_inner_result());
+    }
+
+    #[test]
+    fn via_proxy_encodes_proxy_call() {
+        let metadata = metadata();
+        let remark = || dynamic("System", "remark", vec![Value::from_bytes("a")]);
+        let real = || MultiAddress::<AccountId32, ()>::Id(AccountId32([1; 32]));
+        let remark_data = remark().encode_call_data(&metadata).unwrap();
+
+        // Proxy pallet and call index, `MultiAddress::Id` and the account, then the
+        // optional proxy type and the call being proxied.
+        let expected =
+            |proxy_type: &[u8]| [&[3u8, 0, 0][..], &[1; 32], proxy_type, &remark_data].concat();
+
+        let forced = remark().via_proxy(real(), Some("NonTransfer"));
+        assert_eq!(
+            forced.encode_call_data(&metadata).unwrap(),
+            expected(&[1, 1])
+        );
+
+        let any = remark().via_proxy(real(), None);
+        assert_eq!(any.encode_call_data(&metadata).unwrap(), expected(&[0]));
+    }
+
+    #[test]
+    fn batch_rejects_unknown_calls() {
+        let metadata = metadata();
+        let payloads = vec![dynamic("System", "not_a_call", Vec::<Value>::new())];
+        assert!(matches!(
+            batch(BatchMode::Batch, payloads, &metadata),
+            Err(Error::Metadata(MetadataError::CallNameNotFound(_)))
+        ));
+    }
+}
@@ -70,6 +70,25 @@ impl Era {
             phase: quantized_phase,
         }
     }
+
+    /// Get the block number of the start of the era whose properties this object
+    /// describes that `current` belongs to.
+    pub fn birth(self, current: u64) -> u64 {
+        match self {
+            Self::Immortal => 0,
+            Self::Mortal { period, phase } => {
+                (current.max(phase) - phase) / period * period + phase
+            }
+        }
+    }
+
+    /// Get the block number of the first block at which the era has ended.
+    pub fn death(self, current: u64) -> u64 {
+        match self {
+            Self::Immortal => u64::MAX,
+            Self::Mortal { period, .. } => self.birth(current) + period,
+        }
+    }
 }
 
 // Both copied from `sp_runtime::generic::Era`; this is the wire interface and so
@@ -105,3 +124,101 @@ impl codec::Decode for Era {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codec::{Decode, Encode};
+
+    #[test]
+    fn immortal_era_never_dies() {
+        for current in [0, 1, 1_000, u64::MAX] {
+            assert_eq!(Era::Immortal.birth(current), 0);
+            assert_eq!(Era::Immortal.death(current), u64::MAX);
+        }
+    }
+
+    #[test]
+    fn mortal_era_is_rounded_to_a_valid_period() {
+        assert_eq!(
+            Era::mortal(64, 42),
+            Era::Mortal {
+                period: 64,
+                phase: 42
+            }
+        );
+        assert_eq!(
+            Era::mortal(200, 513),
+            Era::Mortal {
+                period: 256,
+                phase: 1
+            }
+        );
+        assert_eq!(
+            Era::mortal(1, 5),
+            Era::Mortal {
+                period: 4,
+                phase: 1
+            }
+        );
+        assert_eq!(
+            Era::mortal(1 << 20, 32),
+            Era::Mortal {
+                period: 1 << 16,
+                phase: 32
+            }
+        );
+        // Long periods have their phase quantized.
+        assert_eq!(
+            Era::mortal(1 << 16, 12_345),
+            Era::Mortal {
+                period: 1 << 16,
+                phase: 12_336
+            }
+        );
+    }
+
+    #[test]
+    fn mortal_era_birth_and_death() {
+        let era = Era::mortal(64, 42);
+        assert_eq!(era.birth(42), 42);
+        assert_eq!(era.death(42), 106);
+        // Blocks later in the same era give the same birth and death.
+        assert_eq!(era.birth(105), 42);
+        assert_eq!(era.death(105), 106);
+        // Blocks before the phase are treated as being in the first era.
+        assert_eq!(era.birth(10), 42);
+        assert_eq!(era.death(10), 106);
+    }
+
+    #[test]
+    fn mortal_era_wraps_around_to_the_next_period() {
+        let era = Era::mortal(4, 6);
+        assert_eq!(
+            era,
+            Era::Mortal {
+                period: 4,
+                phase: 2
+            }
+        );
+        for current in 6..10 {
+            assert_eq!(era.birth(current), 6);
+            assert_eq!(era.death(current), 10);
+        }
+        assert_eq!(era.birth(10), 10);
+        assert_eq!(era.death(10), 14);
+    }
+
+    #[test]
+    fn era_encoding_roundtrips() {
+        for era in [
+            Era::Immortal,
+            Era::mortal(4, 6),
+            Era::mortal(64, 42),
+            Era::mortal(1 << 16, 12_345),
+        ] {
+            let encoded = era.encode();
+            assert_eq!(Era::decode(&mut &*encoded).unwrap(), era);
+        }
+    }
+}
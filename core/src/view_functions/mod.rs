@@ -0,0 +1,312 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Encode pallet view function payloads, decode the associated values returned from them, and
+//! validate static view function payloads.
+//!
+//! Pallet view functions are read-only queries declared in pallets. They are all executed
+//! through a single runtime API, [`CALL_NAME`], which is handed the view function's query ID
+//! along with its encoded arguments.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use subxt_core::view_functions;
+//! use subxt_core::metadata;
+//! use subxt_core::dynamic::Value;
+//!
+//! // Some metadata which contains the pallet view functions that we want to call:
+//! let metadata_bytes = std::fs::read("metadata_with_view_functions.scale").unwrap();
+//! let metadata = metadata::decode_from(&metadata_bytes[..]).unwrap();
+//!
+//! // Build a payload to call the "get_value" view function in some pallet:
+//! let payload = view_functions::payload::dynamic("Example", "get_value", vec![Value::u128(1)]);
+//!
+//! // We can validate that the payload is compatible with the given metadata.
+//! view_functions::validate(&payload, &metadata).unwrap();
+//!
+//! // Encode the payload arguments to hand to a node, which we'd hand to the runtime API
+//! // named `view_functions::CALL_NAME`:
+//! let _call_args = view_functions::call_args(&payload, &metadata).unwrap();
+//!
+//! // If we were to obtain a value back from the node, we could
+//! // then decode it using the same payload and metadata like so:
+//! let value_bytes = hex::decode("00040c").unwrap();
+//! let value = view_functions::decode_value(&mut &*value_bytes, &payload, &metadata).unwrap();
+//!
+//! println!("Value: {:?}", value.to_value().unwrap());
+//! ```
+
+pub mod payload;
+
+use crate::error::{Error, MetadataError, ViewFunctionError};
+use crate::metadata::{DecodeWithMetadata, Metadata};
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::vec::Vec;
+use codec::{Decode, Encode};
+use payload::Payload;
+
+/// The name of the runtime API call through which all pallet view functions are executed.
+pub const CALL_NAME: &str = "RuntimeViewFunction_execute_view_function";
+
+/// Run the validation logic against some view function payload you'd like to use. Returns `Ok(())`
+/// if the payload is valid (or if it's not possible to check since the payload has no validation hash).
+/// Return an error if the payload was not valid or something went wrong trying to validate it (ie
+/// the view function in question does not exist at all)
+pub fn validate<P: Payload>(payload: &P, metadata: &Metadata) -> Result<(), Error> {
+    let Some(static_hash) = payload.validation_hash() else {
+        return Ok(());
+    };
+
+    let pallet = metadata.pallet_by_name_err(payload.pallet_name())?;
+
+    let Some(runtime_hash) = pallet.view_function_hash(payload.function_name()) else {
+        return Err(MetadataError::IncompatibleCodegen.into());
+    };
+    if static_hash != runtime_hash {
+        return Err(MetadataError::IncompatibleCodegen.into());
+    }
+    Ok(())
+}
+
+/// Return the encoded arguments to hand to the [`CALL_NAME`] runtime API in order to
+/// execute the view function given by the payload. These are the view function's query ID
+/// followed by the SCALE encoded view function arguments.
+pub fn call_args<P: Payload>(payload: &P, metadata: &Metadata) -> Result<Vec<u8>, Error> {
+    let view_function = metadata
+        .pallet_by_name_err(payload.pallet_name())?
+        .view_function_by_name(payload.function_name())
+        .ok_or_else(|| MetadataError::ViewFunctionNotFound(payload.function_name().to_owned()))?;
+
+    let mut out = view_function.query_id().to_vec();
+    payload.encode_args(metadata)?.encode_to(&mut out);
+    Ok(out)
+}
+
+/// Decode the value bytes returned from executing the view function given by the payload.
+pub fn decode_value<P: Payload>(
+    bytes: &mut &[u8],
+    payload: &P,
+    metadata: &Metadata,
+) -> Result<P::ReturnType, Error> {
+    let view_function = metadata
+        .pallet_by_name_err(payload.pallet_name())?
+        .view_function_by_name(payload.function_name())
+        .ok_or_else(|| MetadataError::ViewFunctionNotFound(payload.function_name().to_owned()))?;
+
+    // The runtime hands back a `Result<Vec<u8>, ViewFunctionDispatchError>`.
+    let value_bytes = match <Result<Vec<u8>, ()>>::decode(&mut &**bytes) {
+        Ok(Ok(value_bytes)) => value_bytes,
+        _ => {
+            let error_bytes = bytes.get(1..).unwrap_or_default();
+            return Err(dispatch_error(error_bytes, metadata).into());
+        }
+    };
+
+    let val = <P::ReturnType as DecodeWithMetadata>::decode_with_metadata(
+        &mut &value_bytes[..],
+        view_function.output_ty(),
+        metadata,
+    )?;
+
+    Ok(val)
+}
+
+/// Describe the error returned from a view function which failed to execute, using the
+/// error type given in the metadata for the [`CALL_NAME`] runtime API if we can find it.
+fn dispatch_error(error_bytes: &[u8], metadata: &Metadata) -> ViewFunctionError {
+    let error_ty = metadata
+        .runtime_api_trait_by_name("RuntimeViewFunction")
+        .and_then(|api| api.method_by_name("execute_view_function"))
+        .and_then(|method| metadata.types().resolve(method.output_ty()))
+        .and_then(|ty| match &ty.type_def {
+            scale_info::TypeDef::Variant(v) => v.variants.iter().find(|v| v.name == "Err"),
+            _ => None,
+        })
+        .and_then(|variant| variant.fields.first())
+        .map(|field| field.ty.id);
+
+    let description = error_ty
+        .and_then(|ty| {
+            scale_value::scale::decode_as_type(&mut &*error_bytes, ty, metadata.types()).ok()
+        })
+        .map(|value| format!("{value}"))
+        .unwrap_or_else(|| format!("0x{}", hex::encode(error_bytes)));
+
+    ViewFunctionError::ExecutionFailed(description)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamic::Value;
+    use alloc::vec;
+    use frame_metadata::v16::{
+        CustomMetadata, DeprecationStatus, ExtrinsicMetadata, OuterEnums, PalletMetadata,
+        PalletViewFunctionMetadata, PalletViewFunctionParamMetadata, RuntimeApiMetadata,
+        RuntimeApiMethodMetadata, RuntimeMetadataV16,
+    };
+    use frame_metadata::RuntimeMetadataPrefixed;
+    use scale_info::{meta_type, TypeInfo};
+
+    const QUERY_ID: [u8; 32] = [1; 32];
+
+    /// The error returned from the runtime when a view function can't be executed.
+    #[allow(unused)]
+    #[derive(Encode, TypeInfo)]
+    enum ViewFunctionDispatchError {
+        NotImplemented,
+        NotFound([u8; 32]),
+        Codec,
+    }
+
+    /// Build metadata with a "Test" pallet containing a `get_value(key: u32) -> u64` view
+    /// function, along with the runtime API that view functions are executed through.
+    fn metadata() -> Metadata {
+        let pallets = vec![PalletMetadata {
+            name: "Test",
+            storage: None,
+            calls: None,
+            event: None,
+            constants: vec![],
+            error: None,
+            associated_types: vec![],
+            view_functions: vec![PalletViewFunctionMetadata {
+                name: "get_value",
+                id: QUERY_ID,
+                inputs: vec![PalletViewFunctionParamMetadata {
+                    name: "key",
+                    ty: meta_type::<u32>(),
+                }],
+                output: meta_type::<u64>(),
+                docs: vec![],
+                deprecation_info: DeprecationStatus::NotDeprecated,
+            }],
+            index: 0,
+            docs: vec![],
+            deprecation_info: DeprecationStatus::NotDeprecated,
+        }];
+
+        let apis = vec![RuntimeApiMetadata {
+            name: "RuntimeViewFunction",
+            methods: vec![RuntimeApiMethodMetadata {
+                name: "execute_view_function",
+                inputs: vec![],
+                output: meta_type::<Result<Vec<u8>, ViewFunctionDispatchError>>(),
+                docs: vec![],
+                deprecation_info: DeprecationStatus::NotDeprecated,
+            }],
+            docs: vec![],
+            deprecation_info: DeprecationStatus::NotDeprecated,
+            version: 1,
+        }];
+
+        let extrinsic = ExtrinsicMetadata {
+            versions: vec![4],
+            address_ty: meta_type::<()>(),
+            signature_ty: meta_type::<()>(),
+            transaction_extensions_by_version: Default::default(),
+            transaction_extensions: vec![],
+        };
+
+        let meta = RuntimeMetadataV16::new(
+            pallets,
+            extrinsic,
+            apis,
+            OuterEnums {
+                call_enum_ty: meta_type::<()>(),
+                event_enum_ty: meta_type::<()>(),
+                error_enum_ty: meta_type::<()>(),
+            },
+            CustomMetadata {
+                map: Default::default(),
+            },
+        );
+        let runtime_metadata: RuntimeMetadataPrefixed = meta.into();
+        let metadata: subxt_metadata::Metadata = runtime_metadata.try_into().unwrap();
+        metadata.into()
+    }
+
+    fn get_value(key: u128) -> payload::DynamicPayload {
+        payload::dynamic("Test", "get_value", vec![Value::u128(key)])
+    }
+
+    #[test]
+    fn call_args_are_query_id_and_encoded_args() {
+        let args = call_args(&get_value(7), &metadata()).unwrap();
+
+        // The arguments follow the query ID as a length prefixed byte vector.
+        let mut expected = QUERY_ID.to_vec();
+        7u32.encode().encode_to(&mut expected);
+        assert_eq!(args, expected);
+    }
+
+    #[test]
+    fn call_args_fail_for_unknown_view_function() {
+        let payload = payload::dynamic("Test", "get_other_value", Vec::<Value>::new());
+        assert!(matches!(
+            call_args(&payload, &metadata()),
+            Err(Error::Metadata(MetadataError::ViewFunctionNotFound(name))) if name == "get_other_value"
+        ));
+    }
+
+    #[test]
+    fn decodes_value_returned_from_view_function() {
+        let bytes = Ok::<_, ()>(5u64.encode()).encode();
+        let value = decode_value(&mut &*bytes, &get_value(7), &metadata()).unwrap();
+        assert_eq!(value.to_value().unwrap().as_u128(), Some(5));
+    }
+
+    #[test]
+    fn decodes_error_returned_from_view_function() {
+        let bytes = Err::<Vec<u8>, _>(ViewFunctionDispatchError::Codec).encode();
+        let result = decode_value(&mut &*bytes, &get_value(7), &metadata());
+        let Err(Error::ViewFunction(ViewFunctionError::ExecutionFailed(description))) = result
+        else {
+            panic!("expected the view function to fail");
+        };
+        assert!(description.contains("Codec"), "{description}");
+    }
+
+    #[test]
+    fn describes_undecodable_error_as_hex() {
+        // There's no fourth error variant, so the error can't be decoded.
+        let bytes = [1, 3];
+        let result = decode_value(&mut &bytes[..], &get_value(7), &metadata());
+        let Err(Error::ViewFunction(ViewFunctionError::ExecutionFailed(description))) = result
+        else {
+            panic!("expected the view function to fail");
+        };
+        assert_eq!(description, "0x03");
+    }
+
+    #[test]
+    fn validates_static_payload_hash() {
+        let metadata = metadata();
+        let hash = metadata
+            .pallet_by_name("Test")
+            .and_then(|pallet| pallet.view_function_hash("get_value"))
+            .unwrap();
+        let valid = payload::StaticPayload::<_, u64>::new_static(
+            "Test",
+            "get_value",
+            Value::unnamed_composite([Value::u128(7)]),
+            hash,
+        );
+        assert!(validate(&valid, &metadata).is_ok());
+
+        let invalid = payload::StaticPayload::<_, u64>::new_static(
+            "Test",
+            "get_value",
+            Value::unnamed_composite([Value::u128(7)]),
+            [0; 32],
+        );
+        assert!(matches!(
+            validate(&invalid, &metadata),
+            Err(Error::Metadata(MetadataError::IncompatibleCodegen))
+        ));
+        assert!(validate(&invalid.unvalidated(), &metadata).is_ok());
+    }
+}
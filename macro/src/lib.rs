@@ -55,7 +55,13 @@ struct RuntimeMetadataArgs {
     #[darling(default)]
     no_default_substitutions: bool,
     #[darling(default)]
+    compatibility_report: bool,
+    #[darling(default)]
     unstable_metadata: darling::util::Flag,
+    #[darling(multiple)]
+    allow_item: Vec<String>,
+    #[darling(multiple)]
+    deny_item: Vec<String>,
     #[cfg(feature = "runtime-metadata-path")]
     #[darling(default)]
     runtime_path: Option<String>,
@@ -124,10 +130,17 @@ fn subxt_inner(args: TokenStream, item_mod: syn::ItemMod) -> Result<TokenStream,
     if args.no_default_substitutions {
         codegen.disable_default_substitutes();
     }
+    if args.compatibility_report {
+        codegen.compatibility_report();
+    }
     if !args.generate_docs.is_present() {
         codegen.no_docs()
     }
 
+    // Only generate code for the items asked for:
+    codegen.set_allowed_items(args.allow_item);
+    codegen.set_denied_items(args.deny_item);
+
     // Configure derives:
     codegen.set_additional_global_derives(
         args.derive_for_all_types
@@ -8,6 +8,7 @@ use alloc::string::String;
 
 mod v14;
 mod v15;
+mod v16;
 
 /// An error emitted if something goes wrong converting [`frame_metadata`]
 /// types into [`crate::Metadata`].
@@ -24,6 +25,8 @@ pub enum TryFromError {
     TypeNameNotFound(String),
     /// Invalid type path.
     InvalidTypePath(String),
+    /// A transaction extension index was given which doesn't point to any transaction extension.
+    TransactionExtensionNotFound(u32),
 }
 
 impl Display for TryFromError {
@@ -45,6 +48,10 @@ impl Display for TryFromError {
                 "Type name {e} is expected but not found in the type registry"
             ),
             TryFromError::InvalidTypePath(e) => write!(f, "Type has an invalid path {e}"),
+            TryFromError::TransactionExtensionNotFound(e) => write!(
+                f,
+                "Transaction extension index {e} is expected but not found in the metadata"
+            ),
         }
     }
 }
@@ -108,9 +115,7 @@ impl TryFrom<frame_metadata::RuntimeMetadataPrefixed> for crate::Metadata {
             }
             frame_metadata::RuntimeMetadata::V14(m) => m.try_into(),
             frame_metadata::RuntimeMetadata::V15(m) => m.try_into(),
-            frame_metadata::RuntimeMetadata::V16(_opaque) => {
-                Err(TryFromError::UnsupportedMetadataVersion(16))
-            }
+            frame_metadata::RuntimeMetadata::V16(m) => m.try_into(),
         }
     }
 }
@@ -12,6 +12,8 @@ use crate::{
     StorageEntryModifier, StorageEntryType, StorageHasher, StorageMetadata,
 };
 use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::vec;
 use frame_metadata::v15;
 use hashbrown::HashMap;
 use scale_info::form::PortableForm;
@@ -57,6 +59,7 @@ mod from_v15 {
                     PalletMetadataInner {
                         name,
                         index: p.index,
+                        call_index: p.index,
                         storage,
                         call_ty: p.calls.map(|c| c.ty.id),
                         call_variant_index,
@@ -65,7 +68,15 @@ mod from_v15 {
                         error_ty: p.error.map(|e| e.ty.id),
                         error_variant_index,
                         constants: constants.collect(),
+                        // View functions, associated types and deprecation
+                        // info were introduced in V16 metadata.
+                        view_functions: OrderedMap::new(),
+                        associated_types: vec![],
                         docs: p.docs,
+                        deprecation_info: Default::default(),
+                        call_deprecation_info: BTreeMap::new(),
+                        event_deprecation_info: BTreeMap::new(),
+                        error_deprecation_info: BTreeMap::new(),
                     },
                 );
             }
@@ -85,6 +96,7 @@ mod from_v15 {
             Ok(Metadata {
                 types: m.types,
                 pallets,
+                pallets_by_call_index: pallets_by_index.clone(),
                 pallets_by_index,
                 extrinsic: from_extrinsic_metadata(m.extrinsic),
                 runtime_ty: m.ty.id,
@@ -113,11 +125,18 @@ mod from_v15 {
     fn from_extrinsic_metadata(value: v15::ExtrinsicMetadata<PortableForm>) -> ExtrinsicMetadata {
         ExtrinsicMetadata {
             version: value.version,
-            signed_extensions: value
-                .signed_extensions
-                .into_iter()
-                .map(from_signed_extension_metadata)
-                .collect(),
+            // Metadata prior to V16 only advertises a single extrinsic version,
+            // and has no notion of transaction extension versions.
+            supported_versions: vec![value.version],
+            transaction_extensions_version: 0,
+            signed_extensions_by_version: BTreeMap::from([(
+                0,
+                value
+                    .signed_extensions
+                    .into_iter()
+                    .map(from_signed_extension_metadata)
+                    .collect(),
+            )]),
             address_ty: value.address_ty.id,
             call_ty: value.call_ty.id,
             signature_ty: value.signature_ty.id,
@@ -169,6 +188,7 @@ mod from_v15 {
             entry_type: from_storage_entry_type(s.ty),
             default: s.default,
             docs: s.docs,
+            deprecation_info: Default::default(),
         }
     }
 
@@ -181,6 +201,7 @@ mod from_v15 {
             ty: s.ty.id,
             value: s.value,
             docs: s.docs,
+            deprecation_info: Default::default(),
         }
     }
 
@@ -199,6 +220,7 @@ mod from_v15 {
                     (name.clone(), from_runtime_api_method_metadata(name, m))
                 })
                 .collect(),
+            deprecation_info: Default::default(),
         }
     }
 
@@ -215,6 +237,7 @@ mod from_v15 {
                 .collect(),
             output_ty: s.output.id,
             docs: s.docs,
+            deprecation_info: Default::default(),
         }
     }
 
@@ -331,11 +354,15 @@ mod into_v15 {
     }
 
     fn from_extrinsic_metadata(e: ExtrinsicMetadata) -> v15::ExtrinsicMetadata<PortableForm> {
+        // V15 metadata can only describe a single extrinsic version and set of signed
+        // extensions, so prefer the version 4 format where it's supported.
+        let version = if e.supports_version(4) { 4 } else { e.version };
         v15::ExtrinsicMetadata {
-            version: e.version,
+            version,
             signed_extensions: e
-                .signed_extensions
-                .into_iter()
+                .signed_extensions()
+                .iter()
+                .cloned()
                 .map(from_signed_extension_metadata)
                 .collect(),
             address_ty: e.address_ty.into(),
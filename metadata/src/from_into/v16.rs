@@ -0,0 +1,692 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::TryFromError;
+
+use crate::utils::variant_index::VariantIndex;
+use crate::{
+    utils::ordered_map::OrderedMap, ArcStr, AssociatedTypeMetadata, ConstantMetadata,
+    DeprecationInfo, ExtrinsicMetadata, Metadata, OuterEnumsMetadata, PalletMetadataInner,
+    RuntimeApiMetadataInner, RuntimeApiMethodMetadata, RuntimeApiMethodParamMetadata,
+    SignedExtensionMetadata, StorageEntryMetadata, StorageEntryModifier, StorageEntryType,
+    StorageHasher, StorageMetadata, ViewFunctionMetadata, ViewFunctionParamMetadata,
+};
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use frame_metadata::{v15, v16};
+use hashbrown::HashMap;
+use scale_info::{form::PortableForm, PortableRegistry};
+
+impl TryFrom<v16::RuntimeMetadataV16> for Metadata {
+    type Error = TryFromError;
+    fn try_from(mut m: v16::RuntimeMetadataV16) -> Result<Self, TryFromError> {
+        let mut pallets = OrderedMap::new();
+        let mut pallets_by_index = HashMap::new();
+        for (pos, p) in m.pallets.into_iter().enumerate() {
+            let name: ArcStr = p.name.into();
+
+            let storage = p.storage.map(|s| StorageMetadata {
+                prefix: s.prefix,
+                entries: s
+                    .entries
+                    .into_iter()
+                    .map(|s| {
+                        let name: ArcStr = s.name.clone().into();
+                        (name.clone(), from_storage_entry_metadata(name, s))
+                    })
+                    .collect(),
+            });
+            let constants = p.constants.into_iter().map(|c| {
+                let name: ArcStr = c.name.clone().into();
+                (name.clone(), from_constant_metadata(name, c))
+            });
+            let view_functions = p.view_functions.into_iter().map(|v| {
+                let name: ArcStr = v.name.clone().into();
+                (name.clone(), from_view_function_metadata(name, v))
+            });
+            let associated_types = p
+                .associated_types
+                .into_iter()
+                .map(|a| AssociatedTypeMetadata {
+                    name: a.name,
+                    ty: a.ty.id,
+                    docs: a.docs,
+                })
+                .collect();
+
+            let call_variant_index =
+                VariantIndex::build(p.calls.as_ref().map(|c| c.ty.id), &m.types);
+            let error_variant_index =
+                VariantIndex::build(p.error.as_ref().map(|e| e.ty.id), &m.types);
+            let event_variant_index =
+                VariantIndex::build(p.event.as_ref().map(|e| e.ty.id), &m.types);
+
+            let (call_ty, call_deprecation_info) = match p.calls {
+                Some(c) => (
+                    Some(c.ty.id),
+                    from_enum_deprecation_info(c.deprecation_info, c.ty.id, &m.types),
+                ),
+                None => (None, BTreeMap::new()),
+            };
+            let (event_ty, event_deprecation_info) = match p.event {
+                Some(e) => (
+                    Some(e.ty.id),
+                    from_enum_deprecation_info(e.deprecation_info, e.ty.id, &m.types),
+                ),
+                None => (None, BTreeMap::new()),
+            };
+            let (error_ty, error_deprecation_info) = match p.error {
+                Some(e) => (
+                    Some(e.ty.id),
+                    from_enum_deprecation_info(e.deprecation_info, e.ty.id, &m.types),
+                ),
+                None => (None, BTreeMap::new()),
+            };
+
+            pallets_by_index.insert(p.index, pos);
+            pallets.push_insert(
+                name.clone(),
+                PalletMetadataInner {
+                    name,
+                    index: p.index,
+                    call_index: p.index,
+                    storage,
+                    call_ty,
+                    call_variant_index,
+                    event_ty,
+                    event_variant_index,
+                    error_ty,
+                    error_variant_index,
+                    constants: constants.collect(),
+                    view_functions: view_functions.collect(),
+                    associated_types,
+                    docs: p.docs,
+                    deprecation_info: from_item_deprecation_info(p.deprecation_info),
+                    call_deprecation_info,
+                    event_deprecation_info,
+                    error_deprecation_info,
+                },
+            );
+        }
+
+        let apis = m.apis.into_iter().map(|api| {
+            let name: ArcStr = api.name.clone().into();
+            (name.clone(), from_runtime_api_metadata(name, api))
+        });
+
+        let dispatch_error_ty = m
+            .types
+            .types
+            .iter()
+            .find(|ty| ty.ty.path.segments == ["sp_runtime", "DispatchError"])
+            .map(|ty| ty.id);
+
+        // V16 metadata no longer points to the `Runtime` type, so find it ourselves.
+        let runtime_ty = m
+            .types
+            .types
+            .iter()
+            .find(|ty| is_runtime_type(&ty.ty))
+            .map(|ty| ty.id);
+        let runtime_ty = match runtime_ty {
+            Some(id) => id,
+            None => push_type(
+                &mut m.types,
+                vec!["Runtime".to_owned()],
+                scale_info::TypeDef::Composite(scale_info::TypeDefComposite { fields: vec![] }),
+            ),
+        };
+
+        let extrinsic =
+            from_extrinsic_metadata(m.extrinsic, m.outer_enums.call_enum_ty.id, &mut m.types)?;
+
+        Ok(Metadata {
+            types: m.types,
+            pallets,
+            pallets_by_call_index: pallets_by_index.clone(),
+            pallets_by_index,
+            extrinsic,
+            runtime_ty,
+            dispatch_error_ty,
+            apis: apis.collect(),
+            outer_enums: OuterEnumsMetadata {
+                call_enum_ty: m.outer_enums.call_enum_ty.id,
+                event_enum_ty: m.outer_enums.event_enum_ty.id,
+                error_enum_ty: m.outer_enums.error_enum_ty.id,
+            },
+            custom: v15::CustomMetadata {
+                map: m
+                    .custom
+                    .map
+                    .into_iter()
+                    .map(|(name, c)| {
+                        let value = v15::CustomValueMetadata {
+                            ty: c.ty,
+                            value: c.value,
+                        };
+                        (name, value)
+                    })
+                    .collect(),
+            },
+        })
+    }
+}
+
+/// Is this the unit struct that a runtime is built into, which is named `Runtime` by convention?
+fn is_runtime_type(ty: &scale_info::Type<PortableForm>) -> bool {
+    let is_unit = match &ty.type_def {
+        scale_info::TypeDef::Composite(c) => c.fields.is_empty(),
+        _ => false,
+    };
+    is_unit && ty.path.segments.last().map(|s| s.as_str()) == Some("Runtime")
+}
+
+/// Add a new type with the given path and definition to the registry, returning its ID.
+fn push_type(
+    types: &mut PortableRegistry,
+    segments: Vec<String>,
+    type_def: scale_info::TypeDef<PortableForm>,
+) -> u32 {
+    let id = types.types.len() as u32;
+    types.types.push(scale_info::PortableType {
+        id,
+        ty: scale_info::Type {
+            path: scale_info::Path { segments },
+            type_params: vec![],
+            type_def,
+            docs: vec![],
+        },
+    });
+    id
+}
+
+fn from_extrinsic_metadata(
+    value: v16::ExtrinsicMetadata<PortableForm>,
+    call_ty: u32,
+    types: &mut PortableRegistry,
+) -> Result<ExtrinsicMetadata, TryFromError> {
+    let transaction_extensions: Vec<_> = value
+        .transaction_extensions
+        .into_iter()
+        .map(|t| SignedExtensionMetadata {
+            identifier: t.identifier,
+            extra_ty: t.ty.id,
+            additional_ty: t.implicit.id,
+        })
+        .collect();
+
+    let signed_extensions_by_version: BTreeMap<u8, Vec<SignedExtensionMetadata>> = value
+        .transaction_extensions_by_version
+        .into_iter()
+        .map(|(version, indexes)| {
+            let exts = indexes
+                .into_iter()
+                .map(|idx| {
+                    transaction_extensions
+                        .get(idx as usize)
+                        .cloned()
+                        .ok_or(TryFromError::TransactionExtensionNotFound(idx))
+                })
+                .collect::<Result<_, _>>()?;
+            Ok((version, exts))
+        })
+        .collect::<Result<_, TryFromError>>()?;
+
+    // Version 0 is what's used in anything other than a v5 general extrinsic, so prefer
+    // this if it exists.
+    let transaction_extensions_version = if signed_extensions_by_version.contains_key(&0) {
+        0
+    } else {
+        signed_extensions_by_version
+            .keys()
+            .next_back()
+            .copied()
+            .unwrap_or(0)
+    };
+
+    // V16 metadata no longer contains the tuple of extension types, so build it.
+    let extra_ty = push_type(
+        types,
+        vec![],
+        scale_info::TypeDef::Tuple(scale_info::TypeDefTuple {
+            fields: signed_extensions_by_version
+                .get(&transaction_extensions_version)
+                .into_iter()
+                .flatten()
+                .map(|e| e.extra_ty.into())
+                .collect(),
+        }),
+    );
+
+    let mut supported_versions = value.versions;
+    supported_versions.sort_unstable();
+    supported_versions.dedup();
+
+    Ok(ExtrinsicMetadata {
+        version: supported_versions.last().copied().unwrap_or(4),
+        supported_versions,
+        transaction_extensions_version,
+        signed_extensions_by_version,
+        address_ty: value.address_ty.id,
+        call_ty,
+        signature_ty: value.signature_ty.id,
+        extra_ty,
+    })
+}
+
+fn from_item_deprecation_info(value: v16::DeprecationStatus<PortableForm>) -> DeprecationInfo {
+    match value {
+        v16::DeprecationStatus::NotDeprecated => DeprecationInfo::NotDeprecated,
+        v16::DeprecationStatus::DeprecatedWithoutNote => DeprecationInfo::DeprecatedWithoutNote,
+        v16::DeprecationStatus::Deprecated { note, since } => {
+            DeprecationInfo::Deprecated { note, since }
+        }
+    }
+}
+
+fn from_enum_deprecation_info(
+    value: v16::DeprecationInfo<PortableForm>,
+    enum_ty: u32,
+    types: &PortableRegistry,
+) -> BTreeMap<u8, DeprecationInfo> {
+    match value {
+        v16::DeprecationInfo::NotDeprecated => BTreeMap::new(),
+        // The whole enum is deprecated, so every variant of it is.
+        v16::DeprecationInfo::ItemDeprecated(status) => {
+            let info = from_item_deprecation_info(status);
+            match types.resolve(enum_ty).map(|ty| &ty.type_def) {
+                Some(scale_info::TypeDef::Variant(v)) => v
+                    .variants
+                    .iter()
+                    .map(|variant| (variant.index, info.clone()))
+                    .collect(),
+                _ => BTreeMap::new(),
+            }
+        }
+        v16::DeprecationInfo::VariantsDeprecated(variants) => variants
+            .into_iter()
+            .map(|(index, status)| (index, from_item_deprecation_info(status)))
+            .collect(),
+    }
+}
+
+fn from_storage_hasher(value: v16::StorageHasher) -> StorageHasher {
+    match value {
+        v16::StorageHasher::Blake2_128 => StorageHasher::Blake2_128,
+        v16::StorageHasher::Blake2_256 => StorageHasher::Blake2_256,
+        v16::StorageHasher::Blake2_128Concat => StorageHasher::Blake2_128Concat,
+        v16::StorageHasher::Twox128 => StorageHasher::Twox128,
+        v16::StorageHasher::Twox256 => StorageHasher::Twox256,
+        v16::StorageHasher::Twox64Concat => StorageHasher::Twox64Concat,
+        v16::StorageHasher::Identity => StorageHasher::Identity,
+    }
+}
+
+fn from_storage_entry_type(value: v16::StorageEntryType<PortableForm>) -> StorageEntryType {
+    match value {
+        v16::StorageEntryType::Plain(ty) => StorageEntryType::Plain(ty.id),
+        v16::StorageEntryType::Map {
+            hashers,
+            key,
+            value,
+        } => StorageEntryType::Map {
+            hashers: hashers.into_iter().map(from_storage_hasher).collect(),
+            key_ty: key.id,
+            value_ty: value.id,
+        },
+    }
+}
+
+fn from_storage_entry_modifier(value: v16::StorageEntryModifier) -> StorageEntryModifier {
+    match value {
+        v16::StorageEntryModifier::Optional => StorageEntryModifier::Optional,
+        v16::StorageEntryModifier::Default => StorageEntryModifier::Default,
+    }
+}
+
+fn from_storage_entry_metadata(
+    name: ArcStr,
+    s: v16::StorageEntryMetadata<PortableForm>,
+) -> StorageEntryMetadata {
+    StorageEntryMetadata {
+        name,
+        modifier: from_storage_entry_modifier(s.modifier),
+        entry_type: from_storage_entry_type(s.ty),
+        default: s.default,
+        docs: s.docs,
+        deprecation_info: from_item_deprecation_info(s.deprecation_info),
+    }
+}
+
+fn from_constant_metadata(
+    name: ArcStr,
+    s: v16::PalletConstantMetadata<PortableForm>,
+) -> ConstantMetadata {
+    ConstantMetadata {
+        name,
+        ty: s.ty.id,
+        value: s.value,
+        docs: s.docs,
+        deprecation_info: from_item_deprecation_info(s.deprecation_info),
+    }
+}
+
+fn from_view_function_metadata(
+    name: ArcStr,
+    s: v16::PalletViewFunctionMetadata<PortableForm>,
+) -> ViewFunctionMetadata {
+    ViewFunctionMetadata {
+        name,
+        query_id: s.id,
+        inputs: s
+            .inputs
+            .into_iter()
+            .map(|i| ViewFunctionParamMetadata {
+                name: i.name,
+                ty: i.ty.id,
+            })
+            .collect(),
+        output_ty: s.output.id,
+        docs: s.docs,
+        deprecation_info: from_item_deprecation_info(s.deprecation_info),
+    }
+}
+
+fn from_runtime_api_metadata(
+    name: ArcStr,
+    s: v16::RuntimeApiMetadata<PortableForm>,
+) -> RuntimeApiMetadataInner {
+    RuntimeApiMetadataInner {
+        name,
+        docs: s.docs,
+        methods: s
+            .methods
+            .into_iter()
+            .map(|m| {
+                let name: ArcStr = m.name.clone().into();
+                (name.clone(), from_runtime_api_method_metadata(name, m))
+            })
+            .collect(),
+        deprecation_info: from_item_deprecation_info(s.deprecation_info),
+    }
+}
+
+fn from_runtime_api_method_metadata(
+    name: ArcStr,
+    s: v16::RuntimeApiMethodMetadata<PortableForm>,
+) -> RuntimeApiMethodMetadata {
+    RuntimeApiMethodMetadata {
+        name,
+        inputs: s
+            .inputs
+            .into_iter()
+            .map(|i| RuntimeApiMethodParamMetadata {
+                name: i.name,
+                ty: i.ty.id,
+            })
+            .collect(),
+        output_ty: s.output.id,
+        docs: s.docs,
+        deprecation_info: from_item_deprecation_info(s.deprecation_info),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale_info::{meta_type, TypeDef, TypeDefPrimitive, TypeInfo};
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum Call {
+        Transfer,
+        OldTransfer,
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum Event {
+        Transferred,
+        Burned,
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum Error {
+        TooPoor,
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum RuntimeCall {
+        Test(Call),
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum RuntimeEvent {
+        Test(Event),
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum RuntimeError {
+        Test(Error),
+    }
+
+    // Some V16 metadata containing a single pallet with a little of everything, and the
+    // `CheckNonce`, `VerifySignature` and `CheckGenesis` transaction extensions, which are
+    // used in each version as given.
+    fn metadata(
+        transaction_extensions_by_version: BTreeMap<u8, Vec<u32>>,
+    ) -> v16::RuntimeMetadataV16 {
+        let pallet = v16::PalletMetadata {
+            name: "Test",
+            storage: Some(v16::PalletStorageMetadata {
+                prefix: "Test",
+                entries: vec![v16::StorageEntryMetadata {
+                    name: "Value",
+                    modifier: v16::StorageEntryModifier::Default,
+                    ty: v16::StorageEntryType::Plain(meta_type::<u32>()),
+                    default: vec![0; 4],
+                    docs: vec![],
+                    deprecation_info: v16::DeprecationStatus::Deprecated {
+                        note: "Use NewValue",
+                        since: Some("1.2.0"),
+                    },
+                }],
+            }),
+            calls: Some(v16::PalletCallMetadata {
+                ty: meta_type::<Call>(),
+                deprecation_info: v16::DeprecationInfo::VariantsDeprecated(BTreeMap::from([(
+                    1,
+                    v16::DeprecationStatus::DeprecatedWithoutNote,
+                )])),
+            }),
+            event: Some(v16::PalletEventMetadata {
+                ty: meta_type::<Event>(),
+                deprecation_info: v16::DeprecationInfo::ItemDeprecated(
+                    v16::DeprecationStatus::DeprecatedWithoutNote,
+                ),
+            }),
+            constants: vec![],
+            error: Some(v16::PalletErrorMetadata {
+                ty: meta_type::<Error>(),
+                deprecation_info: v16::DeprecationInfo::NotDeprecated,
+            }),
+            associated_types: vec![v16::PalletAssociatedTypeMetadata {
+                name: "Balance",
+                ty: meta_type::<u128>(),
+                docs: vec!["The balance type."],
+            }],
+            view_functions: vec![v16::PalletViewFunctionMetadata {
+                name: "value_of",
+                id: [7; 32],
+                inputs: vec![v16::PalletViewFunctionParamMetadata {
+                    name: "key",
+                    ty: meta_type::<u32>(),
+                }],
+                output: meta_type::<u64>(),
+                docs: vec![],
+                deprecation_info: v16::DeprecationStatus::NotDeprecated,
+            }],
+            index: 3,
+            docs: vec![],
+            deprecation_info: v16::DeprecationStatus::NotDeprecated,
+        };
+        let extrinsic = v16::ExtrinsicMetadata {
+            versions: vec![5, 4],
+            address_ty: meta_type::<[u8; 32]>(),
+            signature_ty: meta_type::<[u8; 64]>(),
+            transaction_extensions_by_version,
+            transaction_extensions: vec![
+                v16::TransactionExtensionMetadata {
+                    identifier: "CheckNonce",
+                    ty: meta_type::<u32>(),
+                    implicit: meta_type::<()>(),
+                },
+                v16::TransactionExtensionMetadata {
+                    identifier: "VerifySignature",
+                    ty: meta_type::<Option<[u8; 64]>>(),
+                    implicit: meta_type::<()>(),
+                },
+                v16::TransactionExtensionMetadata {
+                    identifier: "CheckGenesis",
+                    ty: meta_type::<()>(),
+                    implicit: meta_type::<[u8; 32]>(),
+                },
+            ],
+        };
+        let outer_enums = v16::OuterEnums {
+            call_enum_ty: meta_type::<RuntimeCall>(),
+            event_enum_ty: meta_type::<RuntimeEvent>(),
+            error_enum_ty: meta_type::<RuntimeError>(),
+        };
+        let custom = v16::CustomMetadata {
+            map: BTreeMap::new(),
+        };
+
+        v16::RuntimeMetadataV16::new(vec![pallet], extrinsic, vec![], outer_enums, custom)
+    }
+
+    fn identifiers(extensions: &[SignedExtensionMetadata]) -> Vec<&str> {
+        extensions.iter().map(|e| e.identifier()).collect()
+    }
+
+    #[test]
+    fn converts_deprecation_info() {
+        let m = Metadata::try_from(metadata(BTreeMap::from([(0, vec![0, 2])]))).unwrap();
+        let pallet = m.pallet_by_name("Test").unwrap();
+
+        assert_eq!(pallet.deprecation_info(), &DeprecationInfo::NotDeprecated);
+
+        // Only the deprecated call variant is deprecated.
+        assert_eq!(
+            pallet.call_variant_deprecation_info(0),
+            &DeprecationInfo::NotDeprecated
+        );
+        assert_eq!(
+            pallet.call_variant_deprecation_info(1),
+            &DeprecationInfo::DeprecatedWithoutNote
+        );
+
+        // Every event variant is deprecated, since the whole enum is.
+        for index in 0..2 {
+            assert_eq!(
+                pallet.event_variant_deprecation_info(index),
+                &DeprecationInfo::DeprecatedWithoutNote
+            );
+        }
+        assert_eq!(
+            pallet.error_variant_deprecation_info(0),
+            &DeprecationInfo::NotDeprecated
+        );
+
+        let entry = pallet.storage().unwrap().entry_by_name("Value").unwrap();
+        assert_eq!(
+            entry.deprecation_info(),
+            &DeprecationInfo::Deprecated {
+                note: "Use NewValue".into(),
+                since: Some("1.2.0".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn converts_transaction_extensions_by_version() {
+        let m = Metadata::try_from(metadata(BTreeMap::from([
+            (0, vec![0, 2]),
+            (1, vec![1, 0, 2]),
+        ])))
+        .unwrap();
+        let extrinsic = m.extrinsic();
+
+        assert_eq!(extrinsic.supported_versions(), &[4, 5]);
+        assert_eq!(extrinsic.version(), 5);
+
+        // Version 0 is preferred, and the extensions of each version are in the given order.
+        assert_eq!(extrinsic.transaction_extensions_version(), 0);
+        assert_eq!(
+            identifiers(extrinsic.signed_extensions()),
+            ["CheckNonce", "CheckGenesis"]
+        );
+        assert_eq!(
+            identifiers(extrinsic.signed_extensions_by_version(1).unwrap()),
+            ["VerifySignature", "CheckNonce", "CheckGenesis"]
+        );
+        assert!(extrinsic.signed_extensions_by_version(2).is_none());
+
+        // Without version 0, the latest version is used.
+        let m = Metadata::try_from(metadata(BTreeMap::from([(1, vec![1, 0])]))).unwrap();
+        assert_eq!(m.extrinsic().transaction_extensions_version(), 1);
+        assert_eq!(
+            identifiers(m.extrinsic().signed_extensions()),
+            ["VerifySignature", "CheckNonce"]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_transaction_extension_indexes() {
+        let err = Metadata::try_from(metadata(BTreeMap::from([(0, vec![0, 3])]))).unwrap_err();
+        assert_eq!(err, TryFromError::TransactionExtensionNotFound(3));
+    }
+
+    #[test]
+    fn converts_view_functions_and_associated_types() {
+        let m = Metadata::try_from(metadata(BTreeMap::from([(0, vec![0, 2])]))).unwrap();
+        let pallet = m.pallet_by_name("Test").unwrap();
+        let resolve = |id| &m.types().resolve(id).unwrap().type_def;
+
+        assert_eq!(pallet.view_functions().len(), 1);
+        let view_function = pallet.view_function_by_name("value_of").unwrap();
+        assert_eq!(view_function.query_id(), &[7; 32]);
+        assert_eq!(
+            view_function.deprecation_info(),
+            &DeprecationInfo::NotDeprecated
+        );
+        let inputs: Vec<_> = view_function.inputs().collect();
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs[0].name, "key");
+        assert_eq!(
+            resolve(inputs[0].ty),
+            &TypeDef::Primitive(TypeDefPrimitive::U32)
+        );
+        assert_eq!(
+            resolve(view_function.output_ty()),
+            &TypeDef::Primitive(TypeDefPrimitive::U64)
+        );
+
+        let associated_types: Vec<_> = pallet.associated_types().collect();
+        assert_eq!(associated_types.len(), 1);
+        assert_eq!(associated_types[0].name(), "Balance");
+        assert_eq!(associated_types[0].docs(), ["The balance type."]);
+        assert_eq!(
+            resolve(associated_types[0].ty()),
+            &TypeDef::Primitive(TypeDefPrimitive::U128)
+        );
+    }
+}
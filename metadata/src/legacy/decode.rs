@@ -0,0 +1,513 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Decoding of the legacy (V9 to V13) metadata formats. These formats describe every
+//! type by name rather than by reference into a type registry, and differ from each
+//! other only in small ways, so we decode all of them into a single representation.
+
+use super::LegacyError;
+use alloc::string::String;
+use alloc::vec::Vec;
+use codec::{Decode, Input};
+use frame_metadata::v15::{StorageEntryModifier, StorageHasher};
+
+/// The magic number that all metadata is prefixed with ("meta").
+pub const META_RESERVED: u32 = 0x6174656d;
+
+/// The oldest legacy metadata version that we know how to decode.
+pub const MIN_LEGACY_VERSION: u8 = 9;
+/// The newest legacy metadata version that we know how to decode.
+pub const MAX_LEGACY_VERSION: u8 = 13;
+
+/// Legacy metadata, common to all of the versions that we support.
+#[derive(Debug, Clone)]
+pub struct LegacyMetadata {
+    pub version: u8,
+    pub modules: Vec<ModuleMetadata>,
+    /// Extrinsic details; these were only introduced in V11 metadata.
+    pub extrinsic: Option<ExtrinsicMetadata>,
+}
+
+#[derive(Debug, Clone, Decode)]
+pub struct ExtrinsicMetadata {
+    pub version: u8,
+    pub signed_extensions: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModuleMetadata {
+    pub name: String,
+    pub storage: Option<StorageMetadata>,
+    pub calls: Option<Vec<FunctionMetadata>>,
+    pub event: Option<Vec<EventMetadata>>,
+    pub constants: Vec<ModuleConstantMetadata>,
+    pub errors: Vec<ErrorMetadata>,
+    /// The module index; this was only introduced in V12 metadata.
+    pub index: Option<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StorageMetadata {
+    pub prefix: String,
+    pub entries: Vec<StorageEntryMetadata>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StorageEntryMetadata {
+    pub name: String,
+    pub modifier: StorageEntryModifier,
+    pub ty: StorageEntryType,
+    pub default: Vec<u8>,
+    pub docs: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum StorageEntryType {
+    Plain(String),
+    Map {
+        hasher: StorageHasher,
+        key: String,
+        value: String,
+        /// Linked maps (V9 and V10 only) store a linkage alongside each value.
+        is_linked: bool,
+    },
+    DoubleMap {
+        hasher: StorageHasher,
+        key1: String,
+        key2: String,
+        value: String,
+        key2_hasher: StorageHasher,
+    },
+    NMap {
+        keys: Vec<String>,
+        hashers: Vec<StorageHasher>,
+        value: String,
+    },
+}
+
+#[derive(Debug, Clone, Decode)]
+pub struct FunctionMetadata {
+    pub name: String,
+    pub arguments: Vec<FunctionArgumentMetadata>,
+    pub docs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Decode)]
+pub struct FunctionArgumentMetadata {
+    pub name: String,
+    pub ty: String,
+}
+
+#[derive(Debug, Clone, Decode)]
+pub struct EventMetadata {
+    pub name: String,
+    pub arguments: Vec<String>,
+    pub docs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Decode)]
+pub struct ModuleConstantMetadata {
+    pub name: String,
+    pub ty: String,
+    pub value: Vec<u8>,
+    pub docs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Decode)]
+pub struct ErrorMetadata {
+    pub name: String,
+    pub docs: Vec<String>,
+}
+
+/// Decode some legacy metadata, including the "meta" magic number and version prefix.
+pub fn decode(bytes: &mut &[u8]) -> Result<LegacyMetadata, LegacyError> {
+    let magic = u32::decode(bytes)?;
+    if magic != META_RESERVED {
+        return Err(LegacyError::InvalidPrefix);
+    }
+
+    let version = u8::decode(bytes)?;
+    if !(MIN_LEGACY_VERSION..=MAX_LEGACY_VERSION).contains(&version) {
+        return Err(LegacyError::UnsupportedMetadataVersion(version));
+    }
+
+    let decoder = Decoder { version };
+    let modules = decoder.vec(bytes, |input| decoder.module(input))?;
+    let extrinsic = if version >= 11 {
+        Some(ExtrinsicMetadata::decode(bytes)?)
+    } else {
+        None
+    };
+
+    Ok(LegacyMetadata {
+        version,
+        modules,
+        extrinsic,
+    })
+}
+
+/// Decodes the parts of legacy metadata whose shape depends on the metadata version.
+struct Decoder {
+    version: u8,
+}
+
+impl Decoder {
+    fn vec<T>(
+        &self,
+        input: &mut &[u8],
+        mut f: impl FnMut(&mut &[u8]) -> Result<T, codec::Error>,
+    ) -> Result<Vec<T>, codec::Error> {
+        let len = codec::Compact::<u32>::decode(input)?.0 as usize;
+        // Don't trust the length to preallocate; it may be bogus.
+        let mut items = Vec::with_capacity(len.min(input.len()));
+        for _ in 0..len {
+            items.push(f(input)?);
+        }
+        Ok(items)
+    }
+
+    fn option<T>(
+        &self,
+        input: &mut &[u8],
+        f: impl FnOnce(&mut &[u8]) -> Result<T, codec::Error>,
+    ) -> Result<Option<T>, codec::Error> {
+        match input.read_byte()? {
+            0 => Ok(None),
+            1 => Ok(Some(f(input)?)),
+            _ => Err("Invalid Option variant index".into()),
+        }
+    }
+
+    fn module(&self, input: &mut &[u8]) -> Result<ModuleMetadata, codec::Error> {
+        let name = String::decode(input)?;
+        let storage = self.option(input, |input| self.storage(input))?;
+        let calls = Option::<Vec<FunctionMetadata>>::decode(input)?;
+        let event = Option::<Vec<EventMetadata>>::decode(input)?;
+        let constants = Vec::<ModuleConstantMetadata>::decode(input)?;
+        let errors = Vec::<ErrorMetadata>::decode(input)?;
+        let index = if self.version >= 12 {
+            Some(u8::decode(input)?)
+        } else {
+            None
+        };
+
+        Ok(ModuleMetadata {
+            name,
+            storage,
+            calls,
+            event,
+            constants,
+            errors,
+            index,
+        })
+    }
+
+    fn storage(&self, input: &mut &[u8]) -> Result<StorageMetadata, codec::Error> {
+        let prefix = String::decode(input)?;
+        let entries = self.vec(input, |input| self.storage_entry(input))?;
+        Ok(StorageMetadata { prefix, entries })
+    }
+
+    fn storage_entry(&self, input: &mut &[u8]) -> Result<StorageEntryMetadata, codec::Error> {
+        let name = String::decode(input)?;
+        let modifier = match input.read_byte()? {
+            0 => StorageEntryModifier::Optional,
+            1 => StorageEntryModifier::Default,
+            _ => return Err("Invalid storage entry modifier".into()),
+        };
+        let ty = self.storage_entry_type(input)?;
+        let default = Vec::<u8>::decode(input)?;
+        let docs = Vec::<String>::decode(input)?;
+
+        Ok(StorageEntryMetadata {
+            name,
+            modifier,
+            ty,
+            default,
+            docs,
+        })
+    }
+
+    fn storage_entry_type(&self, input: &mut &[u8]) -> Result<StorageEntryType, codec::Error> {
+        let ty = match input.read_byte()? {
+            0 => StorageEntryType::Plain(String::decode(input)?),
+            1 => StorageEntryType::Map {
+                hasher: self.hasher(input)?,
+                key: String::decode(input)?,
+                value: String::decode(input)?,
+                // From V11 onwards, this flag is unused and maps are never linked.
+                is_linked: bool::decode(input)? && self.version < 11,
+            },
+            2 => StorageEntryType::DoubleMap {
+                hasher: self.hasher(input)?,
+                key1: String::decode(input)?,
+                key2: String::decode(input)?,
+                value: String::decode(input)?,
+                key2_hasher: self.hasher(input)?,
+            },
+            3 if self.version >= 13 => StorageEntryType::NMap {
+                keys: Vec::<String>::decode(input)?,
+                hashers: self.vec(input, |input| self.hasher(input))?,
+                value: String::decode(input)?,
+            },
+            _ => return Err("Invalid storage entry type".into()),
+        };
+        Ok(ty)
+    }
+
+    fn hasher(&self, input: &mut &[u8]) -> Result<StorageHasher, codec::Error> {
+        use StorageHasher::*;
+
+        // The hasher variants were reordered in V10, and "Identity" was added in V11.
+        let hashers: &[StorageHasher] = match self.version {
+            9 => &[Blake2_128, Blake2_256, Twox128, Twox256, Twox64Concat],
+            10 => &[
+                Blake2_128,
+                Blake2_256,
+                Blake2_128Concat,
+                Twox128,
+                Twox256,
+                Twox64Concat,
+            ],
+            _ => &[
+                Blake2_128,
+                Blake2_256,
+                Blake2_128Concat,
+                Twox128,
+                Twox256,
+                Twox64Concat,
+                Identity,
+            ],
+        };
+
+        let index = input.read_byte()? as usize;
+        hashers
+            .get(index)
+            .cloned()
+            .ok_or_else(|| "Invalid storage hasher".into())
+    }
+}
+
+/// Helpers shared by the tests of the modules which decode legacy metadata.
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use super::*;
+    use codec::{Compact, Encode};
+
+    /// A call, given as its name, argument names and types, and docs.
+    pub(crate) type Call<'a> = (&'a str, Vec<(&'a str, &'a str)>, Vec<String>);
+    /// An event, given as its name, argument types, and docs.
+    pub(crate) type Event<'a> = (&'a str, Vec<&'a str>, Vec<String>);
+
+    /// A module to encode into legacy metadata. The storage entries are given already encoded
+    /// (see [`storage_entry()`]), and are prefixed with the module name.
+    #[derive(Default)]
+    pub(crate) struct Module<'a> {
+        pub name: &'a str,
+        pub storage: Option<Vec<Vec<u8>>>,
+        pub calls: Option<Vec<Call<'a>>>,
+        pub events: Option<Vec<Event<'a>>>,
+        pub index: u8,
+    }
+
+    /// Encode legacy metadata of the given version. Module indexes are only encoded from V12,
+    /// and extrinsic details (a V4 extrinsic with the "CheckNonce" signed extension) from V11.
+    pub(crate) fn encode_metadata(version: u8, modules: &[Module<'_>]) -> Vec<u8> {
+        let mut out = META_RESERVED.encode();
+        out.push(version);
+        Compact(modules.len() as u32).encode_to(&mut out);
+        for module in modules {
+            module.name.encode_to(&mut out);
+            match &module.storage {
+                Some(entries) => {
+                    out.push(1);
+                    module.name.encode_to(&mut out);
+                    Compact(entries.len() as u32).encode_to(&mut out);
+                    out.extend(entries.iter().flatten());
+                }
+                None => out.push(0),
+            }
+            module.calls.encode_to(&mut out);
+            module.events.encode_to(&mut out);
+            // No constants or errors:
+            Vec::<()>::new().encode_to(&mut out);
+            Vec::<()>::new().encode_to(&mut out);
+            if version >= 12 {
+                module.index.encode_to(&mut out);
+            }
+        }
+        if version >= 11 {
+            (4u8, vec!["CheckNonce"]).encode_to(&mut out);
+        }
+        out
+    }
+
+    /// Encode a storage entry with the "Default" modifier, given its encoded type.
+    pub(crate) fn storage_entry(name: &str, ty: Vec<u8>) -> Vec<u8> {
+        let mut out = name.encode();
+        out.push(1);
+        out.extend(ty);
+        // The default value and docs:
+        vec![0u8; 16].encode_to(&mut out);
+        Vec::<String>::new().encode_to(&mut out);
+        out
+    }
+
+    /// Encode the type of a map storage entry, given the index of its hasher.
+    pub(crate) fn map_type(hasher: u8, key: &str, value: &str, is_linked: bool) -> Vec<u8> {
+        let mut out = vec![1, hasher];
+        (key, value, is_linked).encode_to(&mut out);
+        out
+    }
+
+    /// Encode the type of an N map storage entry (V13 onwards), given the indexes of its hashers.
+    pub(crate) fn nmap_type(keys: &[&str], hashers: &[u8], value: &str) -> Vec<u8> {
+        let mut out = vec![3];
+        (keys, hashers, value).encode_to(&mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::test_utils::*;
+    use super::*;
+
+    /// Decode metadata containing a single "Balances" module with the given storage entries.
+    fn decode_storage(version: u8, entries: Vec<Vec<u8>>) -> Result<LegacyMetadata, LegacyError> {
+        let module = Module {
+            name: "Balances",
+            storage: Some(entries),
+            index: 5,
+            ..Default::default()
+        };
+        decode(&mut &*encode_metadata(version, &[module]))
+    }
+
+    fn entry_type(metadata: &LegacyMetadata) -> &StorageEntryType {
+        let storage = metadata.modules[0].storage.as_ref().unwrap();
+        &storage.entries[0].ty
+    }
+
+    #[test]
+    fn rejects_invalid_prefix() {
+        let mut bytes = encode_metadata(11, &[]);
+        bytes[0] = 0;
+        assert!(matches!(
+            decode(&mut &*bytes),
+            Err(LegacyError::InvalidPrefix)
+        ));
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        for version in [8, 14] {
+            let bytes = encode_metadata(version, &[]);
+            assert!(matches!(
+                decode(&mut &*bytes),
+                Err(LegacyError::UnsupportedMetadataVersion(v)) if v == version
+            ));
+        }
+    }
+
+    #[test]
+    fn decodes_v9_metadata() {
+        let entry = storage_entry("FreeBalance", map_type(2, "AccountId", "Balance", true));
+        let metadata = decode_storage(9, vec![entry]).unwrap();
+
+        assert_eq!(metadata.version, 9);
+        assert!(metadata.extrinsic.is_none());
+        assert_eq!(metadata.modules[0].name, "Balances");
+        assert_eq!(metadata.modules[0].index, None);
+        // V9 has no "Blake2_128Concat" hasher, so index 2 is "Twox128".
+        assert!(matches!(
+            entry_type(&metadata),
+            StorageEntryType::Map {
+                hasher: StorageHasher::Twox128,
+                is_linked: true,
+                ..
+            }
+        ));
+        // Nor does it have the "Identity" hasher or anything after "Twox64Concat".
+        let entry = storage_entry("FreeBalance", map_type(5, "AccountId", "Balance", false));
+        assert!(decode_storage(9, vec![entry]).is_err());
+    }
+
+    #[test]
+    fn decodes_v10_metadata() {
+        let entry = storage_entry("FreeBalance", map_type(2, "AccountId", "Balance", true));
+        let metadata = decode_storage(10, vec![entry]).unwrap();
+
+        assert!(metadata.extrinsic.is_none());
+        assert_eq!(metadata.modules[0].index, None);
+        assert!(matches!(
+            entry_type(&metadata),
+            StorageEntryType::Map {
+                hasher: StorageHasher::Blake2_128Concat,
+                is_linked: true,
+                ..
+            }
+        ));
+        // The "Identity" hasher was added in V11.
+        let entry = storage_entry("FreeBalance", map_type(6, "AccountId", "Balance", false));
+        assert!(decode_storage(10, vec![entry]).is_err());
+    }
+
+    #[test]
+    fn decodes_v11_metadata() {
+        let entry = storage_entry("FreeBalance", map_type(6, "AccountId", "Balance", true));
+        let metadata = decode_storage(11, vec![entry]).unwrap();
+
+        let extrinsic = metadata.extrinsic.as_ref().unwrap();
+        assert_eq!(extrinsic.version, 4);
+        assert_eq!(extrinsic.signed_extensions, ["CheckNonce"]);
+        assert_eq!(metadata.modules[0].index, None);
+        // Maps are never linked from V11, whatever the flag says.
+        assert!(matches!(
+            entry_type(&metadata),
+            StorageEntryType::Map {
+                hasher: StorageHasher::Identity,
+                is_linked: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn decodes_v12_metadata() {
+        let entry = storage_entry("FreeBalance", map_type(6, "AccountId", "Balance", false));
+        let metadata = decode_storage(12, vec![entry]).unwrap();
+
+        assert!(metadata.extrinsic.is_some());
+        assert_eq!(metadata.modules[0].index, Some(5));
+        // N maps were added in V13.
+        let entry = storage_entry("Locks", nmap_type(&["AccountId"], &[2], "Balance"));
+        assert!(decode_storage(12, vec![entry]).is_err());
+    }
+
+    #[test]
+    fn decodes_v13_metadata() {
+        let entry = storage_entry(
+            "Locks",
+            nmap_type(&["AccountId", "LockId"], &[2, 6], "Balance"),
+        );
+        let metadata = decode_storage(13, vec![entry]).unwrap();
+
+        assert_eq!(metadata.modules[0].index, Some(5));
+        let StorageEntryType::NMap {
+            keys,
+            hashers,
+            value,
+        } = entry_type(&metadata)
+        else {
+            panic!("expected an N map");
+        };
+        assert_eq!(keys, &["AccountId", "LockId"]);
+        assert!(matches!(
+            hashers[..],
+            [StorageHasher::Blake2_128Concat, StorageHasher::Identity]
+        ));
+        assert_eq!(value, "Balance");
+    }
+}
@@ -0,0 +1,306 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Parse type definitions given in the JSON format used by polkadot.js.
+
+use super::{LegacyError, LegacyTypeBundle, LegacyTypeDef, LegacyTypes, LegacyVariant};
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use serde_json::{Map, Value};
+
+impl LegacyTypes {
+    /// Parse type definitions from a JSON object in the format used by polkadot.js, for example:
+    ///
+    /// ```json
+    /// {
+    ///     "Balance": "u128",
+    ///     "ValidatorPrefs": { "commission": "Compact<Perbill>" },
+    ///     "RewardDestination": { "_enum": ["Staked", "Stash", "Controller"] },
+    ///     "Phase": { "_enum": { "ApplyExtrinsic": "u32", "Finalization": "Null" } }
+    /// }
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, LegacyError> {
+        let value: Value = serde_json::from_str(json).map_err(json_error)?;
+        types_from_value(&value)
+    }
+}
+
+impl LegacyTypeBundle {
+    /// Parse a bundle of type definitions from JSON in the format that polkadot.js uses to
+    /// describe the types for a single chain, for example:
+    ///
+    /// ```json
+    /// {
+    ///     "types": [
+    ///         { "minmax": [0, 1019], "types": { "Weight": "u32" } },
+    ///         { "minmax": [1020, null], "types": { "Weight": "u64" } }
+    ///     ]
+    /// }
+    /// ```
+    ///
+    /// The resulting bundle has no base types; see [`LegacyTypeBundle::with_base`].
+    pub fn from_json(json: &str) -> Result<Self, LegacyError> {
+        let value: Value = serde_json::from_str(json).map_err(json_error)?;
+        let overrides = value
+            .get("types")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid("expected a \"types\" array"))?;
+
+        let mut bundle = LegacyTypeBundle::default();
+        for o in overrides {
+            let (min, max) = match o.get("minmax").and_then(Value::as_array) {
+                Some(minmax) => (
+                    minmax.first().and_then(Value::as_u64).map(|v| v as u32),
+                    minmax.get(1).and_then(Value::as_u64).map(|v| v as u32),
+                ),
+                None => (None, None),
+            };
+            let types = o
+                .get("types")
+                .ok_or_else(|| invalid("expected \"types\" in each override"))?;
+            bundle.add_override(min, max, types_from_value(types)?);
+        }
+        Ok(bundle)
+    }
+}
+
+fn types_from_value(value: &Value) -> Result<LegacyTypes, LegacyError> {
+    let defs = value
+        .as_object()
+        .ok_or_else(|| invalid("expected an object of type definitions"))?;
+
+    let mut types = LegacyTypes::new();
+    for (name, def) in defs {
+        types.insert(name.as_str(), def_from_value(name, def)?);
+    }
+    Ok(types)
+}
+
+fn def_from_value(name: &str, def: &Value) -> Result<LegacyTypeDef, LegacyError> {
+    match def {
+        Value::String(alias) => Ok(LegacyTypeDef::Alias(alias.clone())),
+        Value::Object(obj) => {
+            if let Some(variants) = obj.get("_enum") {
+                enum_from_value(name, variants)
+            } else if obj.contains_key("_set") {
+                Err(invalid(&format!(
+                    "{name}: \"_set\" types are not supported"
+                )))
+            } else {
+                Ok(LegacyTypeDef::Struct(fields_from_map(name, obj)?))
+            }
+        }
+        _ => Err(invalid(&format!("{name}: expected a string or an object"))),
+    }
+}
+
+fn enum_from_value(name: &str, variants: &Value) -> Result<LegacyTypeDef, LegacyError> {
+    let variants = match variants {
+        // A list of variants which contain no data.
+        Value::Array(names) => names
+            .iter()
+            .enumerate()
+            .map(|(index, v)| {
+                let variant_name = v
+                    .as_str()
+                    .ok_or_else(|| invalid(&format!("{name}: expected variant names")))?;
+                Ok(LegacyVariant {
+                    name: variant_name.to_owned(),
+                    index: index as u8,
+                    fields: Vec::new(),
+                })
+            })
+            .collect::<Result<_, LegacyError>>()?,
+        // A map from variant name to either its index or the data that it contains.
+        Value::Object(map) => map
+            .iter()
+            .enumerate()
+            .map(|(index, (variant_name, v))| {
+                let (index, fields) = match v {
+                    Value::Number(n) => {
+                        let index = n.as_u64().ok_or_else(|| {
+                            invalid(&format!("{name}: invalid index for {variant_name}"))
+                        })?;
+                        (index as u8, Vec::new())
+                    }
+                    Value::String(ty) if ty == "Null" => (index as u8, Vec::new()),
+                    Value::String(ty) => (index as u8, vec![(None, ty.clone())]),
+                    Value::Object(fields) => (
+                        index as u8,
+                        fields_from_map(name, fields)?
+                            .into_iter()
+                            .map(|(name, ty)| (Some(name), ty))
+                            .collect(),
+                    ),
+                    _ => return Err(invalid(&format!("{name}: invalid variant {variant_name}"))),
+                };
+                Ok(LegacyVariant {
+                    name: variant_name.clone(),
+                    index,
+                    fields,
+                })
+            })
+            .collect::<Result<_, LegacyError>>()?,
+        _ => return Err(invalid(&format!("{name}: invalid \"_enum\""))),
+    };
+    Ok(LegacyTypeDef::Enum(variants))
+}
+
+fn fields_from_map(
+    name: &str,
+    map: &Map<String, Value>,
+) -> Result<Vec<(String, String)>, LegacyError> {
+    map.iter()
+        .map(|(field, ty)| {
+            let ty = ty
+                .as_str()
+                .ok_or_else(|| invalid(&format!("{name}: expected a type name for {field}")))?;
+            Ok((field.clone(), ty.to_owned()))
+        })
+        .collect()
+}
+
+fn invalid(msg: &str) -> LegacyError {
+    LegacyError::InvalidTypeDefinitions(msg.to_owned())
+}
+
+fn json_error(e: serde_json::Error) -> LegacyError {
+    LegacyError::InvalidTypeDefinitions(format!("{e}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn variant(name: &str, index: u8, fields: &[(Option<&str>, &str)]) -> LegacyVariant {
+        LegacyVariant {
+            name: name.to_owned(),
+            index,
+            fields: fields
+                .iter()
+                .map(|(name, ty)| (name.map(ToOwned::to_owned), (*ty).to_owned()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn parses_type_definitions() {
+        let types = LegacyTypes::from_json(
+            r#"{
+                "Balance": "u128",
+                "ValidatorPrefs": { "commission": "Compact<Perbill>", "blocked": "bool" },
+                "RewardDestination": { "_enum": ["Staked", "Stash", "Controller"] },
+                "Phase": {
+                    "_enum": {
+                        "ApplyExtrinsic": "u32",
+                        "Finalization": "Null",
+                        "Other": { "reason": "Text" }
+                    }
+                },
+                "Indexed": { "_enum": { "First": 1, "Second": 5 } }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            types.get("Balance"),
+            Some(&LegacyTypeDef::Alias("u128".to_owned()))
+        );
+        // Fields keep the order that they are given in.
+        assert_eq!(
+            types.get("ValidatorPrefs"),
+            Some(&LegacyTypeDef::Struct(vec![
+                ("commission".to_owned(), "Compact<Perbill>".to_owned()),
+                ("blocked".to_owned(), "bool".to_owned()),
+            ]))
+        );
+        assert_eq!(
+            types.get("RewardDestination"),
+            Some(&LegacyTypeDef::Enum(vec![
+                variant("Staked", 0, &[]),
+                variant("Stash", 1, &[]),
+                variant("Controller", 2, &[]),
+            ]))
+        );
+        assert_eq!(
+            types.get("Phase"),
+            Some(&LegacyTypeDef::Enum(vec![
+                variant("ApplyExtrinsic", 0, &[(None, "u32")]),
+                variant("Finalization", 1, &[]),
+                variant("Other", 2, &[(Some("reason"), "Text")]),
+            ]))
+        );
+        assert_eq!(
+            types.get("Indexed"),
+            Some(&LegacyTypeDef::Enum(vec![
+                variant("First", 1, &[]),
+                variant("Second", 5, &[]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_type_definitions() {
+        let invalid = [
+            "not json",
+            r#"["u32"]"#,
+            r#"{ "Foo": 1 }"#,
+            r#"{ "Foo": { "_set": { "A": 1 } } }"#,
+            r#"{ "Foo": { "_enum": "A" } }"#,
+            r#"{ "Foo": { "_enum": [1] } }"#,
+            r#"{ "Foo": { "field": 1 } }"#,
+        ];
+        for json in invalid {
+            assert!(
+                matches!(
+                    LegacyTypes::from_json(json),
+                    Err(LegacyError::InvalidTypeDefinitions(_))
+                ),
+                "{json}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_type_bundles() {
+        let bundle = LegacyTypeBundle::from_json(
+            r#"{
+                "types": [
+                    { "minmax": [0, 1019], "types": { "Weight": "u32" } },
+                    { "minmax": [1020, null], "types": { "Weight": "u64" } },
+                    { "types": { "Balance": "u128" } }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let weight = |spec_version| bundle.for_spec_version(spec_version).get("Weight").cloned();
+        assert_eq!(weight(1019), Some(LegacyTypeDef::Alias("u32".to_owned())));
+        assert_eq!(weight(1020), Some(LegacyTypeDef::Alias("u64".to_owned())));
+        // Overrides without a "minmax" apply to every spec version.
+        assert!(bundle.for_spec_version(0).get("Balance").is_some());
+        assert!(bundle.for_spec_version(5000).get("Balance").is_some());
+    }
+
+    #[test]
+    fn rejects_invalid_type_bundles() {
+        let invalid = [
+            r#"{ "Weight": "u32" }"#,
+            r#"{ "types": [{ "minmax": [0, null] }] }"#,
+            r#"{ "types": [{ "types": { "Weight": 1 } }] }"#,
+        ];
+        for json in invalid {
+            assert!(
+                matches!(
+                    LegacyTypeBundle::from_json(json),
+                    Err(LegacyError::InvalidTypeDefinitions(_))
+                ),
+                "{json}"
+            );
+        }
+    }
+}
@@ -0,0 +1,685 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Support for legacy (V9 to V13) metadata. These versions of metadata describe types by
+//! name only, and so in order to use them we need a [`LegacyTypes`] mapping from those
+//! names to the shapes of the types. With this, we build a type registry containing every
+//! type that the metadata refers to, and convert the metadata into our [`Metadata`] type,
+//! so that it can be used to decode blocks from runtimes that predate V14 metadata.
+
+mod decode;
+#[cfg(feature = "legacy-json")]
+mod json;
+mod registry;
+mod type_name;
+mod types;
+
+use crate::{Metadata, TryFromError};
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Display;
+use decode::{LegacyMetadata, StorageEntryType};
+use frame_metadata::v15;
+use registry::TypeResolver;
+use scale_info::form::PortableForm;
+use scale_info::{Field, TypeDef, TypeDefComposite, TypeDefTuple, TypeDefVariant, Variant};
+use type_name::TypeName;
+
+pub use types::{
+    LegacySignedExtension, LegacyTypeBundle, LegacyTypeDef, LegacyTypes, LegacyVariant,
+};
+
+/// The signed extensions assumed for V9 and V10 metadata, which don't list them.
+const FALLBACK_SIGNED_EXTENSIONS: [&str; 7] = [
+    "CheckVersion",
+    "CheckGenesis",
+    "CheckEra",
+    "CheckNonce",
+    "CheckWeight",
+    "ChargeTransactionPayment",
+    "CheckBlockGasLimit",
+];
+
+/// An error emitted if something goes wrong decoding legacy metadata.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LegacyError {
+    /// The bytes did not begin with the expected metadata prefix.
+    InvalidPrefix,
+    /// The metadata is not one of the legacy versions that we support.
+    UnsupportedMetadataVersion(u8),
+    /// The metadata could not be decoded.
+    Decode(codec::Error),
+    /// A type name in the metadata could not be parsed.
+    InvalidTypeName(String),
+    /// A type name in the metadata has no definition.
+    TypeNotFound(String),
+    /// A type alias refers back to itself.
+    RecursiveTypeAlias(String),
+    /// The type definitions that were provided are invalid.
+    InvalidTypeDefinitions(String),
+    /// The metadata could not be converted into [`Metadata`].
+    Conversion(TryFromError),
+}
+
+impl Display for LegacyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LegacyError::InvalidPrefix => write!(f, "Metadata does not have the expected prefix"),
+            LegacyError::UnsupportedMetadataVersion(v) => {
+                write!(f, "Cannot decode v{v} metadata as legacy metadata")
+            }
+            LegacyError::Decode(e) => write!(f, "Cannot decode legacy metadata: {e}"),
+            LegacyError::InvalidTypeName(name) => write!(f, "Cannot parse type name {name}"),
+            LegacyError::TypeNotFound(name) => {
+                write!(f, "No type definition was provided for {name}")
+            }
+            LegacyError::RecursiveTypeAlias(name) => {
+                write!(f, "Type alias {name} refers back to itself")
+            }
+            LegacyError::InvalidTypeDefinitions(e) => write!(f, "Invalid type definitions: {e}"),
+            LegacyError::Conversion(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LegacyError {}
+
+impl From<codec::Error> for LegacyError {
+    fn from(e: codec::Error) -> Self {
+        LegacyError::Decode(e)
+    }
+}
+
+impl From<TryFromError> for LegacyError {
+    fn from(e: TryFromError) -> Self {
+        LegacyError::Conversion(e)
+    }
+}
+
+impl Metadata {
+    /// Decode legacy (V9 to V13) metadata, using the given type definitions to make sense
+    /// of the type names that it contains.
+    ///
+    /// For metadata prior to V12, which does not contain pallet indexes, pallet indexes are
+    /// derived in the same way that runtimes at the time did: [`Metadata::pallet_by_index()`]
+    /// counts only pallets with events, and [`Metadata::pallet_by_call_index()`] counts only
+    /// pallets with calls. Signed extensions without a definition in `types` are assumed to
+    /// add no data to transactions.
+    pub fn decode_legacy(bytes: &[u8], types: &LegacyTypes) -> Result<Metadata, LegacyError> {
+        let metadata = decode::decode(&mut &*bytes)?;
+        from_legacy(metadata, types)
+    }
+}
+
+fn from_legacy(m: LegacyMetadata, types: &LegacyTypes) -> Result<Metadata, LegacyError> {
+    let mut resolver = TypeResolver::new(types);
+    let has_pallet_indexes = m.version >= 12;
+
+    let mut pallets = Vec::with_capacity(m.modules.len());
+    let mut call_indexes = Vec::with_capacity(m.modules.len());
+    let mut call_variants = vec![];
+    let mut event_variants = vec![];
+    let mut error_variants = vec![];
+    let (mut calls_seen, mut events_seen) = (0u8, 0u8);
+
+    for (pos, module) in m.modules.into_iter().enumerate() {
+        // Prior to V12 metadata, calls and events were indexed by their position
+        // amongst the pallets which had calls and events respectively.
+        let call_index = module.index.unwrap_or(calls_seen);
+        let index = match module.index {
+            Some(index) => index,
+            None if module.event.is_some() => events_seen,
+            None => pos as u8,
+        };
+        calls_seen += module.calls.is_some() as u8;
+        events_seen += module.event.is_some() as u8;
+        call_indexes.push(call_index);
+
+        let calls = match &module.calls {
+            Some(calls) => {
+                let variants = calls
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, call)| {
+                        let fields: Vec<_> = call
+                            .arguments
+                            .iter()
+                            .map(|arg| (Some(arg.name.as_str()), arg.ty.as_str()))
+                            .collect();
+                        resolver.variant(&call.name, idx as u8, &fields, &call.docs)
+                    })
+                    .collect::<Result<_, _>>()?;
+                let ty = push_enum(&mut resolver, &module.name, "Call", variants);
+                call_variants.push(outer_variant(&module.name, call_index, ty));
+                Some(v15::PalletCallMetadata { ty: ty.into() })
+            }
+            None => None,
+        };
+
+        let event = match &module.event {
+            Some(events) => {
+                let variants = events
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, event)| {
+                        let fields: Vec<_> = event
+                            .arguments
+                            .iter()
+                            .map(|ty| (None, ty.as_str()))
+                            .collect();
+                        resolver.variant(&event.name, idx as u8, &fields, &event.docs)
+                    })
+                    .collect::<Result<_, _>>()?;
+                let ty = push_enum(&mut resolver, &module.name, "Event", variants);
+                event_variants.push(outer_variant(&module.name, index, ty));
+                Some(v15::PalletEventMetadata { ty: ty.into() })
+            }
+            None => None,
+        };
+
+        let error = if module.errors.is_empty() {
+            None
+        } else {
+            let variants = module
+                .errors
+                .iter()
+                .enumerate()
+                .map(|(idx, error)| resolver.variant(&error.name, idx as u8, &[], &error.docs))
+                .collect::<Result<_, _>>()?;
+            let ty = push_enum(&mut resolver, &module.name, "Error", variants);
+            error_variants.push(outer_variant(&module.name, index, ty));
+            Some(v15::PalletErrorMetadata { ty: ty.into() })
+        };
+
+        let storage = match module.storage {
+            Some(storage) => {
+                let entries = storage
+                    .entries
+                    .into_iter()
+                    .map(|entry| {
+                        Ok(v15::StorageEntryMetadata {
+                            name: entry.name,
+                            modifier: entry.modifier,
+                            ty: storage_entry_type(&mut resolver, entry.ty)?,
+                            default: entry.default,
+                            docs: entry.docs,
+                        })
+                    })
+                    .collect::<Result<_, LegacyError>>()?;
+                Some(v15::PalletStorageMetadata {
+                    prefix: storage.prefix,
+                    entries,
+                })
+            }
+            None => None,
+        };
+
+        let constants = module
+            .constants
+            .into_iter()
+            .map(|c| {
+                Ok(v15::PalletConstantMetadata {
+                    ty: resolver.resolve_str(&c.ty)?.into(),
+                    name: c.name,
+                    value: c.value,
+                    docs: c.docs,
+                })
+            })
+            .collect::<Result<_, LegacyError>>()?;
+
+        pallets.push(v15::PalletMetadata {
+            name: module.name,
+            storage,
+            calls,
+            event,
+            constants,
+            error,
+            index,
+            docs: vec![],
+        });
+    }
+
+    // Build the outer enums. The call enum ID was reserved up front, since calls can contain calls.
+    let call_enum_ty = resolver.call_ty();
+    resolver.set(
+        call_enum_ty,
+        vec!["RuntimeCall".to_owned()],
+        TypeDef::Variant(TypeDefVariant {
+            variants: call_variants,
+        }),
+    );
+    let event_enum_ty = resolver.push(
+        vec!["RuntimeEvent".to_owned()],
+        TypeDef::Variant(TypeDefVariant {
+            variants: event_variants,
+        }),
+    );
+    let error_enum_ty = resolver.push(
+        vec!["RuntimeError".to_owned()],
+        TypeDef::Variant(TypeDefVariant {
+            variants: error_variants,
+        }),
+    );
+
+    let extrinsic = extrinsic_metadata(&mut resolver, types, m.extrinsic, call_enum_ty)?;
+    let runtime_ty = resolver.push(
+        vec!["Runtime".to_owned()],
+        TypeDef::Composite(TypeDefComposite { fields: vec![] }),
+    );
+
+    let mut metadata: Metadata = v15::RuntimeMetadataV15 {
+        types: resolver.into_registry(),
+        pallets,
+        extrinsic,
+        ty: runtime_ty.into(),
+        apis: vec![],
+        outer_enums: v15::OuterEnums {
+            call_enum_ty: call_enum_ty.into(),
+            event_enum_ty: event_enum_ty.into(),
+            error_enum_ty: error_enum_ty.into(),
+        },
+        custom: v15::CustomMetadata {
+            map: Default::default(),
+        },
+    }
+    .try_into()?;
+
+    if !has_pallet_indexes {
+        for (pallet, call_index) in metadata.pallets.values_mut().iter_mut().zip(call_indexes) {
+            pallet.call_index = call_index;
+        }
+        let pallets = metadata.pallets.values().iter().enumerate();
+        metadata.pallets_by_index = pallets
+            .clone()
+            .filter(|(_, p)| p.event_ty.is_some())
+            .map(|(pos, p)| (p.index, pos))
+            .collect();
+        metadata.pallets_by_call_index = pallets
+            .filter(|(_, p)| p.call_ty.is_some())
+            .map(|(pos, p)| (p.call_index, pos))
+            .collect();
+    }
+
+    Ok(metadata)
+}
+
+fn extrinsic_metadata(
+    resolver: &mut TypeResolver<'_>,
+    types: &LegacyTypes,
+    extrinsic: Option<decode::ExtrinsicMetadata>,
+    call_ty: u32,
+) -> Result<v15::ExtrinsicMetadata<PortableForm>, LegacyError> {
+    let (version, signed_extensions) = match extrinsic {
+        Some(e) => (e.version, e.signed_extensions),
+        None => (
+            4,
+            FALLBACK_SIGNED_EXTENSIONS
+                .iter()
+                .map(|&name| name.to_owned())
+                .collect(),
+        ),
+    };
+
+    let signed_extensions = signed_extensions
+        .into_iter()
+        .map(|identifier| {
+            let (extra, additional) = match types.signed_extension(&identifier) {
+                Some(ext) => (ext.extra.as_str(), ext.additional.as_str()),
+                None => ("()", "()"),
+            };
+            Ok(v15::SignedExtensionMetadata {
+                ty: resolver.resolve_str(extra)?.into(),
+                additional_signed: resolver.resolve_str(additional)?.into(),
+                identifier,
+            })
+        })
+        .collect::<Result<Vec<_>, LegacyError>>()?;
+
+    let extra_ty = resolver.push(
+        vec![],
+        TypeDef::Tuple(TypeDefTuple {
+            fields: signed_extensions.iter().map(|e| e.ty).collect(),
+        }),
+    );
+
+    Ok(v15::ExtrinsicMetadata {
+        version,
+        address_ty: resolver.resolve_str("Address")?.into(),
+        call_ty: call_ty.into(),
+        signature_ty: resolver.resolve_str("ExtrinsicSignature")?.into(),
+        extra_ty: extra_ty.into(),
+        signed_extensions,
+    })
+}
+
+fn storage_entry_type(
+    resolver: &mut TypeResolver<'_>,
+    ty: StorageEntryType,
+) -> Result<v15::StorageEntryType<PortableForm>, LegacyError> {
+    let ty = match ty {
+        StorageEntryType::Plain(value) => {
+            v15::StorageEntryType::Plain(resolver.resolve_str(&value)?.into())
+        }
+        StorageEntryType::Map {
+            hasher,
+            key,
+            value,
+            is_linked,
+        } => {
+            let key = TypeName::parse(&key)?;
+            let value = TypeName::parse(&value)?;
+            // Linked maps store the previous and next keys alongside each value.
+            let value = if is_linked {
+                let linkage = TypeName::Named {
+                    name: "Linkage".to_owned(),
+                    params: vec![key.clone()],
+                };
+                TypeName::Tuple(vec![value, linkage])
+            } else {
+                value
+            };
+            v15::StorageEntryType::Map {
+                hashers: vec![hasher],
+                key: resolver.resolve(&key)?.into(),
+                value: resolver.resolve(&value)?.into(),
+            }
+        }
+        StorageEntryType::DoubleMap {
+            hasher,
+            key1,
+            key2,
+            value,
+            key2_hasher,
+        } => {
+            let key = TypeName::Tuple(vec![TypeName::parse(&key1)?, TypeName::parse(&key2)?]);
+            v15::StorageEntryType::Map {
+                hashers: vec![hasher, key2_hasher],
+                key: resolver.resolve(&key)?.into(),
+                value: resolver.resolve_str(&value)?.into(),
+            }
+        }
+        StorageEntryType::NMap {
+            keys,
+            hashers,
+            value,
+        } => {
+            let mut keys = keys
+                .iter()
+                .map(|key| TypeName::parse(key))
+                .collect::<Result<Vec<_>, _>>()?;
+            // As in modern metadata, a single key is not wrapped in a tuple.
+            let key = if keys.len() == 1 {
+                keys.remove(0)
+            } else {
+                TypeName::Tuple(keys)
+            };
+            v15::StorageEntryType::Map {
+                hashers,
+                key: resolver.resolve(&key)?.into(),
+                value: resolver.resolve_str(&value)?.into(),
+            }
+        }
+    };
+    Ok(ty)
+}
+
+/// Add a pallet level enum (eg the pallet's `Call` enum) to the registry.
+fn push_enum(
+    resolver: &mut TypeResolver<'_>,
+    pallet_name: &str,
+    name: &str,
+    variants: Vec<Variant<PortableForm>>,
+) -> u32 {
+    resolver.push(
+        vec![pallet_name.to_owned(), name.to_owned()],
+        TypeDef::Variant(TypeDefVariant { variants }),
+    )
+}
+
+/// A variant of one of the outer enums, which wraps the given pallet level enum.
+fn outer_variant(pallet_name: &str, index: u8, ty: u32) -> Variant<PortableForm> {
+    Variant {
+        name: pallet_name.to_owned(),
+        fields: vec![Field {
+            name: None,
+            ty: ty.into(),
+            type_name: None,
+            docs: vec![],
+        }],
+        index,
+        docs: vec![],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::decode::test_utils::*;
+    use super::*;
+    use scale_info::{TypeDefArray, TypeDefPrimitive};
+
+    /// Legacy metadata of the given version with "System", "Timestamp" and "Balances"
+    /// modules, at indexes 0, 3 and 5 respectively. "Balances" stores the given entries.
+    fn metadata_bytes(version: u8, storage: Vec<Vec<u8>>) -> Vec<u8> {
+        let modules = [
+            Module {
+                name: "System",
+                events: Some(vec![("ExtrinsicSuccess", vec!["DispatchInfo"], vec![])]),
+                index: 0,
+                ..Default::default()
+            },
+            Module {
+                name: "Timestamp",
+                calls: Some(vec![("set", vec![("now", "Compact<T::Moment>")], vec![])]),
+                index: 3,
+                ..Default::default()
+            },
+            Module {
+                name: "Balances",
+                storage: Some(storage),
+                calls: Some(vec![(
+                    "transfer",
+                    vec![
+                        ("dest", "<T::Lookup as StaticLookup>::Source"),
+                        ("value", "Compact<T::Balance>"),
+                    ],
+                    vec![],
+                )]),
+                events: Some(vec![(
+                    "Transfer",
+                    vec!["AccountId", "AccountId", "Balance"],
+                    vec![],
+                )]),
+                index: 5,
+            },
+        ];
+        encode_metadata(version, &modules)
+    }
+
+    /// Decode metadata of the given version, in which "Balances" stores a single "Default"
+    /// map of AccountId to Balance, using the Blake2_256 hasher.
+    fn decode_metadata(version: u8) -> Metadata {
+        let entry = storage_entry(
+            "FreeBalance",
+            map_type(1, "T::AccountId", "T::Balance", false),
+        );
+        Metadata::decode_legacy(
+            &metadata_bytes(version, vec![entry]),
+            &LegacyTypes::substrate(),
+        )
+        .expect("can decode legacy metadata")
+    }
+
+    /// The type that some storage entry in "Balances" maps keys to.
+    fn storage_value_ty<'a>(
+        metadata: &'a Metadata,
+        entry: &str,
+    ) -> &'a scale_info::Type<PortableForm> {
+        let entry = metadata
+            .pallet_by_name("Balances")
+            .and_then(|p| p.storage())
+            .and_then(|s| s.entry_by_name(entry))
+            .unwrap();
+        metadata
+            .types()
+            .resolve(entry.entry_type().value_ty())
+            .unwrap()
+    }
+
+    #[test]
+    fn decodes_v11_metadata() {
+        let metadata = decode_metadata(11);
+
+        // Events are indexed by position amongst pallets with events, and
+        // calls by position amongst pallets with calls.
+        assert_eq!(metadata.pallet_by_index(0).unwrap().name(), "System");
+        assert_eq!(metadata.pallet_by_index(1).unwrap().name(), "Balances");
+        assert_eq!(
+            metadata.pallet_by_call_index(0).unwrap().name(),
+            "Timestamp"
+        );
+        assert_eq!(metadata.pallet_by_call_index(1).unwrap().name(), "Balances");
+
+        let balances = metadata.pallet_by_name("Balances").unwrap();
+        assert_eq!(balances.index(), 1);
+        assert_eq!(balances.call_index(), 1);
+        assert!(balances
+            .storage()
+            .and_then(|s| s.entry_by_name("FreeBalance"))
+            .is_some());
+
+        // Type names are resolved using the type definitions.
+        let transfer = balances.event_variant_by_index(0).unwrap();
+        let account_id = metadata.types().resolve(transfer.fields[0].ty.id).unwrap();
+        assert!(matches!(
+            account_id.type_def,
+            TypeDef::Array(TypeDefArray { len: 32, .. })
+        ));
+
+        let extensions = metadata.extrinsic().signed_extensions();
+        assert_eq!(extensions.len(), 1);
+        assert_eq!(extensions[0].identifier(), "CheckNonce");
+    }
+
+    #[test]
+    fn decodes_v9_and_v10_metadata() {
+        for version in [9, 10] {
+            let metadata = decode_metadata(version);
+
+            // Pallets are indexed as in V11.
+            assert_eq!(metadata.pallet_by_index(1).unwrap().name(), "Balances");
+            assert_eq!(
+                metadata.pallet_by_call_index(0).unwrap().name(),
+                "Timestamp"
+            );
+
+            // Extrinsic details aren't given, so we assume the ones in use at the time.
+            assert_eq!(metadata.extrinsic().version(), 4);
+            let extensions: Vec<_> = metadata
+                .extrinsic()
+                .signed_extensions()
+                .iter()
+                .map(|e| e.identifier())
+                .collect();
+            assert_eq!(extensions, FALLBACK_SIGNED_EXTENSIONS);
+        }
+    }
+
+    #[test]
+    fn linked_maps_store_linkage_with_values() {
+        let entry = storage_entry(
+            "FreeBalance",
+            map_type(1, "T::AccountId", "T::Balance", true),
+        );
+        let metadata =
+            Metadata::decode_legacy(&metadata_bytes(9, vec![entry]), &LegacyTypes::substrate())
+                .unwrap();
+
+        let TypeDef::Tuple(value) = &storage_value_ty(&metadata, "FreeBalance").type_def else {
+            panic!("expected the value and linkage in a tuple");
+        };
+        let linkage = metadata.types().resolve(value.fields[1].id).unwrap();
+        assert_eq!(linkage.path.segments, ["Linkage"]);
+        let TypeDef::Composite(linkage) = &linkage.type_def else {
+            panic!("expected the linkage to be a struct");
+        };
+        let fields: Vec<_> = linkage.fields.iter().map(|f| f.name.as_deref()).collect();
+        assert_eq!(fields, [Some("previous"), Some("next")]);
+    }
+
+    #[test]
+    fn decodes_v12_metadata() {
+        let metadata = decode_metadata(12);
+
+        // Pallets are indexed as the metadata says.
+        assert_eq!(metadata.pallet_by_index(5).unwrap().name(), "Balances");
+        assert_eq!(metadata.pallet_by_call_index(5).unwrap().name(), "Balances");
+        assert_eq!(
+            metadata.pallet_by_call_index(3).unwrap().name(),
+            "Timestamp"
+        );
+        assert_eq!(metadata.pallet_by_name("Timestamp").unwrap().index(), 3);
+        assert!(metadata.pallet_by_index(1).is_none());
+
+        let extensions = metadata.extrinsic().signed_extensions();
+        assert_eq!(extensions[0].identifier(), "CheckNonce");
+    }
+
+    #[test]
+    fn decodes_v13_metadata() {
+        let entries = vec![
+            storage_entry("Locks", nmap_type(&["T::AccountId"], &[2], "T::Balance")),
+            storage_entry(
+                "Reserves",
+                nmap_type(&["T::AccountId", "u32"], &[2, 6], "T::Balance"),
+            ),
+        ];
+        let metadata =
+            Metadata::decode_legacy(&metadata_bytes(13, entries), &LegacyTypes::substrate())
+                .unwrap();
+        assert_eq!(metadata.pallet_by_index(5).unwrap().name(), "Balances");
+
+        let storage = metadata
+            .pallet_by_name("Balances")
+            .unwrap()
+            .storage()
+            .unwrap();
+        let key_ty = |name: &str| {
+            let entry = storage.entry_by_name(name).unwrap();
+            let crate::StorageEntryType::Map {
+                key_ty, hashers, ..
+            } = entry.entry_type()
+            else {
+                panic!("expected a map");
+            };
+            (metadata.types().resolve(*key_ty).unwrap(), hashers.len())
+        };
+
+        // A single key is not wrapped in a tuple, as in modern metadata.
+        let (key, hashers) = key_ty("Locks");
+        assert!(matches!(key.type_def, TypeDef::Array(_)));
+        assert_eq!(hashers, 1);
+        let (key, hashers) = key_ty("Reserves");
+        let TypeDef::Tuple(key) = &key.type_def else {
+            panic!("expected the keys in a tuple");
+        };
+        assert_eq!(key.fields.len(), 2);
+        assert_eq!(hashers, 2);
+
+        assert!(matches!(
+            storage_value_ty(&metadata, "Locks").type_def,
+            TypeDef::Primitive(TypeDefPrimitive::U128)
+        ));
+    }
+
+    #[test]
+    fn errors_on_missing_type_definitions() {
+        let err =
+            Metadata::decode_legacy(&metadata_bytes(11, vec![]), &LegacyTypes::new()).unwrap_err();
+        assert!(matches!(err, LegacyError::TypeNotFound(_)));
+    }
+}
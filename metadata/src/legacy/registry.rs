@@ -0,0 +1,512 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Build a [`PortableRegistry`] from the type names found in legacy metadata.
+
+use super::type_name::TypeName;
+use super::{LegacyError, LegacyTypeDef, LegacyTypes};
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use hashbrown::HashMap;
+use scale_info::form::PortableForm;
+use scale_info::{
+    Field, Path, PortableRegistry, PortableType, Type, TypeDef, TypeDefArray, TypeDefCompact,
+    TypeDefComposite, TypeDefPrimitive, TypeDefSequence, TypeDefTuple, TypeDefVariant, Variant,
+};
+
+/// Resolves type names into types in a [`PortableRegistry`], adding them as needed.
+pub struct TypeResolver<'a> {
+    types: &'a LegacyTypes,
+    registry: PortableRegistry,
+    /// Type IDs for the normalized type names that we've seen so far.
+    cache: HashMap<String, u32>,
+    /// Aliases that we're in the middle of resolving, to catch any cycles.
+    resolving: Vec<String>,
+    /// The type ID that `Call` resolves to.
+    call_ty: u32,
+}
+
+impl<'a> TypeResolver<'a> {
+    /// Create a new resolver. A type ID is reserved for the outer `Call` enum, since
+    /// calls can contain other calls.
+    pub fn new(types: &'a LegacyTypes) -> Self {
+        let mut resolver = TypeResolver {
+            types,
+            registry: PortableRegistry { types: vec![] },
+            cache: HashMap::new(),
+            resolving: vec![],
+            call_ty: 0,
+        };
+        resolver.call_ty = resolver.reserve();
+        resolver
+    }
+
+    /// The type ID reserved for the outer `Call` enum.
+    pub fn call_ty(&self) -> u32 {
+        self.call_ty
+    }
+
+    /// Return the registry containing every type that's been resolved.
+    pub fn into_registry(self) -> PortableRegistry {
+        self.registry
+    }
+
+    /// Reserve a type ID, so that we can refer to a type before we know what it looks like.
+    pub fn reserve(&mut self) -> u32 {
+        self.push(vec![], TypeDef::Tuple(TypeDefTuple { fields: vec![] }))
+    }
+
+    /// Add a new type to the registry, returning its ID.
+    pub fn push(&mut self, path: Vec<String>, type_def: TypeDef<PortableForm>) -> u32 {
+        let id = self.registry.types.len() as u32;
+        self.registry.types.push(PortableType {
+            id,
+            ty: new_type(path, type_def),
+        });
+        id
+    }
+
+    /// Replace the type at some reserved ID.
+    pub fn set(&mut self, id: u32, path: Vec<String>, type_def: TypeDef<PortableForm>) {
+        self.registry.types[id as usize].ty = new_type(path, type_def);
+    }
+
+    /// Build a variant from some type names.
+    pub fn variant(
+        &mut self,
+        name: &str,
+        index: u8,
+        fields: &[(Option<&str>, &str)],
+        docs: &[String],
+    ) -> Result<Variant<PortableForm>, LegacyError> {
+        Ok(Variant {
+            name: name.to_owned(),
+            fields: self.fields(fields)?,
+            index,
+            docs: docs.to_vec(),
+        })
+    }
+
+    /// Build fields from some optional field names and type names.
+    pub fn fields(
+        &mut self,
+        fields: &[(Option<&str>, &str)],
+    ) -> Result<Vec<Field<PortableForm>>, LegacyError> {
+        fields
+            .iter()
+            .map(|(name, ty)| {
+                Ok(Field {
+                    name: name.map(ToOwned::to_owned),
+                    ty: self.resolve_str(ty)?.into(),
+                    type_name: Some((*ty).to_owned()),
+                    docs: vec![],
+                })
+            })
+            .collect()
+    }
+
+    /// Resolve some type name into a type ID.
+    pub fn resolve_str(&mut self, name: &str) -> Result<u32, LegacyError> {
+        let ty = TypeName::parse(name)?;
+        self.resolve(&ty)
+    }
+
+    /// Resolve some parsed type name into a type ID.
+    pub fn resolve(&mut self, ty: &TypeName) -> Result<u32, LegacyError> {
+        let key = ty.to_string();
+        if let Some(id) = self.cache.get(&key) {
+            return Ok(*id);
+        }
+
+        let types = self.types;
+        let id = match ty {
+            TypeName::Tuple(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|f| self.resolve(f).map(Into::into))
+                    .collect::<Result<_, _>>()?;
+                self.push(vec![], TypeDef::Tuple(TypeDefTuple { fields }))
+            }
+            TypeName::Array(inner, len) => {
+                let type_param = self.resolve(inner)?.into();
+                self.push(
+                    vec![],
+                    TypeDef::Array(TypeDefArray {
+                        len: *len,
+                        type_param,
+                    }),
+                )
+            }
+            TypeName::Slice(inner) => self.sequence(inner)?,
+            TypeName::Named { name, params } => {
+                // Definitions for the exact name (eg `BalanceOf<T>`) take precedence, followed by
+                // the generic types we know about, and then definitions for the bare name.
+                if let Some(def) = types.get(&key) {
+                    self.resolve_def(&key, name, def)?
+                } else if let Some(id) = self.resolve_generic(name, params)? {
+                    id
+                } else if let Some(def) = types.get(name) {
+                    self.resolve_def(&key, name, def)?
+                } else if let Some(id) = self.resolve_builtin(name)? {
+                    id
+                } else {
+                    return Err(LegacyError::TypeNotFound(key));
+                }
+            }
+        };
+
+        self.cache.insert(key, id);
+        Ok(id)
+    }
+
+    fn resolve_def(
+        &mut self,
+        key: &str,
+        name: &str,
+        def: &LegacyTypeDef,
+    ) -> Result<u32, LegacyError> {
+        match def {
+            LegacyTypeDef::Alias(alias) => {
+                if self.resolving.iter().any(|n| n == key) {
+                    return Err(LegacyError::RecursiveTypeAlias(key.to_owned()));
+                }
+                self.resolving.push(key.to_owned());
+                let id = self.resolve_str(alias);
+                self.resolving.pop();
+                id
+            }
+            LegacyTypeDef::Struct(fields) => {
+                // Reserve and cache an ID first, so that recursive types can refer to themselves.
+                let id = self.reserve();
+                self.cache.insert(key.to_owned(), id);
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|(name, ty)| (Some(name.as_str()), ty.as_str()))
+                    .collect();
+                let fields = self.fields(&fields)?;
+                self.set(
+                    id,
+                    type_path(name),
+                    TypeDef::Composite(TypeDefComposite { fields }),
+                );
+                Ok(id)
+            }
+            LegacyTypeDef::Enum(variants) => {
+                let id = self.reserve();
+                self.cache.insert(key.to_owned(), id);
+                let variants = variants
+                    .iter()
+                    .map(|v| {
+                        let fields: Vec<_> = v
+                            .fields
+                            .iter()
+                            .map(|(name, ty)| (name.as_deref(), ty.as_str()))
+                            .collect();
+                        self.variant(&v.name, v.index, &fields, &[])
+                    })
+                    .collect::<Result<_, _>>()?;
+                self.set(
+                    id,
+                    type_path(name),
+                    TypeDef::Variant(TypeDefVariant { variants }),
+                );
+                Ok(id)
+            }
+        }
+    }
+
+    /// Resolve generic types that we understand without needing any definitions for.
+    fn resolve_generic(
+        &mut self,
+        name: &str,
+        params: &[TypeName],
+    ) -> Result<Option<u32>, LegacyError> {
+        let id = match (name, params) {
+            ("Vec" | "VecDeque" | "BoundedVec" | "WeakBoundedVec", [inner, ..])
+            | ("BTreeSet" | "BoundedBTreeSet" | "HashSet", [inner, ..]) => self.sequence(inner)?,
+            ("BTreeMap" | "BoundedBTreeMap" | "HashMap", [key, value, ..]) => {
+                let entry = TypeName::Tuple(vec![key.clone(), value.clone()]);
+                self.sequence(&entry)?
+            }
+            ("Box" | "Rc" | "Arc" | "Cow", [inner]) => self.resolve(inner)?,
+            ("Compact", [inner]) => {
+                let type_param = self.resolve(inner)?.into();
+                self.push(vec![], TypeDef::Compact(TypeDefCompact { type_param }))
+            }
+            ("Option", [inner]) => {
+                let inner = inner.to_string();
+                let variants = vec![
+                    self.variant("None", 0, &[], &[])?,
+                    self.variant("Some", 1, &[(None, inner.as_str())], &[])?,
+                ];
+                self.push(
+                    vec!["Option".to_owned()],
+                    TypeDef::Variant(TypeDefVariant { variants }),
+                )
+            }
+            ("Result", [ok, err]) => {
+                let (ok, err) = (ok.to_string(), err.to_string());
+                let variants = vec![
+                    self.variant("Ok", 0, &[(None, ok.as_str())], &[])?,
+                    self.variant("Err", 1, &[(None, err.as_str())], &[])?,
+                ];
+                self.push(
+                    vec!["Result".to_owned()],
+                    TypeDef::Variant(TypeDefVariant { variants }),
+                )
+            }
+            // Linked maps in V9 and V10 metadata store this alongside each value.
+            ("Linkage", [key]) => {
+                let key = format!("Option<{key}>");
+                let fields = self.fields(&[
+                    (Some("previous"), key.as_str()),
+                    (Some("next"), key.as_str()),
+                ])?;
+                self.push(
+                    vec!["Linkage".to_owned()],
+                    TypeDef::Composite(TypeDefComposite { fields }),
+                )
+            }
+            ("PhantomData", _) => self.resolve(&TypeName::Tuple(vec![]))?,
+            _ => return Ok(None),
+        };
+        Ok(Some(id))
+    }
+
+    /// Resolve primitive types and the like.
+    fn resolve_builtin(&mut self, name: &str) -> Result<Option<u32>, LegacyError> {
+        let primitive = match name {
+            "bool" => TypeDefPrimitive::Bool,
+            "char" => TypeDefPrimitive::Char,
+            "str" | "String" | "Text" => TypeDefPrimitive::Str,
+            "u8" => TypeDefPrimitive::U8,
+            "u16" => TypeDefPrimitive::U16,
+            "u32" => TypeDefPrimitive::U32,
+            "u64" => TypeDefPrimitive::U64,
+            "u128" => TypeDefPrimitive::U128,
+            "u256" | "U256" => TypeDefPrimitive::U256,
+            "i8" => TypeDefPrimitive::I8,
+            "i16" => TypeDefPrimitive::I16,
+            "i32" => TypeDefPrimitive::I32,
+            "i64" => TypeDefPrimitive::I64,
+            "i128" => TypeDefPrimitive::I128,
+            "i256" | "I256" => TypeDefPrimitive::I256,
+            "Call" | "RuntimeCall" => return Ok(Some(self.call_ty)),
+            "Null" => return self.resolve(&TypeName::Tuple(vec![])).map(Some),
+            "Bytes" => return self.sequence(&TypeName::named("u8")).map(Some),
+            _ => return Ok(None),
+        };
+        Ok(Some(self.push(vec![], TypeDef::Primitive(primitive))))
+    }
+
+    fn sequence(&mut self, inner: &TypeName) -> Result<u32, LegacyError> {
+        let type_param = self.resolve(inner)?.into();
+        Ok(self.push(vec![], TypeDef::Sequence(TypeDefSequence { type_param })))
+    }
+}
+
+fn new_type(path: Vec<String>, type_def: TypeDef<PortableForm>) -> Type<PortableForm> {
+    Type {
+        path: Path { segments: path },
+        type_params: vec![],
+        type_def,
+        docs: vec![],
+    }
+}
+
+// Subxt looks for the `DispatchError` type by its full path, so we give it that here.
+fn type_path(name: &str) -> Vec<String> {
+    match name {
+        "DispatchError" => vec!["sp_runtime".to_owned(), "DispatchError".to_owned()],
+        _ => vec![name.to_owned()],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::legacy::LegacyVariant;
+
+    fn type_def<'a>(resolver: &'a TypeResolver<'_>, id: u32) -> &'a TypeDef<PortableForm> {
+        &resolver.registry.resolve(id).unwrap().type_def
+    }
+
+    #[test]
+    fn resolves_aliases_of_normalized_names() {
+        let types = LegacyTypes::substrate();
+        let mut resolver = TypeResolver::new(&types);
+
+        let balance = resolver.resolve_str("Balance").unwrap();
+        assert!(matches!(
+            type_def(&resolver, balance),
+            TypeDef::Primitive(TypeDefPrimitive::U128)
+        ));
+        for name in ["T::Balance", "<T as Trait>::Balance", "BalanceOf<T>"] {
+            assert_eq!(resolver.resolve_str(name).unwrap(), balance, "{name}");
+        }
+    }
+
+    #[test]
+    fn resolves_generic_types() {
+        let types = LegacyTypes::new();
+        let mut resolver = TypeResolver::new(&types);
+        let u32_ty = resolver.resolve_str("u32").unwrap();
+
+        let vec = resolver.resolve_str("Vec<u32>").unwrap();
+        assert!(matches!(
+            type_def(&resolver, vec),
+            TypeDef::Sequence(s) if s.type_param.id == u32_ty
+        ));
+
+        let compact = resolver.resolve_str("Compact<u32>").unwrap();
+        assert!(matches!(
+            type_def(&resolver, compact),
+            TypeDef::Compact(c) if c.type_param.id == u32_ty
+        ));
+
+        let map = resolver.resolve_str("BTreeMap<u32, bool>").unwrap();
+        let TypeDef::Sequence(entries) = type_def(&resolver, map) else {
+            panic!("expected a map to be a sequence of entries");
+        };
+        assert!(matches!(
+            type_def(&resolver, entries.type_param.id),
+            TypeDef::Tuple(t) if t.fields.len() == 2
+        ));
+
+        let option = resolver.resolve_str("Option<u32>").unwrap();
+        let TypeDef::Variant(option) = type_def(&resolver, option) else {
+            panic!("expected an option to be an enum");
+        };
+        let variants: Vec<_> = option.variants.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(variants, ["None", "Some"]);
+        assert_eq!(option.variants[1].fields[0].ty.id, u32_ty);
+
+        assert_eq!(resolver.resolve_str("Box<u32>").unwrap(), u32_ty);
+        let unit = resolver.resolve_str("()").unwrap();
+        assert_eq!(resolver.resolve_str("PhantomData<u32>").unwrap(), unit);
+        assert_eq!(resolver.resolve_str("Null").unwrap(), unit);
+    }
+
+    #[test]
+    fn exact_definitions_take_precedence() {
+        let mut types = LegacyTypes::new();
+        types.insert("Foo<u32>", LegacyTypeDef::Alias("u8".to_owned()));
+        types.insert("Foo", LegacyTypeDef::Alias("u64".to_owned()));
+        // This would otherwise be understood without a definition.
+        types.insert("Vec", LegacyTypeDef::Alias("bool".to_owned()));
+        let mut resolver = TypeResolver::new(&types);
+
+        let foo_u32 = resolver.resolve_str("Foo<u32>").unwrap();
+        assert!(matches!(
+            type_def(&resolver, foo_u32),
+            TypeDef::Primitive(TypeDefPrimitive::U8)
+        ));
+        let foo_bool = resolver.resolve_str("Foo<bool>").unwrap();
+        assert!(matches!(
+            type_def(&resolver, foo_bool),
+            TypeDef::Primitive(TypeDefPrimitive::U64)
+        ));
+        // Generic types that we understand take precedence over definitions of their name.
+        let vec = resolver.resolve_str("Vec<u8>").unwrap();
+        assert!(matches!(type_def(&resolver, vec), TypeDef::Sequence(_)));
+    }
+
+    #[test]
+    fn resolves_structs_and_enums() {
+        let mut types = LegacyTypes::new();
+        types.insert(
+            "Node",
+            LegacyTypeDef::Struct(vec![
+                ("value".to_owned(), "u32".to_owned()),
+                ("next".to_owned(), "Option<Node>".to_owned()),
+            ]),
+        );
+        types.insert(
+            "Status",
+            LegacyTypeDef::Enum(vec![
+                LegacyVariant {
+                    name: "Active".to_owned(),
+                    index: 2,
+                    fields: vec![(Some("since".to_owned()), "u32".to_owned())],
+                },
+                LegacyVariant {
+                    name: "Inactive".to_owned(),
+                    index: 5,
+                    fields: vec![],
+                },
+            ]),
+        );
+        let mut resolver = TypeResolver::new(&types);
+
+        // Structs can refer to themselves.
+        let node = resolver.resolve_str("Node").unwrap();
+        let ty = resolver.registry.resolve(node).unwrap();
+        assert_eq!(ty.path.segments, ["Node"]);
+        let TypeDef::Composite(fields) = &ty.type_def else {
+            panic!("expected a struct");
+        };
+        assert_eq!(fields.fields[1].name.as_deref(), Some("next"));
+        let TypeDef::Variant(option) = type_def(&resolver, fields.fields[1].ty.id) else {
+            panic!("expected an option");
+        };
+        assert_eq!(option.variants[1].fields[0].ty.id, node);
+
+        // Enum variants keep their indexes.
+        let status = resolver.resolve_str("Status").unwrap();
+        let TypeDef::Variant(status) = type_def(&resolver, status) else {
+            panic!("expected an enum");
+        };
+        let variants: Vec<_> = status
+            .variants
+            .iter()
+            .map(|v| (v.name.as_str(), v.index, v.fields.len()))
+            .collect();
+        assert_eq!(variants, [("Active", 2, 1), ("Inactive", 5, 0)]);
+    }
+
+    #[test]
+    fn calls_resolve_to_the_reserved_type() {
+        let types = LegacyTypes::substrate();
+        let mut resolver = TypeResolver::new(&types);
+        let call_ty = resolver.call_ty();
+
+        assert_eq!(resolver.resolve_str("Call").unwrap(), call_ty);
+        assert_eq!(
+            resolver.resolve_str("Box<<T as Trait>::Call>").unwrap(),
+            call_ty
+        );
+        assert_eq!(resolver.resolve_str("Proposal").unwrap(), call_ty);
+    }
+
+    #[test]
+    fn dispatch_error_has_its_full_path() {
+        let types = LegacyTypes::substrate();
+        let mut resolver = TypeResolver::new(&types);
+
+        let id = resolver.resolve_str("DispatchError").unwrap();
+        let ty = resolver.registry.resolve(id).unwrap();
+        assert_eq!(ty.path.segments, ["sp_runtime", "DispatchError"]);
+    }
+
+    #[test]
+    fn rejects_unknown_and_recursive_types() {
+        let mut types = LegacyTypes::new();
+        types.insert("A", LegacyTypeDef::Alias("Vec<B>".to_owned()));
+        types.insert("B", LegacyTypeDef::Alias("A".to_owned()));
+        let mut resolver = TypeResolver::new(&types);
+
+        assert!(matches!(
+            resolver.resolve_str("Unknown"),
+            Err(LegacyError::TypeNotFound(name)) if name == "Unknown"
+        ));
+        // Aliases can't refer back to themselves, even via other types.
+        assert!(matches!(
+            resolver.resolve_str("A"),
+            Err(LegacyError::RecursiveTypeAlias(_))
+        ));
+    }
+}
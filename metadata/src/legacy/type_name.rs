@@ -0,0 +1,247 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Parsing of the Rust type names found in legacy metadata.
+
+use super::LegacyError;
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Display, Write};
+
+/// A parsed and normalized type name. Paths are reduced to their final segment, so
+/// `<T as Trait>::Balance`, `T::Balance` and `pallet_balances::Balance` all become `Balance`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeName {
+    /// Some named type with optional generic parameters, eg `Vec<u8>`.
+    Named { name: String, params: Vec<TypeName> },
+    /// A tuple, eg `(u8, bool)`.
+    Tuple(Vec<TypeName>),
+    /// A fixed size array, eg `[u8; 32]`.
+    Array(Box<TypeName>, u32),
+    /// A slice, eg `&[u8]`, which is encoded like a `Vec`.
+    Slice(Box<TypeName>),
+}
+
+impl TypeName {
+    /// Parse a type name.
+    pub fn parse(input: &str) -> Result<TypeName, LegacyError> {
+        let mut parser = Parser { input, pos: 0 };
+        let ty = parser.parse_type();
+        parser.skip_whitespace();
+        match ty {
+            Some(ty) if parser.pos == input.len() => Ok(ty),
+            _ => Err(LegacyError::InvalidTypeName(input.to_owned())),
+        }
+    }
+
+    /// Construct a named type with no generic parameters.
+    pub fn named(name: &str) -> TypeName {
+        TypeName::Named {
+            name: name.to_owned(),
+            params: Vec::new(),
+        }
+    }
+}
+
+impl Display for TypeName {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TypeName::Named { name, params } => {
+                f.write_str(name)?;
+                if !params.is_empty() {
+                    f.write_char('<')?;
+                    write_list(f, params)?;
+                    f.write_char('>')?;
+                }
+                Ok(())
+            }
+            TypeName::Tuple(fields) => {
+                f.write_char('(')?;
+                write_list(f, fields)?;
+                f.write_char(')')
+            }
+            TypeName::Array(ty, len) => write!(f, "[{ty}; {len}]"),
+            TypeName::Slice(ty) => write!(f, "[{ty}]"),
+        }
+    }
+}
+
+fn write_list(f: &mut core::fmt::Formatter<'_>, items: &[TypeName]) -> core::fmt::Result {
+    for (idx, item) in items.iter().enumerate() {
+        if idx > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{item}")?;
+    }
+    Ok(())
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consume the given token (after any whitespace) if it's next.
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ident(&mut self) -> Option<&'a str> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return None;
+        }
+        self.pos += len;
+        Some(&rest[..len])
+    }
+
+    fn parse_type(&mut self) -> Option<TypeName> {
+        if self.eat("&") {
+            // References are encoded like the thing they point to, so we ignore
+            // them along with any lifetime and mutability.
+            if self.eat("'") {
+                self.ident()?;
+            }
+            let start = self.pos;
+            if self.ident() != Some("mut") {
+                self.pos = start;
+            }
+            self.parse_type()
+        } else if self.eat("(") {
+            let fields = self.parse_list(")")?;
+            Some(TypeName::Tuple(fields))
+        } else if self.eat("[") {
+            let ty = Box::new(self.parse_type()?);
+            if self.eat(";") {
+                let len = self.ident()?.parse().ok()?;
+                self.eat("]").then_some(TypeName::Array(ty, len))
+            } else {
+                self.eat("]").then_some(TypeName::Slice(ty))
+            }
+        } else if self.eat("<") {
+            // A qualified path like `<T as Trait<I>>::Balance`; we mostly only
+            // care about whatever comes after it.
+            self.parse_type()?;
+            if self.ident()? != "as" {
+                return None;
+            }
+            let trait_name = self.parse_type()?;
+            if !(self.eat(">") && self.eat("::")) {
+                return None;
+            }
+            let ty = self.parse_path()?;
+            // `<T::Lookup as StaticLookup>::Source` is conventionally called `LookupSource`.
+            match (&trait_name, &ty) {
+                (TypeName::Named { name: t, .. }, TypeName::Named { name, .. })
+                    if t == "StaticLookup" && name == "Source" =>
+                {
+                    Some(TypeName::named("LookupSource"))
+                }
+                _ => Some(ty),
+            }
+        } else {
+            self.parse_path()
+        }
+    }
+
+    /// Parse a path like `frame_system::Trait::AccountId<T>`, keeping only the last segment.
+    fn parse_path(&mut self) -> Option<TypeName> {
+        loop {
+            let name = self.ident()?;
+            let params = if self.eat("<") {
+                self.parse_list(">")?
+            } else {
+                Vec::new()
+            };
+            if !self.eat("::") {
+                return Some(TypeName::Named {
+                    name: name.to_owned(),
+                    params,
+                });
+            }
+        }
+    }
+
+    /// Parse a comma separated list of types, up to and including the closing token.
+    fn parse_list(&mut self, close: &str) -> Option<Vec<TypeName>> {
+        let mut items = Vec::new();
+        loop {
+            if self.eat(close) {
+                return Some(items);
+            }
+            items.push(self.parse_type()?);
+            if !self.eat(",") {
+                return self.eat(close).then_some(items);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn normalized(input: &str) -> String {
+        TypeName::parse(input).unwrap().to_string()
+    }
+
+    #[test]
+    fn normalizes_type_names() {
+        let cases = [
+            ("u32", "u32"),
+            ("T::Balance", "Balance"),
+            ("<T as Trait>::Balance", "Balance"),
+            ("<T as Trait<I>>::Balance", "Balance"),
+            ("BalanceOf<T>", "BalanceOf<T>"),
+            ("Vec<T::AccountId>", "Vec<AccountId>"),
+            (
+                "Vec<(T::AccountId, BalanceOf<T>)>",
+                "Vec<(AccountId, BalanceOf<T>)>",
+            ),
+            ("Box<<T as Trait>::Call>", "Box<Call>"),
+            ("<T::Lookup as StaticLookup>::Source", "LookupSource"),
+            ("[u8; 32]", "[u8; 32]"),
+            ("&'static [u8]", "[u8]"),
+            ("()", "()"),
+            (
+                "Option<(T::BlockNumber, Vec<u8>,)>",
+                "Option<(BlockNumber, Vec<u8>)>",
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(normalized(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_type_names() {
+        for input in ["", "Vec<u8", "[u8; x]", "(u8, u16", "<T>::Foo", "u8 u16"] {
+            assert!(TypeName::parse(input).is_err(), "input: {input}");
+        }
+    }
+}
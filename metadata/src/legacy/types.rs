@@ -0,0 +1,431 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A mapping from the type names found in legacy metadata to the shapes of those types,
+/// much like the type definitions given to polkadot.js.
+///
+/// Legacy metadata (V9 to V13) refers to every type by name (eg `T::Balance` or
+/// `Vec<<T as Trait>::AccountId>`), and so this mapping is needed in order to know how
+/// to decode values of those types. Names are normalized before they are looked up,
+/// so `<T as Trait>::Balance` and `T::Balance` will both be resolved using a definition
+/// for `Balance`. Common generic types (`Vec`, `Option`, `Compact`, `BTreeMap` and so on),
+/// tuples, arrays and primitives are understood without needing any definitions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LegacyTypes {
+    types: BTreeMap<String, LegacyTypeDef>,
+    signed_extensions: BTreeMap<String, LegacySignedExtension>,
+}
+
+/// The shape of a type that's referred to by name in legacy metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LegacyTypeDef {
+    /// Another name for some type, eg `"Balance"` might be an alias for `"u128"`.
+    Alias(String),
+    /// A struct with the given field names and type names, in order.
+    Struct(Vec<(String, String)>),
+    /// An enum with the given variants.
+    Enum(Vec<LegacyVariant>),
+}
+
+/// A single variant of a [`LegacyTypeDef::Enum`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyVariant {
+    /// The name of the variant.
+    pub name: String,
+    /// The index of the variant, which is what's encoded to identify it.
+    pub index: u8,
+    /// The fields of the variant, as pairs of optional field name and type name.
+    pub fields: Vec<(Option<String>, String)>,
+}
+
+/// The types of the data that a signed extension adds to a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacySignedExtension {
+    /// The name of the type of the data encoded into the transaction itself.
+    pub extra: String,
+    /// The name of the type of the data added to the signer payload only.
+    pub additional: String,
+}
+
+impl LegacyTypes {
+    /// Create a new, empty set of type definitions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a set of type definitions which covers common Substrate types (like
+    /// `AccountId`, `Balance`, `Hash`, `DispatchInfo` and `DispatchError`) and signed
+    /// extensions. Definitions for anything chain specific must be added to these in
+    /// order to decode blocks from that chain.
+    pub fn substrate() -> Self {
+        let mut types = Self::new();
+
+        let aliases = [
+            ("AccountId", "[u8; 32]"),
+            ("AccountIndex", "u32"),
+            ("Address", "AccountId"),
+            ("LookupSource", "AccountId"),
+            ("Balance", "u128"),
+            ("BalanceOf", "Balance"),
+            ("BlockNumber", "u32"),
+            ("H160", "[u8; 20]"),
+            ("H256", "[u8; 32]"),
+            ("H512", "[u8; 64]"),
+            ("Hash", "H256"),
+            ("Index", "u32"),
+            ("Moment", "u64"),
+            ("Weight", "u64"),
+            ("Percent", "u8"),
+            ("Permill", "u32"),
+            ("Perbill", "u32"),
+            ("Perquintill", "u64"),
+            ("Proposal", "Call"),
+            ("ExtrinsicSignature", "MultiSignature"),
+            ("ExtrinsicEra", "Era"),
+        ];
+        for (name, ty) in aliases {
+            types.insert(name, LegacyTypeDef::Alias(ty.to_owned()));
+        }
+
+        types.insert(
+            "MultiSignature",
+            LegacyTypeDef::enumeration([
+                ("Ed25519", Some("[u8; 64]")),
+                ("Sr25519", Some("[u8; 64]")),
+                ("Ecdsa", Some("[u8; 65]")),
+            ]),
+        );
+        types.insert(
+            "DispatchInfo",
+            LegacyTypeDef::Struct(vec![
+                ("weight".to_owned(), "Weight".to_owned()),
+                ("class".to_owned(), "DispatchClass".to_owned()),
+                ("pays_fee".to_owned(), "Pays".to_owned()),
+            ]),
+        );
+        types.insert(
+            "DispatchClass",
+            LegacyTypeDef::enumeration([
+                ("Normal", None),
+                ("Operational", None),
+                ("Mandatory", None),
+            ]),
+        );
+        types.insert(
+            "Pays",
+            LegacyTypeDef::enumeration([("Yes", None), ("No", None)]),
+        );
+        types.insert(
+            "DispatchError",
+            LegacyTypeDef::enumeration([
+                ("Other", None),
+                ("CannotLookup", None),
+                ("BadOrigin", None),
+                ("Module", Some("DispatchErrorModule")),
+                ("ConsumerRemaining", None),
+                ("NoProviders", None),
+                ("Token", Some("TokenError")),
+                ("Arithmetic", Some("ArithmeticError")),
+            ]),
+        );
+        types.insert(
+            "DispatchErrorModule",
+            LegacyTypeDef::Struct(vec![
+                ("index".to_owned(), "u8".to_owned()),
+                ("error".to_owned(), "u8".to_owned()),
+            ]),
+        );
+        types.insert(
+            "TokenError",
+            LegacyTypeDef::enumeration([
+                ("NoFunds", None),
+                ("WouldDie", None),
+                ("BelowMinimum", None),
+                ("CannotCreate", None),
+                ("UnknownAsset", None),
+                ("Frozen", None),
+                ("Unsupported", None),
+            ]),
+        );
+        types.insert(
+            "ArithmeticError",
+            LegacyTypeDef::enumeration([
+                ("Underflow", None),
+                ("Overflow", None),
+                ("DivisionByZero", None),
+            ]),
+        );
+
+        // A mortal era is encoded as two bytes, and an immortal era as a single zero byte,
+        // which we can describe as an enum whose first variant has no data.
+        let mut era_variants = vec![LegacyVariant {
+            name: "Immortal".to_owned(),
+            index: 0,
+            fields: vec![],
+        }];
+        era_variants.extend((1..=u8::MAX).map(|index| LegacyVariant {
+            name: format!("Mortal{index}"),
+            index,
+            fields: vec![(None, "u8".to_owned())],
+        }));
+        types.insert("Era", LegacyTypeDef::Enum(era_variants));
+
+        // As in modern runtimes, the tip is wrapped in a struct.
+        types.insert(
+            "ChargeTransactionPayment",
+            LegacyTypeDef::Struct(vec![("tip".to_owned(), "Compact<Balance>".to_owned())]),
+        );
+
+        let signed_extensions = [
+            ("CheckVersion", "()", "u32"),
+            ("CheckSpecVersion", "()", "u32"),
+            ("CheckTxVersion", "()", "u32"),
+            ("CheckGenesis", "()", "Hash"),
+            ("CheckEra", "Era", "Hash"),
+            ("CheckMortality", "Era", "Hash"),
+            ("CheckNonce", "Compact<Index>", "()"),
+            ("CheckWeight", "()", "()"),
+            ("ChargeTransactionPayment", "ChargeTransactionPayment", "()"),
+            ("CheckBlockGasLimit", "()", "()"),
+        ];
+        for (name, extra, additional) in signed_extensions {
+            types.insert_signed_extension(name, extra, additional);
+        }
+
+        types
+    }
+
+    /// Add or replace the definition for some type name.
+    pub fn insert(&mut self, name: impl Into<String>, def: LegacyTypeDef) {
+        self.types.insert(name.into(), def);
+    }
+
+    /// Add or replace the types of the data that some signed extension adds to transactions.
+    /// Any signed extension without a definition is assumed to add no data.
+    pub fn insert_signed_extension(
+        &mut self,
+        name: impl Into<String>,
+        extra: impl Into<String>,
+        additional: impl Into<String>,
+    ) {
+        self.signed_extensions.insert(
+            name.into(),
+            LegacySignedExtension {
+                extra: extra.into(),
+                additional: additional.into(),
+            },
+        );
+    }
+
+    /// Add all of the definitions from `other` to these, replacing any that already exist.
+    pub fn extend(&mut self, other: LegacyTypes) {
+        self.types.extend(other.types);
+        self.signed_extensions.extend(other.signed_extensions);
+    }
+
+    /// Return the definition for some type name, if one exists.
+    pub fn get(&self, name: &str) -> Option<&LegacyTypeDef> {
+        self.types.get(name)
+    }
+
+    /// Return the types of the data that some signed extension adds, if they are known.
+    pub fn signed_extension(&self, name: &str) -> Option<&LegacySignedExtension> {
+        self.signed_extensions.get(name)
+    }
+}
+
+impl LegacyTypeDef {
+    /// Construct an enum from variant names and optional type names, indexing the
+    /// variants in the order that they are given.
+    pub fn enumeration<'a>(variants: impl IntoIterator<Item = (&'a str, Option<&'a str>)>) -> Self {
+        let variants = variants
+            .into_iter()
+            .enumerate()
+            .map(|(index, (name, ty))| LegacyVariant {
+                name: name.to_owned(),
+                index: index as u8,
+                fields: ty.into_iter().map(|ty| (None, ty.to_owned())).collect(),
+            })
+            .collect();
+        LegacyTypeDef::Enum(variants)
+    }
+}
+
+/// Type definitions for some chain which vary with the spec version of its runtime,
+/// much like a polkadot.js type bundle.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LegacyTypeBundle {
+    base: LegacyTypes,
+    overrides: Vec<LegacyTypeOverride>,
+}
+
+/// Type definitions which apply to a range of spec versions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LegacyTypeOverride {
+    min: Option<u32>,
+    max: Option<u32>,
+    types: LegacyTypes,
+}
+
+impl LegacyTypeBundle {
+    /// Create a bundle from some type definitions which apply to every spec version.
+    pub fn new(base: LegacyTypes) -> Self {
+        LegacyTypeBundle {
+            base,
+            overrides: vec![],
+        }
+    }
+
+    /// Replace the type definitions which apply to every spec version in this bundle.
+    pub fn with_base(mut self, base: LegacyTypes) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Add some type definitions which apply only to spec versions between `min` and
+    /// `max` (inclusive). A bound of `None` means that the range is unbounded on that side.
+    /// Overrides are applied in the order that they are added.
+    pub fn add_override(&mut self, min: Option<u32>, max: Option<u32>, types: LegacyTypes) {
+        self.overrides.push(LegacyTypeOverride { min, max, types });
+    }
+
+    /// Return the type definitions which apply to the given spec version.
+    pub fn for_spec_version(&self, spec_version: u32) -> LegacyTypes {
+        let mut types = self.base.clone();
+        for o in &self.overrides {
+            let above_min = o.min.map_or(true, |min| spec_version >= min);
+            let below_max = o.max.map_or(true, |max| spec_version <= max);
+            if above_min && below_max {
+                types.extend(o.types.clone());
+            }
+        }
+        types
+    }
+}
+
+impl From<LegacyTypes> for LegacyTypeBundle {
+    fn from(base: LegacyTypes) -> Self {
+        LegacyTypeBundle::new(base)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn alias(ty: &str) -> LegacyTypeDef {
+        LegacyTypeDef::Alias(ty.to_owned())
+    }
+
+    fn types(defs: &[(&str, &str)]) -> LegacyTypes {
+        let mut types = LegacyTypes::new();
+        for (name, ty) in defs {
+            types.insert(*name, alias(ty));
+        }
+        types
+    }
+
+    #[test]
+    fn enumeration_indexes_variants_in_order() {
+        let LegacyTypeDef::Enum(variants) =
+            LegacyTypeDef::enumeration([("A", None), ("B", Some("u32"))])
+        else {
+            panic!("expected an enum");
+        };
+        assert_eq!(
+            variants,
+            [
+                LegacyVariant {
+                    name: "A".to_owned(),
+                    index: 0,
+                    fields: vec![],
+                },
+                LegacyVariant {
+                    name: "B".to_owned(),
+                    index: 1,
+                    fields: vec![(None, "u32".to_owned())],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn extend_replaces_existing_definitions() {
+        let mut base = types(&[("Weight", "u32"), ("Balance", "u128")]);
+        base.insert_signed_extension("CheckFoo", "u8", "()");
+
+        let mut other = types(&[("Weight", "u64")]);
+        other.insert_signed_extension("CheckFoo", "u16", "()");
+        base.extend(other);
+
+        assert_eq!(base.get("Weight"), Some(&alias("u64")));
+        assert_eq!(base.get("Balance"), Some(&alias("u128")));
+        assert_eq!(base.signed_extension("CheckFoo").unwrap().extra, "u16");
+        assert_eq!(base.get("Missing"), None);
+        assert_eq!(base.signed_extension("CheckMissing"), None);
+    }
+
+    #[test]
+    fn substrate_types_cover_signed_extensions() {
+        let types = LegacyTypes::substrate();
+        let nonce = types.signed_extension("CheckNonce").unwrap();
+        assert_eq!(nonce.extra, "Compact<Index>");
+        assert_eq!(nonce.additional, "()");
+
+        // Every mortal era variant has a single byte following its index.
+        let Some(LegacyTypeDef::Enum(era)) = types.get("Era") else {
+            panic!("expected an era enum");
+        };
+        assert_eq!(era.len(), 256);
+        assert!(era[0].fields.is_empty());
+        assert!(era[1..].iter().all(|v| v.fields.len() == 1));
+    }
+
+    #[test]
+    fn bundle_applies_overrides_for_spec_version() {
+        let mut bundle = LegacyTypeBundle::new(types(&[("Weight", "u32"), ("Balance", "u128")]));
+        bundle.add_override(None, Some(9), types(&[("Balance", "u64")]));
+        bundle.add_override(Some(10), None, types(&[("Weight", "u64")]));
+        // Later overrides win where they overlap with earlier ones.
+        bundle.add_override(Some(20), Some(29), types(&[("Weight", "u128")]));
+
+        let at = |spec_version| {
+            let types = bundle.for_spec_version(spec_version);
+            (
+                types.get("Weight").cloned().unwrap(),
+                types.get("Balance").cloned().unwrap(),
+            )
+        };
+        assert_eq!(at(0), (alias("u32"), alias("u64")));
+        assert_eq!(at(9), (alias("u32"), alias("u64")));
+        assert_eq!(at(10), (alias("u64"), alias("u128")));
+        assert_eq!(at(20), (alias("u128"), alias("u128")));
+        assert_eq!(at(29), (alias("u128"), alias("u128")));
+        assert_eq!(at(30), (alias("u64"), alias("u128")));
+    }
+
+    #[test]
+    fn bundle_base_can_be_replaced() {
+        let mut bundle: LegacyTypeBundle = types(&[("Weight", "u32")]).into();
+        bundle.add_override(Some(10), None, types(&[("Weight", "u64")]));
+        let bundle = bundle.with_base(types(&[("Weight", "u8"), ("Balance", "u128")]));
+
+        assert_eq!(bundle.for_spec_version(0).get("Weight"), Some(&alias("u8")));
+        assert_eq!(
+            bundle.for_spec_version(10).get("Weight"),
+            Some(&alias("u64"))
+        );
+        assert_eq!(
+            bundle.for_spec_version(10).get("Balance"),
+            Some(&alias("u128"))
+        );
+    }
+}
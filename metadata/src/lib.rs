@@ -13,6 +13,8 @@
 //!    from a node (this uses [`codec::Decode`]).
 //! 2. Obtaining [`frame_metadata::RuntimeMetadataPrefixed`], and then
 //!    using `.try_into()` to convert it into [`Metadata`].
+//! 3. Calling `Metadata::decode_legacy()` given some legacy (V9 to V13) metadata bytes
+//!    and the [`LegacyTypes`] needed to make sense of them.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 #![deny(missing_docs)]
@@ -20,9 +22,11 @@
 extern crate alloc;
 
 mod from_into;
+mod legacy;
 mod utils;
 
 use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
@@ -39,6 +43,16 @@ type ArcStr = Arc<str>;
 
 use crate::utils::validation::{get_custom_value_hash, HASH_LEN};
 pub use from_into::TryFromError;
+pub use legacy::{
+    LegacyError, LegacySignedExtension, LegacyTypeBundle, LegacyTypeDef, LegacyTypes, LegacyVariant,
+};
+pub use utils::compatibility::{ChangedItem, CompatibilityReport, MetadataItem};
+pub use utils::diff::{DiffStatus, ItemDiff, MetadataDiff, PalletDiff, RuntimeApiDiff};
+pub use utils::merkleized::{
+    MerkleizedMetadataError, MetadataDigest, MetadataDigestInfo, MetadataProof,
+};
+pub use utils::render::{json_string, TypeFormat, TypeScriptRenderer};
+pub use utils::type_diff::{Breakage, TypeChange, TypeChangeKind};
 pub use utils::validation::MetadataHasher;
 
 /// Node metadata. This can be constructed by providing some compatible [`frame_metadata`]
@@ -52,6 +66,8 @@ pub struct Metadata {
     pallets: OrderedMap<ArcStr, PalletMetadataInner>,
     /// Find the location in the pallet Vec by pallet index.
     pallets_by_index: HashMap<u8, usize>,
+    /// Find the location in the pallet Vec by the pallet index used in encoded calls.
+    pallets_by_call_index: HashMap<u8, usize>,
     /// Metadata of the extrinsic.
     extrinsic: ExtrinsicMetadata,
     /// The type ID of the `Runtime` type.
@@ -76,7 +92,7 @@ impl frame_decode::extrinsics::ExtrinsicTypeInfo for Metadata {
         pallet_index: u8,
         call_index: u8,
     ) -> Result<ExtrinsicCallInfo<'_, Self::TypeId>, ExtrinsicInfoError<'_>> {
-        let pallet = self.pallet_by_index(pallet_index).ok_or({
+        let pallet = self.pallet_by_call_index(pallet_index).ok_or({
             ExtrinsicInfoError::PalletNotFound {
                 index: pallet_index,
             }
@@ -117,21 +133,19 @@ impl frame_decode::extrinsics::ExtrinsicTypeInfo for Metadata {
         &self,
         extension_version: Option<u8>,
     ) -> Result<ExtrinsicExtensionInfo<'_, Self::TypeId>, ExtrinsicInfoError<'_>> {
-        // For now, if there exists an extension version that's non-zero, we say we don't know
-        // how to decode it. When multiple extension versions exist, we may have to tighten up
-        // on this and require V16 metadata to decode.
-        if let Some(extension_version) = extension_version {
-            if extension_version != 0 {
-                return Err(ExtrinsicInfoError::ExtrinsicExtensionVersionNotSupported {
-                    extension_version,
-                });
-            }
-        }
+        // General (v5) extrinsics say which version of the transaction extensions they use,
+        // while anything else uses the default version.
+        let extension_version =
+            extension_version.unwrap_or_else(|| self.extrinsic().transaction_extensions_version());
+        let signed_extensions = self
+            .extrinsic()
+            .signed_extensions_by_version(extension_version)
+            .ok_or(ExtrinsicInfoError::ExtrinsicExtensionVersionNotSupported {
+                extension_version,
+            })?;
 
         Ok(ExtrinsicExtensionInfo {
-            extension_ids: self
-                .extrinsic()
-                .signed_extensions()
+            extension_ids: signed_extensions
                 .iter()
                 .map(|f| ExtrinsicInfoArg {
                     name: Cow::Borrowed(f.identifier()),
@@ -194,6 +208,20 @@ impl Metadata {
         })
     }
 
+    /// Access a pallet given the index used to identify it in encoded calls. This is
+    /// the same as [`Metadata::pallet_by_index()`] for any metadata from V12 onwards.
+    pub fn pallet_by_call_index(&self, call_index: u8) -> Option<PalletMetadata<'_>> {
+        let inner = self
+            .pallets_by_call_index
+            .get(&call_index)
+            .and_then(|i| self.pallets.get_by_index(*i))?;
+
+        Some(PalletMetadata {
+            inner,
+            types: self.types(),
+        })
+    }
+
     /// Access a pallet given its name.
     pub fn pallet_by_name(&self, pallet_name: &str) -> Option<PalletMetadata<'_>> {
         let inner = self.pallets.get_by_key(pallet_name)?;
@@ -246,6 +274,28 @@ impl Metadata {
         utils::retain::retain_metadata(self, pallet_filter, api_filter);
     }
 
+    /// Like [`Metadata::retain()`], but additionally only keep the calls, events, storage entries,
+    /// constants, view functions, runtime API methods and custom values that `item_filter`
+    /// returns true for. Any types which are no longer needed are removed.
+    ///
+    /// The call and event enums of a pallet are left whole if any of the items that we keep
+    /// could return them, so that such values can still be decoded.
+    pub fn retain_items<F, G, H>(&mut self, pallet_filter: F, api_filter: G, item_filter: H)
+    where
+        F: FnMut(&str) -> bool,
+        G: FnMut(&str) -> bool,
+        H: FnMut(&MetadataItem) -> bool,
+    {
+        utils::retain::retain_metadata_items(self, pallet_filter, api_filter, item_filter);
+    }
+
+    /// Have the given metadatas share a single type registry, in which any types that are
+    /// identical across them appear only once. This is useful when working with several
+    /// versions of a runtime at once, since most of their types will be the same.
+    pub fn share_types(metadatas: &mut [Metadata]) {
+        utils::share::share_types(metadatas);
+    }
+
     /// Get type hash for a type in the registry
     pub fn type_hash(&self, id: u32) -> Option<[u8; HASH_LEN]> {
         self.types.resolve(id)?;
@@ -275,11 +325,23 @@ impl<'a> PalletMetadata<'a> {
         self.inner.index
     }
 
+    /// The pallet index used to identify this pallet in encoded calls. Prior to V12
+    /// metadata, runtimes counted pallet indexes separately for calls and events, and so
+    /// this may differ from [`PalletMetadata::index()`] for such metadata.
+    pub fn call_index(&self) -> u8 {
+        self.inner.call_index
+    }
+
     /// The pallet docs.
     pub fn docs(&self) -> &'a [String] {
         &self.inner.docs
     }
 
+    /// Is the pallet deprecated?
+    pub fn deprecation_info(&self) -> &'a DeprecationInfo {
+        &self.inner.deprecation_info
+    }
+
     /// Type ID for the pallet's Call type, if it exists.
     pub fn call_ty_id(&self) -> Option<u32> {
         self.inner.call_ty
@@ -314,6 +376,21 @@ impl<'a> PalletMetadata<'a> {
         )
     }
 
+    /// Return an event variant given it's name.
+    pub fn event_variant_by_name(&self, event_name: &str) -> Option<&'a Variant<PortableForm>> {
+        self.inner
+            .event_variant_index
+            .lookup_by_name(event_name, self.inner.event_ty, self.types)
+    }
+
+    /// Is the event variant with the given index deprecated?
+    pub fn event_variant_deprecation_info(&self, variant_index: u8) -> &'a DeprecationInfo {
+        self.inner
+            .event_deprecation_info
+            .get(&variant_index)
+            .unwrap_or(&NOT_DEPRECATED)
+    }
+
     /// Return all of the call variants, if a call type exists.
     pub fn call_variants(&self) -> Option<&'a [Variant<PortableForm>]> {
         VariantIndex::get(self.inner.call_ty, self.types)
@@ -333,6 +410,14 @@ impl<'a> PalletMetadata<'a> {
             .lookup_by_name(call_name, self.inner.call_ty, self.types)
     }
 
+    /// Is the call variant with the given index deprecated?
+    pub fn call_variant_deprecation_info(&self, variant_index: u8) -> &'a DeprecationInfo {
+        self.inner
+            .call_deprecation_info
+            .get(&variant_index)
+            .unwrap_or(&NOT_DEPRECATED)
+    }
+
     /// Return all of the error variants, if an error type exists.
     pub fn error_variants(&self) -> Option<&'a [Variant<PortableForm>]> {
         VariantIndex::get(self.inner.error_ty, self.types)
@@ -347,6 +432,14 @@ impl<'a> PalletMetadata<'a> {
         )
     }
 
+    /// Is the error variant with the given index deprecated?
+    pub fn error_variant_deprecation_info(&self, variant_index: u8) -> &'a DeprecationInfo {
+        self.inner
+            .error_deprecation_info
+            .get(&variant_index)
+            .unwrap_or(&NOT_DEPRECATED)
+    }
+
     /// Return constant details given the constant name.
     pub fn constant_by_name(&self, name: &str) -> Option<&'a ConstantMetadata> {
         self.inner.constants.get_by_key(name)
@@ -357,6 +450,29 @@ impl<'a> PalletMetadata<'a> {
         self.inner.constants.values().iter()
     }
 
+    /// Return view function details given the view function name.
+    pub fn view_function_by_name(&self, name: &str) -> Option<&'a ViewFunctionMetadata> {
+        self.inner.view_functions.get_by_key(name)
+    }
+
+    /// An iterator over the view functions in this pallet.
+    pub fn view_functions(&self) -> impl ExactSizeIterator<Item = &'a ViewFunctionMetadata> {
+        self.inner.view_functions.values().iter()
+    }
+
+    /// Return associated type details given the associated type name.
+    pub fn associated_type_by_name(&self, name: &str) -> Option<&'a AssociatedTypeMetadata> {
+        self.inner
+            .associated_types
+            .iter()
+            .find(|ty| ty.name == name)
+    }
+
+    /// An iterator over the associated types in this pallet.
+    pub fn associated_types(&self) -> impl ExactSizeIterator<Item = &'a AssociatedTypeMetadata> {
+        self.inner.associated_types.iter()
+    }
+
     /// Return a hash for the storage entry, or None if it was not found.
     pub fn storage_hash(&self, entry_name: &str) -> Option<[u8; HASH_LEN]> {
         crate::utils::validation::get_storage_hash(self, entry_name)
@@ -372,6 +488,16 @@ impl<'a> PalletMetadata<'a> {
         crate::utils::validation::get_call_hash(self, call_name)
     }
 
+    /// Return a hash for the event, or None if it was not found.
+    pub fn event_hash(&self, event_name: &str) -> Option<[u8; HASH_LEN]> {
+        crate::utils::validation::get_event_hash(self, event_name)
+    }
+
+    /// Return a hash for the view function, or None if it was not found.
+    pub fn view_function_hash(&self, view_function_name: &str) -> Option<[u8; HASH_LEN]> {
+        crate::utils::validation::get_view_function_hash(self, view_function_name)
+    }
+
     /// Return a hash for the entire pallet.
     pub fn hash(&self) -> [u8; HASH_LEN] {
         crate::utils::validation::get_pallet_hash(*self, &OuterEnumHashes::empty())
@@ -384,6 +510,8 @@ struct PalletMetadataInner {
     name: ArcStr,
     /// Pallet index.
     index: u8,
+    /// Pallet index used in encoded calls.
+    call_index: u8,
     /// Pallet storage metadata.
     storage: Option<StorageMetadata>,
     /// Type ID for the pallet Call enum.
@@ -400,8 +528,47 @@ struct PalletMetadataInner {
     error_variant_index: VariantIndex,
     /// Map from constant name to constant details.
     constants: OrderedMap<ArcStr, ConstantMetadata>,
+    /// Map from view function name to view function details.
+    view_functions: OrderedMap<ArcStr, ViewFunctionMetadata>,
+    /// Associated types of the pallet's config trait.
+    associated_types: Vec<AssociatedTypeMetadata>,
     /// Pallet documentation.
     docs: Vec<String>,
+    /// Is the pallet deprecated?
+    deprecation_info: DeprecationInfo,
+    /// Deprecated call variants, by variant index.
+    call_deprecation_info: BTreeMap<u8, DeprecationInfo>,
+    /// Deprecated event variants, by variant index.
+    event_deprecation_info: BTreeMap<u8, DeprecationInfo>,
+    /// Deprecated error variants, by variant index.
+    error_deprecation_info: BTreeMap<u8, DeprecationInfo>,
+}
+
+/// Details about whether some item in the metadata is deprecated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DeprecationInfo {
+    /// The item is not deprecated.
+    #[default]
+    NotDeprecated,
+    /// The item is deprecated, but no note was given.
+    DeprecatedWithoutNote,
+    /// The item is deprecated.
+    Deprecated {
+        /// A note explaining the deprecation.
+        note: String,
+        /// The version from which the item is deprecated, if given.
+        since: Option<String>,
+    },
+}
+
+// Returned for items that have no deprecation info of their own.
+static NOT_DEPRECATED: DeprecationInfo = DeprecationInfo::NotDeprecated;
+
+impl DeprecationInfo {
+    /// Is the item deprecated?
+    pub fn is_deprecated(&self) -> bool {
+        !matches!(self, DeprecationInfo::NotDeprecated)
+    }
 }
 
 /// Metadata for the storage entries in a pallet.
@@ -443,6 +610,8 @@ pub struct StorageEntryMetadata {
     default: Vec<u8>,
     /// Storage entry documentation.
     docs: Vec<String>,
+    /// Is the storage entry deprecated?
+    deprecation_info: DeprecationInfo,
 }
 
 impl StorageEntryMetadata {
@@ -466,6 +635,10 @@ impl StorageEntryMetadata {
     pub fn docs(&self) -> &[String] {
         &self.docs
     }
+    /// Is the storage entry deprecated?
+    pub fn deprecation_info(&self) -> &DeprecationInfo {
+        &self.deprecation_info
+    }
 }
 
 /// The type of a storage entry.
@@ -569,6 +742,8 @@ pub struct ConstantMetadata {
     value: Vec<u8>,
     /// Constant documentation.
     docs: Vec<String>,
+    /// Is the constant deprecated?
+    deprecation_info: DeprecationInfo,
 }
 
 impl ConstantMetadata {
@@ -588,6 +763,89 @@ impl ConstantMetadata {
     pub fn docs(&self) -> &[String] {
         &self.docs
     }
+    /// Is the constant deprecated?
+    pub fn deprecation_info(&self) -> &DeprecationInfo {
+        &self.deprecation_info
+    }
+}
+
+/// Metadata for a single pallet view function.
+#[derive(Debug, Clone)]
+pub struct ViewFunctionMetadata {
+    /// Name of the view function.
+    name: ArcStr,
+    /// The identifier used to query the view function.
+    query_id: [u8; 32],
+    /// View function parameters.
+    inputs: Vec<ViewFunctionParamMetadata>,
+    /// View function output type.
+    output_ty: u32,
+    /// View function documentation.
+    docs: Vec<String>,
+    /// Is the view function deprecated?
+    deprecation_info: DeprecationInfo,
+}
+
+impl ViewFunctionMetadata {
+    /// Name of the view function.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// The identifier used to query the view function.
+    pub fn query_id(&self) -> &[u8; 32] {
+        &self.query_id
+    }
+    /// View function inputs.
+    pub fn inputs(&self) -> impl ExactSizeIterator<Item = &ViewFunctionParamMetadata> {
+        self.inputs.iter()
+    }
+    /// View function return type.
+    pub fn output_ty(&self) -> u32 {
+        self.output_ty
+    }
+    /// View function documentation.
+    pub fn docs(&self) -> &[String] {
+        &self.docs
+    }
+    /// Is the view function deprecated?
+    pub fn deprecation_info(&self) -> &DeprecationInfo {
+        &self.deprecation_info
+    }
+}
+
+/// Metadata for a single input parameter to a view function.
+#[derive(Debug, Clone)]
+pub struct ViewFunctionParamMetadata {
+    /// Parameter name.
+    pub name: String,
+    /// Parameter type.
+    pub ty: u32,
+}
+
+/// Metadata for an associated type of a pallet's config trait.
+#[derive(Debug, Clone)]
+pub struct AssociatedTypeMetadata {
+    /// Name of the associated type.
+    name: String,
+    /// Type of the associated type.
+    ty: u32,
+    /// Associated type documentation.
+    docs: Vec<String>,
+}
+
+impl AssociatedTypeMetadata {
+    /// Name of the associated type.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Type of the associated type.
+    pub fn ty(&self) -> u32 {
+        self.ty
+    }
+    /// Associated type documentation.
+    pub fn docs(&self) -> &[String] {
+        &self.docs
+    }
 }
 
 /// Metadata for the extrinsic type.
@@ -603,8 +861,13 @@ pub struct ExtrinsicMetadata {
     extra_ty: u32,
     /// Extrinsic version.
     version: u8,
-    /// The signed extensions in the order they appear in the extrinsic.
-    signed_extensions: Vec<SignedExtensionMetadata>,
+    /// The extrinsic versions that the runtime supports.
+    supported_versions: Vec<u8>,
+    /// The version of the transaction extensions to use in v5 "general" transactions.
+    transaction_extensions_version: u8,
+    /// The signed extensions for each transaction extension version, in the order
+    /// they appear in the extrinsic.
+    signed_extensions_by_version: BTreeMap<u8, Vec<SignedExtensionMetadata>>,
 }
 
 impl ExtrinsicMetadata {
@@ -626,14 +889,48 @@ impl ExtrinsicMetadata {
         self.extra_ty
     }
 
-    /// Extrinsic version.
+    /// Extrinsic version. This is the latest version of the extrinsic format that the
+    /// runtime supports; see [`ExtrinsicMetadata::supported_versions()`] for all of them.
     pub fn version(&self) -> u8 {
         self.version
     }
 
+    /// All of the versions of the extrinsic format that the runtime supports, in ascending
+    /// order. Version 4 extrinsics are either signed or unsigned, while version 5 extrinsics are
+    /// either "bare" or "general", the latter carrying transaction extensions.
+    pub fn supported_versions(&self) -> &[u8] {
+        &self.supported_versions
+    }
+
+    /// Does the runtime support the given extrinsic version?
+    pub fn supports_version(&self, version: u8) -> bool {
+        self.supported_versions.contains(&version)
+    }
+
+    /// The version of the transaction extensions which should be encoded into version 5
+    /// "general" extrinsics. The signed extensions in [`ExtrinsicMetadata::signed_extensions()`]
+    /// are the transaction extensions for this version.
+    pub fn transaction_extensions_version(&self) -> u8 {
+        self.transaction_extensions_version
+    }
+
+    /// All of the transaction extension versions that the runtime supports, in ascending order.
+    pub fn transaction_extensions_versions(&self) -> impl ExactSizeIterator<Item = u8> + '_ {
+        self.signed_extensions_by_version.keys().copied()
+    }
+
     /// The extra/additional information associated with the extrinsic.
     pub fn signed_extensions(&self) -> &[SignedExtensionMetadata] {
-        &self.signed_extensions
+        self.signed_extensions_by_version(self.transaction_extensions_version)
+            .unwrap_or_default()
+    }
+
+    /// The extra/additional information associated with the extrinsic, for the given
+    /// transaction extension version, or `None` if the runtime doesn't support that version.
+    pub fn signed_extensions_by_version(&self, version: u8) -> Option<&[SignedExtensionMetadata]> {
+        self.signed_extensions_by_version
+            .get(&version)
+            .map(|exts| exts.as_slice())
     }
 }
 
@@ -707,6 +1004,10 @@ impl<'a> RuntimeApiMetadata<'a> {
     pub fn docs(&self) -> &[String] {
         &self.inner.docs
     }
+    /// Is the trait deprecated?
+    pub fn deprecation_info(&self) -> &'a DeprecationInfo {
+        &self.inner.deprecation_info
+    }
     /// An iterator over the trait methods.
     pub fn methods(&self) -> impl ExactSizeIterator<Item = &'a RuntimeApiMethodMetadata> {
         self.inner.methods.values().iter()
@@ -734,6 +1035,8 @@ struct RuntimeApiMetadataInner {
     methods: OrderedMap<ArcStr, RuntimeApiMethodMetadata>,
     /// Trait documentation.
     docs: Vec<String>,
+    /// Is the trait deprecated?
+    deprecation_info: DeprecationInfo,
 }
 
 /// Metadata for a single runtime API method.
@@ -747,6 +1050,8 @@ pub struct RuntimeApiMethodMetadata {
     output_ty: u32,
     /// Method documentation.
     docs: Vec<String>,
+    /// Is the method deprecated?
+    deprecation_info: DeprecationInfo,
 }
 
 impl RuntimeApiMethodMetadata {
@@ -766,6 +1071,10 @@ impl RuntimeApiMethodMetadata {
     pub fn output_ty(&self) -> u32 {
         self.output_ty
     }
+    /// Is the method deprecated?
+    pub fn deprecation_info(&self) -> &DeprecationInfo {
+        &self.deprecation_info
+    }
 }
 
 /// Metadata for a single input parameter to a runtime API method.
@@ -857,6 +1166,7 @@ impl codec::Decode for Metadata {
         let metadata = match metadata.1 {
             frame_metadata::RuntimeMetadata::V14(md) => md.try_into(),
             frame_metadata::RuntimeMetadata::V15(md) => md.try_into(),
+            frame_metadata::RuntimeMetadata::V16(md) => md.try_into(),
             _ => return Err("Cannot try_into() to Metadata: unsupported metadata version".into()),
         };
 
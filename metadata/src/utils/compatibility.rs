@@ -0,0 +1,568 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Compare two sets of metadata item by item, reporting which items have changed and how.
+
+use super::type_diff::{TypeChange, TypeDiff};
+use crate::{Metadata, PalletMetadata};
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+/// Some item in the metadata that generated code can depend on.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MetadataItem {
+    /// A call in some pallet.
+    Call {
+        /// The name of the pallet.
+        pallet: String,
+        /// The name of the call.
+        name: String,
+    },
+    /// An event in some pallet.
+    Event {
+        /// The name of the pallet.
+        pallet: String,
+        /// The name of the event.
+        name: String,
+    },
+    /// A storage entry in some pallet.
+    StorageEntry {
+        /// The name of the pallet.
+        pallet: String,
+        /// The name of the storage entry.
+        name: String,
+    },
+    /// A constant in some pallet.
+    Constant {
+        /// The name of the pallet.
+        pallet: String,
+        /// The name of the constant.
+        name: String,
+    },
+    /// A view function in some pallet.
+    ViewFunction {
+        /// The name of the pallet.
+        pallet: String,
+        /// The name of the view function.
+        name: String,
+    },
+    /// A method in some runtime API trait.
+    RuntimeApiMethod {
+        /// The name of the runtime API trait.
+        trait_name: String,
+        /// The name of the method.
+        name: String,
+    },
+    /// A custom value.
+    CustomValue {
+        /// The name of the custom value.
+        name: String,
+    },
+}
+
+impl Display for MetadataItem {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MetadataItem::Call { pallet, name } => write!(f, "call {pallet}::{name}"),
+            MetadataItem::Event { pallet, name } => write!(f, "event {pallet}::{name}"),
+            MetadataItem::StorageEntry { pallet, name } => {
+                write!(f, "storage entry {pallet}::{name}")
+            }
+            MetadataItem::Constant { pallet, name } => write!(f, "constant {pallet}::{name}"),
+            MetadataItem::ViewFunction { pallet, name } => {
+                write!(f, "view function {pallet}::{name}")
+            }
+            MetadataItem::RuntimeApiMethod { trait_name, name } => {
+                write!(f, "runtime API {trait_name}::{name}")
+            }
+            MetadataItem::CustomValue { name } => write!(f, "custom value {name}"),
+        }
+    }
+}
+
+/// The result of comparing some metadata (usually the metadata that code was generated
+/// from) with some newer metadata (usually the metadata of a live node). See
+/// [`Metadata::compatibility_report()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatibilityReport {
+    /// Items which exist in both sets of metadata but which differ in some way.
+    pub changed: Vec<ChangedItem>,
+    /// Items which no longer exist in the newer metadata.
+    pub removed: Vec<MetadataItem>,
+}
+
+impl CompatibilityReport {
+    /// Returns true if no items were changed or removed.
+    pub fn is_compatible(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl Display for CompatibilityReport {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.is_compatible() {
+            return f.write_str("no incompatible changes");
+        }
+        for changed in &self.changed {
+            writeln!(f, "changed: {}", changed.item)?;
+            for change in &changed.changes {
+                writeln!(f, "    {change}")?;
+            }
+        }
+        for removed in &self.removed {
+            writeln!(f, "removed: {removed}")?;
+        }
+        Ok(())
+    }
+}
+
+/// An item which differs between two sets of metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedItem {
+    /// The item that changed.
+    pub item: MetadataItem,
+    /// The individual changes that were found. This may be empty if the hash of the item
+    /// changed in some way that we can't describe.
+    pub changes: Vec<TypeChange>,
+}
+
+impl Metadata {
+    /// Compare this metadata with some newer metadata, reporting every call, event, storage
+    /// entry, constant, view function, runtime API method and custom value in this metadata
+    /// which has been removed or changed in the newer one. Items which only exist in the
+    /// newer metadata are not reported.
+    ///
+    /// An item is reported as changed if its hash differs (see [`PalletMetadata::call_hash()`]
+    /// and friends), or if any variant inside it has moved to a different index. Where
+    /// possible, the report describes each change that was found inside the item, in the
+    /// same way as [`Metadata::diff()`].
+    pub fn compatibility_report(&self, newer: &Metadata) -> CompatibilityReport {
+        let mut builder = ReportBuilder {
+            old: self,
+            new: newer,
+            report: CompatibilityReport::default(),
+        };
+
+        for pallet in self.pallets() {
+            builder.pallet(pallet);
+        }
+        builder.runtime_apis();
+        builder.custom_values();
+
+        builder.report
+    }
+}
+
+struct ReportBuilder<'a> {
+    old: &'a Metadata,
+    new: &'a Metadata,
+    report: CompatibilityReport,
+}
+
+impl<'a> ReportBuilder<'a> {
+    fn type_diff(&self) -> TypeDiff<'a> {
+        TypeDiff::new(self.old.types(), self.new.types())
+    }
+
+    /// Record the outcome of comparing some item which exists in both sets of metadata.
+    fn compared(&mut self, item: MetadataItem, hashes_match: bool, diff: TypeDiff) {
+        if !hashes_match || !diff.changes.is_empty() {
+            self.report.changed.push(ChangedItem {
+                item,
+                changes: diff.changes,
+            });
+        }
+    }
+
+    fn pallet(&mut self, old: PalletMetadata<'a>) {
+        let pallet_name = old.name();
+        let Some(new) = self.new.pallet_by_name(pallet_name) else {
+            self.report.removed.extend(pallet_items(old));
+            return;
+        };
+
+        for call in old.call_variants().unwrap_or_default() {
+            let item = MetadataItem::Call {
+                pallet: pallet_name.to_owned(),
+                name: call.name.clone(),
+            };
+            let Some(new_call) = new.call_variant_by_name(&call.name) else {
+                self.report.removed.push(item);
+                continue;
+            };
+            let mut diff = self.type_diff();
+            diff.variant(call, new_call);
+            let hashes_match = old.call_hash(&call.name) == new.call_hash(&call.name);
+            self.compared(item, hashes_match, diff);
+        }
+
+        for event in old.event_variants().unwrap_or_default() {
+            let item = MetadataItem::Event {
+                pallet: pallet_name.to_owned(),
+                name: event.name.clone(),
+            };
+            let Some(new_event) = new.event_variant_by_name(&event.name) else {
+                self.report.removed.push(item);
+                continue;
+            };
+            let mut diff = self.type_diff();
+            diff.variant(event, new_event);
+            let hashes_match = old.event_hash(&event.name) == new.event_hash(&event.name);
+            self.compared(item, hashes_match, diff);
+        }
+
+        for entry in old.storage().map(|s| s.entries()).unwrap_or_default() {
+            let item = MetadataItem::StorageEntry {
+                pallet: pallet_name.to_owned(),
+                name: entry.name().to_owned(),
+            };
+            let Some(new_entry) = new.storage().and_then(|s| s.entry_by_name(entry.name())) else {
+                self.report.removed.push(item);
+                continue;
+            };
+
+            let mut diff = self.type_diff();
+            diff.storage_entry(entry, new_entry);
+
+            let hashes_match = old.storage_hash(entry.name()) == new.storage_hash(entry.name());
+            self.compared(item, hashes_match, diff);
+        }
+
+        for constant in old.constants() {
+            let item = MetadataItem::Constant {
+                pallet: pallet_name.to_owned(),
+                name: constant.name().to_owned(),
+            };
+            let Some(new_constant) = new.constant_by_name(constant.name()) else {
+                self.report.removed.push(item);
+                continue;
+            };
+            let mut diff = self.type_diff();
+            diff.diff("", constant.ty(), new_constant.ty());
+            let hashes_match =
+                old.constant_hash(constant.name()) == new.constant_hash(constant.name());
+            self.compared(item, hashes_match, diff);
+        }
+
+        for view_function in old.view_functions() {
+            let item = MetadataItem::ViewFunction {
+                pallet: pallet_name.to_owned(),
+                name: view_function.name().to_owned(),
+            };
+            let Some(new_view_function) = new.view_function_by_name(view_function.name()) else {
+                self.report.removed.push(item);
+                continue;
+            };
+            let mut diff = self.type_diff();
+            let inputs = |v: &crate::ViewFunctionMetadata| -> Vec<_> {
+                v.inputs().map(|i| (i.name.clone(), i.ty)).collect()
+            };
+            diff.named_fields("", &inputs(view_function), &inputs(new_view_function));
+            diff.diff(
+                "output",
+                view_function.output_ty(),
+                new_view_function.output_ty(),
+            );
+            let hashes_match = old.view_function_hash(view_function.name())
+                == new.view_function_hash(view_function.name());
+            self.compared(item, hashes_match, diff);
+        }
+    }
+
+    fn runtime_apis(&mut self) {
+        for api in self.old.runtime_api_traits() {
+            let new_api = self.new.runtime_api_trait_by_name(api.name());
+            for method in api.methods() {
+                let item = MetadataItem::RuntimeApiMethod {
+                    trait_name: api.name().to_owned(),
+                    name: method.name().to_owned(),
+                };
+                let Some((new_api, new_method)) = new_api
+                    .as_ref()
+                    .and_then(|a| Some((a, a.method_by_name(method.name())?)))
+                else {
+                    self.report.removed.push(item);
+                    continue;
+                };
+                let mut diff = self.type_diff();
+                let inputs = |m: &crate::RuntimeApiMethodMetadata| -> Vec<_> {
+                    m.inputs().map(|i| (i.name.clone(), i.ty)).collect()
+                };
+                diff.named_fields("", &inputs(method), &inputs(new_method));
+                diff.diff("output", method.output_ty(), new_method.output_ty());
+                let hashes_match =
+                    api.method_hash(method.name()) == new_api.method_hash(method.name());
+                self.compared(item, hashes_match, diff);
+            }
+        }
+    }
+
+    fn custom_values(&mut self) {
+        let new_custom = self.new.custom();
+        for value in self.old.custom().iter() {
+            let item = MetadataItem::CustomValue {
+                name: value.name().to_owned(),
+            };
+            let Some(new_value) = new_custom.get(value.name()) else {
+                self.report.removed.push(item);
+                continue;
+            };
+            let mut diff = self.type_diff();
+            diff.diff("", value.type_id(), new_value.type_id());
+            let hashes_match = value.hash() == new_value.hash();
+            self.compared(item, hashes_match, diff);
+        }
+    }
+}
+
+/// Every item in some pallet.
+fn pallet_items<'a>(pallet: PalletMetadata<'a>) -> impl Iterator<Item = MetadataItem> + 'a {
+    let name = pallet.name();
+    let calls = pallet
+        .call_variants()
+        .unwrap_or_default()
+        .iter()
+        .map(move |v| MetadataItem::Call {
+            pallet: name.to_owned(),
+            name: v.name.clone(),
+        });
+    let events = pallet
+        .event_variants()
+        .unwrap_or_default()
+        .iter()
+        .map(move |v| MetadataItem::Event {
+            pallet: name.to_owned(),
+            name: v.name.clone(),
+        });
+    let storage = pallet
+        .storage()
+        .map(|s| s.entries())
+        .unwrap_or_default()
+        .iter()
+        .map(move |e| MetadataItem::StorageEntry {
+            pallet: name.to_owned(),
+            name: e.name().to_owned(),
+        });
+    let constants = pallet.constants().map(move |c| MetadataItem::Constant {
+        pallet: name.to_owned(),
+        name: c.name().to_owned(),
+    });
+    let view_functions = pallet
+        .view_functions()
+        .map(move |v| MetadataItem::ViewFunction {
+            pallet: name.to_owned(),
+            name: v.name().to_owned(),
+        });
+
+    calls
+        .chain(events)
+        .chain(storage)
+        .chain(constants)
+        .chain(view_functions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::type_diff::test_utils::{change, pallet_metadata};
+    use crate::utils::type_diff::Breakage;
+    use crate::utils::type_diff::TypeChangeKind;
+    use scale_info::{meta_type, MetaType};
+
+    fn metadata(
+        calls: MetaType,
+        events: MetaType,
+        constants: Vec<(&'static str, MetaType)>,
+    ) -> Metadata {
+        pallet_metadata(calls, Some(events), constants)
+    }
+
+    #[allow(dead_code)]
+    #[derive(scale_info::TypeInfo)]
+    enum Target {
+        Id(u32),
+        Index(u8),
+    }
+
+    mod before {
+        #[allow(dead_code)]
+        #[derive(scale_info::TypeInfo)]
+        pub enum Call {
+            #[codec(index = 0)]
+            Transfer { dest: super::Target, value: u64 },
+            #[codec(index = 1)]
+            Remark { remark: Vec<u8> },
+        }
+
+        #[allow(dead_code)]
+        #[derive(scale_info::TypeInfo)]
+        pub enum Event {
+            #[codec(index = 0)]
+            Transferred { amount: u64 },
+            #[codec(index = 1)]
+            Remarked,
+        }
+    }
+
+    mod after {
+        #[allow(dead_code)]
+        #[derive(scale_info::TypeInfo)]
+        pub enum Target {
+            Id(u32),
+            Raw(Vec<u8>),
+        }
+
+        #[allow(dead_code)]
+        #[derive(scale_info::TypeInfo)]
+        pub enum Call {
+            #[codec(index = 0)]
+            Transfer {
+                dest: Target,
+                value: u128,
+                keep_alive: bool,
+            },
+        }
+
+        #[allow(dead_code)]
+        #[derive(scale_info::TypeInfo)]
+        pub enum Event {
+            #[codec(index = 0)]
+            Remarked,
+            #[codec(index = 1)]
+            Transferred { amount: u64 },
+        }
+    }
+
+    #[test]
+    fn identical_metadata_is_compatible() {
+        let old = metadata(
+            meta_type::<before::Call>(),
+            meta_type::<before::Event>(),
+            vec![("Max", meta_type::<u32>())],
+        );
+        let report = old.compatibility_report(&old.clone());
+        assert!(report.is_compatible(), "{report}");
+    }
+
+    #[test]
+    fn reports_changed_and_removed_items() {
+        let old = metadata(
+            meta_type::<before::Call>(),
+            meta_type::<before::Event>(),
+            vec![("Max", meta_type::<u32>()), ("Min", meta_type::<u32>())],
+        );
+        let new = metadata(
+            meta_type::<after::Call>(),
+            meta_type::<after::Event>(),
+            vec![("Max", meta_type::<u32>())],
+        );
+
+        let report = old.compatibility_report(&new);
+        let item =
+            |f: fn(String, String) -> MetadataItem, name: &str| f("Test".into(), name.into());
+
+        assert_eq!(
+            report.changed,
+            vec![
+                ChangedItem {
+                    item: item(
+                        |pallet, name| MetadataItem::Call { pallet, name },
+                        "Transfer"
+                    ),
+                    changes: vec![
+                        change(
+                            "dest",
+                            TypeChangeKind::VariantRenamed {
+                                index: 1,
+                                old: "Index".into(),
+                                new: "Raw".into()
+                            },
+                            Breakage::NONE
+                        ),
+                        change(
+                            "dest.Index.0",
+                            TypeChangeKind::TypeChanged {
+                                old: "u8".into(),
+                                new: "Vec<u8>".into()
+                            },
+                            Breakage::BOTH
+                        ),
+                        change(
+                            "value",
+                            TypeChangeKind::IntegerWidthChanged {
+                                old: "u64".into(),
+                                new: "u128".into()
+                            },
+                            Breakage::BOTH
+                        ),
+                        change(
+                            "",
+                            TypeChangeKind::FieldAdded {
+                                name: "keep_alive".into()
+                            },
+                            Breakage::BOTH
+                        ),
+                    ],
+                },
+                ChangedItem {
+                    item: item(
+                        |pallet, name| MetadataItem::Event { pallet, name },
+                        "Transferred"
+                    ),
+                    changes: vec![change(
+                        "",
+                        TypeChangeKind::IndexChanged {
+                            name: "Transferred".into(),
+                            old: 0,
+                            new: 1
+                        },
+                        Breakage::BOTH
+                    )],
+                },
+                ChangedItem {
+                    item: item(
+                        |pallet, name| MetadataItem::Event { pallet, name },
+                        "Remarked"
+                    ),
+                    changes: vec![change(
+                        "",
+                        TypeChangeKind::IndexChanged {
+                            name: "Remarked".into(),
+                            old: 1,
+                            new: 0
+                        },
+                        Breakage::BOTH
+                    )],
+                },
+            ]
+        );
+        assert_eq!(
+            report.removed,
+            vec![
+                item(|pallet, name| MetadataItem::Call { pallet, name }, "Remark"),
+                item(
+                    |pallet, name| MetadataItem::Constant { pallet, name },
+                    "Min"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_removed_pallets() {
+        let old = metadata(
+            meta_type::<before::Call>(),
+            meta_type::<before::Event>(),
+            vec![],
+        );
+        let mut new = old.clone();
+        new.pallets.retain(|_| false);
+
+        let report = old.compatibility_report(&new);
+        assert!(report.changed.is_empty());
+        assert_eq!(report.removed.len(), 4);
+    }
+}
@@ -0,0 +1,537 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Compute the differences between two sets of metadata, following each item down into the
+//! types that it uses and classifying every change by whether it breaks code which encodes
+//! or decodes values according to the older metadata.
+
+use super::type_diff::{Breakage, TypeChange, TypeChangeKind, TypeDiff};
+use crate::{Metadata, PalletMetadata};
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use scale_info::{form::PortableForm, PortableRegistry, Variant};
+
+/// Whether something was added, removed or changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DiffStatus {
+    /// Only exists in the newer metadata.
+    Added,
+    /// Only exists in the older metadata.
+    Removed,
+    /// Exists in both sets of metadata, but differs.
+    Changed,
+}
+
+/// The differences between two sets of metadata. See [`Metadata::diff()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetadataDiff {
+    /// Pallets which were added, removed or changed, ordered by name.
+    pub pallets: Vec<PalletDiff>,
+    /// Runtime APIs which were added, removed or changed, ordered by name.
+    pub runtime_apis: Vec<RuntimeApiDiff>,
+}
+
+impl MetadataDiff {
+    /// Returns true if no differences were found.
+    pub fn is_empty(&self) -> bool {
+        self.pallets.is_empty() && self.runtime_apis.is_empty()
+    }
+
+    /// Which code the differences break, if any.
+    pub fn breakage(&self) -> Breakage {
+        let pallets = self.pallets.iter().map(PalletDiff::breakage);
+        let apis = self.runtime_apis.iter().map(RuntimeApiDiff::breakage);
+        pallets.chain(apis).fold(Breakage::NONE, Breakage::union)
+    }
+}
+
+/// The differences in a single pallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PalletDiff {
+    /// The name of the pallet.
+    pub name: String,
+    /// Whether the pallet was added, removed or changed. The remaining fields are only
+    /// populated for changed pallets.
+    pub status: DiffStatus,
+    /// Changes to the pallet itself, such as its index.
+    pub changes: Vec<TypeChange>,
+    /// Calls which were added, removed or changed, ordered by name.
+    pub calls: Vec<ItemDiff>,
+    /// Events which were added, removed or changed, ordered by name.
+    pub events: Vec<ItemDiff>,
+    /// Errors which were added, removed or changed, ordered by name.
+    pub errors: Vec<ItemDiff>,
+    /// Storage entries which were added, removed or changed, ordered by name.
+    pub storage_entries: Vec<ItemDiff>,
+    /// Constants which were added, removed or changed, ordered by name.
+    pub constants: Vec<ItemDiff>,
+}
+
+impl PalletDiff {
+    /// Which code the differences in this pallet break, if any.
+    pub fn breakage(&self) -> Breakage {
+        let items = self
+            .calls
+            .iter()
+            .chain(&self.events)
+            .chain(&self.errors)
+            .chain(&self.storage_entries)
+            .chain(&self.constants)
+            .map(ItemDiff::breakage);
+        let changes = self.changes.iter().map(|c| c.breakage);
+        items
+            .chain(changes)
+            .fold(status_breakage(self.status), Breakage::union)
+    }
+}
+
+/// The differences in a single runtime API trait.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RuntimeApiDiff {
+    /// The name of the runtime API trait.
+    pub name: String,
+    /// Whether the trait was added, removed or changed. Methods are only populated for
+    /// changed traits.
+    pub status: DiffStatus,
+    /// Methods which were added, removed or changed, ordered by name.
+    pub methods: Vec<ItemDiff>,
+}
+
+impl RuntimeApiDiff {
+    /// Which code the differences in this runtime API break, if any.
+    pub fn breakage(&self) -> Breakage {
+        self.methods
+            .iter()
+            .map(ItemDiff::breakage)
+            .fold(status_breakage(self.status), Breakage::union)
+    }
+}
+
+/// The differences in a single call, event, error, storage entry, constant or runtime API method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ItemDiff {
+    /// The name of the item.
+    pub name: String,
+    /// Whether the item was added, removed or changed. Changes are only populated for
+    /// changed items.
+    pub status: DiffStatus,
+    /// The changes found in the item.
+    pub changes: Vec<TypeChange>,
+}
+
+impl ItemDiff {
+    /// Which code the differences in this item break, if any.
+    pub fn breakage(&self) -> Breakage {
+        self.changes
+            .iter()
+            .map(|c| c.breakage)
+            .fold(status_breakage(self.status), Breakage::union)
+    }
+}
+
+impl Metadata {
+    /// Compute the differences between this metadata and some newer metadata.
+    ///
+    /// Pallets, runtime APIs and the items within them are matched up by name, and then the
+    /// types that each item uses are compared in the way that they are SCALE encoded: fields
+    /// by position and enum variants by index. Each change that's found records whether it
+    /// breaks code which encodes values according to this metadata (for example, to submit
+    /// calls) or decodes values according to this metadata (for example, to read events).
+    ///
+    /// For instance, adding a new variant to an enum breaks decoders but not encoders,
+    /// renaming a field without changing its type breaks neither, and changing the width of
+    /// an integer breaks both.
+    pub fn diff(&self, newer: &Metadata) -> MetadataDiff {
+        let old_types = self.types();
+        let new_types = newer.types();
+
+        let pallets = diff_by_name(
+            self.pallets(),
+            newer.pallets(),
+            |p| p.name().to_owned(),
+            |old, new| {
+                let diff = diff_pallets(old_types, new_types, *old, *new);
+                let unchanged = diff.changes.is_empty()
+                    && diff.calls.is_empty()
+                    && diff.events.is_empty()
+                    && diff.errors.is_empty()
+                    && diff.storage_entries.is_empty()
+                    && diff.constants.is_empty();
+                (!unchanged).then_some(diff)
+            },
+        )
+        .into_iter()
+        .map(|(name, status, diff)| {
+            diff.unwrap_or_else(|| PalletDiff {
+                name,
+                status,
+                changes: Vec::new(),
+                calls: Vec::new(),
+                events: Vec::new(),
+                errors: Vec::new(),
+                storage_entries: Vec::new(),
+                constants: Vec::new(),
+            })
+        })
+        .collect();
+
+        let runtime_apis = diff_by_name(
+            self.runtime_api_traits(),
+            newer.runtime_api_traits(),
+            |api| api.name().to_owned(),
+            |old, new| {
+                let methods = diff_items(
+                    old.methods(),
+                    new.methods(),
+                    |m| m.name().to_owned(),
+                    |old, new| {
+                        let mut diff = TypeDiff::new(old_types, new_types);
+                        let params = |m: &crate::RuntimeApiMethodMetadata| -> Vec<_> {
+                            m.inputs().map(|i| (i.name.clone(), i.ty)).collect()
+                        };
+                        diff.named_fields("", &params(old), &params(new));
+                        diff.diff("output", old.output_ty(), new.output_ty());
+                        diff.changes
+                    },
+                );
+                (!methods.is_empty()).then_some(methods)
+            },
+        )
+        .into_iter()
+        .map(|(name, status, methods)| RuntimeApiDiff {
+            name,
+            status,
+            methods: methods.unwrap_or_default(),
+        })
+        .collect();
+
+        MetadataDiff {
+            pallets,
+            runtime_apis,
+        }
+    }
+}
+
+fn status_breakage(status: DiffStatus) -> Breakage {
+    match status {
+        DiffStatus::Added | DiffStatus::Changed => Breakage::NONE,
+        DiffStatus::Removed => Breakage::BOTH,
+    }
+}
+
+/// Match up some old and new things by name, returning the name and status of each thing
+/// that was added, removed, or that `compare` returns something for. The results are
+/// ordered by name.
+fn diff_by_name<T, R>(
+    old: impl IntoIterator<Item = T>,
+    new: impl IntoIterator<Item = T>,
+    name: impl Fn(&T) -> String,
+    mut compare: impl FnMut(&T, &T) -> Option<R>,
+) -> Vec<(String, DiffStatus, Option<R>)> {
+    let mut entries: BTreeMap<String, (Option<T>, Option<T>)> = BTreeMap::new();
+    for t in old {
+        let key = name(&t);
+        entries.entry(key).or_insert((None, None)).0 = Some(t);
+    }
+    for t in new {
+        let key = name(&t);
+        entries.entry(key).or_insert((None, None)).1 = Some(t);
+    }
+
+    entries
+        .into_iter()
+        .filter_map(|(name, entry)| match entry {
+            (Some(_), None) => Some((name, DiffStatus::Removed, None)),
+            (None, Some(_)) => Some((name, DiffStatus::Added, None)),
+            (Some(old), Some(new)) => {
+                compare(&old, &new).map(|r| (name, DiffStatus::Changed, Some(r)))
+            }
+            (None, None) => None,
+        })
+        .collect()
+}
+
+/// Like [`diff_by_name`], but for items whose changes are described by a list of type changes.
+fn diff_items<T>(
+    old: impl IntoIterator<Item = T>,
+    new: impl IntoIterator<Item = T>,
+    name: impl Fn(&T) -> String,
+    mut compare: impl FnMut(&T, &T) -> Vec<TypeChange>,
+) -> Vec<ItemDiff> {
+    diff_by_name(old, new, name, |old, new| {
+        let changes = compare(old, new);
+        (!changes.is_empty()).then_some(changes)
+    })
+    .into_iter()
+    .map(|(name, status, changes)| ItemDiff {
+        name,
+        status,
+        changes: changes.unwrap_or_default(),
+    })
+    .collect()
+}
+
+fn diff_pallets(
+    old_types: &PortableRegistry,
+    new_types: &PortableRegistry,
+    old: PalletMetadata,
+    new: PalletMetadata,
+) -> PalletDiff {
+    let mut changes = Vec::new();
+    if old.index() != new.index() {
+        changes.push(TypeChange {
+            path: String::new(),
+            kind: TypeChangeKind::IndexChanged {
+                name: old.name().to_owned(),
+                old: old.index(),
+                new: new.index(),
+            },
+            breakage: Breakage::BOTH,
+        });
+    }
+
+    let variants = |old: Option<&[Variant<PortableForm>]>,
+                    new: Option<&[Variant<PortableForm>]>| {
+        diff_items(
+            old.unwrap_or_default(),
+            new.unwrap_or_default(),
+            |v| v.name.clone(),
+            |old, new| {
+                let mut diff = TypeDiff::new(old_types, new_types);
+                diff.variant(old, new);
+                diff.changes
+            },
+        )
+    };
+
+    let storage_entries = diff_items(
+        old.storage().map(|s| s.entries()).unwrap_or_default(),
+        new.storage().map(|s| s.entries()).unwrap_or_default(),
+        |e| e.name().to_owned(),
+        |old, new| {
+            let mut diff = TypeDiff::new(old_types, new_types);
+            diff.storage_entry(old, new);
+            diff.changes
+        },
+    );
+
+    let constants = diff_items(
+        old.constants(),
+        new.constants(),
+        |c| c.name().to_owned(),
+        |old, new| {
+            let mut diff = TypeDiff::new(old_types, new_types);
+            diff.diff("", old.ty(), new.ty());
+            if old.value() != new.value() {
+                diff.push("", TypeChangeKind::ConstantValueChanged, Breakage::NONE);
+            }
+            diff.changes
+        },
+    );
+
+    PalletDiff {
+        name: old.name().to_owned(),
+        status: DiffStatus::Changed,
+        changes,
+        calls: variants(old.call_variants(), new.call_variants()),
+        events: variants(old.event_variants(), new.event_variants()),
+        errors: variants(old.error_variants(), new.error_variants()),
+        storage_entries,
+        constants,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::type_diff::test_utils::{change, pallet_metadata};
+    use scale_info::{meta_type, MetaType};
+
+    fn metadata(calls: MetaType) -> Metadata {
+        pallet_metadata(calls, None, vec![])
+    }
+
+    /// The changes to the single call in some metadata built from the given call types.
+    fn call_changes(old: MetaType, new: MetaType) -> Vec<TypeChange> {
+        let diff = metadata(old).diff(&metadata(new));
+        assert_eq!(diff.pallets.len(), 1, "expected a single pallet diff");
+        let calls = &diff.pallets[0].calls;
+        assert_eq!(calls.len(), 1, "expected a single call diff");
+        calls[0].changes.clone()
+    }
+
+    #[test]
+    fn identical_metadata_has_no_diff() {
+        #[allow(dead_code)]
+        #[derive(scale_info::TypeInfo)]
+        enum Call {
+            Foo { a: u8 },
+        }
+
+        let md = metadata(meta_type::<Call>());
+        let diff = md.diff(&md.clone());
+        assert!(diff.is_empty());
+        assert!(!diff.breakage().is_breaking());
+    }
+
+    #[test]
+    fn trailing_variants_only_break_decoders() {
+        mod old {
+            #[allow(dead_code)]
+            #[derive(scale_info::TypeInfo)]
+            pub enum Kind {
+                A,
+                B,
+            }
+            #[allow(dead_code)]
+            #[derive(scale_info::TypeInfo)]
+            pub enum Call {
+                Foo { kind: Kind },
+            }
+        }
+        mod new {
+            #[allow(dead_code)]
+            #[derive(scale_info::TypeInfo)]
+            pub enum Kind {
+                A,
+                B,
+                C,
+            }
+            #[allow(dead_code)]
+            #[derive(scale_info::TypeInfo)]
+            pub enum Call {
+                Foo { kind: Kind },
+            }
+        }
+
+        assert_eq!(
+            call_changes(meta_type::<old::Call>(), meta_type::<new::Call>()),
+            vec![change(
+                "kind",
+                TypeChangeKind::VariantAdded {
+                    name: "C".into(),
+                    index: 2
+                },
+                Breakage::DECODERS
+            )]
+        );
+    }
+
+    #[test]
+    fn renamed_fields_with_the_same_encoding_are_not_breaking() {
+        mod old {
+            #[allow(dead_code)]
+            #[derive(scale_info::TypeInfo)]
+            pub enum Call {
+                Foo { who: [u8; 32], amount: u64 },
+            }
+        }
+        mod new {
+            #[allow(dead_code)]
+            #[derive(scale_info::TypeInfo)]
+            pub enum Call {
+                Foo { dest: [u8; 32], amount: u64 },
+            }
+        }
+
+        let changes = call_changes(meta_type::<old::Call>(), meta_type::<new::Call>());
+        assert_eq!(
+            changes,
+            vec![change(
+                "",
+                TypeChangeKind::FieldRenamed {
+                    old: "who".into(),
+                    new: "dest".into()
+                },
+                Breakage::NONE
+            )]
+        );
+    }
+
+    #[test]
+    fn integer_width_changes_are_classified() {
+        mod old {
+            #[allow(dead_code)]
+            #[derive(scale_info::TypeInfo)]
+            pub enum Call {
+                Foo {
+                    a: u32,
+                    #[codec(compact)]
+                    b: u32,
+                    #[codec(compact)]
+                    c: u64,
+                },
+            }
+        }
+        mod new {
+            #[allow(dead_code)]
+            #[derive(scale_info::TypeInfo)]
+            pub enum Call {
+                Foo {
+                    a: u64,
+                    #[codec(compact)]
+                    b: u64,
+                    #[codec(compact)]
+                    c: u32,
+                },
+            }
+        }
+
+        let integer = |old: &str, new: &str| TypeChangeKind::IntegerWidthChanged {
+            old: old.into(),
+            new: new.into(),
+        };
+        let changes = call_changes(meta_type::<old::Call>(), meta_type::<new::Call>());
+        assert_eq!(
+            changes,
+            vec![
+                change("a", integer("u32", "u64"), Breakage::BOTH),
+                change("b", integer("u32", "u64"), Breakage::DECODERS),
+                change("c", integer("u64", "u32"), Breakage::ENCODERS),
+            ]
+        );
+    }
+
+    #[test]
+    fn removed_items_are_breaking() {
+        mod old {
+            #[allow(dead_code)]
+            #[derive(scale_info::TypeInfo)]
+            pub enum Call {
+                Foo,
+                Bar,
+            }
+        }
+        mod new {
+            #[allow(dead_code)]
+            #[derive(scale_info::TypeInfo)]
+            pub enum Call {
+                Foo,
+                Baz,
+            }
+        }
+
+        let diff = metadata(meta_type::<old::Call>()).diff(&metadata(meta_type::<new::Call>()));
+        let calls: Vec<_> = diff.pallets[0]
+            .calls
+            .iter()
+            .map(|c| (c.name.as_str(), c.status, c.breakage()))
+            .collect();
+        assert_eq!(
+            calls,
+            vec![
+                ("Bar", DiffStatus::Removed, Breakage::BOTH),
+                ("Baz", DiffStatus::Added, Breakage::NONE),
+            ]
+        );
+        assert_eq!(diff.breakage(), Breakage::BOTH);
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Utilities for computing the merkleized metadata digest and proofs described in
+//! [RFC-0078](https://polkadot-fellows.github.io/RFCs/approved/0078-merkleized-metadata.html).
+//! The hash of the digest is what the `CheckMetadataHash` signed extension expects, and
+//! the proofs contain the type information that a hardware signer needs in order to
+//! decode and display a given transaction.
+
+use crate::Metadata;
+use alloc::string::{String, ToString};
+use codec::{Decode, Encode};
+use core::fmt::Display;
+use merkleized_frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
+use merkleized_metadata::SignedExtrinsicData;
+
+pub use merkleized_metadata::types::MetadataDigest;
+pub use merkleized_metadata::Proof as MetadataProof;
+
+/// The information, in addition to the metadata itself, which is needed in order to
+/// compute the [`MetadataDigest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataDigestInfo {
+    /// The spec version of the runtime.
+    pub spec_version: u32,
+    /// The spec name of the runtime.
+    pub spec_name: String,
+    /// The SS58 prefix used for addresses on the chain.
+    pub base58_prefix: u16,
+    /// The number of decimals of the chain's native token.
+    pub decimals: u8,
+    /// The symbol of the chain's native token.
+    pub token_symbol: String,
+}
+
+impl MetadataDigestInfo {
+    /// Construct a [`MetadataDigestInfo`], reading the spec name from the `System.Version`
+    /// constant and the SS58 prefix from the `System.SS58Prefix` constant in the metadata. The
+    /// spec version should be that of the runtime that the metadata was obtained from, and the
+    /// decimals and token symbol describe the chain's native token, which isn't something that the
+    /// metadata itself knows about.
+    pub fn from_metadata(
+        metadata: &Metadata,
+        spec_version: u32,
+        decimals: u8,
+        token_symbol: impl Into<String>,
+    ) -> Result<Self, MerkleizedMetadataError> {
+        let system = metadata
+            .pallet_by_name("System")
+            .ok_or(MerkleizedMetadataError::ConstantNotFound("System.Version"))?;
+
+        // The runtime version begins with the spec name, so we only decode that much of it.
+        let version = system
+            .constant_by_name("Version")
+            .ok_or(MerkleizedMetadataError::ConstantNotFound("System.Version"))?;
+        let spec_name = String::decode(&mut version.value())
+            .map_err(|_| MerkleizedMetadataError::ConstantNotDecodable("System.Version"))?;
+
+        let ss58_prefix = system.constant_by_name("SS58Prefix").ok_or(
+            MerkleizedMetadataError::ConstantNotFound("System.SS58Prefix"),
+        )?;
+        let base58_prefix = u16::decode(&mut ss58_prefix.value())
+            .map_err(|_| MerkleizedMetadataError::ConstantNotDecodable("System.SS58Prefix"))?;
+
+        Ok(MetadataDigestInfo {
+            spec_version,
+            spec_name,
+            base58_prefix,
+            decimals,
+            token_symbol: token_symbol.into(),
+        })
+    }
+}
+
+/// An error emitted if something goes wrong computing the merkleized metadata
+/// digest or proofs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MerkleizedMetadataError {
+    /// A constant that we need in order to build the [`MetadataDigestInfo`] was not found.
+    ConstantNotFound(&'static str),
+    /// A constant that we need in order to build the [`MetadataDigestInfo`] could not be decoded.
+    ConstantNotDecodable(&'static str),
+    /// Something went wrong merkleizing the metadata.
+    Merkleization(String),
+}
+
+impl Display for MerkleizedMetadataError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MerkleizedMetadataError::ConstantNotFound(e) => {
+                write!(f, "The constant {e} was not found in the metadata")
+            }
+            MerkleizedMetadataError::ConstantNotDecodable(e) => {
+                write!(f, "The constant {e} could not be decoded")
+            }
+            MerkleizedMetadataError::Merkleization(e) => {
+                write!(f, "Could not merkleize the metadata: {e}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MerkleizedMetadataError {}
+
+impl Metadata {
+    /// Compute the RFC-0078 [`MetadataDigest`] for this metadata.
+    pub fn metadata_digest(
+        &self,
+        info: &MetadataDigestInfo,
+    ) -> Result<MetadataDigest, MerkleizedMetadataError> {
+        let extra_info = merkleized_metadata::ExtraInfo {
+            spec_version: info.spec_version,
+            spec_name: info.spec_name.clone(),
+            base58_prefix: info.base58_prefix,
+            decimals: info.decimals,
+            token_symbol: info.token_symbol.clone(),
+        };
+        merkleized_metadata::generate_metadata_digest(&self.to_runtime_metadata()?, extra_info)
+            .map_err(MerkleizedMetadataError::Merkleization)
+    }
+
+    /// Compute the RFC-0078 metadata hash for this metadata. This is the hash of the
+    /// [`MetadataDigest`], and is what the `CheckMetadataHash` signed extension expects.
+    pub fn metadata_digest_hash(
+        &self,
+        info: &MetadataDigestInfo,
+    ) -> Result<[u8; 32], MerkleizedMetadataError> {
+        self.metadata_digest(info).map(|digest| digest.hash())
+    }
+
+    /// Generate a [`MetadataProof`] containing the type information needed to decode a
+    /// transaction with the given call data and, if it is signed, the given signed extension
+    /// `extra` bytes (sent with the transaction) and `additional` bytes (only signed). Hardware
+    /// signers can use this proof alongside the metadata hash to decode the transaction.
+    pub fn metadata_proof(
+        &self,
+        call_data: &[u8],
+        signed_extension_data: Option<(&[u8], &[u8])>,
+    ) -> Result<MetadataProof, MerkleizedMetadataError> {
+        let signed_ext_data =
+            signed_extension_data.map(|(extra, additional)| SignedExtrinsicData {
+                included_in_extrinsic: extra,
+                included_in_signed_data: additional,
+            });
+        merkleized_metadata::generate_proof_for_extrinsic_parts(
+            call_data,
+            signed_ext_data,
+            &self.to_runtime_metadata()?,
+        )
+        .map_err(MerkleizedMetadataError::Merkleization)
+    }
+
+    // `merkleized-metadata` works with a different version of `frame-metadata` than we do,
+    // so we convert our metadata into the V15 format that it expects via its SCALE encoding.
+    fn to_runtime_metadata(&self) -> Result<RuntimeMetadata, MerkleizedMetadataError> {
+        let bytes = frame_metadata::RuntimeMetadataPrefixed::from(self.clone()).encode();
+        let metadata = RuntimeMetadataPrefixed::decode(&mut &*bytes)
+            .map_err(|e| MerkleizedMetadataError::Merkleization(e.to_string()))?;
+        Ok(metadata.1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use codec::Decode;
+    use merkleized_metadata::types::MetadataDigest;
+
+    fn load_metadata() -> Metadata {
+        let bytes = std::fs::read("../artifacts/polkadot_metadata_full.scale").unwrap();
+        Metadata::decode(&mut &*bytes).unwrap()
+    }
+
+    #[test]
+    fn digest_info_is_read_from_metadata() {
+        let metadata = load_metadata();
+        let info = MetadataDigestInfo::from_metadata(&metadata, 1_000_000, 12, "ROC").unwrap();
+
+        assert_eq!(info.spec_name, "rococo");
+        assert_eq!(info.base58_prefix, 42);
+        assert_eq!(info.decimals, 12);
+        assert_eq!(info.token_symbol, "ROC");
+    }
+
+    #[test]
+    fn digest_contains_given_info() {
+        let metadata = load_metadata();
+        let info = MetadataDigestInfo::from_metadata(&metadata, 1_000_000, 12, "ROC").unwrap();
+        let digest = metadata.metadata_digest(&info).unwrap();
+
+        let MetadataDigest::V1 {
+            spec_version,
+            spec_name,
+            token_symbol,
+            ..
+        } = &digest
+        else {
+            panic!("expected a V1 digest");
+        };
+        assert_eq!(*spec_version, 1_000_000);
+        assert_eq!(spec_name, "rococo");
+        assert_eq!(token_symbol, "ROC");
+
+        // Changing the extra info changes the hash:
+        let other_info = MetadataDigestInfo {
+            decimals: 10,
+            ..info.clone()
+        };
+        assert_ne!(
+            metadata.metadata_digest_hash(&info).unwrap(),
+            metadata.metadata_digest_hash(&other_info).unwrap()
+        );
+    }
+}
@@ -2,7 +2,13 @@
 // This file is dual-licensed as Apache-2.0 or GPL-3.0.
 // see LICENSE for license details.
 
+pub mod compatibility;
+pub mod diff;
+pub mod merkleized;
 pub mod ordered_map;
+pub mod render;
 pub mod retain;
+pub mod share;
+pub mod type_diff;
 pub mod validation;
 pub mod variant_index;
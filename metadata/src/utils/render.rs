@@ -0,0 +1,908 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Render the types in some metadata as Rust-like definitions, TypeScript types or JSON Schemas.
+
+use crate::Metadata;
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use hashbrown::{HashMap, HashSet};
+use scale_info::{form::PortableForm, Field, PortableRegistry, Type, TypeDef, TypeDefPrimitive};
+#[cfg(feature = "json-schema")]
+use serde_json::{json, Map, Value as Json};
+
+/// The format to render a type in. See [`Metadata::render_type()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeFormat {
+    /// Rust-like struct and enum definitions.
+    Rust,
+    /// TypeScript type definitions, describing the JSON that a `scale_value::Value`
+    /// serializes to.
+    TypeScript,
+    /// A JSON Schema (draft 2020-12) that validates the JSON that a `scale_value::Value`
+    /// serializes to.
+    #[cfg(feature = "json-schema")]
+    JsonSchema,
+}
+
+impl Metadata {
+    /// Render the type with the given ID, along with every named type that it refers to.
+    /// Returns `None` if the type, or any type that it refers to, can't be found.
+    ///
+    /// - [`TypeFormat::Rust`] and [`TypeFormat::TypeScript`] output one definition per named
+    ///   type, with any generic parameters kept generic. The first definition is for the
+    ///   requested type, which is given a name like `Type123` if it doesn't have one.
+    /// - `TypeFormat::JsonSchema` outputs a single schema, with every named type placed
+    ///   into `$defs`. Since JSON Schema has no notion of generics, each instance of a
+    ///   generic type gets its own definition. This needs the `json-schema` feature.
+    ///
+    /// The TypeScript and JSON Schema output describe the shape of a `scale_value::Value`
+    /// once it's been serialized to JSON: structs with named fields are objects, anything
+    /// else with fields is an array, and enum values are objects like
+    /// `{ "name": "Variant", "values": ... }`.
+    pub fn render_type(&self, type_id: u32, format: TypeFormat) -> Option<String> {
+        render_type(self.types(), type_id, format)
+    }
+}
+
+/// Render some type from a type registry. See [`Metadata::render_type()`].
+pub fn render_type(types: &PortableRegistry, type_id: u32, format: TypeFormat) -> Option<String> {
+    let renderer = Renderer::new(types, type_id, format)?;
+    let mut out = String::new();
+    match format {
+        TypeFormat::Rust => {
+            let root = renderer.root_key(type_id);
+            if !renderer.names.contains_key(&root) {
+                let ty = renderer.expr(type_id, &[], None);
+                out = format!("pub type Type{type_id} = {ty};\n");
+            }
+            for key in &renderer.order {
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+                out.push_str(&renderer.definition(key));
+            }
+        }
+        TypeFormat::TypeScript => {
+            let ts = TypeScriptRenderer::new(types, |id, ty| {
+                let key = renderer.def_key(id, ty)?;
+                Some(renderer.names[&key].clone())
+            });
+            let root = renderer.root_key(type_id);
+            if !renderer.names.contains_key(&root) {
+                out = format!("export type Type{type_id} = {};\n", ts.expr(type_id));
+            }
+            for key in &renderer.order {
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+                let ty = types
+                    .resolve(renderer.instances[key])
+                    .expect("type was found when collecting types");
+                out.push_str(&ts.definition(&renderer.names[key], ty));
+                out.push('\n');
+            }
+        }
+        #[cfg(feature = "json-schema")]
+        TypeFormat::JsonSchema => {
+            let mut root = Map::new();
+            root.insert(
+                "$schema".to_owned(),
+                json!("https://json-schema.org/draft/2020-12/schema"),
+            );
+            match renderer.schema(type_id) {
+                Json::Object(fields) => root.extend(fields),
+                schema => {
+                    root.insert("allOf".to_owned(), json!([schema]));
+                }
+            }
+            let defs: Map<_, _> = renderer
+                .order
+                .iter()
+                .map(|key| (renderer.names[key].clone(), renderer.def_schema(key)))
+                .collect();
+            root.insert("$defs".to_owned(), Json::Object(defs));
+            out = serde_json::to_string_pretty(&root).expect("JSON values always serialize");
+            out.push('\n');
+        }
+    }
+    Some(out)
+}
+
+/// Renders the types in a registry as TypeScript, describing the JSON that a
+/// `scale_value::Value` serializes to. How structs and enums are referred to is up to the
+/// caller: [`Metadata::render_type()`] gives each one its own definition, while generated
+/// bindings can place them in namespaces instead.
+pub struct TypeScriptRenderer<'a> {
+    types: &'a PortableRegistry,
+    name: Box<dyn Fn(u32, &Type<PortableForm>) -> Option<String> + 'a>,
+}
+
+impl<'a> TypeScriptRenderer<'a> {
+    /// Create a renderer for the given types. `name` is given the ID and definition of each
+    /// struct or enum that is referred to, and returns the name to refer to it by, or `None`
+    /// to write the type out in full. Recursive types must be given a name.
+    pub fn new(
+        types: &'a PortableRegistry,
+        name: impl Fn(u32, &Type<PortableForm>) -> Option<String> + 'a,
+    ) -> Self {
+        TypeScriptRenderer {
+            types,
+            name: Box::new(name),
+        }
+    }
+
+    /// Render a reference to the type with the given ID as a type expression.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the type, or any type that it refers to, can't be found.
+    pub fn expr(&self, id: u32) -> String {
+        self.expr_with_params(id, &[], None)
+    }
+
+    /// Render the fields of some struct or enum variant: an object if all of the fields are
+    /// named, and an array otherwise.
+    pub fn fields(&self, fields: &[Field<PortableForm>]) -> String {
+        self.fields_with_params(fields, &[])
+    }
+
+    /// Render the definition of some struct or enum, given the name to define it as. Any
+    /// generic parameters are kept generic, and each enum variant is given its own line.
+    pub fn definition(&self, name: &str, ty: &Type<PortableForm>) -> String {
+        let params = type_params(ty);
+        let generics = if params.is_empty() {
+            String::new()
+        } else {
+            let names: Vec<_> = params.iter().map(|(_, name)| *name).collect();
+            format!("<{}>", names.join(", "))
+        };
+
+        match &ty.type_def {
+            TypeDef::Composite(c) => format!(
+                "export type {name}{generics} = {};",
+                self.fields_with_params(&c.fields, &params)
+            ),
+            TypeDef::Variant(v) if v.variants.is_empty() => {
+                format!("export type {name}{generics} = never;")
+            }
+            TypeDef::Variant(v) => {
+                let mut out = format!("export type {name}{generics} =");
+                for variant in &v.variants {
+                    let _ = write!(
+                        out,
+                        "\n    | {{ name: {}; values: {} }}",
+                        json_string(&variant.name),
+                        self.fields_with_params(&variant.fields, &params)
+                    );
+                }
+                out.push(';');
+                out
+            }
+            _ => panic!("only structs and enums have definitions"),
+        }
+    }
+
+    /// Generic parameters are given as pairs of type ID and name; any type whose ID matches
+    /// one is rendered as that name, as long as `hint` (the type name given in the metadata,
+    /// if any) mentions it.
+    fn expr_with_params(&self, id: u32, params: &[(u32, &str)], hint: Option<&str>) -> String {
+        if let Some((_, name)) = params
+            .iter()
+            .find(|(param_id, name)| *param_id == id && hint.map_or(true, |h| mentions(h, name)))
+        {
+            return (*name).to_owned();
+        }
+        let ty = self
+            .types
+            .resolve(id)
+            .expect("type should be in the registry");
+
+        let is_def = matches!(ty.type_def, TypeDef::Composite(_) | TypeDef::Variant(_));
+        if let Some(name) = is_def.then(|| (self.name)(id, ty)).flatten() {
+            let args: Vec<_> = type_params(ty)
+                .iter()
+                .map(|(param_id, _)| self.expr_with_params(*param_id, params, hint))
+                .collect();
+            return if args.is_empty() {
+                name
+            } else {
+                format!("{name}<{}>", args.join(", "))
+            };
+        }
+
+        match &ty.type_def {
+            TypeDef::Composite(c) => self.fields_with_params(&c.fields, params),
+            TypeDef::Variant(v) if v.variants.is_empty() => "never".to_owned(),
+            TypeDef::Variant(v) => {
+                let variants: Vec<_> = v
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        format!(
+                            "{{ name: {}; values: {} }}",
+                            json_string(&variant.name),
+                            self.fields_with_params(&variant.fields, params)
+                        )
+                    })
+                    .collect();
+                format!("({})", variants.join(" | "))
+            }
+            TypeDef::Sequence(s) => {
+                format!(
+                    "Array<{}>",
+                    self.expr_with_params(s.type_param.id, params, hint)
+                )
+            }
+            TypeDef::Array(a) => {
+                format!(
+                    "Array<{}>",
+                    self.expr_with_params(a.type_param.id, params, hint)
+                )
+            }
+            TypeDef::Tuple(t) => {
+                let fields: Vec<_> = t
+                    .fields
+                    .iter()
+                    .map(|f| self.expr_with_params(f.id, params, hint))
+                    .collect();
+                format!("[{}]", fields.join(", "))
+            }
+            TypeDef::Primitive(p) => ts_primitive(p).to_owned(),
+            TypeDef::Compact(c) => self.expr_with_params(c.type_param.id, params, hint),
+            TypeDef::BitSequence(_) => "{ __bitvec__values__: Array<boolean> }".to_owned(),
+        }
+    }
+
+    fn fields_with_params(&self, fields: &[Field<PortableForm>], params: &[(u32, &str)]) -> String {
+        let exprs: Vec<_> = fields
+            .iter()
+            .map(|f| self.expr_with_params(f.ty.id, params, f.type_name.as_deref()))
+            .collect();
+        if is_named(fields) {
+            let fields: Vec<_> = fields
+                .iter()
+                .zip(exprs)
+                .map(|(f, e)| format!("{}: {e}", f.name.as_deref().unwrap_or_default()))
+                .collect();
+            format!("{{ {} }}", fields.join("; "))
+        } else {
+            format!("[{}]", exprs.join(", "))
+        }
+    }
+}
+
+/// Rust types which are common enough that we don't render definitions for them.
+const RUST_PRELUDE: [&str; 5] = ["Option", "Result", "BTreeMap", "BTreeSet", "Cow"];
+
+/// Named types are identified by their path when rendering generic definitions (Rust and
+/// TypeScript), and by their type ID otherwise (JSON Schema).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum DefKey {
+    Path(Vec<String>),
+    Id(u32),
+}
+
+struct Renderer<'a> {
+    types: &'a PortableRegistry,
+    format: TypeFormat,
+    /// Named types that we'll render definitions for, in the order they were found.
+    order: Vec<DefKey>,
+    /// The name that each definition is given.
+    names: HashMap<DefKey, String>,
+    /// The type ID used to render each definition.
+    instances: HashMap<DefKey, u32>,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(types: &'a PortableRegistry, root: u32, format: TypeFormat) -> Option<Self> {
+        let mut renderer = Renderer {
+            types,
+            format,
+            order: Vec::new(),
+            names: HashMap::new(),
+            instances: HashMap::new(),
+        };
+
+        // Find every type reachable from the root, noting those that need definitions.
+        let mut seen = HashSet::new();
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            if !seen.insert(id) {
+                continue;
+            }
+            let ty = types.resolve(id)?;
+            if let Some(key) = renderer.def_key(id, ty) {
+                if !renderer.instances.contains_key(&key) {
+                    renderer.instances.insert(key.clone(), id);
+                    renderer.order.push(key);
+                }
+            }
+            // Push in reverse so that types are found in the order that they appear.
+            let mut children = child_types(ty);
+            children.reverse();
+            stack.extend(children);
+        }
+
+        // Name each definition after the last segment of its path, falling back to the full
+        // path to avoid clashes, and to the type ID for generic instances in JSON Schemas.
+        let mut by_ident: BTreeMap<String, Vec<DefKey>> = BTreeMap::new();
+        for key in &renderer.order {
+            let id = renderer.instances[key];
+            let ident = types
+                .resolve(id)
+                .and_then(|ty| ty.path.ident())
+                .unwrap_or_else(|| format!("Type{id}"));
+            by_ident.entry(ident).or_default().push(key.clone());
+        }
+        for (ident, keys) in by_ident {
+            let paths: HashSet<_> = keys
+                .iter()
+                .map(|key| {
+                    &types
+                        .resolve(renderer.instances[key])
+                        .expect("seen above")
+                        .path
+                        .segments
+                })
+                .collect();
+            for key in keys {
+                let id = renderer.instances[&key];
+                let path = &types.resolve(id).expect("seen above").path.segments;
+                let mut name = if paths.len() > 1 {
+                    match format {
+                        TypeFormat::Rust => path.join("::"),
+                        _ => path.join("_"),
+                    }
+                } else {
+                    ident.clone()
+                };
+                if matches!(key, DefKey::Id(_)) && !type_params(types.resolve(id)?).is_empty() {
+                    name = format!("{name}_{id}");
+                }
+                renderer.names.insert(key, name);
+            }
+        }
+
+        Some(renderer)
+    }
+
+    /// The key for some type if we render a definition for it.
+    fn def_key(&self, id: u32, ty: &Type<PortableForm>) -> Option<DefKey> {
+        let anonymous = ty.path.segments.is_empty();
+        let is_def = matches!(ty.type_def, TypeDef::Composite(_) | TypeDef::Variant(_));
+        if !is_def {
+            return None;
+        }
+        if self.format == TypeFormat::Rust
+            && ty.path.segments.len() == 1
+            && RUST_PRELUDE.contains(&ty.path.segments[0].as_str())
+        {
+            return None;
+        }
+        if anonymous || self.is_json_schema() {
+            Some(DefKey::Id(id))
+        } else {
+            Some(DefKey::Path(ty.path.segments.clone()))
+        }
+    }
+
+    fn is_json_schema(&self) -> bool {
+        #[cfg(feature = "json-schema")]
+        if self.format == TypeFormat::JsonSchema {
+            return true;
+        }
+        false
+    }
+
+    fn root_key(&self, id: u32) -> DefKey {
+        self.types
+            .resolve(id)
+            .and_then(|ty| self.def_key(id, ty))
+            .unwrap_or(DefKey::Id(id))
+    }
+
+    /// Render a reference to some type as a Rust type expression. Generic parameters are
+    /// handled as in [`TypeScriptRenderer`].
+    fn expr(&self, id: u32, params: &[(u32, &str)], hint: Option<&str>) -> String {
+        if let Some((_, name)) = params
+            .iter()
+            .find(|(param_id, name)| *param_id == id && hint.map_or(true, |h| mentions(h, name)))
+        {
+            return (*name).to_owned();
+        }
+        let ty = self
+            .types
+            .resolve(id)
+            .expect("type was found when collecting types");
+        let key = self.def_key(id, ty);
+        if key.is_some() || matches!(ty.type_def, TypeDef::Composite(_) | TypeDef::Variant(_)) {
+            let name = match &key {
+                Some(key) => self.names[key].clone(),
+                None => ty.path.segments.join("::"),
+            };
+            let args: Vec<_> = type_params(ty)
+                .iter()
+                .map(|(param_id, _)| self.expr(*param_id, params, hint))
+                .collect();
+            return if args.is_empty() {
+                name
+            } else {
+                format!("{name}<{}>", args.join(", "))
+            };
+        }
+
+        match &ty.type_def {
+            TypeDef::Composite(_) | TypeDef::Variant(_) => unreachable!("handled above"),
+            TypeDef::Sequence(s) => format!("Vec<{}>", self.expr(s.type_param.id, params, hint)),
+            TypeDef::Array(a) => {
+                let inner = self.expr(a.type_param.id, params, hint);
+                format!("[{inner}; {}]", a.len)
+            }
+            TypeDef::Tuple(t) => {
+                let fields: Vec<_> = t
+                    .fields
+                    .iter()
+                    .map(|f| self.expr(f.id, params, hint))
+                    .collect();
+                if fields.len() == 1 {
+                    format!("({},)", fields[0])
+                } else {
+                    format!("({})", fields.join(", "))
+                }
+            }
+            TypeDef::Primitive(p) => rust_primitive(p).to_owned(),
+            TypeDef::Compact(c) => format!("Compact<{}>", self.expr(c.type_param.id, params, hint)),
+            TypeDef::BitSequence(_) => "BitVec".to_owned(),
+        }
+    }
+
+    /// Render the Rust definition of some named type.
+    fn definition(&self, key: &DefKey) -> String {
+        let id = self.instances[key];
+        let ty = self
+            .types
+            .resolve(id)
+            .expect("type was found when collecting types");
+        let name = &self.names[key];
+
+        let params: Vec<(u32, &str)> = match key {
+            DefKey::Path(_) => type_params(ty),
+            DefKey::Id(_) => Vec::new(),
+        };
+        let generics = if params.is_empty() {
+            String::new()
+        } else {
+            let names: Vec<_> = params.iter().map(|(_, name)| *name).collect();
+            format!("<{}>", names.join(", "))
+        };
+
+        let mut out = String::new();
+        match &ty.type_def {
+            TypeDef::Composite(c) => {
+                let _ = write!(out, "pub struct {name}{generics}");
+                out.push_str(&self.rust_fields(&c.fields, &params, "pub "));
+                if c.fields.is_empty() || c.fields[0].name.is_none() {
+                    out.push(';');
+                }
+            }
+            TypeDef::Variant(v) => {
+                let _ = write!(out, "pub enum {name}{generics} {{");
+                for (pos, variant) in v.variants.iter().enumerate() {
+                    out.push_str("\n    ");
+                    if variant.index as usize != pos {
+                        let _ = write!(out, "#[codec(index = {})]\n    ", variant.index);
+                    }
+                    out.push_str(&variant.name);
+                    let fields = self.rust_fields(&variant.fields, &params, "");
+                    out.push_str(&fields.replace('\n', "\n    "));
+                    out.push(',');
+                }
+                if !v.variants.is_empty() {
+                    out.push('\n');
+                }
+                out.push('}');
+            }
+            _ => unreachable!("only composite and variant types have definitions"),
+        }
+        out.push('\n');
+        out
+    }
+
+    fn rust_fields(
+        &self,
+        fields: &[Field<PortableForm>],
+        params: &[(u32, &str)],
+        vis: &str,
+    ) -> String {
+        if fields.is_empty() {
+            return String::new();
+        }
+        let exprs = fields
+            .iter()
+            .map(|f| self.expr(f.ty.id, params, f.type_name.as_deref()));
+        if fields[0].name.is_none() {
+            let exprs: Vec<_> = exprs.map(|e| format!("{vis}{e}")).collect();
+            format!("({})", exprs.join(", "))
+        } else {
+            let mut out = " {".to_owned();
+            for (field, expr) in fields.iter().zip(exprs) {
+                let name = field.name.as_deref().unwrap_or_default();
+                let _ = write!(out, "\n    {vis}{name}: {expr},");
+            }
+            out.push_str("\n}");
+            out
+        }
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl Renderer<'_> {
+    /// A JSON Schema which refers to some type.
+    fn schema(&self, id: u32) -> Json {
+        let ty = self
+            .types
+            .resolve(id)
+            .expect("type was found when collecting types");
+        if let Some(key) = self.def_key(id, ty) {
+            return json!({ "$ref": format!("#/$defs/{}", self.names[&key]) });
+        }
+
+        match &ty.type_def {
+            TypeDef::Composite(_) | TypeDef::Variant(_) => unreachable!("these have definitions"),
+            TypeDef::Sequence(s) => json!({
+                "type": "array",
+                "items": self.schema(s.type_param.id),
+            }),
+            TypeDef::Array(a) => json!({
+                "type": "array",
+                "items": self.schema(a.type_param.id),
+                "minItems": a.len,
+                "maxItems": a.len,
+            }),
+            TypeDef::Tuple(t) => {
+                let items = t.fields.iter().map(|f| self.schema(f.id)).collect();
+                tuple_schema(items)
+            }
+            TypeDef::Primitive(p) => primitive_schema(p),
+            TypeDef::Compact(c) => self.schema(c.type_param.id),
+            TypeDef::BitSequence(_) => json!({
+                "type": "object",
+                "properties": {
+                    "__bitvec__values__": {
+                        "type": "array",
+                        "items": { "type": "boolean" },
+                    },
+                },
+                "required": ["__bitvec__values__"],
+                "additionalProperties": false,
+            }),
+        }
+    }
+
+    /// The JSON Schema definition of some named type.
+    fn def_schema(&self, key: &DefKey) -> Json {
+        let ty = self
+            .types
+            .resolve(self.instances[key])
+            .expect("type was found when collecting types");
+        match &ty.type_def {
+            TypeDef::Composite(c) => self.fields_schema(&c.fields),
+            TypeDef::Variant(v) => {
+                let variants: Vec<_> = v
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        json!({
+                            "type": "object",
+                            "properties": {
+                                "name": { "const": variant.name },
+                                "values": self.fields_schema(&variant.fields),
+                            },
+                            "required": ["name", "values"],
+                            "additionalProperties": false,
+                        })
+                    })
+                    .collect();
+                json!({ "oneOf": variants })
+            }
+            _ => unreachable!("only composite and variant types have definitions"),
+        }
+    }
+
+    fn fields_schema(&self, fields: &[Field<PortableForm>]) -> Json {
+        if !is_named(fields) {
+            return tuple_schema(fields.iter().map(|f| self.schema(f.ty.id)).collect());
+        }
+        let properties: Map<_, _> = fields
+            .iter()
+            .map(|f| {
+                let name = f.name.clone().unwrap_or_default();
+                (name, self.schema(f.ty.id))
+            })
+            .collect();
+        let required: Vec<_> = fields
+            .iter()
+            .map(|f| f.name.as_deref().unwrap_or_default())
+            .collect();
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        })
+    }
+}
+
+/// The types that some type refers to directly.
+fn child_types(ty: &Type<PortableForm>) -> Vec<u32> {
+    let mut children: Vec<u32> = match &ty.type_def {
+        TypeDef::Composite(c) => c.fields.iter().map(|f| f.ty.id).collect(),
+        TypeDef::Variant(v) => v
+            .variants
+            .iter()
+            .flat_map(|v| v.fields.iter().map(|f| f.ty.id))
+            .collect(),
+        TypeDef::Sequence(s) => vec![s.type_param.id],
+        TypeDef::Array(a) => vec![a.type_param.id],
+        TypeDef::Tuple(t) => t.fields.iter().map(|f| f.id).collect(),
+        TypeDef::Compact(c) => vec![c.type_param.id],
+        TypeDef::Primitive(_) | TypeDef::BitSequence(_) => vec![],
+    };
+    children.extend(type_params(ty).into_iter().map(|(id, _)| id));
+    children
+}
+
+/// The generic parameters of some type which have concrete types, as pairs of type ID and name.
+fn type_params(ty: &Type<PortableForm>) -> Vec<(u32, &str)> {
+    ty.type_params
+        .iter()
+        .filter_map(|p| Some((p.ty?.id, p.name.as_str())))
+        .collect()
+}
+
+/// `scale_value` serializes composites as objects only if they have some fields, all named.
+fn is_named(fields: &[Field<PortableForm>]) -> bool {
+    !fields.is_empty() && fields.iter().all(|f| f.name.is_some())
+}
+
+/// Does some type name (eg `Vec<T::AccountId>`) mention the given identifier?
+fn mentions(type_name: &str, ident: &str) -> bool {
+    type_name
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|segment| segment == ident)
+}
+
+fn rust_primitive(primitive: &TypeDefPrimitive) -> &'static str {
+    match primitive {
+        TypeDefPrimitive::Bool => "bool",
+        TypeDefPrimitive::Char => "char",
+        TypeDefPrimitive::Str => "String",
+        TypeDefPrimitive::U8 => "u8",
+        TypeDefPrimitive::U16 => "u16",
+        TypeDefPrimitive::U32 => "u32",
+        TypeDefPrimitive::U64 => "u64",
+        TypeDefPrimitive::U128 => "u128",
+        TypeDefPrimitive::U256 => "U256",
+        TypeDefPrimitive::I8 => "i8",
+        TypeDefPrimitive::I16 => "i16",
+        TypeDefPrimitive::I32 => "i32",
+        TypeDefPrimitive::I64 => "i64",
+        TypeDefPrimitive::I128 => "i128",
+        TypeDefPrimitive::I256 => "I256",
+    }
+}
+
+fn ts_primitive(primitive: &TypeDefPrimitive) -> &'static str {
+    match primitive {
+        TypeDefPrimitive::Bool => "boolean",
+        TypeDefPrimitive::Char | TypeDefPrimitive::Str => "string",
+        // 256 bit integers are serialized as their 32 bytes.
+        TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => "Array<number>",
+        _ => "number",
+    }
+}
+
+#[cfg(feature = "json-schema")]
+fn primitive_schema(primitive: &TypeDefPrimitive) -> Json {
+    let integer = |min: Option<i64>, max: Option<i64>| {
+        let mut schema = json!({ "type": "integer" });
+        if let Some(min) = min {
+            schema["minimum"] = min.into();
+        }
+        if let Some(max) = max {
+            schema["maximum"] = max.into();
+        }
+        schema
+    };
+
+    match primitive {
+        TypeDefPrimitive::Bool => json!({ "type": "boolean" }),
+        TypeDefPrimitive::Char => json!({ "type": "string", "minLength": 1, "maxLength": 1 }),
+        TypeDefPrimitive::Str => json!({ "type": "string" }),
+        TypeDefPrimitive::U8 => integer(Some(0), Some(u8::MAX.into())),
+        TypeDefPrimitive::U16 => integer(Some(0), Some(u16::MAX.into())),
+        TypeDefPrimitive::U32 => integer(Some(0), Some(u32::MAX.into())),
+        TypeDefPrimitive::U64 | TypeDefPrimitive::U128 => integer(Some(0), None),
+        TypeDefPrimitive::I8 => integer(Some(i8::MIN.into()), Some(i8::MAX.into())),
+        TypeDefPrimitive::I16 => integer(Some(i16::MIN.into()), Some(i16::MAX.into())),
+        TypeDefPrimitive::I32 => integer(Some(i32::MIN.into()), Some(i32::MAX.into())),
+        TypeDefPrimitive::I64 | TypeDefPrimitive::I128 => integer(None, None),
+        // 256 bit integers are serialized as their 32 bytes.
+        TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => json!({
+            "type": "array",
+            "items": integer(Some(0), Some(u8::MAX.into())),
+            "minItems": 32,
+            "maxItems": 32,
+        }),
+    }
+}
+
+/// A schema for an array containing exactly the given items.
+#[cfg(feature = "json-schema")]
+fn tuple_schema(items: Vec<Json>) -> Json {
+    let len = items.len();
+    json!({
+        "type": "array",
+        "prefixItems": items,
+        "items": false,
+        "minItems": len,
+    })
+}
+
+/// Quote and escape a string as a JSON string literal, which is also a valid TypeScript one.
+/// Unlike the JSON Schema output this is always available, so it doesn't lean on `serde_json`.
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale_info::{meta_type, Registry, TypeInfo};
+
+    fn registry<T: TypeInfo + 'static>() -> (PortableRegistry, u32) {
+        let mut registry = Registry::new();
+        let id = registry.register_type(&meta_type::<T>()).id;
+        (registry.into(), id)
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    struct Wrapper<T> {
+        value: T,
+        items: Vec<T>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum Tree {
+        Leaf(u8),
+        #[codec(index = 5)]
+        Node {
+            children: Vec<Tree>,
+        },
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    struct Root {
+        tree: Tree,
+        wrapped: Wrapper<bool>,
+    }
+
+    #[test]
+    fn renders_rust() {
+        let (types, id) = registry::<Root>();
+        let out = render_type(&types, id, TypeFormat::Rust).unwrap();
+        assert_eq!(
+            out,
+            "pub struct Root {
+    pub tree: Tree,
+    pub wrapped: Wrapper<bool>,
+}
+
+pub enum Tree {
+    Leaf(u8),
+    #[codec(index = 5)]
+    Node {
+        children: Vec<Tree>,
+    },
+}
+
+pub struct Wrapper<T> {
+    pub value: T,
+    pub items: Vec<T>,
+}
+"
+        );
+    }
+
+    #[test]
+    fn renders_typescript() {
+        let (types, id) = registry::<Root>();
+        let out = render_type(&types, id, TypeFormat::TypeScript).unwrap();
+        assert_eq!(
+            out,
+            "export type Root = { tree: Tree; wrapped: Wrapper<boolean> };
+
+export type Tree =
+    | { name: \"Leaf\"; values: [number] }
+    | { name: \"Node\"; values: { children: Array<Tree> } };
+
+export type Wrapper<T> = { value: T; items: Array<T> };
+"
+        );
+
+        let (types, id) = registry::<Vec<(u32, char)>>();
+        let out = render_type(&types, id, TypeFormat::TypeScript).unwrap();
+        assert!(out.starts_with(&format!(
+            "export type Type{id} = Array<[number, string]>;\n"
+        )));
+    }
+
+    #[test]
+    fn typescript_renderer_uses_given_names() {
+        let (types, id) = registry::<Root>();
+        let renderer = TypeScriptRenderer::new(&types, |_, ty| match ty.path.ident()?.as_str() {
+            "Root" => None,
+            ident => Some(format!("ns.{ident}")),
+        });
+        assert_eq!(
+            renderer.expr(id),
+            "{ tree: ns.Tree; wrapped: ns.Wrapper<boolean> }"
+        );
+        let tree = types
+            .types
+            .iter()
+            .find(|ty| ty.ty.path.ident().as_deref() == Some("Tree"))
+            .unwrap();
+        assert_eq!(
+            renderer.definition("Tree", &tree.ty),
+            "export type Tree =
+    | { name: \"Leaf\"; values: [number] }
+    | { name: \"Node\"; values: { children: Array<ns.Tree> } };"
+        );
+    }
+
+    #[test]
+    fn escapes_json_strings() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
+    }
+
+    #[cfg(feature = "json-schema")]
+    #[test]
+    fn renders_json_schema() {
+        let (types, id) = registry::<(Tree, Wrapper<u16>, Wrapper<bool>)>();
+        let out = render_type(&types, id, TypeFormat::JsonSchema).unwrap();
+
+        assert!(out.contains("\"$schema\": \"https://json-schema.org/draft/2020-12/schema\""));
+        assert!(out.contains("\"$ref\": \"#/$defs/Tree\""));
+        // Each instance of a generic type gets its own definition.
+        let wrappers = out.matches("\"#/$defs/Wrapper_").count();
+        assert_eq!(wrappers, 2, "{out}");
+        assert!(out.contains("\"const\": \"Node\""));
+        assert!(out.contains("\"maximum\": 65535"));
+    }
+}
@@ -4,14 +4,17 @@
 
 //! Utility functions to generate a subset of the metadata.
 
+use crate::utils::variant_index::VariantIndex;
 use crate::{
-    ExtrinsicMetadata, Metadata, PalletMetadataInner, RuntimeApiMetadataInner, StorageEntryType,
+    ExtrinsicMetadata, Metadata, MetadataItem, PalletMetadataInner, RuntimeApiMetadataInner,
+    StorageEntryType,
 };
 use alloc::collections::BTreeSet;
+use alloc::string::ToString;
 use alloc::vec::Vec;
 use scale_info::{
-    PortableType, TypeDef, TypeDefArray, TypeDefBitSequence, TypeDefCompact, TypeDefComposite,
-    TypeDefSequence, TypeDefTuple, TypeDefVariant,
+    PortableRegistry, PortableType, TypeDef, TypeDefArray, TypeDefBitSequence, TypeDefCompact,
+    TypeDefComposite, TypeDefSequence, TypeDefTuple, TypeDefVariant,
 };
 
 #[derive(Clone)]
@@ -95,7 +98,7 @@ impl TypeSet {
             self.insert(ty);
         }
 
-        for signed in &extrinsic.signed_extensions {
+        for signed in extrinsic.signed_extensions_by_version.values().flatten() {
             self.insert(signed.extra_ty);
             self.insert(signed.additional_ty);
         }
@@ -129,6 +132,14 @@ impl TypeSet {
         for constant in pallet.constants.values() {
             self.collect_types(metadata, constant.ty);
         }
+
+        for view_function in pallet.view_functions.values() {
+            self.collect_types(metadata, view_function.output_ty);
+        }
+
+        for associated_type in &pallet.associated_types {
+            self.collect_types(metadata, associated_type.ty);
+        }
     }
 }
 
@@ -152,13 +163,34 @@ fn resolve_typ(metadata: &Metadata, typ: u32) -> &PortableType {
 ///
 /// Panics if the [`scale_info::PortableRegistry`] did not retain all needed types,
 /// or the metadata does not contain the "sp_runtime::DispatchError" type.
-pub fn retain_metadata<F, G>(
+pub fn retain_metadata<F, G>(metadata: &mut Metadata, pallets_filter: F, runtime_apis_filter: G)
+where
+    F: FnMut(&str) -> bool,
+    G: FnMut(&str) -> bool,
+{
+    retain_metadata_items(metadata, pallets_filter, runtime_apis_filter, |_| true)
+}
+
+/// Like [`retain_metadata`], but additionally only keep the calls, events, storage entries,
+/// constants, view functions, runtime API methods and custom values that `items_filter`
+/// returns true for.
+///
+/// The call and event enums of a pallet are only stripped of variants if nothing we're
+/// keeping can return them, for the same reason that we keep the outer enums whole in that
+/// case: so that values of those types can still be decoded.
+///
+/// # Panics
+///
+/// Panics under the same conditions as [`retain_metadata`].
+pub fn retain_metadata_items<F, G, H>(
     metadata: &mut Metadata,
     mut pallets_filter: F,
     mut runtime_apis_filter: G,
+    mut items_filter: H,
 ) where
     F: FnMut(&str) -> bool,
     G: FnMut(&str) -> bool,
+    H: FnMut(&MetadataItem) -> bool,
 {
     // 1. Delete pallets we don't want to keep.
     metadata
@@ -171,11 +203,42 @@ pub fn retain_metadata<F, G>(
         .enumerate()
         .map(|(pos, p)| (p.index, pos))
         .collect();
+    metadata.pallets_by_call_index = metadata
+        .pallets
+        .values()
+        .iter()
+        .enumerate()
+        .map(|(pos, p)| (p.call_index, pos))
+        .collect();
 
     // 2. Delete runtime APIs we don't want to keep.
     metadata.apis.retain(|api| runtime_apis_filter(&api.name));
 
-    // 3. For each outer enum type, strip it if possible, ie if it is not returned by any
+    // 3. Delete the individual items that we don't want to keep.
+    retain_items(metadata, &mut items_filter);
+    let mut find_type_id = keep_outer_enum(metadata, &mut pallets_filter, &mut runtime_apis_filter);
+    for pallet in metadata.pallets.values_mut() {
+        if let Some(call_ty) = pallet.call_ty.filter(|ty| !find_type_id(*ty)) {
+            strip_variants_in_enum_type(&mut metadata.types, call_ty, |name| {
+                items_filter(&MetadataItem::Call {
+                    pallet: pallet.name.to_string(),
+                    name: name.to_string(),
+                })
+            });
+            pallet.call_variant_index = VariantIndex::build(Some(call_ty), &metadata.types);
+        }
+        if let Some(event_ty) = pallet.event_ty.filter(|ty| !find_type_id(*ty)) {
+            strip_variants_in_enum_type(&mut metadata.types, event_ty, |name| {
+                items_filter(&MetadataItem::Event {
+                    pallet: pallet.name.to_string(),
+                    name: name.to_string(),
+                })
+            });
+            pallet.event_variant_index = VariantIndex::build(Some(event_ty), &metadata.types);
+        }
+    }
+
+    // 4. For each outer enum type, strip it if possible, ie if it is not returned by any
     // of the things we're keeping (because if it is, we need to keep all of it so that we
     // can still decode values into it).
     let outer_enums = metadata.outer_enums();
@@ -186,15 +249,15 @@ pub fn retain_metadata<F, G>(
         outer_enums.event_enum_ty(),
     ] {
         if !find_type_id(outer_enum_ty_id) {
-            strip_variants_in_enum_type(metadata, &mut pallets_filter, outer_enum_ty_id);
+            strip_variants_in_enum_type(&mut metadata.types, outer_enum_ty_id, &mut pallets_filter);
         }
     }
 
-    // 4. Collect all of the type IDs we still want to keep after deleting.
+    // 5. Collect all of the type IDs we still want to keep after deleting.
     let mut keep_these_type_ids: BTreeSet<u32> =
         iterate_metadata_types(metadata).map(|x| *x).collect();
 
-    // 5. Additionally, subxt depends on the `DispatchError` type existing; we use the same
+    // 6. Additionally, subxt depends on the `DispatchError` type existing; we use the same
     // logic here that is used when building our `Metadata` to ensure we keep it too.
     let dispatch_error_ty = metadata
         .types
@@ -205,12 +268,12 @@ pub fn retain_metadata<F, G>(
 
     keep_these_type_ids.insert(dispatch_error_ty.id);
 
-    // 5. Strip all of the type IDs we no longer need, based on the above set.
+    // 7. Strip all of the type IDs we no longer need, based on the above set.
     let map_ids = metadata
         .types
         .retain(|id| keep_these_type_ids.contains(&id));
 
-    // 6. Now, update the type IDs referenced in our metadata to reflect this.
+    // 8. Now, update the type IDs referenced in our metadata to reflect this.
     for id in iterate_metadata_types(metadata) {
         if let Some(new_id) = map_ids.get(id) {
             *id = *new_id;
@@ -220,13 +283,58 @@ pub fn retain_metadata<F, G>(
     }
 }
 
-fn strip_variants_in_enum_type<F>(metadata: &mut Metadata, mut pallets_filter: F, id: u32)
+/// Delete the storage entries, constants, view functions, runtime API methods and custom values
+/// that we don't want to keep.
+fn retain_items<H>(metadata: &mut Metadata, items_filter: &mut H)
+where
+    H: FnMut(&MetadataItem) -> bool,
+{
+    for pallet in metadata.pallets.values_mut() {
+        let pallet_name = &pallet.name;
+        if let Some(storage) = &mut pallet.storage {
+            storage.entries.retain(|entry| {
+                items_filter(&MetadataItem::StorageEntry {
+                    pallet: pallet_name.to_string(),
+                    name: entry.name.to_string(),
+                })
+            });
+        }
+        pallet.constants.retain(|constant| {
+            items_filter(&MetadataItem::Constant {
+                pallet: pallet_name.to_string(),
+                name: constant.name.to_string(),
+            })
+        });
+        pallet.view_functions.retain(|view_function| {
+            items_filter(&MetadataItem::ViewFunction {
+                pallet: pallet_name.to_string(),
+                name: view_function.name.to_string(),
+            })
+        });
+    }
+
+    for api in metadata.apis.values_mut() {
+        let trait_name = &api.name;
+        api.methods.retain(|method| {
+            items_filter(&MetadataItem::RuntimeApiMethod {
+                trait_name: trait_name.to_string(),
+                name: method.name.to_string(),
+            })
+        });
+    }
+
+    metadata
+        .custom
+        .map
+        .retain(|name, _| items_filter(&MetadataItem::CustomValue { name: name.clone() }));
+}
+
+fn strip_variants_in_enum_type<F>(types: &mut PortableRegistry, id: u32, mut variants_filter: F)
 where
     F: FnMut(&str) -> bool,
 {
     let ty = {
-        metadata
-            .types
+        types
             .types
             .get_mut(id as usize)
             .expect("Metadata should contain enum type in registry")
@@ -236,12 +344,12 @@ where
         panic!("Metadata type is expected to be a variant type");
     };
 
-    variant.variants.retain(|v| pallets_filter(&v.name));
+    variant.variants.retain(|v| variants_filter(&v.name));
 }
 
 /// Returns an iterator that allows modifying each type ID seen in the metadata (not recursively).
 /// This will iterate over every type referenced in the metadata outside of `metadata.types`.
-fn iterate_metadata_types(metadata: &mut Metadata) -> impl Iterator<Item = &mut u32> {
+pub(crate) fn iterate_metadata_types(metadata: &mut Metadata) -> impl Iterator<Item = &mut u32> {
     let mut types = alloc::vec::Vec::new();
 
     // collect outer_enum top-level types
@@ -282,6 +390,17 @@ fn iterate_metadata_types(metadata: &mut Metadata) -> impl Iterator<Item = &mut
         for constant in pallet.constants.values_mut() {
             types.push(&mut constant.ty);
         }
+
+        for view_function in pallet.view_functions.values_mut() {
+            for input in &mut view_function.inputs {
+                types.push(&mut input.ty);
+            }
+            types.push(&mut view_function.output_ty);
+        }
+
+        for associated_type in &mut pallet.associated_types {
+            types.push(&mut associated_type.ty);
+        }
     }
 
     // collect extrinsic type_ids
@@ -294,7 +413,12 @@ fn iterate_metadata_types(metadata: &mut Metadata) -> impl Iterator<Item = &mut
         types.push(ty);
     }
 
-    for signed in &mut metadata.extrinsic.signed_extensions {
+    for signed in metadata
+        .extrinsic
+        .signed_extensions_by_version
+        .values_mut()
+        .flatten()
+    {
         types.push(&mut signed.extra_ty);
         types.push(&mut signed.additional_ty);
     }
@@ -413,6 +537,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn retain_individual_items() {
+        let full_metadata = load_metadata();
+        let mut pallet_only = full_metadata.clone();
+        retain_metadata(&mut pallet_only, |p| p == "Balances", |_| false);
+
+        let mut metadata = full_metadata.clone();
+        retain_metadata_items(
+            &mut metadata,
+            |p| p == "Balances",
+            |_| false,
+            |item| {
+                matches!(
+                    item,
+                    MetadataItem::Call { name, .. } if name == "transfer_keep_alive"
+                ) || matches!(
+                    item,
+                    MetadataItem::StorageEntry { name, .. } if name == "Account"
+                )
+            },
+        );
+
+        let pallet = metadata.pallet_by_name("Balances").unwrap();
+        let calls: Vec<_> = pallet
+            .call_variants()
+            .unwrap()
+            .iter()
+            .map(|v| v.name.as_str())
+            .collect();
+        assert_eq!(calls, ["transfer_keep_alive"]);
+        let entries: Vec<_> = pallet
+            .storage()
+            .unwrap()
+            .entries()
+            .iter()
+            .map(|e| e.name())
+            .collect();
+        assert_eq!(entries, ["Account"]);
+        assert_eq!(pallet.constants().len(), 0);
+        assert_eq!(pallet.event_variants().unwrap().len(), 0);
+
+        // The items we keep are unchanged, and we need fewer types for them.
+        let full_pallet = full_metadata.pallet_by_name("Balances").unwrap();
+        assert_eq!(
+            pallet.call_hash("transfer_keep_alive"),
+            full_pallet.call_hash("transfer_keep_alive")
+        );
+        assert_eq!(
+            pallet.storage_hash("Account"),
+            full_pallet.storage_hash("Account")
+        );
+        assert!(metadata.types.types.len() < pallet_only.types.types.len());
+    }
+
     #[test]
     fn issue_1659() {
         let full_metadata = load_metadata_custom("../artifacts/regressions/1659.scale");
@@ -0,0 +1,302 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Utility functions to have several metadatas share a single type registry.
+
+use crate::utils::retain::iterate_metadata_types;
+use crate::utils::validation::{get_type_hash, Hash, OuterEnumHashes};
+use crate::Metadata;
+use alloc::vec::Vec;
+use hashbrown::{HashMap, HashSet};
+use scale_info::{
+    form::PortableForm, interner::UntrackedSymbol, Field, PortableRegistry, PortableType, Type,
+    TypeDef,
+};
+
+/// Replace the type registry of each of the given metadatas with one registry that they all
+/// share, in which types that are identical across the metadatas appear only once. Type IDs
+/// in each metadata are updated to point into the shared registry.
+///
+/// Types are first grouped by their [`get_type_hash()`], but since that hash is not sensitive
+/// to the order of fields and variants (which affects how values are encoded), nor to type
+/// paths or generic parameters, types are only merged if they are also structurally identical.
+/// Docs are ignored when comparing types; the docs of the first type seen are kept.
+pub fn share_types(metadatas: &mut [Metadata]) {
+    let mut shared = PortableRegistry { types: Vec::new() };
+    // The shared types with a given type hash.
+    let mut shared_by_hash: HashMap<Hash, Vec<u32>> = HashMap::new();
+
+    for metadata in metadatas.iter_mut() {
+        let types = &metadata.types;
+        let mut comparer = TypeComparer::new(types, &shared);
+
+        // 1. Find a matching shared type for each type, or decide on the ID it will get.
+        let mut next_id = shared.types.len() as u32;
+        let mut map_ids = HashMap::new();
+        let mut new_types = Vec::new();
+        for ty in types.types.iter() {
+            let hash = get_type_hash(types, ty.id, &OuterEnumHashes::empty());
+            let existing = shared_by_hash
+                .get(&hash)
+                .and_then(|ids| ids.iter().copied().find(|&id| comparer.equal(ty.id, id)));
+            let new_id = existing.unwrap_or_else(|| {
+                new_types.push((ty.id, hash));
+                next_id += 1;
+                next_id - 1
+            });
+            map_ids.insert(ty.id, new_id);
+        }
+
+        // 2. Add the types that we didn't find to the shared registry.
+        for (id, hash) in new_types {
+            let mut ty = types
+                .resolve(id)
+                .expect("type IDs come from the registry; qed")
+                .clone();
+            remap_type(&mut ty, &map_ids);
+            let new_id = shared.types.len() as u32;
+            shared.types.push(PortableType { id: new_id, ty });
+            shared_by_hash.entry(hash).or_default().push(new_id);
+        }
+
+        // 3. Update the type IDs in the metadata. Variant indexes point at positions in the
+        // variant types, which are the same because we only merge identical types.
+        remap_metadata(metadata, &map_ids);
+    }
+
+    for metadata in metadatas.iter_mut() {
+        metadata.types = shared.clone();
+    }
+}
+
+fn remap_metadata(metadata: &mut Metadata, map_ids: &HashMap<u32, u32>) {
+    let map = |id: &mut u32| *id = map_ids[&*id];
+
+    for id in iterate_metadata_types(metadata) {
+        map(id);
+    }
+    if let Some(id) = &mut metadata.dispatch_error_ty {
+        map(id);
+    }
+    for value in metadata.custom.map.values_mut() {
+        map(&mut value.ty.id);
+    }
+}
+
+fn remap_type(ty: &mut Type<PortableForm>, map_ids: &HashMap<u32, u32>) {
+    let map = |symbol: &mut UntrackedSymbol<core::any::TypeId>| symbol.id = map_ids[&symbol.id];
+    let map_fields = |fields: &mut Vec<Field<PortableForm>>| {
+        for field in fields {
+            map(&mut field.ty);
+        }
+    };
+
+    for param in &mut ty.type_params {
+        if let Some(symbol) = &mut param.ty {
+            map(symbol);
+        }
+    }
+    match &mut ty.type_def {
+        TypeDef::Composite(c) => map_fields(&mut c.fields),
+        TypeDef::Variant(v) => {
+            for variant in &mut v.variants {
+                map_fields(&mut variant.fields);
+            }
+        }
+        TypeDef::Sequence(s) => map(&mut s.type_param),
+        TypeDef::Array(a) => map(&mut a.type_param),
+        TypeDef::Tuple(t) => {
+            for field in &mut t.fields {
+                map(field);
+            }
+        }
+        TypeDef::Primitive(_) => {}
+        TypeDef::Compact(c) => map(&mut c.type_param),
+        TypeDef::BitSequence(b) => {
+            map(&mut b.bit_store_type);
+            map(&mut b.bit_order_type);
+        }
+    }
+}
+
+/// Compares types in one registry with types in another.
+struct TypeComparer<'a> {
+    a: &'a PortableRegistry,
+    b: &'a PortableRegistry,
+    /// Pairs of types that we've already compared.
+    known: HashMap<(u32, u32), bool>,
+}
+
+impl<'a> TypeComparer<'a> {
+    fn new(a: &'a PortableRegistry, b: &'a PortableRegistry) -> Self {
+        TypeComparer {
+            a,
+            b,
+            known: HashMap::new(),
+        }
+    }
+
+    /// Are the types with the given IDs identical, ignoring docs?
+    fn equal(&mut self, a_id: u32, b_id: u32) -> bool {
+        // Types can be recursive, so while comparing we assume that any pair of types that
+        // we're already in the middle of comparing is equal. If the comparison succeeds then
+        // this assumption held for every such pair.
+        let mut assumed = HashSet::new();
+        let equal = self.equal_recurse(a_id, b_id, &mut assumed);
+        if equal {
+            self.known
+                .extend(assumed.into_iter().map(|pair| (pair, true)));
+        } else {
+            self.known.insert((a_id, b_id), false);
+        }
+        equal
+    }
+
+    fn equal_recurse(&self, a_id: u32, b_id: u32, assumed: &mut HashSet<(u32, u32)>) -> bool {
+        if let Some(equal) = self.known.get(&(a_id, b_id)) {
+            return *equal;
+        }
+        if !assumed.insert((a_id, b_id)) {
+            return true;
+        }
+
+        let (Some(a), Some(b)) = (self.a.resolve(a_id), self.b.resolve(b_id)) else {
+            return false;
+        };
+        if a.path != b.path || a.type_params.len() != b.type_params.len() {
+            return false;
+        }
+        let params_equal = a.type_params.iter().zip(&b.type_params).all(|(a, b)| {
+            a.name == b.name
+                && match (&a.ty, &b.ty) {
+                    (Some(a), Some(b)) => self.equal_recurse(a.id, b.id, assumed),
+                    (None, None) => true,
+                    _ => false,
+                }
+        });
+        if !params_equal {
+            return false;
+        }
+
+        match (&a.type_def, &b.type_def) {
+            (TypeDef::Composite(a), TypeDef::Composite(b)) => {
+                self.fields_equal(&a.fields, &b.fields, assumed)
+            }
+            (TypeDef::Variant(a), TypeDef::Variant(b)) => {
+                a.variants.len() == b.variants.len()
+                    && a.variants.iter().zip(&b.variants).all(|(a, b)| {
+                        a.name == b.name
+                            && a.index == b.index
+                            && self.fields_equal(&a.fields, &b.fields, assumed)
+                    })
+            }
+            (TypeDef::Sequence(a), TypeDef::Sequence(b)) => {
+                self.equal_recurse(a.type_param.id, b.type_param.id, assumed)
+            }
+            (TypeDef::Array(a), TypeDef::Array(b)) => {
+                a.len == b.len && self.equal_recurse(a.type_param.id, b.type_param.id, assumed)
+            }
+            (TypeDef::Tuple(a), TypeDef::Tuple(b)) => {
+                a.fields.len() == b.fields.len()
+                    && a.fields
+                        .iter()
+                        .zip(&b.fields)
+                        .all(|(a, b)| self.equal_recurse(a.id, b.id, assumed))
+            }
+            (TypeDef::Primitive(a), TypeDef::Primitive(b)) => a == b,
+            (TypeDef::Compact(a), TypeDef::Compact(b)) => {
+                self.equal_recurse(a.type_param.id, b.type_param.id, assumed)
+            }
+            (TypeDef::BitSequence(a), TypeDef::BitSequence(b)) => {
+                self.equal_recurse(a.bit_store_type.id, b.bit_store_type.id, assumed)
+                    && self.equal_recurse(a.bit_order_type.id, b.bit_order_type.id, assumed)
+            }
+            _ => false,
+        }
+    }
+
+    fn fields_equal(
+        &self,
+        a: &[Field<PortableForm>],
+        b: &[Field<PortableForm>],
+        assumed: &mut HashSet<(u32, u32)>,
+    ) -> bool {
+        a.len() == b.len()
+            && a.iter().zip(b).all(|(a, b)| {
+                a.name == b.name
+                    && a.type_name == b.type_name
+                    && self.equal_recurse(a.ty.id, b.ty.id, assumed)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codec::Decode;
+    use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
+    use std::fs;
+
+    fn load_metadata(path: &str) -> Metadata {
+        let bytes = fs::read(path).expect("Cannot read metadata blob");
+        let meta: RuntimeMetadataPrefixed =
+            Decode::decode(&mut &*bytes).expect("Cannot decode scale metadata");
+
+        match meta.1 {
+            RuntimeMetadata::V14(v14) => v14.try_into().unwrap(),
+            RuntimeMetadata::V15(v15) => v15.try_into().unwrap(),
+            _ => panic!("Unsupported metadata version {:?}", meta.1),
+        }
+    }
+
+    #[test]
+    fn shares_identical_types() {
+        let full = load_metadata("../artifacts/polkadot_metadata_full.scale");
+        let small = load_metadata("../artifacts/polkadot_metadata_small.scale");
+        let hashes = [full.hasher().hash(), small.hasher().hash()];
+        let num_types = full.types().types.len() + small.types().types.len();
+
+        let mut metadatas = [full, small];
+        share_types(&mut metadatas);
+        let [full, small] = &metadatas;
+
+        // The metadata is unchanged, apart from where the types live.
+        assert_eq!(full.hasher().hash(), hashes[0]);
+        assert_eq!(small.hasher().hash(), hashes[1]);
+
+        // Both contain the same version of the System pallet, so its types are shared.
+        let shared_types = full.types().types.len();
+        assert!(shared_types < num_types);
+        assert_eq!(small.types().types.len(), shared_types);
+        assert_eq!(
+            full.pallet_by_name("System").unwrap().call_ty_id(),
+            small.pallet_by_name("System").unwrap().call_ty_id()
+        );
+    }
+
+    #[test]
+    fn does_not_share_reordered_types() {
+        let metadata = load_metadata("../artifacts/polkadot_metadata_small.scale");
+        let mut reordered = metadata.clone();
+        let call_ty = reordered
+            .pallet_by_name("System")
+            .unwrap()
+            .call_ty_id()
+            .unwrap();
+        let TypeDef::Variant(v) = &mut reordered.types.types[call_ty as usize].ty.type_def else {
+            panic!("call type should be a variant");
+        };
+        // Swapping variants doesn't change the type hash, but does change the encoding.
+        v.variants.swap(0, 1);
+        assert_eq!(metadata.type_hash(call_ty), reordered.type_hash(call_ty));
+
+        let mut metadatas = [metadata, reordered];
+        share_types(&mut metadatas);
+        let [metadata, reordered] = &metadatas;
+        assert_ne!(
+            metadata.pallet_by_name("System").unwrap().call_ty_id(),
+            reordered.pallet_by_name("System").unwrap().call_ty_id()
+        );
+    }
+}
@@ -0,0 +1,611 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Walk two types in parallel, in the way that they are SCALE encoded, recording each change
+//! that's found and which code it breaks. This is shared by [`Metadata::diff()`] and
+//! [`Metadata::compatibility_report()`].
+//!
+//! [`Metadata::diff()`]: crate::Metadata::diff
+//! [`Metadata::compatibility_report()`]: crate::Metadata::compatibility_report
+
+use crate::{StorageEntryMetadata, StorageEntryType};
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Display;
+use hashbrown::HashSet;
+use scale_info::{form::PortableForm, Field, PortableRegistry, TypeDef, TypeDefPrimitive, Variant};
+
+/// Which code a change breaks, if any. Changes are judged by their effect on the SCALE
+/// encoding of values, so for example renaming a field breaks neither encoders nor decoders.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Breakage {
+    /// Values encoded according to the older metadata may not be understood by the newer runtime.
+    pub encoders: bool,
+    /// Values produced by the newer runtime may not decode according to the older metadata.
+    pub decoders: bool,
+}
+
+impl Breakage {
+    /// Breaks nothing.
+    pub const NONE: Breakage = Breakage {
+        encoders: false,
+        decoders: false,
+    };
+    /// Breaks encoders only.
+    pub const ENCODERS: Breakage = Breakage {
+        encoders: true,
+        decoders: false,
+    };
+    /// Breaks decoders only.
+    pub const DECODERS: Breakage = Breakage {
+        encoders: false,
+        decoders: true,
+    };
+    /// Breaks both encoders and decoders.
+    pub const BOTH: Breakage = Breakage {
+        encoders: true,
+        decoders: true,
+    };
+
+    /// Does this break either encoders or decoders?
+    pub fn is_breaking(&self) -> bool {
+        self.encoders || self.decoders
+    }
+
+    /// Combine two breakages, so that the result breaks anything that either of them do.
+    pub fn union(self, other: Breakage) -> Breakage {
+        Breakage {
+            encoders: self.encoders || other.encoders,
+            decoders: self.decoders || other.decoders,
+        }
+    }
+}
+
+impl Display for Breakage {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match (self.encoders, self.decoders) {
+            (false, false) => f.write_str("non-breaking"),
+            (true, false) => f.write_str("breaks encoders"),
+            (false, true) => f.write_str("breaks decoders"),
+            (true, true) => f.write_str("breaks encoders and decoders"),
+        }
+    }
+}
+
+/// A single change found inside some item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TypeChange {
+    /// Where in the item the change was found, as a `.` separated path of field and
+    /// variant names (eg `dest.Id`). This is empty if the change applies to the item itself.
+    pub path: String,
+    /// What changed.
+    pub kind: TypeChangeKind,
+    /// Which code the change breaks, if any.
+    pub breakage: Breakage,
+}
+
+impl Display for TypeChange {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        write!(f, "{} ({})", self.kind, self.breakage)
+    }
+}
+
+/// The kind of a [`TypeChange`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TypeChangeKind {
+    /// A field (or runtime API parameter) was added. Unnamed fields are named by position.
+    FieldAdded {
+        /// The name of the field.
+        name: String,
+    },
+    /// A field (or runtime API parameter) was removed. Unnamed fields are named by position.
+    FieldRemoved {
+        /// The name of the field.
+        name: String,
+    },
+    /// A field (or runtime API parameter) in the same position was renamed.
+    FieldRenamed {
+        /// The old name of the field.
+        old: String,
+        /// The new name of the field.
+        new: String,
+    },
+    /// A variant was added to an enum.
+    VariantAdded {
+        /// The name of the variant.
+        name: String,
+        /// The index of the variant.
+        index: u8,
+    },
+    /// A variant was removed from an enum.
+    VariantRemoved {
+        /// The name of the variant.
+        name: String,
+        /// The index of the variant.
+        index: u8,
+    },
+    /// The variant with some index was renamed.
+    VariantRenamed {
+        /// The index of the variant.
+        index: u8,
+        /// The old name of the variant.
+        old: String,
+        /// The new name of the variant.
+        new: String,
+    },
+    /// A pallet, call, event or error now has a different index.
+    IndexChanged {
+        /// The name of the thing whose index changed.
+        name: String,
+        /// The old index.
+        old: u8,
+        /// The new index.
+        new: u8,
+    },
+    /// An integer type was replaced with one of a different width or signedness.
+    IntegerWidthChanged {
+        /// The name of the old integer type.
+        old: String,
+        /// The name of the new integer type.
+        new: String,
+    },
+    /// A type was replaced with a different kind of type.
+    TypeChanged {
+        /// The name of the old type.
+        old: String,
+        /// The name of the new type.
+        new: String,
+    },
+    /// The hashers of a storage entry changed, or it changed from a plain entry to a map
+    /// or vice versa.
+    StorageHashersChanged,
+    /// A storage entry changed from being optional to having a default value or vice versa.
+    StorageModifierChanged,
+    /// The default value of a storage entry changed.
+    StorageDefaultChanged,
+    /// The value of a constant changed.
+    ConstantValueChanged,
+}
+
+impl Display for TypeChangeKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TypeChangeKind::FieldAdded { name } => write!(f, "field `{name}` added"),
+            TypeChangeKind::FieldRemoved { name } => write!(f, "field `{name}` removed"),
+            TypeChangeKind::FieldRenamed { old, new } => {
+                write!(f, "field `{old}` renamed to `{new}`")
+            }
+            TypeChangeKind::VariantAdded { name, index } => {
+                write!(f, "variant `{name}` added at index {index}")
+            }
+            TypeChangeKind::VariantRemoved { name, index } => {
+                write!(f, "variant `{name}` removed from index {index}")
+            }
+            TypeChangeKind::VariantRenamed { index, old, new } => {
+                write!(f, "variant `{old}` at index {index} renamed to `{new}`")
+            }
+            TypeChangeKind::IndexChanged { name, old, new } => {
+                write!(f, "`{name}` moved from index {old} to {new}")
+            }
+            TypeChangeKind::IntegerWidthChanged { old, new } => {
+                write!(f, "integer changed from `{old}` to `{new}`")
+            }
+            TypeChangeKind::TypeChanged { old, new } => {
+                write!(f, "type changed from `{old}` to `{new}`")
+            }
+            TypeChangeKind::StorageHashersChanged => f.write_str("storage hashers changed"),
+            TypeChangeKind::StorageModifierChanged => f.write_str("storage modifier changed"),
+            TypeChangeKind::StorageDefaultChanged => f.write_str("storage default value changed"),
+            TypeChangeKind::ConstantValueChanged => f.write_str("constant value changed"),
+        }
+    }
+}
+
+/// Walks two types in parallel, in the way that they are SCALE encoded, recording the
+/// differences between them.
+pub(crate) struct TypeDiff<'a> {
+    old: &'a PortableRegistry,
+    new: &'a PortableRegistry,
+    /// Pairs of types that we've already compared, which also guards against recursion.
+    visited: HashSet<(u32, u32)>,
+    pub(crate) changes: Vec<TypeChange>,
+}
+
+impl<'a> TypeDiff<'a> {
+    pub(crate) fn new(old: &'a PortableRegistry, new: &'a PortableRegistry) -> Self {
+        TypeDiff {
+            old,
+            new,
+            visited: HashSet::new(),
+            changes: Vec::new(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, path: &str, kind: TypeChangeKind, breakage: Breakage) {
+        self.changes.push(TypeChange {
+            path: path.to_owned(),
+            kind,
+            breakage,
+        });
+    }
+
+    pub(crate) fn diff(&mut self, path: &str, old_id: u32, new_id: u32) {
+        if !self.visited.insert((old_id, new_id)) {
+            return;
+        }
+        let (Some(old_ty), Some(new_ty)) = (self.old.resolve(old_id), self.new.resolve(new_id))
+        else {
+            return;
+        };
+
+        match (&old_ty.type_def, &new_ty.type_def) {
+            (TypeDef::Composite(old), TypeDef::Composite(new)) => {
+                self.fields(path, &old.fields, &new.fields)
+            }
+            (TypeDef::Variant(old), TypeDef::Variant(new)) => {
+                self.variants(path, &old.variants, &new.variants)
+            }
+            (TypeDef::Sequence(old), TypeDef::Sequence(new)) => {
+                self.diff(path, old.type_param.id, new.type_param.id)
+            }
+            (TypeDef::Array(old), TypeDef::Array(new)) if old.len == new.len => {
+                self.diff(path, old.type_param.id, new.type_param.id)
+            }
+            (TypeDef::Tuple(old), TypeDef::Tuple(new)) if old.fields.len() == new.fields.len() => {
+                for (idx, (old, new)) in old.fields.iter().zip(&new.fields).enumerate() {
+                    self.diff(&join(path, &idx.to_string()), old.id, new.id);
+                }
+            }
+            (TypeDef::Compact(old), TypeDef::Compact(new)) => {
+                self.compact(path, old.type_param.id, new.type_param.id)
+            }
+            (TypeDef::Primitive(old), TypeDef::Primitive(new)) if old == new => {}
+            (TypeDef::Primitive(old), TypeDef::Primitive(new))
+                if integer_bits(old).is_some() && integer_bits(new).is_some() =>
+            {
+                self.push(
+                    path,
+                    TypeChangeKind::IntegerWidthChanged {
+                        old: type_name(self.old, old_id),
+                        new: type_name(self.new, new_id),
+                    },
+                    Breakage::BOTH,
+                )
+            }
+            (TypeDef::BitSequence(old), TypeDef::BitSequence(new)) => {
+                self.diff(path, old.bit_store_type.id, new.bit_store_type.id);
+                self.diff(path, old.bit_order_type.id, new.bit_order_type.id);
+            }
+            _ => self.push(
+                path,
+                TypeChangeKind::TypeChanged {
+                    old: type_name(self.old, old_id),
+                    new: type_name(self.new, new_id),
+                },
+                Breakage::BOTH,
+            ),
+        }
+    }
+
+    /// Compare a call, event or error variant which has been matched up by name. Any change
+    /// to its index is reported, along with changes to its fields.
+    pub(crate) fn variant(&mut self, old: &Variant<PortableForm>, new: &Variant<PortableForm>) {
+        if old.index != new.index {
+            self.push(
+                "",
+                TypeChangeKind::IndexChanged {
+                    name: old.name.clone(),
+                    old: old.index,
+                    new: new.index,
+                },
+                Breakage::BOTH,
+            );
+        }
+        self.fields("", &old.fields, &new.fields);
+    }
+
+    /// Compare two storage entries which have been matched up by name.
+    pub(crate) fn storage_entry(&mut self, old: &StorageEntryMetadata, new: &StorageEntryMetadata) {
+        let hashers = |entry_type: &StorageEntryType| -> Vec<u8> {
+            match entry_type {
+                StorageEntryType::Plain(_) => Vec::new(),
+                StorageEntryType::Map { hashers, .. } => hashers.iter().map(|h| *h as u8).collect(),
+            }
+        };
+        if hashers(old.entry_type()) != hashers(new.entry_type()) {
+            self.push("", TypeChangeKind::StorageHashersChanged, Breakage::BOTH);
+        }
+        if old.modifier() != new.modifier() {
+            // The value is encoded in the same way, but what's returned when there is no
+            // value will be different.
+            self.push(
+                "",
+                TypeChangeKind::StorageModifierChanged,
+                Breakage::DECODERS,
+            );
+        }
+        if let (Some(old_key), Some(new_key)) =
+            (old.entry_type().key_ty(), new.entry_type().key_ty())
+        {
+            self.diff("key", old_key, new_key);
+        }
+        self.diff(
+            "value",
+            old.entry_type().value_ty(),
+            new.entry_type().value_ty(),
+        );
+        if old.default_bytes() != new.default_bytes() {
+            self.push("", TypeChangeKind::StorageDefaultChanged, Breakage::NONE);
+        }
+    }
+
+    /// Compact integers are encoded in the same way regardless of their width, so widening
+    /// one only breaks decoders (which may see values too large for them), and narrowing
+    /// one only breaks encoders.
+    fn compact(&mut self, path: &str, old_id: u32, new_id: u32) {
+        let primitive = |types: &PortableRegistry, id: u32| match types.resolve(id) {
+            Some(ty) => match &ty.type_def {
+                TypeDef::Primitive(p) => integer_bits(p),
+                _ => None,
+            },
+            None => None,
+        };
+
+        match (primitive(self.old, old_id), primitive(self.new, new_id)) {
+            (Some(old_bits), Some(new_bits)) if old_bits != new_bits => {
+                let breakage = if new_bits > old_bits {
+                    Breakage::DECODERS
+                } else {
+                    Breakage::ENCODERS
+                };
+                self.push(
+                    path,
+                    TypeChangeKind::IntegerWidthChanged {
+                        old: type_name(self.old, old_id),
+                        new: type_name(self.new, new_id),
+                    },
+                    breakage,
+                )
+            }
+            _ => self.diff(path, old_id, new_id),
+        }
+    }
+
+    /// Compare enum variants, matching them up by index.
+    fn variants(
+        &mut self,
+        path: &str,
+        old: &[Variant<PortableForm>],
+        new: &[Variant<PortableForm>],
+    ) {
+        for old_variant in old {
+            let Some(new_variant) = new.iter().find(|v| v.index == old_variant.index) else {
+                // Old encoders might still try to use this variant.
+                self.push(
+                    path,
+                    TypeChangeKind::VariantRemoved {
+                        name: old_variant.name.clone(),
+                        index: old_variant.index,
+                    },
+                    Breakage::ENCODERS,
+                );
+                continue;
+            };
+            if old_variant.name != new_variant.name {
+                self.push(
+                    path,
+                    TypeChangeKind::VariantRenamed {
+                        index: old_variant.index,
+                        old: old_variant.name.clone(),
+                        new: new_variant.name.clone(),
+                    },
+                    Breakage::NONE,
+                );
+            }
+            let variant_path = join(path, &old_variant.name);
+            self.fields(&variant_path, &old_variant.fields, &new_variant.fields);
+        }
+
+        for new_variant in new {
+            if !old.iter().any(|v| v.index == new_variant.index) {
+                // Old decoders won't know about this variant if it's handed back.
+                self.push(
+                    path,
+                    TypeChangeKind::VariantAdded {
+                        name: new_variant.name.clone(),
+                        index: new_variant.index,
+                    },
+                    Breakage::DECODERS,
+                );
+            }
+        }
+    }
+
+    fn fields(&mut self, path: &str, old: &[Field<PortableForm>], new: &[Field<PortableForm>]) {
+        // Unnamed fields are named by their position.
+        let named = |fields: &[Field<PortableForm>]| -> Vec<(String, u32)> {
+            fields
+                .iter()
+                .enumerate()
+                .map(|(idx, f)| (f.name.clone().unwrap_or_else(|| idx.to_string()), f.ty.id))
+                .collect()
+        };
+        self.named_fields(path, &named(old), &named(new));
+    }
+
+    /// Compare some fields or parameters, matching them up by position.
+    pub(crate) fn named_fields(
+        &mut self,
+        path: &str,
+        old: &[(String, u32)],
+        new: &[(String, u32)],
+    ) {
+        for idx in 0..old.len().max(new.len()) {
+            match (old.get(idx), new.get(idx)) {
+                (Some((old_name, old_ty)), Some((new_name, new_ty))) => {
+                    if old_name != new_name {
+                        self.push(
+                            path,
+                            TypeChangeKind::FieldRenamed {
+                                old: old_name.clone(),
+                                new: new_name.clone(),
+                            },
+                            Breakage::NONE,
+                        );
+                    }
+                    self.diff(&join(path, old_name), *old_ty, *new_ty);
+                }
+                (Some((name, _)), None) => self.push(
+                    path,
+                    TypeChangeKind::FieldRemoved { name: name.clone() },
+                    Breakage::BOTH,
+                ),
+                (None, Some((name, _))) => self.push(
+                    path,
+                    TypeChangeKind::FieldAdded { name: name.clone() },
+                    Breakage::BOTH,
+                ),
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+/// The number of bits in some integer type, or `None` if it's not an integer.
+fn integer_bits(primitive: &TypeDefPrimitive) -> Option<u16> {
+    match primitive {
+        TypeDefPrimitive::U8 | TypeDefPrimitive::I8 => Some(8),
+        TypeDefPrimitive::U16 | TypeDefPrimitive::I16 => Some(16),
+        TypeDefPrimitive::U32 | TypeDefPrimitive::I32 => Some(32),
+        TypeDefPrimitive::U64 | TypeDefPrimitive::I64 => Some(64),
+        TypeDefPrimitive::U128 | TypeDefPrimitive::I128 => Some(128),
+        TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => Some(256),
+        TypeDefPrimitive::Bool | TypeDefPrimitive::Char | TypeDefPrimitive::Str => None,
+    }
+}
+
+fn join(path: &str, segment: &str) -> String {
+    if path.is_empty() {
+        segment.to_owned()
+    } else {
+        format!("{path}.{segment}")
+    }
+}
+
+/// A short, human readable name for some type.
+fn type_name(types: &PortableRegistry, id: u32) -> String {
+    let Some(ty) = types.resolve(id) else {
+        return format!("<unknown type {id}>");
+    };
+
+    if let Some(ident) = ty.path.ident() {
+        let params: Vec<_> = ty
+            .type_params
+            .iter()
+            .filter_map(|p| p.ty.map(|t| type_name(types, t.id)))
+            .collect();
+        return if params.is_empty() {
+            ident
+        } else {
+            format!("{ident}<{}>", params.join(", "))
+        };
+    }
+
+    match &ty.type_def {
+        TypeDef::Composite(_) => "struct".to_owned(),
+        TypeDef::Variant(_) => "enum".to_owned(),
+        TypeDef::Sequence(s) => format!("Vec<{}>", type_name(types, s.type_param.id)),
+        TypeDef::Array(a) => format!("[{}; {}]", type_name(types, a.type_param.id), a.len),
+        TypeDef::Tuple(t) => {
+            let mut out = String::from("(");
+            for (idx, field) in t.fields.iter().enumerate() {
+                if idx > 0 {
+                    out.push_str(", ");
+                }
+                out.push_str(&type_name(types, field.id));
+            }
+            out.push(')');
+            out
+        }
+        TypeDef::Primitive(p) => format!("{p:?}").to_lowercase(),
+        TypeDef::Compact(c) => format!("Compact<{}>", type_name(types, c.type_param.id)),
+        TypeDef::BitSequence(_) => "BitSequence".to_owned(),
+    }
+}
+
+/// Helpers shared by the tests of the modules which compare metadata.
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use super::*;
+    use crate::Metadata;
+    use frame_metadata::v15;
+    use scale_info::{meta_type, MetaType};
+
+    /// Build metadata containing a single pallet called `Test`, with the given call and
+    /// event types and constants.
+    pub(crate) fn pallet_metadata(
+        calls: MetaType,
+        events: Option<MetaType>,
+        constants: Vec<(&'static str, MetaType)>,
+    ) -> Metadata {
+        let pallet = v15::PalletMetadata {
+            name: "Test",
+            storage: None,
+            calls: Some(v15::PalletCallMetadata { ty: calls }),
+            event: events.map(|ty| v15::PalletEventMetadata { ty }),
+            constants: constants
+                .into_iter()
+                .map(|(name, ty)| v15::PalletConstantMetadata {
+                    name,
+                    ty,
+                    value: vec![],
+                    docs: vec![],
+                })
+                .collect(),
+            error: None,
+            index: 0,
+            docs: vec![],
+        };
+
+        v15::RuntimeMetadataV15::new(
+            vec![pallet],
+            v15::ExtrinsicMetadata {
+                version: 0,
+                signed_extensions: vec![],
+                address_ty: meta_type::<()>(),
+                call_ty: meta_type::<()>(),
+                signature_ty: meta_type::<()>(),
+                extra_ty: meta_type::<()>(),
+            },
+            meta_type::<()>(),
+            vec![],
+            v15::OuterEnums {
+                call_enum_ty: meta_type::<()>(),
+                event_enum_ty: meta_type::<()>(),
+                error_enum_ty: meta_type::<()>(),
+            },
+            v15::CustomMetadata {
+                map: Default::default(),
+            },
+        )
+        .try_into()
+        .expect("can build valid metadata")
+    }
+
+    pub(crate) fn change(path: &str, kind: TypeChangeKind, breakage: Breakage) -> TypeChange {
+        TypeChange {
+            path: path.to_owned(),
+            kind,
+            breakage,
+        }
+    }
+}
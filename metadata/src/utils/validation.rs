@@ -5,12 +5,13 @@
 //! Utility functions for metadata validation.
 
 use crate::{
-    CustomMetadata, CustomValueMetadata, ExtrinsicMetadata, Metadata, PalletMetadata,
-    RuntimeApiMetadata, RuntimeApiMethodMetadata, StorageEntryMetadata, StorageEntryType,
+    CustomMetadata, CustomValueMetadata, DeprecationInfo, ExtrinsicMetadata, Metadata,
+    PalletMetadata, RuntimeApiMetadata, RuntimeApiMethodMetadata, SignedExtensionMetadata,
+    StorageEntryMetadata, StorageEntryType, ViewFunctionMetadata,
 };
 use alloc::vec::Vec;
 use hashbrown::HashMap;
-use outer_enum_hashes::OuterEnumHashes;
+pub(crate) use outer_enum_hashes::OuterEnumHashes;
 use polkadot_sdk::sp_crypto_hashing;
 use scale_info::{form::PortableForm, Field, PortableRegistry, TypeDef, TypeDefVariant, Variant};
 
@@ -300,7 +301,43 @@ fn get_extrinsic_hash(
         &[extrinsic.version; 32],
     );
 
-    for signed_extension in extrinsic.signed_extensions.iter() {
+    bytes = get_signed_extensions_hash(
+        registry,
+        bytes,
+        extrinsic.signed_extensions(),
+        outer_enum_hashes,
+    );
+
+    // Only V16 metadata onwards can describe several extrinsic versions and transaction
+    // extension versions, so we only hash these when present to keep older hashes the same.
+    if extrinsic.supported_versions != [extrinsic.version]
+        || extrinsic.transaction_extensions_version != 0
+    {
+        bytes = concat_and_hash3(
+            &bytes,
+            &hash(&extrinsic.supported_versions),
+            &[extrinsic.transaction_extensions_version; HASH_LEN],
+        );
+    }
+    for (version, signed_extensions) in &extrinsic.signed_extensions_by_version {
+        if *version == extrinsic.transaction_extensions_version {
+            continue;
+        }
+        bytes = concat_and_hash2(&bytes, &[*version; HASH_LEN]);
+        bytes = get_signed_extensions_hash(registry, bytes, signed_extensions, outer_enum_hashes);
+    }
+
+    bytes
+}
+
+/// Fold the hashes of the given signed extensions, in order, into `bytes`.
+fn get_signed_extensions_hash(
+    registry: &PortableRegistry,
+    mut bytes: Hash,
+    signed_extensions: &[SignedExtensionMetadata],
+    outer_enum_hashes: &OuterEnumHashes,
+) -> Hash {
+    for signed_extension in signed_extensions {
         bytes = concat_and_hash4(
             &bytes,
             &hash(signed_extension.identifier.as_bytes()),
@@ -308,10 +345,87 @@ fn get_extrinsic_hash(
             &get_type_hash(registry, signed_extension.additional_ty, outer_enum_hashes),
         )
     }
-
     bytes
 }
 
+/// Obtain the hash of some deprecation info, or `None` if the item is not deprecated.
+fn get_deprecation_hash(deprecation_info: &DeprecationInfo) -> Option<Hash> {
+    match deprecation_info {
+        DeprecationInfo::NotDeprecated => None,
+        DeprecationInfo::DeprecatedWithoutNote => Some(hash(&[1])),
+        DeprecationInfo::Deprecated { note, since } => Some(concat_and_hash2(
+            &hash(note.as_bytes()),
+            &hash(since.as_deref().unwrap_or_default().as_bytes()),
+        )),
+    }
+}
+
+/// The kinds of item whose deprecation info is hashed, to tell apart items with the same name.
+#[derive(Clone, Copy)]
+#[repr(u8)]
+enum DeprecatedItem {
+    Pallet,
+    Call,
+    Event,
+    Error,
+    StorageEntry,
+    Constant,
+    ViewFunction,
+    RuntimeApi,
+    RuntimeApiMethod,
+}
+
+/// Accumulates the deprecation info of items, in any order. Items which are not
+/// deprecated don't contribute to the hash, so it's zero if nothing is deprecated.
+#[derive(Default)]
+struct DeprecationHasher {
+    bytes: Hash,
+}
+
+impl DeprecationHasher {
+    fn add(&mut self, item: DeprecatedItem, name: &[u8], deprecation_info: &DeprecationInfo) {
+        if let Some(deprecation_hash) = get_deprecation_hash(deprecation_info) {
+            let item_hash =
+                concat_and_hash3(&[item as u8; HASH_LEN], &hash(name), &deprecation_hash);
+            self.bytes = xor(self.bytes, item_hash);
+        }
+    }
+
+    /// Fold the deprecation hash into `bytes` if anything was deprecated.
+    fn finish(self, bytes: Hash) -> Hash {
+        if self.bytes == [0u8; HASH_LEN] {
+            bytes
+        } else {
+            concat_and_hash2(&bytes, &self.bytes)
+        }
+    }
+}
+
+/// Get the hash corresponding to a single view function.
+fn get_view_function_method_hash(
+    registry: &PortableRegistry,
+    view_function: &ViewFunctionMetadata,
+    outer_enum_hashes: &OuterEnumHashes,
+) -> Hash {
+    let mut bytes = concat_and_hash2(
+        &hash(view_function.name.as_bytes()),
+        &view_function.query_id,
+    );
+
+    for input in &view_function.inputs {
+        bytes = concat_and_hash3(
+            &bytes,
+            &hash(input.name.as_bytes()),
+            &get_type_hash(registry, input.ty, outer_enum_hashes),
+        );
+    }
+
+    concat_and_hash2(
+        &bytes,
+        &get_type_hash(registry, view_function.output_ty, outer_enum_hashes),
+    )
+}
+
 /// Get the hash corresponding to a single storage entry.
 fn get_storage_entry_hash(
     registry: &PortableRegistry,
@@ -403,7 +517,24 @@ pub fn get_runtime_trait_hash(
             )
         });
 
-    concat_and_hash2(&hash(trait_name.as_bytes()), &method_bytes)
+    let mut deprecation = DeprecationHasher::default();
+    deprecation.add(
+        DeprecatedItem::RuntimeApi,
+        trait_name.as_bytes(),
+        trait_metadata.deprecation_info(),
+    );
+    for method in trait_metadata.methods() {
+        deprecation.add(
+            DeprecatedItem::RuntimeApiMethod,
+            method.name.as_bytes(),
+            method.deprecation_info(),
+        );
+    }
+
+    deprecation.finish(concat_and_hash2(
+        &hash(trait_name.as_bytes()),
+        &method_bytes,
+    ))
 }
 
 fn get_custom_metadata_hash(
@@ -474,6 +605,28 @@ pub fn get_call_hash(pallet: &PalletMetadata, call_name: &str) -> Option<Hash> {
     Some(hash)
 }
 
+/// Obtain the hash for a specific event, or an error if it's not found.
+pub fn get_event_hash(pallet: &PalletMetadata, event_name: &str) -> Option<Hash> {
+    let event_variant = pallet.event_variant_by_name(event_name)?;
+
+    // hash the specific variant representing the event we are interested in.
+    let hash = get_variant_hash(
+        pallet.types,
+        event_variant,
+        &mut HashMap::new(),
+        &OuterEnumHashes::empty(),
+    );
+    Some(hash)
+}
+
+/// Obtain the hash for a specific view function, or an error if it's not found.
+pub fn get_view_function_hash(pallet: &PalletMetadata, view_function_name: &str) -> Option<Hash> {
+    let view_function = pallet.view_function_by_name(view_function_name)?;
+    let hash =
+        get_view_function_method_hash(pallet.types, view_function, &OuterEnumHashes::empty());
+    Some(hash)
+}
+
 /// Obtain the hash of a specific runtime API function, or an error if it's not found.
 pub fn get_runtime_api_hash(runtime_apis: &RuntimeApiMetadata, method_name: &str) -> Option<Hash> {
     let trait_name = &*runtime_apis.inner.name;
@@ -532,13 +685,82 @@ pub fn get_pallet_hash(pallet: PalletMetadata, outer_enum_hashes: &OuterEnumHash
     };
 
     // Hash all of the above together:
-    concat_and_hash5(
+    let mut bytes = concat_and_hash5(
         &call_bytes,
         &event_bytes,
         &error_bytes,
         &constant_bytes,
         &storage_bytes,
-    )
+    );
+
+    // View functions, associated types and deprecation info only exist in V16 metadata
+    // onwards, so we only hash them when present to keep the hashes of older metadata the same.
+    if pallet.view_functions().len() > 0 {
+        let view_function_bytes =
+            pallet
+                .view_functions()
+                .fold([0u8; HASH_LEN], |bytes, view_function| {
+                    xor(
+                        bytes,
+                        get_view_function_method_hash(registry, view_function, outer_enum_hashes),
+                    )
+                });
+        bytes = concat_and_hash2(&bytes, &view_function_bytes);
+    }
+    if pallet.associated_types().len() > 0 {
+        let associated_type_bytes =
+            pallet
+                .associated_types()
+                .fold([0u8; HASH_LEN], |bytes, associated_type| {
+                    let associated_type_hash = concat_and_hash2(
+                        &hash(associated_type.name.as_bytes()),
+                        &get_type_hash(registry, associated_type.ty, outer_enum_hashes),
+                    );
+                    xor(bytes, associated_type_hash)
+                });
+        bytes = concat_and_hash2(&bytes, &associated_type_bytes);
+    }
+
+    let mut deprecation = DeprecationHasher::default();
+    deprecation.add(
+        DeprecatedItem::Pallet,
+        pallet.name().as_bytes(),
+        pallet.deprecation_info(),
+    );
+    for (item, variants) in [
+        (DeprecatedItem::Call, &pallet.inner.call_deprecation_info),
+        (DeprecatedItem::Event, &pallet.inner.event_deprecation_info),
+        (DeprecatedItem::Error, &pallet.inner.error_deprecation_info),
+    ] {
+        for (index, deprecation_info) in variants {
+            deprecation.add(item, &[*index], deprecation_info);
+        }
+    }
+    if let Some(storage) = pallet.storage() {
+        for entry in storage.entries() {
+            deprecation.add(
+                DeprecatedItem::StorageEntry,
+                entry.name.as_bytes(),
+                entry.deprecation_info(),
+            );
+        }
+    }
+    for constant in pallet.constants() {
+        deprecation.add(
+            DeprecatedItem::Constant,
+            constant.name.as_bytes(),
+            constant.deprecation_info(),
+        );
+    }
+    for view_function in pallet.view_functions() {
+        deprecation.add(
+            DeprecatedItem::ViewFunction,
+            view_function.name.as_bytes(),
+            view_function.deprecation_info(),
+        );
+    }
+
+    deprecation.finish(bytes)
 }
 
 /// Obtain a hash representation of our metadata or some part of it.
@@ -1248,4 +1470,92 @@ mod tests {
 
         assert_eq!(hash, hash_trimmed);
     }
+
+    fn load_metadata(name: &str) -> Metadata {
+        let bytes = std::fs::read(format!("../artifacts/{name}.scale")).unwrap();
+        codec::Decode::decode(&mut &*bytes).unwrap()
+    }
+
+    fn to_hex(hash: Hash) -> String {
+        hash.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn v14_and_v15_hashes_are_unchanged() {
+        // These are the hashes from before V16 metadata was supported. Nothing that is only
+        // found in V16 metadata is present here, and so they must not change.
+        for (name, v15_hash, v14_hash) in [
+            (
+                "polkadot_metadata_full",
+                "6fcfee519a88421ff055e979383d6ae6425577cac9c406afc95798fc77f0b4bc",
+                "79bf257e8394b914a90016326d7a89194b109bceb344a833de8d7a02be937706",
+            ),
+            (
+                "polkadot_metadata_small",
+                "89643e71f4a35141439d5f46eea748a00d257fcae8d020c5b41c70a4bcb1da34",
+                "b069979203cc2eb21e71744186ea4c187c84f5f805b3125a22f53167ecc72c2c",
+            ),
+        ] {
+            let metadata = load_metadata(name);
+            let v14: frame_metadata::v14::RuntimeMetadataV14 = metadata.clone().into();
+            let v14 = Metadata::try_from(v14).unwrap();
+
+            assert_eq!(to_hex(metadata.hasher().hash()), v15_hash, "{name} as V15");
+            assert_eq!(to_hex(v14.hasher().hash()), v14_hash, "{name} as V14");
+        }
+    }
+
+    #[test]
+    fn v16_only_fields_change_hashes() {
+        let metadata = load_metadata("polkadot_metadata_small");
+        let hash_with = |change: fn(&mut Metadata)| {
+            let mut m = metadata.clone();
+            change(&mut m);
+            m.hasher().hash()
+        };
+
+        let mut hashes = vec![
+            metadata.hasher().hash(),
+            hash_with(|m| {
+                m.pallets.values_mut()[0].view_functions.push_insert(
+                    "value_of".into(),
+                    ViewFunctionMetadata {
+                        name: "value_of".into(),
+                        query_id: [7; 32],
+                        inputs: vec![],
+                        output_ty: 0,
+                        docs: vec![],
+                        deprecation_info: DeprecationInfo::NotDeprecated,
+                    },
+                )
+            }),
+            hash_with(|m| {
+                m.pallets.values_mut()[0]
+                    .associated_types
+                    .push(crate::AssociatedTypeMetadata {
+                        name: "Balance".into(),
+                        ty: 0,
+                        docs: vec![],
+                    })
+            }),
+            hash_with(|m| {
+                m.pallets.values_mut()[0].deprecation_info = DeprecationInfo::DeprecatedWithoutNote
+            }),
+            hash_with(|m| {
+                m.pallets.values_mut()[0]
+                    .call_deprecation_info
+                    .insert(0, DeprecationInfo::DeprecatedWithoutNote);
+            }),
+            hash_with(|m| m.extrinsic.supported_versions = vec![4, 5]),
+            hash_with(|m| {
+                m.extrinsic.signed_extensions_by_version.insert(1, vec![]);
+            }),
+        ];
+
+        // Each change leads to a different hash.
+        let count = hashes.len();
+        hashes.sort();
+        hashes.dedup();
+        assert_eq!(hashes.len(), count);
+    }
 }
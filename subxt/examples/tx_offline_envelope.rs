@@ -0,0 +1,54 @@
+#![allow(missing_docs)]
+use subxt::tx::{EnvelopeSignature, TransactionEnvelope};
+use subxt::{OfflineClient, OnlineClient, PolkadotConfig};
+use subxt_signer::sr25519::dev;
+
+#[subxt::subxt(runtime_metadata_path = "../artifacts/polkadot_metadata_small.scale")]
+pub mod polkadot {}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // On the online machine, build a partial transaction and export it as JSON:
+    let api = OnlineClient::<PolkadotConfig>::new().await?;
+    let dest = dev::bob().public_key().into();
+    let balance_transfer_tx = polkadot::tx().balances().transfer_allow_death(dest, 10_000);
+    let partial_tx = api
+        .tx()
+        .create_partial_signed(
+            &balance_transfer_tx,
+            &dev::alice().public_key().to_account_id(),
+            Default::default(),
+        )
+        .await?;
+    let envelope_json = partial_tx.to_envelope()?.to_json()?;
+
+    // On the offline machine, create an OfflineClient from details that we trust.
+    // Here we'll just borrow them from the online client to keep things simple:
+    let offline_api = OfflineClient::<PolkadotConfig>::new(
+        api.genesis_hash(),
+        api.runtime_version(),
+        api.metadata(),
+    );
+
+    // Import the envelope, check what we're signing and then sign it:
+    let envelope = TransactionEnvelope::from_json(&envelope_json)?;
+    println!(
+        "Signing: {}",
+        envelope.describe::<PolkadotConfig>(&offline_api.metadata())?
+    );
+    let signature_json = offline_api
+        .tx()
+        .sign_envelope(&envelope, &dev::alice())?
+        .to_json()?;
+
+    // Back on the online machine, import the signature and submit the transaction:
+    let signature = EnvelopeSignature::from_json(&signature_json)?;
+    api.tx()
+        .create_from_envelope(&envelope, &signature)?
+        .submit_and_watch()
+        .await?
+        .wait_for_finalized_success()
+        .await?;
+
+    Ok(())
+}
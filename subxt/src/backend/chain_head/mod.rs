@@ -703,6 +703,20 @@ impl<T: Config + Send + Sync + 'static> Backend<T> for ChainHeadBackend<T> {
         Ok(StreamOf(Box::pin(tx_stream)))
     }
 
+    async fn broadcast_transaction(&self, extrinsic: &[u8]) -> Result<Option<String>, Error> {
+        match self.methods.transaction_v1_broadcast(extrinsic).await? {
+            Some(operation_id) => Ok(Some(operation_id)),
+            None => Err(RpcError::request_rejected(
+                "transaction_v1_broadcast: the server cannot handle the request at the moment",
+            )
+            .into()),
+        }
+    }
+
+    async fn stop_broadcast(&self, operation_id: &str) -> Result<(), Error> {
+        self.methods.transaction_v1_stop(operation_id).await
+    }
+
     async fn call(
         &self,
         method: &str,
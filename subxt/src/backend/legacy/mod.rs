@@ -397,6 +397,17 @@ impl<T: Config + Send + Sync + 'static> Backend<T> for LegacyBackend<T> {
         Ok(StreamOf::new(Box::pin(sub)))
     }
 
+    async fn broadcast_transaction(&self, extrinsic: &[u8]) -> Result<Option<String>, Error> {
+        // The node will keep gossiping the transaction until it's included or dropped, and
+        // there's no way to stop this via the legacy methods.
+        self.methods.author_submit_extrinsic(extrinsic).await?;
+        Ok(None)
+    }
+
+    async fn stop_broadcast(&self, _operation_id: &str) -> Result<(), Error> {
+        Ok(())
+    }
+
     async fn call(
         &self,
         method: &str,
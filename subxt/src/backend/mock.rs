@@ -0,0 +1,388 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! An in-memory [`Backend`] for testing the things which are built on top of one.
+
+use super::{
+    sealed, Backend, BlockRef, StorageResponse, StreamOf, StreamOfResults, TransactionStatus,
+};
+use crate::{
+    client::{OnlineClient, RuntimeVersion},
+    config::{substrate::Digest, Header},
+    error::Error,
+    Config, Metadata, SubstrateConfig,
+};
+use async_trait::async_trait;
+use futures::channel::mpsc;
+use primitive_types::H256;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+type MockHeader = <SubstrateConfig as Config>::Header;
+type HeaderSender = mpsc::UnboundedSender<Result<(MockHeader, BlockRef<H256>), Error>>;
+type CallHandler = Box<dyn FnMut(Option<&[u8]>) -> Result<Vec<u8>, Error> + Send>;
+type SubmitHandler =
+    Box<dyn FnMut(&[u8]) -> Result<StreamOfResults<TransactionStatus<H256>>, Error> + Send>;
+type BroadcastHandler = Box<dyn FnMut(&[u8]) -> Result<Option<String>, Error> + Send>;
+
+/// The runtime version that [`MockBackend`] reports.
+pub(crate) const RUNTIME_VERSION: RuntimeVersion = RuntimeVersion {
+    spec_version: 1,
+    transaction_version: 1,
+};
+
+/// A [`Backend`] which serves blocks and storage that the test adds to it, and answers runtime
+/// API calls and transaction submissions with handlers that the test provides. Subscriptions to
+/// best and finalized blocks begin with the current best or finalized block, and are then
+/// given each block that the test passes to [`MockBackend::set_best()`] or
+/// [`MockBackend::finalize()`].
+pub(crate) struct MockBackend {
+    state: Mutex<State>,
+}
+
+struct State {
+    genesis_hash: H256,
+    headers: HashMap<H256, MockHeader>,
+    bodies: HashMap<H256, Vec<Vec<u8>>>,
+    storage: BTreeMap<Vec<u8>, Vec<u8>>,
+    best: H256,
+    finalized: H256,
+    best_subscribers: Vec<HeaderSender>,
+    finalized_subscribers: Vec<HeaderSender>,
+    calls: HashMap<String, CallHandler>,
+    on_submit: Option<SubmitHandler>,
+    on_broadcast: Option<BroadcastHandler>,
+    stopped_broadcasts: Vec<String>,
+}
+
+impl MockBackend {
+    /// Create a backend whose chain contains only an empty genesis block.
+    pub(crate) fn new() -> Arc<Self> {
+        let genesis = header(H256::zero(), 0, 0);
+        let genesis_hash = genesis.hash();
+        Arc::new(MockBackend {
+            state: Mutex::new(State {
+                genesis_hash,
+                headers: HashMap::from([(genesis_hash, genesis)]),
+                bodies: HashMap::from([(genesis_hash, Vec::new())]),
+                storage: BTreeMap::new(),
+                best: genesis_hash,
+                finalized: genesis_hash,
+                best_subscribers: Vec::new(),
+                finalized_subscribers: Vec::new(),
+                calls: HashMap::new(),
+                on_submit: None,
+                on_broadcast: None,
+                stopped_broadcasts: Vec::new(),
+            }),
+        })
+    }
+
+    /// Create a client which uses this backend and the `polkadot_metadata_small` metadata.
+    pub(crate) fn client(self: &Arc<Self>) -> OnlineClient<SubstrateConfig> {
+        let metadata_bytes = include_bytes!("../../../artifacts/polkadot_metadata_small.scale");
+        let metadata = subxt_core::metadata::decode_from(&metadata_bytes[..]).unwrap();
+        self.client_with_metadata(metadata)
+    }
+
+    /// Create a client which uses this backend and the given metadata.
+    pub(crate) fn client_with_metadata(
+        self: &Arc<Self>,
+        metadata: Metadata,
+    ) -> OnlineClient<SubstrateConfig> {
+        OnlineClient::from_backend_with(
+            self.genesis_hash(),
+            RUNTIME_VERSION,
+            metadata,
+            self.clone(),
+        )
+        .unwrap()
+    }
+
+    /// The hash of the genesis block.
+    pub(crate) fn genesis_hash(&self) -> H256 {
+        self.state().genesis_hash
+    }
+
+    /// Add a block containing the given extrinsics on top of the given parent, returning its
+    /// hash. Subscribers are not told about the block.
+    pub(crate) fn add_block(&self, parent_hash: H256, extrinsics: Vec<Vec<u8>>) -> H256 {
+        let mut state = self.state();
+        let number = state.headers[&parent_hash].number + 1;
+        // Blocks with the same parent and extrinsics are told apart by their state root.
+        let header = header(parent_hash, number, state.headers.len() as u64);
+        let hash = header.hash();
+        state.headers.insert(hash, header);
+        state.bodies.insert(hash, extrinsics);
+        hash
+    }
+
+    /// Make the given block the best block, telling any subscribers about it.
+    pub(crate) fn set_best(&self, hash: H256) {
+        let mut state = self.state();
+        state.best = hash;
+        let block = (state.headers[&hash].clone(), BlockRef::from_hash(hash));
+        notify(&mut state.best_subscribers, block);
+    }
+
+    /// Finalize the given block, telling any subscribers about it.
+    pub(crate) fn finalize(&self, hash: H256) {
+        let mut state = self.state();
+        state.finalized = hash;
+        let block = (state.headers[&hash].clone(), BlockRef::from_hash(hash));
+        notify(&mut state.finalized_subscribers, block);
+    }
+
+    /// Wait until there are at least the given number of subscriptions to best and to
+    /// finalized blocks, so that blocks added after this are seen by them.
+    pub(crate) async fn wait_for_subscribers(&self, best: usize, finalized: usize) {
+        loop {
+            {
+                let state = self.state();
+                let open = |subs: &[HeaderSender]| subs.iter().filter(|s| !s.is_closed()).count();
+                if open(&state.best_subscribers) >= best
+                    && open(&state.finalized_subscribers) >= finalized
+                {
+                    return;
+                }
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+
+    /// Set the value stored at the given key. Storage is the same at every block.
+    pub(crate) fn set_storage(&self, key: Vec<u8>, value: Vec<u8>) {
+        self.state().storage.insert(key, value);
+    }
+
+    /// Answer calls to the given runtime API method with the given handler, which is
+    /// given the call parameters. Calls to other methods return an error.
+    pub(crate) fn on_call(
+        &self,
+        method: &str,
+        handler: impl FnMut(Option<&[u8]>) -> Result<Vec<u8>, Error> + Send + 'static,
+    ) {
+        self.state()
+            .calls
+            .insert(method.to_owned(), Box::new(handler));
+    }
+
+    /// Answer transaction submissions with the given handler, which is given the transaction
+    /// bytes and returns the statuses to report for it.
+    pub(crate) fn on_submit(
+        &self,
+        mut handler: impl FnMut(&[u8]) -> Result<Vec<TransactionStatus<H256>>, Error> + Send + 'static,
+    ) {
+        self.on_submit_stream(move |bytes| {
+            let statuses = handler(bytes)?.into_iter().map(Ok);
+            Ok(StreamOf::new(Box::pin(futures::stream::iter(statuses))))
+        });
+    }
+
+    /// Like [`MockBackend::on_submit()`], but the handler returns a stream of statuses, so
+    /// that the test can decide when each status is reported.
+    pub(crate) fn on_submit_stream(
+        &self,
+        handler: impl FnMut(&[u8]) -> Result<StreamOfResults<TransactionStatus<H256>>, Error>
+            + Send
+            + 'static,
+    ) {
+        self.state().on_submit = Some(Box::new(handler));
+    }
+
+    /// Answer transaction broadcasts with the given handler, which is given the transaction
+    /// bytes and returns the ID of the broadcast operation, if any.
+    pub(crate) fn on_broadcast(
+        &self,
+        handler: impl FnMut(&[u8]) -> Result<Option<String>, Error> + Send + 'static,
+    ) {
+        self.state().on_broadcast = Some(Box::new(handler));
+    }
+
+    /// The IDs of the broadcast operations that have been stopped.
+    pub(crate) fn stopped_broadcasts(&self) -> Vec<String> {
+        self.state().stopped_broadcasts.clone()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("shouldn't be poisoned")
+    }
+
+    /// Subscribe to best or finalized blocks, starting with the current one.
+    fn subscribe(&self, finalized: bool) -> StreamOfResults<(MockHeader, BlockRef<H256>)> {
+        let (tx, rx) = mpsc::unbounded();
+        let mut state = self.state();
+        let current = if finalized {
+            state.finalized
+        } else {
+            state.best
+        };
+        let block = (
+            state.headers[&current].clone(),
+            BlockRef::from_hash(current),
+        );
+        tx.unbounded_send(Ok(block))
+            .expect("receiver is alive; qed");
+        if finalized {
+            state.finalized_subscribers.push(tx);
+        } else {
+            state.best_subscribers.push(tx);
+        }
+        StreamOf::new(Box::pin(rx))
+    }
+}
+
+fn header(parent_hash: H256, number: u32, state_root: u64) -> MockHeader {
+    MockHeader {
+        parent_hash,
+        number,
+        state_root: H256::from_low_u64_be(state_root),
+        extrinsics_root: H256::zero(),
+        digest: Digest::default(),
+    }
+}
+
+fn notify(subscribers: &mut Vec<HeaderSender>, block: (MockHeader, BlockRef<H256>)) {
+    subscribers.retain(|tx| tx.unbounded_send(Ok(block.clone())).is_ok());
+}
+
+impl sealed::Sealed for MockBackend {}
+
+#[async_trait]
+impl Backend<SubstrateConfig> for MockBackend {
+    async fn storage_fetch_values(
+        &self,
+        keys: Vec<Vec<u8>>,
+        _at: H256,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        let state = self.state();
+        let responses: Vec<_> = keys
+            .into_iter()
+            .filter_map(|key| {
+                let value = state.storage.get(&key)?.clone();
+                Some(Ok(StorageResponse { key, value }))
+            })
+            .collect();
+        Ok(StreamOf::new(Box::pin(futures::stream::iter(responses))))
+    }
+
+    async fn storage_fetch_descendant_keys(
+        &self,
+        key: Vec<u8>,
+        _at: H256,
+    ) -> Result<StreamOfResults<Vec<u8>>, Error> {
+        let keys: Vec<_> = self
+            .state()
+            .storage
+            .keys()
+            .filter(|k| k.starts_with(&key))
+            .map(|k| Ok(k.clone()))
+            .collect();
+        Ok(StreamOf::new(Box::pin(futures::stream::iter(keys))))
+    }
+
+    async fn storage_fetch_descendant_values(
+        &self,
+        key: Vec<u8>,
+        _at: H256,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        let responses: Vec<_> = self
+            .state()
+            .storage
+            .iter()
+            .filter(|(k, _)| k.starts_with(&key))
+            .map(|(k, v)| {
+                Ok(StorageResponse {
+                    key: k.clone(),
+                    value: v.clone(),
+                })
+            })
+            .collect();
+        Ok(StreamOf::new(Box::pin(futures::stream::iter(responses))))
+    }
+
+    async fn genesis_hash(&self) -> Result<H256, Error> {
+        Ok(self.state().genesis_hash)
+    }
+
+    async fn block_header(&self, at: H256) -> Result<Option<MockHeader>, Error> {
+        Ok(self.state().headers.get(&at).cloned())
+    }
+
+    async fn block_body(&self, at: H256) -> Result<Option<Vec<Vec<u8>>>, Error> {
+        Ok(self.state().bodies.get(&at).cloned())
+    }
+
+    async fn latest_finalized_block_ref(&self) -> Result<BlockRef<H256>, Error> {
+        Ok(BlockRef::from_hash(self.state().finalized))
+    }
+
+    async fn current_runtime_version(&self) -> Result<RuntimeVersion, Error> {
+        Ok(RUNTIME_VERSION)
+    }
+
+    async fn stream_runtime_version(&self) -> Result<StreamOfResults<RuntimeVersion>, Error> {
+        unimplemented!("runtime upgrades aren't mocked")
+    }
+
+    async fn stream_all_block_headers(
+        &self,
+    ) -> Result<StreamOfResults<(MockHeader, BlockRef<H256>)>, Error> {
+        unimplemented!("use best or finalized block subscriptions")
+    }
+
+    async fn stream_best_block_headers(
+        &self,
+    ) -> Result<StreamOfResults<(MockHeader, BlockRef<H256>)>, Error> {
+        Ok(self.subscribe(false))
+    }
+
+    async fn stream_finalized_block_headers(
+        &self,
+    ) -> Result<StreamOfResults<(MockHeader, BlockRef<H256>)>, Error> {
+        Ok(self.subscribe(true))
+    }
+
+    async fn submit_transaction(
+        &self,
+        bytes: &[u8],
+    ) -> Result<StreamOfResults<TransactionStatus<H256>>, Error> {
+        let mut state = self.state();
+        let handler = state
+            .on_submit
+            .as_mut()
+            .expect("no submit handler was given");
+        handler(bytes)
+    }
+
+    async fn broadcast_transaction(&self, bytes: &[u8]) -> Result<Option<String>, Error> {
+        let mut state = self.state();
+        let handler = state
+            .on_broadcast
+            .as_mut()
+            .expect("no broadcast handler was given");
+        handler(bytes)
+    }
+
+    async fn stop_broadcast(&self, operation_id: &str) -> Result<(), Error> {
+        self.state()
+            .stopped_broadcasts
+            .push(operation_id.to_owned());
+        Ok(())
+    }
+
+    async fn call(
+        &self,
+        method: &str,
+        call_parameters: Option<&[u8]>,
+        _at: H256,
+    ) -> Result<Vec<u8>, Error> {
+        match self.state().calls.get_mut(method) {
+            Some(handler) => handler(call_parameters),
+            None => Err(Error::Other(format!(
+                "No mock for the runtime API {method}"
+            ))),
+        }
+    }
+}
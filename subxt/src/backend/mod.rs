@@ -11,6 +11,9 @@ pub mod legacy;
 pub mod rpc;
 pub mod utils;
 
+#[cfg(test)]
+pub(crate) mod mock;
+
 use subxt_core::client::RuntimeVersion;
 
 use crate::error::Error;
@@ -95,6 +98,15 @@ pub trait Backend<T: Config>: sealed::Sealed + Send + Sync + 'static {
         bytes: &[u8],
     ) -> Result<StreamOfResults<TransactionStatus<T::Hash>>, Error>;
 
+    /// Broadcast a transaction to the network without watching its progress. This may return
+    /// an operation ID which can be given to [`Backend::stop_broadcast()`] to stop broadcasting
+    /// it. `None` is returned if the broadcast can't be stopped.
+    async fn broadcast_transaction(&self, bytes: &[u8]) -> Result<Option<String>, Error>;
+
+    /// Stop broadcasting a transaction, given the operation ID returned from
+    /// [`Backend::broadcast_transaction()`].
+    async fn stop_broadcast(&self, operation_id: &str) -> Result<(), Error>;
+
     /// Make a call to some runtime API.
     async fn call(
         &self,
@@ -770,6 +782,23 @@ mod test {
             assert_eq!(hash, response)
         }
 
+        #[tokio::test]
+        async fn broadcast_transaction_has_no_operation_id() {
+            let rpc_client = MockRpcBuilder::default()
+                .add_method("author_submitExtrinsic", |_, _, _| {
+                    Box::pin(async move {
+                        let hash = serde_json::to_string(&random_hash()).unwrap();
+                        Ok(RawValue::from_string(hash).unwrap())
+                    })
+                })
+                .build();
+
+            // The legacy methods can't stop a broadcast, so no operation ID is given.
+            let backend: LegacyBackend<Conf> = LegacyBackend::builder().build(rpc_client);
+            let operation_id = backend.broadcast_transaction(&[1, 2, 3]).await.unwrap();
+            assert_eq!(operation_id, None);
+        }
+
         #[tokio::test]
         /// This test should cover the logic of the following methods:
         /// - `stream_runtime_version`
@@ -11,6 +11,7 @@ use crate::{
     events,
     runtime_api::RuntimeApi,
     storage::Storage,
+    view_functions::ViewFunctionsApi,
 };
 
 use codec::{Decode, Encode};
@@ -102,6 +103,11 @@ where
         Ok(RuntimeApi::new(self.client.clone(), self.block_ref.clone()))
     }
 
+    /// Execute pallet view functions at this block.
+    pub fn view_functions(&self) -> ViewFunctionsApi<T, C> {
+        ViewFunctionsApi::new(self.client.clone(), self.block_ref.clone())
+    }
+
     /// Get the account nonce for a given account ID at this block.
     pub async fn account_nonce(&self, account_id: &T::AccountId) -> Result<u64, Error> {
         get_account_nonce(&self.client, account_id, self.hash()).await
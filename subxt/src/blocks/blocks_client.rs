@@ -45,7 +45,9 @@ where
     ///
     /// This call only supports blocks produced since the most recent
     /// runtime upgrade. You can attempt to retrieve older blocks,
-    /// but may run into errors attempting to work with them.
+    /// but may run into errors attempting to work with them, unless
+    /// historic metadata has been enabled on the client via
+    /// [`crate::OnlineClient::with_historic_metadata()`].
     pub fn at(
         &self,
         block_ref: impl Into<BlockRef<T::Hash>>,
@@ -79,6 +81,8 @@ where
                 None => return Err(BlockError::not_found(block_ref.hash()).into()),
             };
 
+            // Work with the block using the metadata that was in use at that block.
+            let client = client.client_at_block(block_ref.hash()).await?;
             Ok(Block::new(block_header, block_ref, client))
         }
     }
@@ -6,7 +6,7 @@ use crate::{
     blocks::block_types::{get_events, CachedEvents},
     client::{OfflineClientT, OnlineClientT},
     config::{Config, Hasher},
-    error::Error,
+    error::{DispatchError, Error},
     events,
 };
 
@@ -161,6 +161,21 @@ where
         self.inner.is_signed()
     }
 
+    /// See [`subxt_core::blocks::ExtrinsicDetails::version()`].
+    pub fn version(&self) -> u8 {
+        self.inner.version()
+    }
+
+    /// See [`subxt_core::blocks::ExtrinsicDetails::is_general()`].
+    pub fn is_general(&self) -> bool {
+        self.inner.is_general()
+    }
+
+    /// See [`subxt_core::blocks::ExtrinsicDetails::transaction_extensions_version()`].
+    pub fn transaction_extensions_version(&self) -> Option<u8> {
+        self.inner.transaction_extensions_version()
+    }
+
     /// See [`subxt_core::blocks::ExtrinsicDetails::index()`].
     pub fn index(&self) -> u32 {
         self.inner.index()
@@ -345,6 +360,26 @@ impl<T: Config> ExtrinsicEvents<T> {
         self.find::<Ev>().last().transpose()
     }
 
+    /// If this transaction contained a `Utility.batch` call (for instance one constructed via
+    /// [`crate::tx::TxClient::batch()`]) which was interrupted by one of the batched calls failing,
+    /// return a [`BatchInterrupted`] containing the index of that call and the error it produced.
+    /// Returns `Ok(None)` if no `Utility.BatchInterrupted` event was found.
+    pub fn batch_interrupted(&self) -> Result<Option<BatchInterrupted>, Error> {
+        for ev in self.iter() {
+            let ev = ev?;
+            if ev.pallet_name() == "Utility" && ev.variant_name() == "BatchInterrupted" {
+                let mut bytes = ev.field_bytes();
+                let index = <u32 as codec::Decode>::decode(&mut bytes)?;
+                let error = DispatchError::decode_from(bytes, self.events.metadata().clone())?;
+                return Ok(Some(BatchInterrupted {
+                    index: index as usize,
+                    error,
+                }));
+            }
+        }
+        Ok(None)
+    }
+
     /// Find an event in those associated with this transaction. Returns true if it was found.
     ///
     /// This works in the same way that [`events::Events::has()`] does, with the
@@ -353,3 +388,54 @@ impl<T: Config> ExtrinsicEvents<T> {
         Ok(self.find::<Ev>().next().transpose()?.is_some())
     }
 }
+
+/// Details of a `Utility.BatchInterrupted` event. See [`ExtrinsicEvents::batch_interrupted()`].
+#[derive(Debug)]
+pub struct BatchInterrupted {
+    /// The index of the call in the batch which failed. When the batch was constructed
+    /// via [`crate::tx::TxClient::batch()`], this is the index of the corresponding payload.
+    pub index: usize,
+    /// The error that the call failed with.
+    pub error: DispatchError,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::events::test_utils::{events, metadata, module_error};
+    use crate::SubstrateConfig;
+    use scale_value::Value;
+
+    #[test]
+    fn batch_interrupted_decodes_index_and_module_error() {
+        let metadata = metadata();
+        let interrupted = Value::unnamed_variant(
+            "Utility",
+            [Value::named_variant(
+                "BatchInterrupted",
+                [
+                    ("index", Value::u128(2)),
+                    (
+                        "error",
+                        module_error(&metadata, "Balances", "InsufficientBalance"),
+                    ),
+                ],
+            )],
+        );
+        let events = events::<SubstrateConfig>(&metadata, vec![(1, interrupted)]);
+
+        // The event belongs to the extrinsic at index 1, and not to any other.
+        let other = ExtrinsicEvents::new(Default::default(), 0, events.clone());
+        assert!(other.batch_interrupted().unwrap().is_none());
+
+        let ext_events = ExtrinsicEvents::new(Default::default(), 1, events);
+        let interrupted = ext_events.batch_interrupted().unwrap().unwrap();
+        assert_eq!(interrupted.index, 2);
+        let DispatchError::Module(module_error) = interrupted.error else {
+            panic!("expected a module error, got {:?}", interrupted.error);
+        };
+        let details = module_error.details().unwrap();
+        assert_eq!(details.pallet.name(), "Balances");
+        assert_eq!(details.variant.name, "InsufficientBalance");
+    }
+}
@@ -223,6 +223,19 @@
 #![doc = include_str!("../../../examples/tx_partial.rs")]
 //! ```
 //!
+//! ### Signing transactions on an air-gapped machine
+//!
+//! If the keys live on a machine that isn't connected to the network, the signer payload alone
+//! doesn't carry enough context for that machine to know what it's signing. Instead, a
+//! [`crate::tx::PartialExtrinsic`] can be exported into a [`crate::tx::TransactionEnvelope`], which
+//! also contains the genesis hash, runtime versions, metadata hash and a human readable description
+//! of the call. This can be serialized to JSON or bytes, verified and signed offline using an
+//! [`crate::OfflineClient`], and the resulting [`crate::tx::EnvelopeSignature`] handed back:
+//!
+//! ```rust,ignore
+#![doc = include_str!("../../../examples/tx_offline_envelope.rs")]
+//! ```
+//!
 //! Take a look at the API docs for [`crate::tx::TxProgress`], [`crate::tx::TxStatus`] and
 //! [`crate::tx::TxInBlock`] for more options.
 //!
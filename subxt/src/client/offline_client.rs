@@ -5,7 +5,8 @@
 use crate::custom_values::CustomValuesClient;
 use crate::{
     blocks::BlocksClient, constants::ConstantsClient, events::EventsClient,
-    runtime_api::RuntimeApiClient, storage::StorageClient, tx::TxClient, Config, Metadata,
+    runtime_api::RuntimeApiClient, storage::StorageClient, tx::TxClient,
+    view_functions::ViewFunctionsClient, Config, Metadata,
 };
 
 use derive_where::derive_where;
@@ -63,6 +64,11 @@ pub trait OfflineClientT<T: Config>: Clone + Send + Sync + 'static {
         RuntimeApiClient::new(self.clone())
     }
 
+    /// Work with pallet view functions.
+    fn view_functions(&self) -> ViewFunctionsClient<T, Self> {
+        ViewFunctionsClient::new(self.clone())
+    }
+
     /// Work this custom types.
     fn custom_values(&self) -> CustomValuesClient<T, Self> {
         CustomValuesClient::new(self.clone())
@@ -13,18 +13,34 @@ use crate::{
     runtime_api::RuntimeApiClient,
     storage::StorageClient,
     tx::TxClient,
+    view_functions::ViewFunctionsClient,
     Config, Metadata,
 };
+use codec::Decode;
 use derive_where::derive_where;
-use futures::future;
+use futures::future::{self, BoxFuture};
+use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use subxt_core::client::{ClientState, RuntimeVersion};
+use subxt_metadata::{LegacyTypeBundle, LegacyTypes};
 
 /// A trait representing a client that can perform
 /// online actions.
 pub trait OnlineClientT<T: Config>: OfflineClientT<T> {
     /// Return a backend that can be used to communicate with a node.
     fn backend(&self) -> &dyn Backend<T>;
+
+    /// Return a client which is configured to work with the given block. By default this is
+    /// just a copy of the current client, but clients may choose to return a client configured
+    /// with the metadata and runtime version that were in use at that block instead.
+    ///
+    /// This is used when we obtain a [`crate::blocks::Block`] or [`crate::events::Events`] at some
+    /// specific block, so that they are decoded using the correct metadata.
+    fn client_at_block(&self, block_hash: T::Hash) -> BoxFuture<'static, Result<Self, Error>> {
+        let _ = block_hash;
+        let client = self.clone();
+        Box::pin(async move { Ok(client) })
+    }
 }
 
 /// A client that can be used to perform API calls (that is, either those
@@ -33,8 +49,13 @@ pub trait OnlineClientT<T: Config>: OfflineClientT<T> {
 pub struct OnlineClient<T: Config> {
     inner: Arc<RwLock<Inner<T>>>,
     backend: Arc<dyn Backend<T>>,
+    historic_metadata: Option<HistoricMetadata>,
+    legacy_types: Option<Arc<LegacyTypeBundle>>,
 }
 
+/// Metadata that we've fetched for historic blocks, keyed by spec version.
+type HistoricMetadata = Arc<RwLock<HashMap<u32, Metadata>>>;
+
 #[derive_where(Debug)]
 struct Inner<T: Config> {
     genesis_hash: T::Hash,
@@ -153,9 +174,44 @@ impl<T: Config> OnlineClient<T> {
                 metadata: metadata.into(),
             })),
             backend,
+            historic_metadata: None,
+            legacy_types: None,
         })
     }
 
+    /// Opt in to decoding historic blocks using the metadata that was in use at the time.
+    ///
+    /// By default, a client uses a single [`Metadata`] for everything, and so blocks and events
+    /// from before a runtime upgrade may fail to decode, or be decoded incorrectly. When this is
+    /// enabled, [`BlocksClient::at`] and [`EventsClient::at`] will look up the spec version
+    /// of the runtime at the given block, fetch the metadata for that runtime if it differs from
+    /// the one this client uses, and then use it to decode everything at that block. Metadata is
+    /// cached by spec version, so it is only fetched once for each runtime.
+    ///
+    /// This costs an additional runtime API call each time a block is obtained via `at`, and so
+    /// is mostly useful for things like indexers which need to sync blocks from genesis.
+    ///
+    /// # Warning
+    ///
+    /// Metadata can only be fetched for runtimes which expose metadata V14 or newer. See
+    /// [`OnlineClient::with_legacy_types()`] for working with runtimes older than this.
+    pub fn with_historic_metadata(mut self) -> Self {
+        if self.historic_metadata.is_none() {
+            self.historic_metadata = Some(Default::default());
+        }
+        self
+    }
+
+    /// Like [`OnlineClient::with_historic_metadata()`], but additionally able to decode blocks
+    /// from runtimes which predate V14 metadata. Such metadata describes types by name only,
+    /// and so the given type definitions are used to make sense of it, picking whichever
+    /// definitions apply to the spec version of the runtime at each block.
+    pub fn with_legacy_types(self, types: impl Into<LegacyTypeBundle>) -> Self {
+        let mut client = self.with_historic_metadata();
+        client.legacy_types = Some(Arc::new(types.into()));
+        client
+    }
+
     /// Fetch the metadata from substrate using the runtime API.
     async fn fetch_metadata(
         backend: &dyn Backend<T>,
@@ -186,15 +242,14 @@ impl<T: Config> OnlineClient<T> {
         backend: &dyn Backend<T>,
         block_hash: T::Hash,
     ) -> Result<Metadata, Error> {
-        // This is the latest stable metadata that subxt can utilize.
-        const V15_METADATA_VERSION: u32 = 15;
+        // These are the stable metadata versions that subxt can utilize, newest first.
+        const METADATA_VERSIONS: [u32; 2] = [16, 15];
 
-        // Try to fetch the metadata version.
-        if let Ok(bytes) = backend
-            .metadata_at_version(V15_METADATA_VERSION, block_hash)
-            .await
-        {
-            return Ok(bytes);
+        // Try to fetch each metadata version in turn.
+        for version in METADATA_VERSIONS {
+            if let Ok(bytes) = backend.metadata_at_version(version, block_hash).await {
+                return Ok(bytes);
+            }
         }
 
         // If that fails, fetch the metadata V14 using the old API.
@@ -349,6 +404,11 @@ impl<T: Config> OnlineClient<T> {
     pub fn runtime_api(&self) -> RuntimeApiClient<T, Self> {
         <Self as OfflineClientT<T>>::runtime_api(self)
     }
+
+    /// Work with pallet view functions.
+    pub fn view_functions(&self) -> ViewFunctionsClient<T, Self> {
+        <Self as OfflineClientT<T>>::view_functions(self)
+    }
 }
 
 impl<T: Config> OfflineClientT<T> for OnlineClient<T> {
@@ -376,6 +436,127 @@ impl<T: Config> OnlineClientT<T> for OnlineClient<T> {
     fn backend(&self) -> &dyn Backend<T> {
         &*self.backend
     }
+
+    fn client_at_block(&self, block_hash: T::Hash) -> BoxFuture<'static, Result<Self, Error>> {
+        let client = self.clone();
+        Box::pin(async move {
+            let Some(historic_metadata) = client.historic_metadata.clone() else {
+                return Ok(client);
+            };
+
+            let core_version = core_version_at(&*client.backend, block_hash).await?;
+            let runtime_version = core_version.runtime_version;
+            if runtime_version.spec_version == client.runtime_version().spec_version {
+                return Ok(client);
+            }
+
+            let cached = historic_metadata
+                .read()
+                .expect("shouldn't be poisoned")
+                .get(&runtime_version.spec_version)
+                .cloned();
+            let metadata = match cached {
+                Some(metadata) => metadata,
+                None => {
+                    let metadata = match OnlineClient::fetch_metadata(&*client.backend, block_hash)
+                        .await
+                    {
+                        Ok(metadata) => metadata,
+                        // Runtimes which can't hand back metadata at a given version may
+                        // predate V14 metadata, in which case we can try to decode their
+                        // metadata using any legacy type definitions we were given.
+                        Err(e) => match &client.legacy_types {
+                            Some(types) if !core_version.has_metadata_at_version() => {
+                                let types = types.for_spec_version(runtime_version.spec_version);
+                                legacy_metadata_at(&*client.backend, block_hash, &types).await?
+                            }
+                            _ => return Err(e),
+                        },
+                    };
+                    historic_metadata
+                        .write()
+                        .expect("shouldn't be poisoned")
+                        .insert(runtime_version.spec_version, metadata.clone());
+                    metadata
+                }
+            };
+
+            Ok(OnlineClient {
+                inner: Arc::new(RwLock::new(Inner {
+                    genesis_hash: client.genesis_hash(),
+                    runtime_version,
+                    metadata,
+                })),
+                backend: client.backend,
+                historic_metadata: Some(historic_metadata),
+                legacy_types: client.legacy_types,
+            })
+        })
+    }
+}
+
+/// Fetch and decode legacy (pre-V14) metadata at the given block.
+async fn legacy_metadata_at<T: Config>(
+    backend: &dyn Backend<T>,
+    block_hash: T::Hash,
+    types: &LegacyTypes,
+) -> Result<Metadata, Error> {
+    let opaque: frame_metadata::OpaqueMetadata = backend
+        .call_decoding("Metadata_metadata", None, block_hash)
+        .await?;
+    let metadata = subxt_metadata::Metadata::decode_legacy(&opaque.0, types)?;
+    Ok(metadata.into())
+}
+
+/// The parts of the version of a runtime, as returned from its `Core_version` runtime API,
+/// that we make use of.
+struct CoreVersion {
+    runtime_version: RuntimeVersion,
+    /// The runtime APIs that the runtime exposes, as pairs of API ID and API version.
+    apis: Vec<([u8; 8], u32)>,
+}
+
+impl CoreVersion {
+    /// Does the runtime expose the `Metadata_metadata_at_version` runtime API? This was added
+    /// in version 2 of the `Metadata` API, and so runtimes without it may predate V14 metadata.
+    fn has_metadata_at_version(&self) -> bool {
+        // The ID of a runtime API is the blake2_64 hash of its name; here, "Metadata".
+        const METADATA_API_ID: [u8; 8] = [0x37, 0xe3, 0x97, 0xfc, 0x7c, 0x91, 0xf5, 0xe4];
+        self.apis
+            .iter()
+            .any(|(id, version)| *id == METADATA_API_ID && *version >= 2)
+    }
+}
+
+/// Fetch the version of the runtime in use at the given block via the `Core_version` runtime API.
+async fn core_version_at<T: Config>(
+    backend: &dyn Backend<T>,
+    block_hash: T::Hash,
+) -> Result<CoreVersion, Error> {
+    // The leading fields of the `RuntimeVersion` returned from the runtime. The
+    // `transaction_version` follows these, but is absent in very old runtimes.
+    #[derive(Decode)]
+    struct EncodedCoreVersion {
+        _spec_name: String,
+        _impl_name: String,
+        _authoring_version: u32,
+        spec_version: u32,
+        _impl_version: u32,
+        apis: Vec<([u8; 8], u32)>,
+    }
+
+    let bytes = backend.call("Core_version", None, block_hash).await?;
+    let cursor = &mut &*bytes;
+    let version = EncodedCoreVersion::decode(cursor)?;
+    let transaction_version = u32::decode(cursor).unwrap_or_default();
+
+    Ok(CoreVersion {
+        runtime_version: RuntimeVersion {
+            spec_version: version.spec_version,
+            transaction_version,
+        },
+        apis: version.apis,
+    })
 }
 
 /// Client wrapper for performing runtime updates. See [`OnlineClient::updater()`]
@@ -552,3 +733,154 @@ async fn wait_runtime_upgrade_in_finalized_block<T: Config>(
 
     Some(Ok(block_ref))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::mock::{MockBackend, RUNTIME_VERSION};
+    use codec::Encode;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// The ID of the `Metadata` runtime API, which gained `Metadata_metadata_at_version` in
+    /// version 2.
+    const METADATA_API_ID: [u8; 8] = [0x37, 0xe3, 0x97, 0xfc, 0x7c, 0x91, 0xf5, 0xe4];
+
+    /// The encoded `RuntimeVersion` returned from the `Core_version` runtime API.
+    fn core_version(
+        spec_version: u32,
+        transaction_version: Option<u32>,
+        metadata_api_version: u32,
+    ) -> Vec<u8> {
+        let apis = vec![([1u8; 8], 2u32), (METADATA_API_ID, metadata_api_version)];
+        let mut bytes = ("test-chain", "test-node", 1u32, spec_version, 1u32, apis).encode();
+        if let Some(transaction_version) = transaction_version {
+            transaction_version.encode_to(&mut bytes);
+        }
+        bytes
+    }
+
+    /// The encoded response from `Metadata_metadata` for a runtime with V12 metadata, which
+    /// contains a single "System" pallet with one event.
+    fn legacy_metadata() -> Vec<u8> {
+        let events = vec![(
+            "ExtrinsicSuccess",
+            vec!["DispatchInfo"],
+            Vec::<String>::new(),
+        )];
+        // Name, storage, calls, events, constants, errors and index.
+        let system = (
+            "System",
+            None::<()>,
+            None::<()>,
+            Some(events),
+            Vec::<()>::new(),
+            Vec::<()>::new(),
+            0u8,
+        );
+
+        let mut bytes = b"meta".to_vec();
+        bytes.push(12);
+        vec![system].encode_to(&mut bytes);
+        // The extrinsic version and signed extensions.
+        (4u8, Vec::<String>::new()).encode_to(&mut bytes);
+        frame_metadata::OpaqueMetadata(bytes).encode()
+    }
+
+    /// Mock a runtime which hands back [`legacy_metadata()`] and reports a spec version of 0,
+    /// along with the given version of the `Metadata` runtime API.
+    fn mock_legacy_runtime(backend: &MockBackend, metadata_api_version: u32) {
+        backend.on_call("Core_version", move |_| {
+            Ok(core_version(0, Some(1), metadata_api_version))
+        });
+        backend.on_call("Metadata_metadata_at_version", |_| {
+            Err(Error::Other(
+                "Metadata_metadata_at_version not found".into(),
+            ))
+        });
+        backend.on_call("Metadata_metadata", |_| Ok(legacy_metadata()));
+    }
+
+    #[tokio::test]
+    async fn core_version_at_decodes_transaction_version() {
+        let backend = MockBackend::new();
+        backend.on_call("Core_version", |_| Ok(core_version(7, Some(3), 2)));
+
+        let version = core_version_at(&*backend, backend.genesis_hash())
+            .await
+            .unwrap();
+        assert_eq!(version.runtime_version.spec_version, 7);
+        assert_eq!(version.runtime_version.transaction_version, 3);
+        assert!(version.has_metadata_at_version());
+    }
+
+    #[tokio::test]
+    async fn core_version_at_allows_missing_transaction_version() {
+        let backend = MockBackend::new();
+        backend.on_call("Core_version", |_| Ok(core_version(7, None, 1)));
+
+        let version = core_version_at(&*backend, backend.genesis_hash())
+            .await
+            .unwrap();
+        assert_eq!(version.runtime_version.spec_version, 7);
+        assert_eq!(version.runtime_version.transaction_version, 0);
+        assert!(!version.has_metadata_at_version());
+    }
+
+    #[tokio::test]
+    async fn historic_metadata_is_cached_by_spec_version() {
+        let backend = MockBackend::new();
+        let spec_version = Arc::new(RwLock::new(RUNTIME_VERSION.spec_version));
+        let metadata_fetches = Arc::new(AtomicUsize::new(0));
+        {
+            let spec_version = spec_version.clone();
+            backend.on_call("Core_version", move |_| {
+                Ok(core_version(*spec_version.read().unwrap(), Some(1), 2))
+            });
+            let metadata_fetches = metadata_fetches.clone();
+            backend.on_call("Metadata_metadata_at_version", move |_| {
+                metadata_fetches.fetch_add(1, Ordering::SeqCst);
+                let bytes = include_bytes!("../../../artifacts/polkadot_metadata_small.scale");
+                Ok(Some(frame_metadata::OpaqueMetadata(bytes.to_vec())).encode())
+            });
+        }
+        let client = backend.client().with_historic_metadata();
+        let block_hash = backend.genesis_hash();
+
+        // Blocks from the current runtime use the client's own metadata.
+        client.client_at_block(block_hash).await.unwrap();
+        assert_eq!(metadata_fetches.load(Ordering::SeqCst), 0);
+
+        // Metadata for an older runtime is fetched once and then reused.
+        *spec_version.write().unwrap() = 0;
+        let historic = client.client_at_block(block_hash).await.unwrap();
+        assert_eq!(historic.runtime_version().spec_version, 0);
+        client.client_at_block(block_hash).await.unwrap();
+        assert_eq!(metadata_fetches.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn legacy_metadata_is_used_without_metadata_at_version() {
+        let backend = MockBackend::new();
+        mock_legacy_runtime(&backend, 1);
+        let client = backend.client().with_legacy_types(LegacyTypes::substrate());
+
+        let historic = client
+            .client_at_block(backend.genesis_hash())
+            .await
+            .unwrap();
+        let metadata = historic.metadata();
+        let system = metadata.pallet_by_name("System").unwrap();
+        assert_eq!(system.event_variants().unwrap()[0].name, "ExtrinsicSuccess");
+    }
+
+    #[tokio::test]
+    async fn legacy_metadata_is_not_used_with_metadata_at_version() {
+        let backend = MockBackend::new();
+        mock_legacy_runtime(&backend, 2);
+        let client = backend.client().with_legacy_types(LegacyTypes::substrate());
+
+        // The error from fetching the metadata is handed back as is.
+        let result = client.client_at_block(backend.genesis_hash()).await;
+        assert!(matches!(result, Err(Error::Codec(_))));
+    }
+}
@@ -11,7 +11,7 @@ use scale_decode::{visitor::DecodeAsTypeResult, DecodeAsType, TypeResolver};
 
 use std::{borrow::Cow, marker::PhantomData};
 
-use super::{Error, MetadataError};
+use super::{Error, MetadataError, StaticError};
 
 /// An error dispatching a transaction.
 #[derive(Debug, thiserror::Error, PartialEq, Eq)]
@@ -216,6 +216,23 @@ impl ModuleError {
 
         Ok(decoded)
     }
+
+    /// Attempts to decode the ModuleError into the error type of a specific pallet, which is
+    /// exposed in the codegen as `pallet_name::Error`. Returns `None` if the error is not from
+    /// the pallet that `E` belongs to, or cannot be decoded into `E`.
+    pub fn as_error<E: StaticError>(&self) -> Option<E> {
+        let pallet = self.metadata.pallet_by_index(self.pallet_index())?;
+        if pallet.name() != E::PALLET {
+            return None;
+        }
+        // The first byte is the pallet index, and the rest are the encoded pallet error.
+        E::decode_as_type(
+            &mut &self.bytes[1..],
+            pallet.error_ty_id()?,
+            self.metadata.types(),
+        )
+        .ok()
+    }
 }
 
 /// Details about the module error.
@@ -227,6 +244,16 @@ pub struct ModuleErrorDetails<'a> {
 }
 
 impl DispatchError {
+    /// Returns true if this is an error from the pallet that `E` belongs to. `E` is the
+    /// error type of some pallet, which is exposed in the codegen as `pallet_name::Error`.
+    /// Use [`ModuleError::as_error()`] to obtain the error itself.
+    pub fn is<E: StaticError>(&self) -> bool {
+        match self {
+            DispatchError::Module(module_error) => module_error.as_error::<E>().is_some(),
+            _ => false,
+        }
+    }
+
     /// Attempt to decode a runtime [`DispatchError`].
     #[doc(hidden)]
     pub fn decode_from<'a>(
@@ -21,7 +21,11 @@ pub use dispatch_error::{
 pub use crate::metadata::Metadata;
 pub use scale_decode::Error as DecodeError;
 pub use scale_encode::Error as EncodeError;
-pub use subxt_core::error::{ExtrinsicParamsError, MetadataError, StorageAddressError};
+pub use subxt_core::error::{
+    EnvelopeError, ExtrinsicParamsError, MetadataError, StaticError, StorageAddressError,
+    ViewFunctionError,
+};
+pub use subxt_metadata::LegacyError as LegacyMetadataError;
 pub use subxt_metadata::TryFromError as MetadataTryFromError;
 
 /// The underlying error enum, generic over the type held by the `Runtime`
@@ -48,6 +52,9 @@ pub enum Error {
     /// Error decoding metadata.
     #[error("Metadata Decoding error: {0}")]
     MetadataDecoding(#[from] MetadataTryFromError),
+    /// Error decoding legacy metadata.
+    #[error("Legacy metadata decoding error: {0}")]
+    LegacyMetadataDecoding(#[from] LegacyMetadataError),
     /// Runtime error.
     #[error("Runtime error: {0}")]
     Runtime(#[from] DispatchError),
@@ -69,6 +76,15 @@ pub enum Error {
     /// An error encoding a storage address.
     #[error("Error encoding storage address: {0}")]
     StorageAddress(#[from] StorageAddressError),
+    /// An error working with a transaction envelope.
+    #[error("Transaction envelope error: {0}")]
+    Envelope(#[from] EnvelopeError),
+    /// An error executing a pallet view function.
+    #[error("View function error: {0}")]
+    ViewFunction(#[from] ViewFunctionError),
+    /// Multisig error.
+    #[error("Multisig error: {0}")]
+    Multisig(#[from] MultisigError),
     /// The bytes representing an error that we were unable to decode.
     #[error("An error occurred but it could not be decoded: {0:?}")]
     Unknown(Vec<u8>),
@@ -92,6 +108,8 @@ impl From<CoreError> for Error {
             CoreError::Encode(e) => Error::Encode(e),
             CoreError::ExtrinsicParams(e) => Error::ExtrinsicParams(e),
             CoreError::Block(e) => Error::Block(e.into()),
+            CoreError::Envelope(e) => Error::Envelope(e),
+            CoreError::ViewFunction(e) => Error::ViewFunction(e),
         }
     }
 }
@@ -130,6 +148,17 @@ impl Error {
     pub fn is_rejected(&self) -> bool {
         matches!(self, Error::Rpc(RpcError::RequestRejected(_)))
     }
+
+    /// If this is an error returned from some pallet (for instance from
+    /// [`crate::tx::TxInBlock::wait_for_success()`]), attempt to decode it into the error type
+    /// of that pallet, which is exposed in the codegen as `pallet_name::Error`. Returns `None`
+    /// if this is not an error from the pallet that `E` belongs to.
+    pub fn as_pallet_error<E: StaticError>(&self) -> Option<E> {
+        match self {
+            Error::Runtime(DispatchError::Module(module_error)) => module_error.as_error(),
+            _ => None,
+        }
+    }
 }
 
 /// An RPC error. Since we are generic over the RPC client that is used,
@@ -218,6 +247,38 @@ impl BlockError {
     }
 }
 
+/// Something went wrong working with a [`crate::multisig::Multisig`].
+#[derive(Clone, Debug, Eq, thiserror::Error, PartialEq)]
+#[non_exhaustive]
+pub enum MultisigError {
+    /// A multisig account needs at least two distinct signatories.
+    #[error("A multisig account needs at least two distinct signatories, but {0} were given")]
+    NotEnoughSignatories(usize),
+    /// The threshold must be at least 1 and no greater than the number of signatories.
+    #[error("The threshold {threshold} is not valid for {num_signatories} signatories")]
+    InvalidThreshold {
+        /// The threshold that was given.
+        threshold: u16,
+        /// The number of distinct signatories.
+        num_signatories: usize,
+    },
+    /// Multisig accounts can only be derived for 32 byte account IDs.
+    #[error("Multisig accounts require 32 byte account IDs, but an account ID was {0} bytes")]
+    InvalidAccountIdLength(usize),
+    /// The account is not one of the signatories of the multisig.
+    #[error("The account is not a signatory of this multisig")]
+    NotASignatory,
+    /// The account has already approved the call.
+    #[error("The account has already approved this call")]
+    AlreadyApproved,
+    /// There is no pending multisig operation for the given call hash.
+    #[error("No pending multisig operation found for call hash 0x{}", hex::encode(.0))]
+    NotPending([u8; 32]),
+    /// A `Multisig.Multisigs` storage key was too short to contain a call hash.
+    #[error("The multisig storage key 0x{} does not end with a call hash", hex::encode(.0))]
+    InvalidStorageKey(Vec<u8>),
+}
+
 /// Transaction error.
 #[derive(Clone, Debug, Eq, thiserror::Error, PartialEq)]
 #[non_exhaustive]
@@ -235,4 +296,12 @@ pub enum TransactionError {
     /// The transaction was dropped.
     #[error("The transaction was dropped: {0}")]
     Dropped(String),
+    /// The transaction did not reach the given stage before the deadline given to
+    /// [`crate::tx::TxProgress`] passed.
+    #[error("The transaction was not {0} before the deadline passed")]
+    Timeout(crate::tx::TxStage),
+    /// The [`crate::tx::TxQueue`] can no longer accept transactions, because its driver
+    /// has been dropped.
+    #[error("The transaction queue is no longer running")]
+    QueueClosed,
 }
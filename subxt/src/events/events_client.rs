@@ -36,7 +36,9 @@ where
     ///
     /// This call only supports blocks produced since the most recent
     /// runtime upgrade. You can attempt to retrieve events from older blocks,
-    /// but may run into errors attempting to work with them.
+    /// but may run into errors attempting to work with them, unless
+    /// historic metadata has been enabled on the client via
+    /// [`crate::OnlineClient::with_historic_metadata()`].
     pub fn at(
         &self,
         block_ref: impl Into<BlockRef<T::Hash>>,
@@ -64,6 +66,8 @@ where
                 None => client.backend().latest_finalized_block_ref().await?,
             };
 
+            // Decode the events using the metadata that was in use at that block.
+            let client = client.client_at_block(block_ref.hash()).await?;
             let event_bytes = get_event_bytes(client.backend(), block_ref.hash()).await?;
             Ok(Events::decode_from(event_bytes, client.metadata()))
         }
@@ -26,3 +26,67 @@ where
     let event_bytes = events_client::get_event_bytes(client.backend(), block_hash).await?;
     Ok(Events::<T>::decode_from(event_bytes, metadata))
 }
+
+/// Event related test utilities used outside this module.
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use super::*;
+    use codec::Encode;
+    use scale_encode::EncodeAsType;
+    use scale_value::Value;
+
+    /// The full Polkadot metadata, which (unlike the metadata used by
+    /// [`crate::backend::mock::MockBackend`]) knows about the events of every pallet.
+    pub fn metadata() -> Metadata {
+        let metadata_bytes = include_bytes!("../../../artifacts/polkadot_metadata_full.scale");
+        subxt_core::metadata::decode_from(&metadata_bytes[..]).unwrap()
+    }
+
+    /// Encode the given events as they would be stored in `System.Events`. Each event is given
+    /// as the index of the extrinsic which emitted it, and a value of the outer event enum, like
+    /// `Utility(BatchInterrupted(..))`.
+    pub fn event_bytes<T: Config>(metadata: &Metadata, events: Vec<(u32, Value)>) -> Vec<u8> {
+        let mut bytes = codec::Compact(events.len() as u32).encode();
+        for (extrinsic_index, event) in events {
+            Phase::ApplyExtrinsic(extrinsic_index).encode_to(&mut bytes);
+            event
+                .encode_as_type_to(
+                    metadata.outer_enums().event_enum_ty(),
+                    metadata.types(),
+                    &mut bytes,
+                )
+                .expect("event should encode");
+            Vec::<T::Hash>::new().encode_to(&mut bytes);
+        }
+        bytes
+    }
+
+    /// Encode the given events into the events of a block. See [`event_bytes()`].
+    pub fn events<T: Config>(metadata: &Metadata, events: Vec<(u32, Value)>) -> Events<T> {
+        Events::decode_from(event_bytes::<T>(metadata, events), metadata.clone())
+    }
+
+    /// A `DispatchError::Module` value for the named error of the named pallet.
+    pub fn module_error(metadata: &Metadata, pallet_name: &str, error_name: &str) -> Value {
+        let pallet = metadata.pallet_by_name(pallet_name).expect("pallet exists");
+        let error = pallet
+            .error_variants()
+            .and_then(|errors| errors.iter().find(|e| e.name == error_name))
+            .expect("error exists");
+        Value::unnamed_variant(
+            "Module",
+            [Value::named_composite([
+                ("index", Value::u128(pallet.index().into())),
+                (
+                    "error",
+                    Value::unnamed_composite([
+                        Value::u128(error.index.into()),
+                        Value::u128(0),
+                        Value::u128(0),
+                        Value::u128(0),
+                    ]),
+                ),
+            ])],
+        )
+    }
+}
@@ -49,10 +49,12 @@ pub mod constants;
 pub mod custom_values;
 pub mod error;
 pub mod events;
+pub mod multisig;
 pub mod runtime_api;
 pub mod storage;
 pub mod tx;
 pub mod utils;
+pub mod view_functions;
 
 /// This module provides a [`Config`] type, which is used to define various
 /// types that are important in order to speak to a particular chain.
@@ -62,16 +64,18 @@ pub mod utils;
 pub mod config {
     pub use subxt_core::config::{
         polkadot, signed_extensions, substrate, BlockHash, Config, DefaultExtrinsicParams,
-        DefaultExtrinsicParamsBuilder, ExtrinsicParams, ExtrinsicParamsEncoder, Hasher, Header,
-        PolkadotConfig, PolkadotExtrinsicParams, RefineParams, RefineParamsData, SignedExtension,
-        SubstrateConfig, SubstrateExtrinsicParams,
+        DefaultExtrinsicParamsBuilder, DynamicDefaultExtrinsicParams, ExtrinsicParams,
+        ExtrinsicParamsEncoder, Hasher, Header, PolkadotConfig, PolkadotExtrinsicParams,
+        RefineParams, RefineParamsData, SignedExtension, SubstrateConfig, SubstrateExtrinsicParams,
     };
     pub use subxt_core::error::ExtrinsicParamsError;
 }
 
 /// Types representing the metadata obtained from a node.
 pub mod metadata {
-    pub use subxt_core::metadata::{DecodeWithMetadata, EncodeWithMetadata, Metadata};
+    pub use subxt_core::metadata::{
+        CompatibilityReport, DecodeWithMetadata, EncodeWithMetadata, Metadata,
+    };
     // Expose metadata types under a sub module in case somebody needs to reference them:
     pub use subxt_metadata as types;
 }
@@ -79,7 +83,8 @@ pub mod metadata {
 /// Submit dynamic transactions.
 pub mod dynamic {
     pub use subxt_core::dynamic::{
-        constant, runtime_api_call, storage, tx, At, DecodedValue, DecodedValueThunk, Value,
+        constant, runtime_api_call, storage, tx, view_function_call, At, DecodedValue,
+        DecodedValueThunk, Value,
     };
 }
 
@@ -321,6 +326,39 @@ pub mod ext {
 /// feature in conjunction with `runtime_types_only` (or manually specify a bunch of defaults to make codegen work properly when
 /// generating the subxt interfaces).
 ///
+/// ## `compatibility_report`
+///
+/// This attribute generates a `compatibility_report` function alongside `is_codegen_valid_for`, which compares the
+/// metadata of a live node with the metadata that the code was generated from, and reports every item that has been
+/// changed or removed. To do this, the metadata is embedded into the generated code, so this is off by default. The
+/// generated function relies on the standard library, so it can't be used in `no_std` crates.
+///
+/// ```rust,no_run
+/// #[subxt::subxt(
+///     runtime_metadata_path = "../artifacts/polkadot_metadata_full.scale",
+///     compatibility_report
+/// )]
+/// mod polkadot {}
+/// ```
+///
+/// ## `allow_item` and `deny_item`
+///
+/// By default, the macro generates code for every pallet and runtime API in the metadata. These attributes restrict code
+/// generation to the given pallets or runtime APIs (like `"Balances"`), or to individual calls, events, storage entries,
+/// constants, view functions or runtime API methods within them (like `"Balances::transfer_keep_alive"` or `"System::Account"`).
+/// Both can be given any number of times, and anything denied is removed even if it's also allowed. Runtime types that are
+/// no longer needed by the remaining items are not generated, which can save a lot of compile time.
+///
+/// ```rust,no_run
+/// #[subxt::subxt(
+///     runtime_metadata_path = "../artifacts/polkadot_metadata_full.scale",
+///     allow_item = "Balances",
+///     allow_item = "System::Account",
+///     deny_item = "Balances::transfer_all"
+/// )]
+/// mod polkadot {}
+/// ```
+///
 /// ## `unstable_metadata`
 ///
 /// This attribute works only in combination with `runtime_metadata_insecure_url`. By default, the macro will fetch the latest stable
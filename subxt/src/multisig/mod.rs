@@ -0,0 +1,47 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Work with `pallet_multisig` accounts.
+//!
+//! A [`Multisig`] describes a multisig account via its signatories and threshold. From this,
+//! it can derive the multisig account ID, read any pending operations from the
+//! `Multisig.Multisigs` storage entry, and build the appropriate `Multisig.as_multi` or
+//! `Multisig.approve_as_multi` call for a signatory to approve some call with. Everything is
+//! done dynamically via the metadata, and so it works with any [`crate::Config`] whose
+//! `AccountId` is 32 bytes.
+//!
+//! ```rust,no_run
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use subxt::dynamic::Value;
+//! use subxt::multisig::Multisig;
+//! use subxt::{OnlineClient, PolkadotConfig};
+//! use subxt_signer::sr25519::dev;
+//!
+//! let api = OnlineClient::<PolkadotConfig>::new().await?;
+//!
+//! let signatories = [dev::alice(), dev::bob(), dev::charlie()].map(|s| s.public_key().into());
+//! let multisig = Multisig::<PolkadotConfig>::new(signatories, 2)?;
+//!
+//! // Some call that we want the multisig account to make:
+//! let call = subxt::dynamic::tx("System", "remark", vec![Value::from_bytes("Hello")]);
+//!
+//! // Alice and then Bob approve it. The second approval will execute it.
+//! for signer in [dev::alice(), dev::bob()] {
+//!     let approval = multisig
+//!         .approve(&api, &signer.public_key().into(), &call)
+//!         .await?;
+//!     api.tx()
+//!         .sign_and_submit_then_watch_default(&approval, &signer)
+//!         .await?
+//!         .wait_for_finalized_success()
+//!         .await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod multisig_types;
+
+pub use multisig_types::{call_hash, Multisig, PendingMultisig, Timepoint};
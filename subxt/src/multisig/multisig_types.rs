@@ -0,0 +1,643 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use crate::{
+    client::OnlineClientT,
+    config::Config,
+    dynamic::{DecodedValue, Value},
+    error::{Error, MultisigError},
+    tx::{DynamicPayload, Payload},
+    utils::AccountId32,
+};
+use codec::Encode;
+use derive_where::derive_where;
+use polkadot_sdk::sp_crypto_hashing::blake2_256;
+use scale_decode::DecodeAsType;
+
+/// Compute the hash of some call data, as used by `pallet_multisig` to identify the call
+/// that a multisig operation will dispatch.
+pub fn call_hash(call_data: &[u8]) -> [u8; 32] {
+    blake2_256(call_data)
+}
+
+/// The block height and extrinsic index at which a multisig operation was first approved.
+/// This must be provided by every subsequent approval of the operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, DecodeAsType)]
+pub struct Timepoint {
+    /// The height of the block in which the operation was first approved.
+    pub height: u64,
+    /// The index of the extrinsic in that block.
+    pub index: u32,
+}
+
+impl Timepoint {
+    fn to_value(self) -> Value {
+        Value::named_composite([
+            ("height", Value::u128(self.height as u128)),
+            ("index", Value::u128(self.index as u128)),
+        ])
+    }
+}
+
+/// A multisig operation which has been approved by some, but not yet enough, of
+/// the signatories. This is read from the `Multisig.Multisigs` storage entry.
+#[derive_where(Clone, Debug; T::AccountId)]
+pub struct PendingMultisig<T: Config> {
+    /// When the operation was first approved.
+    pub when: Timepoint,
+    /// The amount held in reserve of the depositor until the operation is complete.
+    pub deposit: u128,
+    /// The account which made the deposit (ie the first to approve the operation).
+    pub depositor: T::AccountId,
+    /// The signatories who have approved the operation so far.
+    pub approvals: Vec<T::AccountId>,
+}
+
+// The shape of the `pallet_multisig::Multisig` struct stored in `Multisig.Multisigs`.
+#[derive(DecodeAsType)]
+struct MultisigDetails {
+    when: Timepoint,
+    deposit: u128,
+    depositor: AccountId32,
+    approvals: Vec<AccountId32>,
+}
+
+impl<T: Config> From<MultisigDetails> for PendingMultisig<T>
+where
+    T::AccountId: From<[u8; 32]>,
+{
+    fn from(details: MultisigDetails) -> Self {
+        PendingMultisig {
+            when: details.when,
+            deposit: details.deposit,
+            depositor: details.depositor.0.into(),
+            approvals: details.approvals.into_iter().map(|a| a.0.into()).collect(),
+        }
+    }
+}
+
+/// A `pallet_multisig` multisig account, described by its signatories and threshold.
+/// See the [module docs](crate::multisig) for more.
+#[derive_where(Clone, Debug; T::AccountId)]
+pub struct Multisig<T: Config> {
+    // The signatories, sorted by their encoded bytes and deduplicated, as the pallet expects.
+    signatories: Vec<T::AccountId>,
+    threshold: u16,
+}
+
+impl<T: Config> Multisig<T>
+where
+    T::AccountId: From<[u8; 32]>,
+{
+    /// Describe a multisig account via its signatories (in any order) and the number of them
+    /// that must approve an operation before it is dispatched.
+    pub fn new(
+        signatories: impl IntoIterator<Item = T::AccountId>,
+        threshold: u16,
+    ) -> Result<Self, Error> {
+        let mut signatories: Vec<(Vec<u8>, T::AccountId)> = signatories
+            .into_iter()
+            .map(|account| (account.encode(), account))
+            .collect();
+        if let Some((bytes, _)) = signatories.iter().find(|(bytes, _)| bytes.len() != 32) {
+            return Err(MultisigError::InvalidAccountIdLength(bytes.len()).into());
+        }
+
+        signatories.sort_by(|a, b| a.0.cmp(&b.0));
+        signatories.dedup_by(|a, b| a.0 == b.0);
+        if signatories.len() < 2 {
+            return Err(MultisigError::NotEnoughSignatories(signatories.len()).into());
+        }
+        if threshold == 0 || threshold as usize > signatories.len() {
+            return Err(MultisigError::InvalidThreshold {
+                threshold,
+                num_signatories: signatories.len(),
+            }
+            .into());
+        }
+
+        Ok(Multisig {
+            signatories: signatories
+                .into_iter()
+                .map(|(_, account)| account)
+                .collect(),
+            threshold,
+        })
+    }
+
+    /// The signatories of this multisig, in the order that the pallet expects them.
+    pub fn signatories(&self) -> &[T::AccountId] {
+        &self.signatories
+    }
+
+    /// The number of signatories that must approve an operation before it is dispatched.
+    pub fn threshold(&self) -> u16 {
+        self.threshold
+    }
+
+    /// The account ID of this multisig, derived from its signatories and threshold in
+    /// the same way that `pallet_multisig` does.
+    pub fn account_id(&self) -> T::AccountId {
+        let entropy = (b"modlpy/utilisuba", &self.signatories, self.threshold).encode();
+        blake2_256(&entropy).into()
+    }
+
+    /// The signatories other than the given one, which need to be provided
+    /// alongside each call made by that signatory.
+    pub fn other_signatories(&self, signatory: &T::AccountId) -> Result<Vec<T::AccountId>, Error> {
+        let signatory = signatory.encode();
+        let others: Vec<_> = self
+            .signatories
+            .iter()
+            .filter(|account| account.encode() != signatory)
+            .cloned()
+            .collect();
+        if others.len() == self.signatories.len() {
+            return Err(MultisigError::NotASignatory.into());
+        }
+        Ok(others)
+    }
+
+    /// Fetch the pending operation for the call with the given [`call_hash()`] at the latest
+    /// block, returning `None` if there is no such operation.
+    pub async fn pending<C: OnlineClientT<T>>(
+        &self,
+        client: &C,
+        call_hash: [u8; 32],
+    ) -> Result<Option<PendingMultisig<T>>, Error> {
+        let address = crate::dynamic::storage(
+            "Multisig",
+            "Multisigs",
+            vec![
+                Value::from_bytes(self.account_id().encode()),
+                Value::from_bytes(call_hash),
+            ],
+        );
+        let Some(details) = client.storage().at_latest().await?.fetch(&address).await? else {
+            return Ok(None);
+        };
+        let details: MultisigDetails = details.as_type()?;
+        Ok(Some(details.into()))
+    }
+
+    /// Fetch all of the pending operations for this multisig at the latest block,
+    /// along with the call hash of each one.
+    pub async fn pending_all<C: OnlineClientT<T>>(
+        &self,
+        client: &C,
+    ) -> Result<Vec<([u8; 32], PendingMultisig<T>)>, Error> {
+        let address = crate::dynamic::storage(
+            "Multisig",
+            "Multisigs",
+            vec![Value::from_bytes(self.account_id().encode())],
+        );
+        let mut entries = client.storage().at_latest().await?.iter(address).await?;
+
+        let mut pending = Vec::new();
+        while let Some(entry) = entries.next().await {
+            let entry = entry?;
+            // The call hash is stored in full (via a `Blake2_128Concat` hasher)
+            // at the end of the storage key.
+            let call_hash: [u8; 32] = entry
+                .key_bytes
+                .len()
+                .checked_sub(32)
+                .and_then(|start| entry.key_bytes.get(start..))
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| MultisigError::InvalidStorageKey(entry.key_bytes.clone()))?;
+            let details: MultisigDetails = entry.value.as_type()?;
+            pending.push((call_hash, details.into()));
+        }
+        Ok(pending)
+    }
+
+    /// Build the call that the given signatory should submit in order to approve the given
+    /// call on behalf of this multisig. This looks up any pending operation for the call, and:
+    ///
+    /// - If this is the final approval needed, returns a `Multisig.as_multi` call which
+    ///   will dispatch the call. The maximum weight is obtained via the
+    ///   `TransactionPaymentCallApi.query_call_info` runtime API.
+    /// - Else, returns a `Multisig.approve_as_multi` call, which approves the call hash.
+    /// - If the threshold is 1, returns a `Multisig.as_multi_threshold_1` call.
+    pub async fn approve<C, Call>(
+        &self,
+        client: &C,
+        signatory: &T::AccountId,
+        call: &Call,
+    ) -> Result<DynamicPayload, Error>
+    where
+        C: OnlineClientT<T>,
+        Call: Payload,
+    {
+        let other_signatories = self.other_signatories_value(signatory)?;
+        let call_data = client.tx().call_data(call)?;
+        let call_value = decode_call(&call_data, client)?;
+
+        if self.threshold == 1 {
+            return Ok(crate::dynamic::tx(
+                "Multisig",
+                "as_multi_threshold_1",
+                vec![other_signatories, call_value],
+            ));
+        }
+
+        let call_hash = call_hash(&call_data);
+        let pending = self.pending(client, call_hash).await?;
+        let num_approvals = match &pending {
+            Some(pending) => {
+                let signatory = signatory.encode();
+                if pending.approvals.iter().any(|a| a.encode() == signatory) {
+                    return Err(MultisigError::AlreadyApproved.into());
+                }
+                pending.approvals.len()
+            }
+            None => 0,
+        };
+        let maybe_timepoint = match pending {
+            Some(pending) => Value::unnamed_variant("Some", [pending.when.to_value()]),
+            None => Value::unnamed_variant("None", []),
+        };
+        let threshold = Value::u128(self.threshold as u128);
+
+        if num_approvals + 1 >= self.threshold as usize {
+            let max_weight = query_call_weight(client, &call_value, call_data.len()).await?;
+            Ok(crate::dynamic::tx(
+                "Multisig",
+                "as_multi",
+                vec![
+                    threshold,
+                    other_signatories,
+                    maybe_timepoint,
+                    call_value,
+                    max_weight,
+                ],
+            ))
+        } else {
+            Ok(crate::dynamic::tx(
+                "Multisig",
+                "approve_as_multi",
+                vec![
+                    threshold,
+                    other_signatories,
+                    maybe_timepoint,
+                    Value::from_bytes(call_hash),
+                    weight_value(0, 0),
+                ],
+            ))
+        }
+    }
+
+    /// Build the `Multisig.cancel_as_multi` call that the given signatory (who must be
+    /// the depositor) should submit in order to cancel the pending operation for the call
+    /// with the given [`call_hash()`].
+    pub async fn cancel<C: OnlineClientT<T>>(
+        &self,
+        client: &C,
+        signatory: &T::AccountId,
+        call_hash: [u8; 32],
+    ) -> Result<DynamicPayload, Error> {
+        let other_signatories = self.other_signatories_value(signatory)?;
+        let pending = self
+            .pending(client, call_hash)
+            .await?
+            .ok_or(MultisigError::NotPending(call_hash))?;
+
+        Ok(crate::dynamic::tx(
+            "Multisig",
+            "cancel_as_multi",
+            vec![
+                Value::u128(self.threshold as u128),
+                other_signatories,
+                pending.when.to_value(),
+                Value::from_bytes(call_hash),
+            ],
+        ))
+    }
+
+    fn other_signatories_value(&self, signatory: &T::AccountId) -> Result<Value, Error> {
+        let others = self.other_signatories(signatory)?;
+        Ok(Value::unnamed_composite(
+            others.iter().map(|a| Value::from_bytes(a.encode())),
+        ))
+    }
+}
+
+// Decode some call data into a value representing the outer call enum, so that
+// it can be nested inside of a dynamic `Multisig` call.
+fn decode_call<T: Config, C: OnlineClientT<T>>(
+    call_data: &[u8],
+    client: &C,
+) -> Result<Value, Error> {
+    let metadata = client.metadata();
+    let call_ty = metadata.outer_enums().call_enum_ty();
+    let value: DecodedValue =
+        scale_value::scale::decode_as_type(&mut &*call_data, call_ty, metadata.types())?;
+    Ok(value.remove_context())
+}
+
+// Ask the runtime for the weight of some call, returning it as a `Weight` value.
+async fn query_call_weight<T: Config, C: OnlineClientT<T>>(
+    client: &C,
+    call_value: &Value,
+    call_len: usize,
+) -> Result<Value, Error> {
+    let payload = crate::dynamic::runtime_api_call(
+        "TransactionPaymentCallApi",
+        "query_call_info",
+        vec![call_value.clone(), Value::u128(call_len as u128)],
+    );
+    let info = client
+        .runtime_api()
+        .at_latest()
+        .await?
+        .call(payload)
+        .await?
+        .as_type::<CallInfo>()?;
+    Ok(weight_value(info.weight.ref_time, info.weight.proof_size))
+}
+
+// The parts of `pallet_transaction_payment::RuntimeDispatchInfo` that we care about.
+#[derive(DecodeAsType)]
+struct CallInfo {
+    weight: Weight,
+}
+
+#[derive(DecodeAsType)]
+struct Weight {
+    ref_time: u64,
+    proof_size: u64,
+}
+
+fn weight_value(ref_time: u64, proof_size: u64) -> Value {
+    Value::named_composite([
+        ("ref_time", Value::u128(ref_time as u128)),
+        ("proof_size", Value::u128(proof_size as u128)),
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::mock::MockBackend;
+    use crate::{Metadata, OnlineClient, PolkadotConfig, SubstrateConfig};
+    use codec::{Compact, Decode};
+    use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use subxt_signer::sr25519::dev;
+
+    fn account(n: u8) -> AccountId32 {
+        AccountId32([n; 32])
+    }
+
+    #[test]
+    fn account_id_matches_pallet_multisig() {
+        let multisig = Multisig::<PolkadotConfig>::new(
+            [
+                dev::alice().public_key().to_account_id(),
+                dev::bob().public_key().to_account_id(),
+                dev::charlie().public_key().to_account_id(),
+            ],
+            2,
+        )
+        .unwrap();
+
+        // The well known 2-of-3 multisig account of Alice, Bob and Charlie.
+        let expected =
+            AccountId32::from_str("5DjYJStmdZ2rcqXbXGX7TW85JsrW6uG4y9MUcLq2BoPMpRA7").unwrap();
+        assert_eq!(multisig.account_id(), expected);
+    }
+
+    #[test]
+    fn signatories_are_sorted_and_deduplicated() {
+        let multisig =
+            Multisig::<PolkadotConfig>::new([account(3), account(1), account(2), account(1)], 2)
+                .unwrap();
+        assert_eq!(
+            multisig.signatories(),
+            &[account(1), account(2), account(3)]
+        );
+
+        // The order that signatories are given in doesn't affect the account ID:
+        let other =
+            Multisig::<PolkadotConfig>::new([account(2), account(3), account(1)], 2).unwrap();
+        assert_eq!(multisig.account_id(), other.account_id());
+
+        // But the threshold does:
+        let other =
+            Multisig::<PolkadotConfig>::new([account(2), account(3), account(1)], 3).unwrap();
+        assert_ne!(multisig.account_id(), other.account_id());
+    }
+
+    #[test]
+    fn invalid_multisigs_are_rejected() {
+        assert!(matches!(
+            Multisig::<PolkadotConfig>::new([account(1), account(1)], 1),
+            Err(Error::Multisig(MultisigError::NotEnoughSignatories(1)))
+        ));
+        assert!(matches!(
+            Multisig::<PolkadotConfig>::new([account(1), account(2)], 3),
+            Err(Error::Multisig(MultisigError::InvalidThreshold { .. }))
+        ));
+    }
+
+    #[test]
+    fn other_signatories_excludes_signatory() {
+        let multisig =
+            Multisig::<PolkadotConfig>::new([account(1), account(2), account(3)], 2).unwrap();
+        assert_eq!(
+            multisig.other_signatories(&account(2)).unwrap(),
+            vec![account(1), account(3)]
+        );
+        assert!(matches!(
+            multisig.other_signatories(&account(4)),
+            Err(Error::Multisig(MultisigError::NotASignatory))
+        ));
+    }
+
+    // The Polkadot metadata that we test against predates the `TransactionPaymentCallApi`
+    // runtime API which is used to find the weight of calls, so add it, based on the
+    // `TransactionPaymentApi.query_info` method which has the same output.
+    fn metadata() -> Metadata {
+        let bytes = include_bytes!("../../../artifacts/polkadot_metadata_small.scale");
+        let RuntimeMetadataPrefixed(prefix, RuntimeMetadata::V15(mut metadata)) =
+            RuntimeMetadataPrefixed::decode(&mut &bytes[..]).unwrap()
+        else {
+            panic!("expected V15 metadata");
+        };
+
+        let mut api = metadata
+            .apis
+            .iter()
+            .find(|api| api.name == "TransactionPaymentApi")
+            .unwrap()
+            .clone();
+        api.name = "TransactionPaymentCallApi".into();
+        api.methods.retain(|method| method.name == "query_info");
+        api.methods[0].name = "query_call_info".into();
+        api.methods[0].inputs[0].name = "call".into();
+        api.methods[0].inputs[0].ty = metadata.outer_enums.call_enum_ty;
+        metadata.apis.push(api);
+
+        let bytes = RuntimeMetadataPrefixed(prefix, RuntimeMetadata::V15(metadata)).encode();
+        subxt_core::metadata::decode_from(&bytes[..]).unwrap()
+    }
+
+    fn remark() -> DynamicPayload {
+        crate::dynamic::tx("System", "remark", vec![Value::from_bytes("hello")])
+    }
+
+    // Store a pending operation for the given call of the given multisig.
+    fn set_pending(
+        backend: &MockBackend,
+        client: &OnlineClient<SubstrateConfig>,
+        multisig: &Multisig<SubstrateConfig>,
+        call_hash: [u8; 32],
+        when: Timepoint,
+        approvals: Vec<AccountId32>,
+    ) {
+        let address = crate::dynamic::storage(
+            "Multisig",
+            "Multisigs",
+            vec![
+                Value::from_bytes(multisig.account_id()),
+                Value::from_bytes(call_hash),
+            ],
+        );
+        let key = client.storage().address_bytes(&address).unwrap();
+        let value = (
+            (when.height as u32, when.index),
+            100u128,
+            approvals[0].clone(),
+            approvals,
+        );
+        backend.set_storage(key, value.encode());
+    }
+
+    fn setup(
+        threshold: u16,
+    ) -> (
+        Arc<MockBackend>,
+        OnlineClient<SubstrateConfig>,
+        Multisig<SubstrateConfig>,
+    ) {
+        let backend = MockBackend::new();
+        let client = backend.client_with_metadata(metadata());
+        let multisig = Multisig::new([account(1), account(2), account(3)], threshold).unwrap();
+        (backend, client, multisig)
+    }
+
+    // The unnamed arguments of a dynamic call.
+    fn args(payload: &DynamicPayload) -> Vec<Value> {
+        payload.call_data().values().cloned().collect()
+    }
+
+    #[tokio::test]
+    async fn first_approval_approves_the_call_hash() {
+        let (_backend, client, multisig) = setup(2);
+        let call_data = client.tx().call_data(&remark()).unwrap();
+
+        let payload = multisig
+            .approve(&client, &account(1), &remark())
+            .await
+            .unwrap();
+        assert_eq!(payload.call_name(), "approve_as_multi");
+        let args = args(&payload);
+        assert_eq!(args[0], Value::u128(2));
+        assert_eq!(args[2], Value::unnamed_variant("None", []));
+        assert_eq!(args[3], Value::from_bytes(call_hash(&call_data)));
+    }
+
+    #[tokio::test]
+    async fn final_approval_dispatches_the_call() {
+        let (backend, client, multisig) = setup(2);
+        let call_data = client.tx().call_data(&remark()).unwrap();
+        let when = Timepoint {
+            height: 10,
+            index: 3,
+        };
+        set_pending(
+            &backend,
+            &client,
+            &multisig,
+            call_hash(&call_data),
+            when,
+            vec![account(1)],
+        );
+        backend.on_call("TransactionPaymentCallApi_query_call_info", |_| {
+            // The weight, dispatch class and partial fee.
+            Ok((Compact(1_000u64), Compact(64u64), 0u8, 0u128).encode())
+        });
+
+        let payload = multisig
+            .approve(&client, &account(2), &remark())
+            .await
+            .unwrap();
+        assert_eq!(payload.call_name(), "as_multi");
+        let args = args(&payload);
+        assert_eq!(
+            args[1],
+            Value::unnamed_composite([
+                Value::from_bytes(account(1)),
+                Value::from_bytes(account(3))
+            ])
+        );
+        assert_eq!(args[2], Value::unnamed_variant("Some", [when.to_value()]));
+        assert_eq!(args[4], weight_value(1_000, 64));
+
+        // The account which already approved can't approve again:
+        assert!(matches!(
+            multisig.approve(&client, &account(1), &remark()).await,
+            Err(Error::Multisig(MultisigError::AlreadyApproved))
+        ));
+    }
+
+    #[tokio::test]
+    async fn threshold_of_one_dispatches_the_call_immediately() {
+        let (_backend, client, multisig) = setup(1);
+
+        let payload = multisig
+            .approve(&client, &account(3), &remark())
+            .await
+            .unwrap();
+        assert_eq!(payload.call_name(), "as_multi_threshold_1");
+    }
+
+    #[tokio::test]
+    async fn cancel_passes_the_timepoint_through() {
+        let (backend, client, multisig) = setup(2);
+        let call_hash = [7; 32];
+        assert!(matches!(
+            multisig.cancel(&client, &account(1), call_hash).await,
+            Err(Error::Multisig(MultisigError::NotPending(h))) if h == call_hash
+        ));
+
+        let when = Timepoint {
+            height: 10,
+            index: 3,
+        };
+        set_pending(
+            &backend,
+            &client,
+            &multisig,
+            call_hash,
+            when,
+            vec![account(1)],
+        );
+
+        let payload = multisig
+            .cancel(&client, &account(1), call_hash)
+            .await
+            .unwrap();
+        assert_eq!(payload.call_name(), "cancel_as_multi");
+        let args = args(&payload);
+        assert_eq!(args[2], when.to_value());
+        assert_eq!(args[3], Value::from_bytes(call_hash));
+
+        let pending = multisig.pending_all(&client).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].0, call_hash);
+        assert_eq!(pending[0].1.when, when);
+    }
+}
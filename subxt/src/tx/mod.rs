@@ -11,8 +11,12 @@
 
 use crate::macros::cfg_substrate_compat;
 
+mod tx_broadcast;
 mod tx_client;
 mod tx_progress;
+mod tx_queue;
+mod tx_receipt;
+mod tx_track;
 
 // The PairSigner impl currently relies on Substrate bits and pieces, so make it an optional
 // feature if we want to avoid needing sp_core and sp_runtime.
@@ -20,10 +24,18 @@ cfg_substrate_compat! {
     pub use subxt_core::tx::signer::PairSigner;
 }
 
-pub use subxt_core::tx::payload::{dynamic, DefaultPayload, DynamicPayload, Payload};
+pub use subxt_core::tx::description::{SignedExtensionDescription, TransactionDescription};
+pub use subxt_core::tx::envelope::{CallDescription, EnvelopeSignature, TransactionEnvelope};
+pub use subxt_core::tx::payload::{
+    dynamic, BatchMode, BatchPayload, DefaultPayload, DynamicPayload, Payload, WrappedPayload,
+};
 pub use subxt_core::tx::signer::{self, Signer};
+pub use subxt_core::tx::MetadataProof;
+pub use tx_broadcast::TxBroadcast;
 pub use tx_client::{
     PartialExtrinsic, SubmittableExtrinsic, TransactionInvalid, TransactionUnknown, TxClient,
     ValidationResult,
 };
-pub use tx_progress::{TxInBlock, TxProgress, TxStatus};
+pub use tx_progress::{TxDeadline, TxInBlock, TxProgress, TxStage, TxStatus};
+pub use tx_queue::{TxJobId, TxQueue, TxQueueBuilder, TxQueueDriver, TxQueueOutcome};
+pub use tx_receipt::{TxReceipt, Weight};
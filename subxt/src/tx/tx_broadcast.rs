@@ -0,0 +1,113 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use crate::{
+    client::OnlineClientT,
+    error::Error,
+    tx::{TxClient, TxInBlock},
+    Config,
+};
+use derive_where::derive_where;
+
+/// This struct represents a transaction that has been broadcast to the network via
+/// [`crate::tx::SubmittableExtrinsic::broadcast()`], without its progress being watched.
+#[derive_where(Debug; C)]
+pub struct TxBroadcast<T: Config, C> {
+    client: C,
+    ext_hash: T::Hash,
+    operation_id: Option<String>,
+    check_inner_result: bool,
+}
+
+impl<T: Config, C> TxBroadcast<T, C> {
+    pub(crate) fn new(
+        client: C,
+        ext_hash: T::Hash,
+        operation_id: Option<String>,
+        check_inner_result: bool,
+    ) -> Self {
+        Self {
+            client,
+            ext_hash,
+            operation_id,
+            check_inner_result,
+        }
+    }
+
+    /// Return the hash of the extrinsic that was broadcast.
+    pub fn extrinsic_hash(&self) -> T::Hash {
+        self.ext_hash
+    }
+
+    /// Return the ID of the broadcast operation, if the backend provided one. This can be
+    /// used to stop the broadcast from elsewhere, and is `None` if it can't be stopped.
+    pub fn operation_id(&self) -> Option<&str> {
+        self.operation_id.as_deref()
+    }
+}
+
+impl<T: Config, C: OnlineClientT<T>> TxBroadcast<T, C> {
+    /// Stop broadcasting the transaction. This does nothing if the backend doesn't support
+    /// stopping broadcasts. Note that the transaction may already have been gossiped to other
+    /// nodes, and so may still make it into a block.
+    pub async fn stop(self) -> Result<(), Error> {
+        match &self.operation_id {
+            Some(operation_id) => self.client.backend().stop_broadcast(operation_id).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Look for the transaction in finalized blocks. See
+    /// [`TxClient::find_in_finalized_blocks()`] for more details.
+    pub async fn find_in_finalized_blocks(
+        &self,
+        max_blocks: usize,
+    ) -> Result<Option<TxInBlock<T, C>>, Error> {
+        let in_block = TxClient::<T, C>::new(self.client.clone())
+            .find_in_finalized_blocks(self.ext_hash, max_blocks)
+            .await?;
+        Ok(in_block.map(|in_block| {
+            if self.check_inner_result {
+                in_block.check_inner_result()
+            } else {
+                in_block
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{backend::mock::MockBackend, dynamic::Value};
+
+    #[tokio::test]
+    async fn broadcast_without_operation_id_cannot_be_stopped() {
+        let backend = MockBackend::new();
+        // The legacy backend can't stop broadcasts, and so returns no operation ID.
+        backend.on_broadcast(|_| Ok(None));
+
+        let remark = crate::dynamic::tx("System", "remark", vec![Value::from_bytes([1])]);
+        let tx = backend.client().tx().create_unsigned(&remark).unwrap();
+        let broadcast = tx.broadcast().await.unwrap();
+        assert_eq!(broadcast.extrinsic_hash(), tx.hash());
+        assert_eq!(broadcast.operation_id(), None);
+
+        broadcast.stop().await.unwrap();
+        assert!(backend.stopped_broadcasts().is_empty());
+    }
+
+    #[tokio::test]
+    async fn broadcast_with_operation_id_can_be_stopped() {
+        let backend = MockBackend::new();
+        backend.on_broadcast(|_| Ok(Some("op1".to_owned())));
+
+        let remark = crate::dynamic::tx("System", "remark", vec![Value::from_bytes([1])]);
+        let tx = backend.client().tx().create_unsigned(&remark).unwrap();
+        let broadcast = tx.broadcast().await.unwrap();
+        assert_eq!(broadcast.operation_id(), Some("op1"));
+
+        broadcast.stop().await.unwrap();
+        assert_eq!(backend.stopped_broadcasts(), ["op1"]);
+    }
+}
@@ -2,12 +2,17 @@
 // This file is dual-licensed as Apache-2.0 or GPL-3.0.
 // see LICENSE for license details.
 
+use super::tx_progress::find_extrinsic_index;
+use super::tx_track;
 use crate::{
     backend::{BackendExt, BlockRef, TransactionStatus},
     client::{OfflineClientT, OnlineClientT},
     config::{Config, ExtrinsicParams, Header, RefineParams, RefineParamsData},
-    error::{BlockError, Error},
-    tx::{Payload, Signer as SignerT, TxProgress},
+    error::{BlockError, Error, RpcError},
+    tx::{
+        BatchMode, BatchPayload, EnvelopeSignature, MetadataProof, Payload, Signer as SignerT,
+        TransactionDescription, TransactionEnvelope, TxBroadcast, TxInBlock, TxProgress,
+    },
     utils::PhantomDataSendSync,
 };
 use codec::{Compact, Decode, Encode};
@@ -50,6 +55,22 @@ impl<T: Config, C: OfflineClientT<T>> TxClient<T, C> {
         subxt_core::tx::call_data(call, &self.client.metadata()).map_err(Into::into)
     }
 
+    /// Combine several payloads into a single `Utility` pallet batch call using the given
+    /// [`BatchMode`]. The payloads may be static or dynamic, and may come from different codegen
+    /// modules (use `Box<dyn Payload>` to mix them). Each payload is validated against the metadata
+    /// and encoded into the outer call enum. If a `Utility.batch` call is interrupted, use
+    /// [`crate::blocks::ExtrinsicEvents::batch_interrupted()`] to find out which payload failed.
+    pub fn batch<Call>(
+        &self,
+        mode: BatchMode,
+        payloads: impl IntoIterator<Item = Call>,
+    ) -> Result<BatchPayload, Error>
+    where
+        Call: Payload,
+    {
+        subxt_core::tx::payload::batch(mode, payloads, &self.client.metadata()).map_err(Into::into)
+    }
+
     /// Creates an unsigned extrinsic without submitting it.
     pub fn create_unsigned<Call>(&self, call: &Call) -> Result<SubmittableExtrinsic<T, C>, Error>
     where
@@ -59,6 +80,7 @@ impl<T: Config, C: OfflineClientT<T>> TxClient<T, C> {
             .map(|tx| SubmittableExtrinsic {
                 client: self.client.clone(),
                 inner: tx,
+                check_inner_result: call.reports_inner_result(),
             })
             .map_err(Into::into)
     }
@@ -79,6 +101,7 @@ impl<T: Config, C: OfflineClientT<T>> TxClient<T, C> {
             .map(|tx| PartialExtrinsic {
                 client: self.client.clone(),
                 inner: tx,
+                check_inner_result: call.reports_inner_result(),
             })
             .map_err(Into::into)
     }
@@ -101,9 +124,52 @@ impl<T: Config, C: OfflineClientT<T>> TxClient<T, C> {
             .map(|tx| SubmittableExtrinsic {
                 client: self.client.clone(),
                 inner: tx,
+                check_inner_result: call.reports_inner_result(),
             })
             .map_err(Into::into)
     }
+
+    /// Check that a [`TransactionEnvelope`] (likely created on some other machine via
+    /// [`PartialExtrinsic::to_envelope()`]) lines up with the genesis hash, runtime version
+    /// and metadata of this client, that its call description matches its call data, and that
+    /// its signed extension data signs the genesis hash and runtime versions that it claims to.
+    pub fn verify_envelope(&self, envelope: &TransactionEnvelope) -> Result<(), Error> {
+        envelope
+            .verify(&self.client.client_state())
+            .map_err(Into::into)
+    }
+
+    /// Verify a [`TransactionEnvelope`] (see [`TxClient::verify_envelope()`]) and then sign it
+    /// with the given signer. This is expected to be called on an offline machine, for instance
+    /// using an [`crate::OfflineClient`], and the resulting [`EnvelopeSignature`] can be handed back
+    /// to [`TxClient::create_from_envelope()`] to build the final transaction.
+    pub fn sign_envelope<Signer>(
+        &self,
+        envelope: &TransactionEnvelope,
+        signer: &Signer,
+    ) -> Result<EnvelopeSignature, Error>
+    where
+        Signer: SignerT<T>,
+    {
+        envelope
+            .sign(&self.client.client_state(), signer)
+            .map_err(Into::into)
+    }
+
+    /// Verify a [`TransactionEnvelope`] (see [`TxClient::verify_envelope()`]) and then build a
+    /// [`SubmittableExtrinsic`] from it and the [`EnvelopeSignature`] obtained by signing it.
+    pub fn create_from_envelope(
+        &self,
+        envelope: &TransactionEnvelope,
+        signature: &EnvelopeSignature,
+    ) -> Result<SubmittableExtrinsic<T, C>, Error> {
+        self.verify_envelope(envelope)?;
+        Ok(SubmittableExtrinsic {
+            client: self.client.clone(),
+            inner: envelope.to_transaction(signature),
+            check_inner_result: false,
+        })
+    }
 }
 
 impl<T, C> TxClient<T, C>
@@ -118,14 +184,26 @@ where
         params: &mut <T::ExtrinsicParams as ExtrinsicParams<T>>::Params,
     ) -> Result<(), Error> {
         let block_ref = self.client.backend().latest_finalized_block_ref().await?;
+        let account_nonce =
+            crate::blocks::get_account_nonce(&self.client, account_id, block_ref.hash()).await?;
+        self.refine_params_with_nonce(block_ref, account_nonce, params)
+            .await
+    }
+
+    /// Fetch the header for the given block and use it, along with the account nonce given, to
+    /// refine [`ExtrinsicParams::Params`].
+    async fn refine_params_with_nonce(
+        &self,
+        block_ref: BlockRef<T::Hash>,
+        account_nonce: u64,
+        params: &mut <T::ExtrinsicParams as ExtrinsicParams<T>>::Params,
+    ) -> Result<(), Error> {
         let block_header = self
             .client
             .backend()
             .block_header(block_ref.hash())
             .await?
             .ok_or_else(|| Error::Block(BlockError::not_found(block_ref.hash())))?;
-        let account_nonce =
-            crate::blocks::get_account_nonce(&self.client, account_id, block_ref.hash()).await?;
 
         params.refine(&RefineParamsData::new(
             account_nonce,
@@ -182,6 +260,26 @@ where
         Ok(partial_signed.sign(signer))
     }
 
+    /// Creates a signed extrinsic using the account nonce given rather than fetching it from
+    /// the chain. This is used when we are keeping track of nonces ourselves.
+    pub(crate) async fn create_signed_with_nonce<Call, Signer>(
+        &self,
+        call: &Call,
+        signer: &Signer,
+        account_nonce: u64,
+        mut params: <T::ExtrinsicParams as ExtrinsicParams<T>>::Params,
+    ) -> Result<SubmittableExtrinsic<T, C>, Error>
+    where
+        Call: Payload,
+        Signer: SignerT<T>,
+    {
+        self.validate(call)?;
+        let block_ref = self.client.backend().latest_finalized_block_ref().await?;
+        self.refine_params_with_nonce(block_ref, account_nonce, &mut params)
+            .await?;
+        self.create_signed_offline(call, signer, params)
+    }
+
     /// Creates and signs an extrinsic and submits it to the chain. Passes default parameters
     /// to construct the "signed extra" and "additional" payloads needed by the extrinsic.
     ///
@@ -267,12 +365,69 @@ where
             .submit()
             .await
     }
+
+    /// Look for the extrinsic with the given hash in finalized blocks, starting from the
+    /// current finalized block and then following new ones as they are finalized. Returns
+    /// `None` if it isn't found in the first `max_blocks` blocks that are checked.
+    ///
+    /// This is useful for following transactions submitted with
+    /// [`SubmittableExtrinsic::broadcast()`]. Blocks finalized prior to the current one are
+    /// not checked, so start looking soon after the transaction is broadcast.
+    pub async fn find_in_finalized_blocks(
+        &self,
+        ext_hash: T::Hash,
+        max_blocks: usize,
+    ) -> Result<Option<TxInBlock<T, C>>, Error> {
+        let backend = self.client.backend();
+        let mut blocks = backend.stream_finalized_block_headers().await?;
+
+        for _ in 0..max_blocks {
+            let Some(block) = blocks.next().await else {
+                return Err(RpcError::SubscriptionDropped.into());
+            };
+            let (_, block_ref) = block?;
+            let Some(block_body) = backend.block_body(block_ref.hash()).await? else {
+                continue;
+            };
+            if find_extrinsic_index::<T>(&block_body, ext_hash).is_some() {
+                return Ok(Some(TxInBlock::new(
+                    block_ref,
+                    ext_hash,
+                    self.client.clone(),
+                )));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Follow the progress of a transaction that was submitted elsewhere, given its hash. Best
+    /// and finalized blocks from `search_from_block` onwards are searched for the transaction,
+    /// and a [`TxProgress`] is returned which reports when it's in a best block (or no longer
+    /// is, following a reorg) and when it's been finalized.
+    ///
+    /// Once the transaction is seen in a block, its signed [`crate::utils::Era`] is used to
+    /// work out when it will no longer be valid. Until then, we assume that it's valid for the
+    /// longest possible mortality period from `search_from_block`. If it's not finalized
+    /// before then, a `Dropped` status is reported.
+    ///
+    /// **Note:** This assumes that block headers begin with the hash of the parent block,
+    /// as is the case for Substrate based chains.
+    pub async fn track(
+        &self,
+        ext_hash: T::Hash,
+        search_from_block: impl Into<BlockRef<T::Hash>>,
+    ) -> Result<TxProgress<T, C>, Error> {
+        let sub = tx_track::track(self.client.clone(), ext_hash, search_from_block.into()).await?;
+        Ok(TxProgress::new(sub, self.client.clone(), ext_hash))
+    }
 }
 
 /// This payload contains the information needed to produce an extrinsic.
 pub struct PartialExtrinsic<T: Config, C> {
     client: C,
     inner: subxt_core::tx::PartialTransaction<T>,
+    check_inner_result: bool,
 }
 
 impl<T, C> PartialExtrinsic<T, C>
@@ -292,6 +447,33 @@ where
         self.inner.call_data()
     }
 
+    /// Export this [`PartialExtrinsic`] into a [`TransactionEnvelope`], which can be serialized
+    /// and handed to an offline machine to be verified and signed. See [`TxClient::sign_envelope()`]
+    /// and [`TxClient::create_from_envelope()`].
+    pub fn to_envelope(&self) -> Result<TransactionEnvelope, Error> {
+        self.inner
+            .to_envelope(&self.client.client_state())
+            .map_err(Into::into)
+    }
+
+    /// Decode the call data and signed extension data of this extrinsic, returning a
+    /// [`TransactionDescription`] of everything that will be signed. This is useful to
+    /// show to a user before asking them to approve a transaction. See
+    /// [`subxt_core::tx::PartialTransaction::describe()`] for details.
+    pub fn describe(&self) -> Result<TransactionDescription<T>, Error> {
+        self.inner
+            .describe(&self.client.metadata())
+            .map_err(Into::into)
+    }
+
+    /// Generate an RFC-0078 metadata proof for this extrinsic, containing the type information
+    /// that a hardware signer needs in order to decode and display it.
+    pub fn metadata_proof(&self) -> Result<MetadataProof, Error> {
+        self.inner
+            .metadata_proof(&self.client.metadata())
+            .map_err(Into::into)
+    }
+
     /// Convert this [`PartialExtrinsic`] into a [`SubmittableExtrinsic`], ready to submit.
     /// The provided `signer` is responsible for providing the "from" address for the transaction,
     /// as well as providing a signature to attach to it.
@@ -302,6 +484,7 @@ where
         SubmittableExtrinsic {
             client: self.client.clone(),
             inner: self.inner.sign(signer),
+            check_inner_result: self.check_inner_result,
         }
     }
 
@@ -319,14 +502,39 @@ where
             inner: self
                 .inner
                 .sign_with_address_and_signature(address, signature),
+            check_inner_result: self.check_inner_result,
+        }
+    }
+
+    /// Convert this [`PartialExtrinsic`] into a version 5 "general" [`SubmittableExtrinsic`],
+    /// ready to submit. See
+    /// [`subxt_core::tx::PartialTransaction::sign_with_account_and_signature()`].
+    pub fn sign_with_account_and_signature(
+        &self,
+        account_id: &T::AccountId,
+        signature: &T::Signature,
+    ) -> SubmittableExtrinsic<T, C> {
+        SubmittableExtrinsic {
+            client: self.client.clone(),
+            inner: self
+                .inner
+                .sign_with_account_and_signature(account_id, signature),
+            check_inner_result: self.check_inner_result,
         }
     }
+
+    /// The version of the extrinsic format that this will be encoded with once signed.
+    pub fn version(&self) -> u8 {
+        self.inner.version()
+    }
 }
 
 /// This represents an extrinsic that has been signed and is ready to submit.
 pub struct SubmittableExtrinsic<T, C> {
     client: C,
     inner: subxt_core::tx::Transaction<T>,
+    // Whether the payload wraps an inner call whose result should be checked.
+    check_inner_result: bool,
 }
 
 impl<T, C> SubmittableExtrinsic<T, C>
@@ -345,6 +553,7 @@ where
         Self {
             client,
             inner: subxt_core::tx::Transaction::from_bytes(tx_bytes),
+            check_inner_result: false,
         }
     }
 
@@ -385,7 +594,34 @@ where
             .submit_transaction(self.encoded())
             .await?;
 
-        Ok(TxProgress::new(sub, self.client.clone(), ext_hash))
+        let progress = TxProgress::new(sub, self.client.clone(), ext_hash);
+        Ok(if self.check_inner_result {
+            progress.check_inner_result()
+        } else {
+            progress
+        })
+    }
+
+    /// Broadcasts the extrinsic to the network without watching its progress.
+    ///
+    /// Returns a [`TxBroadcast`], which can be used to stop broadcasting the transaction or
+    /// to find the block that it was included in, via
+    /// [`TxClient::find_in_finalized_blocks()`]. Unlike [`SubmittableExtrinsic::submit()`],
+    /// no subscription is held open.
+    pub async fn broadcast(&self) -> Result<TxBroadcast<T, C>, Error> {
+        let ext_hash = self.hash();
+        let operation_id = self
+            .client
+            .backend()
+            .broadcast_transaction(self.encoded())
+            .await?;
+
+        Ok(TxBroadcast::new(
+            self.client.clone(),
+            ext_hash,
+            operation_id,
+            self.check_inner_result,
+        ))
     }
 
     /// Submits the extrinsic to the chain for block inclusion.
@@ -745,4 +981,62 @@ mod test {
             assert_eq!(decoded, validation_result);
         }
     }
+
+    fn remark_tx(
+        client: &crate::OnlineClient<crate::SubstrateConfig>,
+    ) -> SubmittableExtrinsic<crate::SubstrateConfig, crate::OnlineClient<crate::SubstrateConfig>>
+    {
+        let remark = crate::dynamic::tx(
+            "System",
+            "remark",
+            vec![crate::dynamic::Value::from_bytes([1, 2, 3])],
+        );
+        client.tx().create_unsigned(&remark).unwrap()
+    }
+
+    #[tokio::test]
+    async fn find_in_finalized_blocks_finds_transaction() {
+        let backend = crate::backend::mock::MockBackend::new();
+        let client = backend.client();
+        let tx = remark_tx(&client);
+        let tx_hash = tx.hash();
+
+        let search =
+            tokio::spawn(async move { client.tx().find_in_finalized_blocks(tx_hash, 3).await });
+        backend.wait_for_subscribers(0, 1).await;
+
+        // The current finalized block is searched first, and then each newly finalized block.
+        let block_1 = backend.add_block(backend.genesis_hash(), vec![]);
+        backend.finalize(block_1);
+        let block_2 = backend.add_block(block_1, vec![tx.encoded().to_vec()]);
+        backend.finalize(block_2);
+
+        let found = search
+            .await
+            .unwrap()
+            .unwrap()
+            .expect("transaction is found");
+        assert_eq!(found.block_hash(), block_2);
+        assert_eq!(found.extrinsic_hash(), tx_hash);
+    }
+
+    #[tokio::test]
+    async fn find_in_finalized_blocks_gives_up_after_max_blocks() {
+        let backend = crate::backend::mock::MockBackend::new();
+        let client = backend.client();
+        let tx = remark_tx(&client);
+        let tx_hash = tx.hash();
+
+        let search =
+            tokio::spawn(async move { client.tx().find_in_finalized_blocks(tx_hash, 2).await });
+        backend.wait_for_subscribers(0, 1).await;
+
+        // The transaction is only in the third block searched.
+        let block_1 = backend.add_block(backend.genesis_hash(), vec![]);
+        backend.finalize(block_1);
+        let block_2 = backend.add_block(block_1, vec![tx.encoded().to_vec()]);
+        backend.finalize(block_2);
+
+        assert!(search.await.unwrap().unwrap().is_none());
+    }
 }
@@ -6,22 +6,53 @@
 
 use std::task::Poll;
 
+use super::tx_track::find_in_ancestors;
 use crate::{
     backend::{BlockRef, StreamOfResults, TransactionStatus as BackendTxStatus},
     client::OnlineClientT,
-    error::{DispatchError, Error, RpcError, TransactionError},
+    config::Header,
+    error::{BlockError, DispatchError, Error, RpcError, TransactionError},
     events::EventsClient,
+    tx::TxReceipt,
     utils::strip_compact_prefix,
-    Config,
+    Config, Metadata,
 };
 use derive_where::derive_where;
-use futures::{Stream, StreamExt};
+use futures::{
+    future::{self, Either},
+    FutureExt, Stream, StreamExt,
+};
+use std::time::Duration;
 
 /// This struct represents a subscription to the progress of some transaction.
 pub struct TxProgress<T: Config, C> {
     sub: Option<StreamOfResults<BackendTxStatus<T::Hash>>>,
     ext_hash: T::Hash,
     client: C,
+    in_best_block_deadline: Option<TxDeadline>,
+    finalized_deadline: Option<TxDeadline>,
+    check_inner_result: bool,
+}
+
+/// A deadline for a transaction to reach some stage, given to
+/// [`TxProgress::in_best_block_deadline()`] or [`TxProgress::finalized_deadline()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxDeadline {
+    /// The stage must be reached within this much wall-clock time.
+    Time(Duration),
+    /// The stage must be reached within this many blocks. Best blocks are counted for
+    /// the in-best-block deadline, and finalized blocks for the finalized deadline.
+    Blocks(u64),
+}
+
+/// The stages of a transaction that a [`TxDeadline`] can apply to. This is given in a
+/// [`TransactionError::Timeout`] error to say which deadline passed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxStage {
+    /// The transaction is included in a best block.
+    InBestBlock,
+    /// The transaction is included in a finalized block.
+    InFinalizedBlock,
 }
 
 impl<T: Config, C> std::fmt::Debug for TxProgress<T, C> {
@@ -30,6 +61,9 @@ impl<T: Config, C> std::fmt::Debug for TxProgress<T, C> {
             .field("sub", &"<subscription>")
             .field("ext_hash", &self.ext_hash)
             .field("client", &"<client>")
+            .field("in_best_block_deadline", &self.in_best_block_deadline)
+            .field("finalized_deadline", &self.finalized_deadline)
+            .field("check_inner_result", &self.check_inner_result)
             .finish()
     }
 }
@@ -50,13 +84,56 @@ impl<T: Config, C> TxProgress<T, C> {
             sub: Some(sub),
             client,
             ext_hash,
+            in_best_block_deadline: None,
+            finalized_deadline: None,
+            check_inner_result: false,
         }
     }
 
+    /// Set a deadline for the transaction to make it into a best block when waiting for it via
+    /// [`TxProgress::wait_for_finalized()`], measured from when we start waiting. If this passes,
+    /// we look for the transaction in the current best chain ourselves, and return a
+    /// [`TransactionError::Timeout`] error if it's not found.
+    pub fn in_best_block_deadline(mut self, deadline: TxDeadline) -> Self {
+        self.in_best_block_deadline = Some(deadline);
+        self
+    }
+
+    /// Set a deadline for the transaction to be finalized when waiting for it via
+    /// [`TxProgress::wait_for_finalized()`], measured from when we start waiting. If this passes,
+    /// we look for the transaction in the finalized chain ourselves, and return a
+    /// [`TransactionError::Timeout`] error if it's not found.
+    pub fn finalized_deadline(mut self, deadline: TxDeadline) -> Self {
+        self.finalized_deadline = Some(deadline);
+        self
+    }
+
+    /// Treat the failure of an inner call wrapped via `Proxy.proxy` or `Sudo.sudo` as a failure
+    /// of the transaction; see [`TxInBlock::check_inner_result()`]. This is done automatically
+    /// for transactions whose payload was wrapped via [`crate::tx::Payload::via_proxy()`] or
+    /// [`crate::tx::Payload::sudo()`].
+    pub fn check_inner_result(mut self) -> Self {
+        self.check_inner_result = true;
+        self
+    }
+
     /// Return the hash of the extrinsic.
     pub fn extrinsic_hash(&self) -> T::Hash {
         self.ext_hash
     }
+
+    // A [`TxInBlock`] for this transaction in the given block.
+    fn in_block(&self, block_ref: BlockRef<T::Hash>) -> TxInBlock<T, C>
+    where
+        C: Clone,
+    {
+        TxInBlock {
+            block_ref,
+            ext_hash: self.ext_hash,
+            client: self.client.clone(),
+            check_inner_result: self.check_inner_result,
+        }
+    }
 }
 
 impl<T, C> TxProgress<T, C>
@@ -81,24 +158,87 @@ where
     /// probability that the transaction will not make it into a block but there is no guarantee
     /// that this is true. In those cases the stream is closed however, so you currently have no way to find
     /// out if they finally made it into a block or not.
+    ///
+    /// **Note:** This will wait forever if the node never reports a final status for the
+    /// transaction. Use [`TxProgress::in_best_block_deadline()`] and
+    /// [`TxProgress::finalized_deadline()`] to stop waiting after some time.
     pub async fn wait_for_finalized(mut self) -> Result<TxInBlock<T, C>, Error> {
-        while let Some(status) = self.next().await {
-            match status? {
-                // Finalized! Return.
-                TxStatus::InFinalizedBlock(s) => return Ok(s),
-                // Error scenarios; return the error.
-                TxStatus::Error { message } => return Err(TransactionError::Error(message).into()),
-                TxStatus::Invalid { message } => {
-                    return Err(TransactionError::Invalid(message).into())
+        if self.in_best_block_deadline.is_none() && self.finalized_deadline.is_none() {
+            while let Some(status) = self.next().await {
+                if let Some(res) = finalized_or_error(status?) {
+                    return res;
+                }
+            }
+            return Err(RpcError::SubscriptionDropped.into());
+        }
+
+        // If a deadline passes, we'll look for the transaction in blocks from this one onwards.
+        let backend = self.client.backend();
+        let start_block_ref = backend.latest_finalized_block_ref().await?;
+        let start_number: u64 = backend
+            .block_header(start_block_ref.hash())
+            .await?
+            .ok_or_else(|| Error::Block(BlockError::not_found(start_block_ref.hash())))?
+            .number()
+            .into();
+
+        let mut deadlines = futures::stream::select_all(
+            [
+                (TxStage::InBestBlock, self.in_best_block_deadline),
+                (TxStage::InFinalizedBlock, self.finalized_deadline),
+            ]
+            .into_iter()
+            .filter_map(|(stage, deadline)| {
+                let fut = deadline_passed(self.client.clone(), stage, deadline?);
+                Some(futures::stream::once(fut.map(move |res| res.map(|_| stage))).boxed())
+            }),
+        );
+        let mut in_best_block = false;
+
+        loop {
+            // Wait for the next status, or for a deadline to pass.
+            let next = if deadlines.is_empty() {
+                Either::Left(self.next().await)
+            } else {
+                match future::select(Box::pin(self.next()), deadlines.next()).await {
+                    Either::Left((status, _)) => Either::Left(status),
+                    Either::Right((None, _)) => continue,
+                    Either::Right((Some(stage), _)) => Either::Right(stage?),
                 }
-                TxStatus::Dropped { message } => {
-                    return Err(TransactionError::Dropped(message).into())
+            };
+
+            let status = match next {
+                Either::Left(Some(status)) => status?,
+                Either::Left(None) => return Err(RpcError::SubscriptionDropped.into()),
+                // We've already seen the transaction in a best block, so this deadline is met.
+                Either::Right(TxStage::InBestBlock) if in_best_block => continue,
+                // A deadline has passed; the node may just not have told us about the
+                // transaction, so look for it ourselves before giving up.
+                Either::Right(stage) => {
+                    let block_ref =
+                        find_in_chain(&self.client, self.ext_hash, stage, start_number).await?;
+                    match (stage, block_ref) {
+                        (TxStage::InFinalizedBlock, Some(block_ref)) => {
+                            return Ok(self.in_block(block_ref));
+                        }
+                        (TxStage::InBestBlock, Some(_)) => {
+                            in_best_block = true;
+                            continue;
+                        }
+                        (_, None) => {
+                            return Err(TransactionError::Timeout(stage).into());
+                        }
+                    }
                 }
-                // Ignore and wait for next status event:
-                _ => continue,
+            };
+
+            if let TxStatus::InBestBlock(_) = status {
+                in_best_block = true;
+            }
+            if let Some(res) = finalized_or_error(status) {
+                return res;
             }
         }
-        Err(RpcError::SubscriptionDropped.into())
     }
 
     /// Wait for the transaction to be finalized, and for the transaction events to indicate
@@ -137,17 +277,11 @@ impl<T: Config, C: Clone> Stream for TxProgress<T, C> {
                 BackendTxStatus::Validated => TxStatus::Validated,
                 BackendTxStatus::Broadcasted { num_peers } => TxStatus::Broadcasted { num_peers },
                 BackendTxStatus::NoLongerInBestBlock => TxStatus::NoLongerInBestBlock,
-                BackendTxStatus::InBestBlock { hash } => {
-                    TxStatus::InBestBlock(TxInBlock::new(hash, self.ext_hash, self.client.clone()))
-                }
+                BackendTxStatus::InBestBlock { hash } => TxStatus::InBestBlock(self.in_block(hash)),
                 // These stream events mean that nothing further will be sent:
                 BackendTxStatus::InFinalizedBlock { hash } => {
                     self.sub = None;
-                    TxStatus::InFinalizedBlock(TxInBlock::new(
-                        hash,
-                        self.ext_hash,
-                        self.client.clone(),
-                    ))
+                    TxStatus::InFinalizedBlock(self.in_block(hash))
                 }
                 BackendTxStatus::Error { message } => {
                     self.sub = None;
@@ -225,6 +359,7 @@ pub struct TxInBlock<T: Config, C> {
     block_ref: BlockRef<T::Hash>,
     ext_hash: T::Hash,
     client: C,
+    check_inner_result: bool,
 }
 
 impl<T: Config, C> TxInBlock<T, C> {
@@ -233,9 +368,20 @@ impl<T: Config, C> TxInBlock<T, C> {
             block_ref,
             ext_hash,
             client,
+            check_inner_result: false,
         }
     }
 
+    /// Treat the failure of an inner call wrapped via `Proxy.proxy` or `Sudo.sudo`, which is
+    /// reported by a `Proxy.ProxyExecuted`, `Sudo.Sudid` or `Sudo.SudoAsDone` event, as a failure
+    /// of the transaction in [`TxInBlock::wait_for_success()`] and [`TxInBlock::fetch_receipt()`].
+    /// This is done automatically for transactions whose payload was wrapped via
+    /// [`crate::tx::Payload::via_proxy()`] or [`crate::tx::Payload::sudo()`].
+    pub fn check_inner_result(mut self) -> Self {
+        self.check_inner_result = true;
+        self
+    }
+
     /// Return the hash of the block that the transaction has made it into.
     pub fn block_hash(&self) -> T::Hash {
         self.block_ref.hash()
@@ -253,6 +399,14 @@ impl<T: Config, C: OnlineClientT<T>> TxInBlock<T, C> {
     /// the events associated with it. If the transaction was not successful, or
     /// something else went wrong, we return an error.
     ///
+    /// If the transaction wraps some inner call via `Proxy.proxy` or `Sudo.sudo` (see
+    /// [`crate::tx::Payload::via_proxy()`] and [`crate::tx::Payload::sudo()`]), then an error
+    /// is also returned if a `ProxyExecuted` or `Sudid` event reports that the inner call failed.
+    /// See [`TxInBlock::check_inner_result()`].
+    ///
+    /// If some pallet returned the error, use [`Error::as_pallet_error()`] to decode it into
+    /// the error type of that pallet, for instance `error.as_pallet_error::<balances::Error>()`.
+    ///
     /// **Note:** If multiple `ExtrinsicFailed` errors are returned (for instance
     /// because a pallet chooses to emit one as an event, which is considered
     /// abnormal behaviour), it is not specified which of the errors is returned here.
@@ -264,19 +418,44 @@ impl<T: Config, C: OnlineClientT<T>> TxInBlock<T, C> {
     pub async fn wait_for_success(&self) -> Result<crate::blocks::ExtrinsicEvents<T>, Error> {
         let events = self.fetch_events().await?;
 
-        // Try to find any errors; return the first one we encounter.
-        for ev in events.iter() {
-            let ev = ev?;
-            if ev.pallet_name() == "System" && ev.variant_name() == "ExtrinsicFailed" {
-                let dispatch_error =
-                    DispatchError::decode_from(ev.field_bytes(), self.client.metadata())?;
-                return Err(dispatch_error.into());
-            }
+        let metadata = self.client.metadata();
+        if let Some(dispatch_error) =
+            find_dispatch_error(&events, metadata, self.check_inner_result)?
+        {
+            return Err(dispatch_error.into());
         }
 
         Ok(events)
     }
 
+    /// Fetch the events associated with this transaction and build a [`TxReceipt`] from them,
+    /// containing the block number, whether the transaction succeeded (see
+    /// [`TxInBlock::wait_for_success()`]), the fee paid and the weight consumed. This succeeds
+    /// whether the transaction was a success or not.
+    ///
+    /// **Note:** This has to download block details from the node and decode events
+    /// from them.
+    pub async fn fetch_receipt(&self) -> Result<TxReceipt<T>, Error> {
+        let events = self.fetch_events().await?;
+        let block_hash = self.block_ref.hash();
+        let block_number = self
+            .client
+            .backend()
+            .block_header(block_hash)
+            .await?
+            .ok_or_else(|| Error::Block(BlockError::not_found(block_hash)))?
+            .number()
+            .into();
+
+        TxReceipt::from_events(
+            block_hash,
+            block_number,
+            events,
+            self.client.metadata(),
+            self.check_inner_result,
+        )
+    }
+
     /// Fetch all of the events associated with this transaction. This succeeds whether
     /// the transaction was a success or not; it's up to you to handle the error and
     /// success events however you prefer.
@@ -291,16 +470,7 @@ impl<T: Config, C: OnlineClientT<T>> TxInBlock<T, C> {
             .await?
             .ok_or(Error::Transaction(TransactionError::BlockNotFound))?;
 
-        let extrinsic_idx = block_body
-            .iter()
-            .position(|ext| {
-                use crate::config::Hasher;
-                let Ok((_, stripped)) = strip_compact_prefix(ext) else {
-                    return false;
-                };
-                let hash = T::Hasher::hash_of(&stripped);
-                hash == self.ext_hash
-            })
+        let extrinsic_idx = find_extrinsic_index::<T>(&block_body, self.ext_hash)
             // If we successfully obtain the block hash we think contains our
             // extrinsic, the extrinsic should be in there somewhere..
             .ok_or(Error::Transaction(TransactionError::BlockNotFound))?;
@@ -317,16 +487,149 @@ impl<T: Config, C: OnlineClientT<T>> TxInBlock<T, C> {
     }
 }
 
+// Return the result of waiting for finalization if the status given is a final one.
+fn finalized_or_error<T: Config, C>(
+    status: TxStatus<T, C>,
+) -> Option<Result<TxInBlock<T, C>, Error>> {
+    match status {
+        // Finalized! Return.
+        TxStatus::InFinalizedBlock(s) => Some(Ok(s)),
+        // Error scenarios; return the error.
+        TxStatus::Error { message } => Some(Err(TransactionError::Error(message).into())),
+        TxStatus::Invalid { message } => Some(Err(TransactionError::Invalid(message).into())),
+        TxStatus::Dropped { message } => Some(Err(TransactionError::Dropped(message).into())),
+        // Ignore and wait for next status event:
+        _ => None,
+    }
+}
+
+impl std::fmt::Display for TxStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TxStage::InBestBlock => f.write_str("included in a best block"),
+            TxStage::InFinalizedBlock => f.write_str("finalized"),
+        }
+    }
+}
+
+// Resolves once the given deadline for some stage has passed.
+async fn deadline_passed<T: Config, C: OnlineClientT<T>>(
+    client: C,
+    stage: TxStage,
+    deadline: TxDeadline,
+) -> Result<(), Error> {
+    match deadline {
+        TxDeadline::Time(duration) => {
+            futures_timer::Delay::new(duration).await;
+        }
+        TxDeadline::Blocks(num_blocks) => {
+            let mut blocks = match stage {
+                TxStage::InBestBlock => client.backend().stream_best_block_headers().await?,
+                TxStage::InFinalizedBlock => {
+                    client.backend().stream_finalized_block_headers().await?
+                }
+            };
+            // The first block is the current one, and then we wait for `num_blocks` more.
+            for _ in 0..=num_blocks {
+                match blocks.next().await {
+                    Some(block) => block?,
+                    None => return Err(RpcError::SubscriptionDropped.into()),
+                };
+            }
+        }
+    }
+    Ok(())
+}
+
+// Look for the extrinsic in the current best or finalized chain, down to the given block number.
+async fn find_in_chain<T: Config, C: OnlineClientT<T>>(
+    client: &C,
+    ext_hash: T::Hash,
+    stage: TxStage,
+    stop_number: u64,
+) -> Result<Option<BlockRef<T::Hash>>, Error> {
+    let backend = client.backend();
+    let (header, block_ref) = match stage {
+        TxStage::InBestBlock => match backend.stream_best_block_headers().await?.next().await {
+            Some(block) => block?,
+            None => return Err(RpcError::SubscriptionDropped.into()),
+        },
+        TxStage::InFinalizedBlock => {
+            let block_ref = backend.latest_finalized_block_ref().await?;
+            let header = backend
+                .block_header(block_ref.hash())
+                .await?
+                .ok_or_else(|| Error::Block(BlockError::not_found(block_ref.hash())))?;
+            (header, block_ref)
+        }
+    };
+    find_in_ancestors(client, ext_hash, header, block_ref, stop_number).await
+}
+
+// Find the index of the extrinsic with the given hash in some block body.
+pub(crate) fn find_extrinsic_index<T: Config>(
+    block_body: &[Vec<u8>],
+    ext_hash: T::Hash,
+) -> Option<usize> {
+    block_body.iter().position(|ext| {
+        use crate::config::Hasher;
+        let Ok((_, stripped)) = strip_compact_prefix(ext) else {
+            return false;
+        };
+        let hash = T::Hasher::hash_of(&stripped);
+        hash == ext_hash
+    })
+}
+
+// Find the first error associated with a transaction. This is the error from a
+// `System.ExtrinsicFailed` event or, if `check_inner_result` is true, the error from an inner
+// call wrapped via `Proxy.proxy` or `Sudo.sudo`, which succeed even if the inner call fails.
+pub(crate) fn find_dispatch_error<T: Config>(
+    events: &crate::blocks::ExtrinsicEvents<T>,
+    metadata: Metadata,
+    check_inner_result: bool,
+) -> Result<Option<DispatchError>, Error> {
+    for ev in events.iter() {
+        let ev = ev?;
+        if ev.pallet_name() == "System" && ev.variant_name() == "ExtrinsicFailed" {
+            let dispatch_error = DispatchError::decode_from(ev.field_bytes(), metadata)?;
+            return Ok(Some(dispatch_error));
+        }
+
+        let is_inner_result = check_inner_result
+            && matches!(
+                (ev.pallet_name(), ev.variant_name()),
+                ("Proxy", "ProxyExecuted") | ("Sudo", "Sudid") | ("Sudo", "SudoAsDone")
+            );
+        if is_inner_result {
+            // The only field is a `DispatchResult`, which is `Ok` if the first byte is 0.
+            if let [1, error_bytes @ ..] = ev.field_bytes() {
+                let dispatch_error = DispatchError::decode_from(error_bytes, metadata)?;
+                return Ok(Some(dispatch_error));
+            }
+        }
+    }
+    Ok(None)
+}
+
 #[cfg(test)]
 mod test {
     use subxt_core::client::RuntimeVersion;
 
     use crate::{
-        backend::{StreamOfResults, TransactionStatus},
+        backend::{mock::MockBackend, BlockRef, StreamOfResults, TransactionStatus},
         client::{OfflineClientT, OnlineClientT},
-        tx::TxProgress,
-        Config, Error, SubstrateConfig,
+        dynamic::Value,
+        error::{DispatchError, TransactionError},
+        events::{
+            self,
+            test_utils::{event_bytes, module_error},
+        },
+        tx::{Payload, SubmittableExtrinsic, TxDeadline, TxInBlock, TxProgress, TxStage},
+        Config, Error, OnlineClient, SubstrateConfig,
     };
+    use futures::StreamExt;
+    use std::time::Duration;
 
     type MockTxProgress = TxProgress<SubstrateConfig, MockClient>;
     type MockHash = <SubstrateConfig as Config>::Hash;
@@ -405,6 +708,179 @@ mod test {
         ));
     }
 
+    // Report the given statuses, and then nothing more.
+    fn pending_after(
+        statuses: Vec<MockSubstrateTxStatus>,
+    ) -> StreamOfResults<MockSubstrateTxStatus> {
+        let results = futures::stream::iter(statuses.into_iter().map(Ok));
+        StreamOfResults::new(Box::pin(results.chain(futures::stream::pending())))
+    }
+
+    fn remark_tx(
+        client: &OnlineClient<SubstrateConfig>,
+    ) -> SubmittableExtrinsic<SubstrateConfig, OnlineClient<SubstrateConfig>> {
+        let remark = crate::dynamic::tx("System", "remark", vec![Value::from_bytes([1, 2, 3])]);
+        client.tx().create_unsigned(&remark).unwrap()
+    }
+
+    fn is_timeout(res: Result<impl std::fmt::Debug, Error>, stage: TxStage) -> bool {
+        matches!(res, Err(Error::Transaction(TransactionError::Timeout(s))) if s == stage)
+    }
+
+    #[tokio::test]
+    async fn time_deadline_passes_if_transaction_is_not_found() {
+        let backend = MockBackend::new();
+        let client = backend.client();
+        let tx = remark_tx(&client);
+
+        let res = TxProgress::new(pending_after(vec![]), client, tx.hash())
+            .finalized_deadline(TxDeadline::Time(Duration::from_millis(10)))
+            .wait_for_finalized()
+            .await;
+        assert!(is_timeout(res, TxStage::InFinalizedBlock));
+    }
+
+    #[tokio::test]
+    async fn time_deadline_looks_for_transaction_in_finalized_blocks() {
+        let backend = MockBackend::new();
+        let client = backend.client();
+        let tx = remark_tx(&client);
+
+        // The transaction was finalized, but the node didn't tell us.
+        let block_1 = backend.add_block(backend.genesis_hash(), vec![tx.encoded().to_vec()]);
+        backend.finalize(block_1);
+
+        let in_block = TxProgress::new(pending_after(vec![]), client, tx.hash())
+            .finalized_deadline(TxDeadline::Time(Duration::from_millis(10)))
+            .wait_for_finalized()
+            .await
+            .unwrap();
+        assert_eq!(in_block.block_hash(), block_1);
+    }
+
+    #[tokio::test]
+    async fn blocks_deadline_passes_after_best_blocks() {
+        let backend = MockBackend::new();
+        let client = backend.client();
+        let tx = remark_tx(&client);
+
+        let progress = TxProgress::new(pending_after(vec![]), client, tx.hash())
+            .in_best_block_deadline(TxDeadline::Blocks(2));
+        let wait = tokio::spawn(progress.wait_for_finalized());
+        backend.wait_for_subscribers(1, 0).await;
+
+        let block_1 = backend.add_block(backend.genesis_hash(), vec![]);
+        backend.set_best(block_1);
+        let block_2 = backend.add_block(block_1, vec![]);
+        backend.set_best(block_2);
+
+        assert!(is_timeout(wait.await.unwrap(), TxStage::InBestBlock));
+    }
+
+    #[tokio::test]
+    async fn blocks_deadline_looks_for_transaction_in_finalized_blocks() {
+        let backend = MockBackend::new();
+        let client = backend.client();
+        let tx = remark_tx(&client);
+
+        let progress = TxProgress::new(pending_after(vec![]), client, tx.hash())
+            .finalized_deadline(TxDeadline::Blocks(1));
+        let wait = tokio::spawn(progress.wait_for_finalized());
+        backend.wait_for_subscribers(0, 1).await;
+
+        let block_1 = backend.add_block(backend.genesis_hash(), vec![tx.encoded().to_vec()]);
+        backend.finalize(block_1);
+
+        let in_block = wait.await.unwrap().unwrap();
+        assert_eq!(in_block.block_hash(), block_1);
+    }
+
+    #[tokio::test]
+    async fn best_block_deadline_is_met_by_status() {
+        let backend = MockBackend::new();
+        let client = backend.client();
+        let tx = remark_tx(&client);
+
+        let block_1 = backend.add_block(backend.genesis_hash(), vec![tx.encoded().to_vec()]);
+        let statuses = vec![MockSubstrateTxStatus::InBestBlock {
+            hash: BlockRef::from_hash(block_1),
+        }];
+
+        // The node told us about the best block, so only the finalized deadline can pass.
+        let res = TxProgress::new(pending_after(statuses), client, tx.hash())
+            .in_best_block_deadline(TxDeadline::Time(Duration::from_millis(1)))
+            .finalized_deadline(TxDeadline::Time(Duration::from_millis(50)))
+            .wait_for_finalized()
+            .await;
+        assert!(is_timeout(res, TxStage::InFinalizedBlock));
+    }
+
+    // Store events in which a `Proxy.proxy` call made by the extrinsic at index 0 of
+    // every block fails with a module error.
+    fn set_failed_proxy_events(backend: &MockBackend, client: &OnlineClient<SubstrateConfig>) {
+        let metadata = client.metadata();
+        let proxy_executed = Value::unnamed_variant(
+            "Proxy",
+            [Value::named_variant(
+                "ProxyExecuted",
+                [(
+                    "result",
+                    Value::unnamed_variant(
+                        "Err",
+                        [module_error(&metadata, "Balances", "InsufficientBalance")],
+                    ),
+                )],
+            )],
+        );
+        let events_address = crate::dynamic::storage("System", "Events", vec![]);
+        backend.set_storage(
+            client.storage().address_bytes(&events_address).unwrap(),
+            event_bytes::<SubstrateConfig>(&metadata, vec![(0, proxy_executed)]),
+        );
+    }
+
+    #[tokio::test]
+    async fn inner_results_only_checked_for_wrapped_payloads() {
+        let backend = MockBackend::new();
+        let client = backend.client_with_metadata(events::test_utils::metadata());
+        set_failed_proxy_events(&backend, &client);
+
+        // A transaction which isn't wrapped, like a batch of proxy calls, only fails if
+        // `System.ExtrinsicFailed` is emitted.
+        let tx = remark_tx(&client);
+        let block_1 = backend.add_block(backend.genesis_hash(), vec![tx.encoded().to_vec()]);
+        let in_block = TxInBlock::new(BlockRef::from_hash(block_1), tx.hash(), client.clone());
+        assert!(in_block.wait_for_success().await.is_ok());
+
+        // Unless we ask for inner results to be checked:
+        let in_block = in_block.check_inner_result();
+        assert!(matches!(
+            in_block.wait_for_success().await,
+            Err(Error::Runtime(DispatchError::Module(_)))
+        ));
+
+        // Transactions wrapped via `Proxy.proxy` check them automatically.
+        let remark = crate::dynamic::tx("System", "remark", vec![Value::from_bytes([1, 2, 3])]);
+        let real = Value::unnamed_variant("Id", [Value::from_bytes([1; 32])]);
+        let tx = client
+            .tx()
+            .create_unsigned(&remark.via_proxy(real, None))
+            .unwrap();
+        let block_2 = backend.add_block(block_1, vec![tx.encoded().to_vec()]);
+        backend.on_submit(move |_| {
+            Ok(vec![MockSubstrateTxStatus::InFinalizedBlock {
+                hash: BlockRef::from_hash(block_2),
+            }])
+        });
+        let res = tx
+            .submit_and_watch()
+            .await
+            .unwrap()
+            .wait_for_finalized_success()
+            .await;
+        assert!(matches!(res, Err(Error::Runtime(DispatchError::Module(_)))));
+    }
+
     fn mock_tx_progress(statuses: Vec<MockSubstrateTxStatus>) -> MockTxProgress {
         let sub = create_substrate_tx_status_subscription(statuses);
         TxProgress::new(sub, MockClient, Default::default())
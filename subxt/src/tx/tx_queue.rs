@@ -0,0 +1,791 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! A queue for submitting transactions on behalf of many signers at once.
+
+use crate::{
+    client::OnlineClientT,
+    config::{Config, ExtrinsicParams},
+    error::{Error, TransactionError},
+    tx::{Payload, Signer as SignerT, TxClient, TxInBlock},
+};
+use codec::Encode;
+use derive_where::derive_where;
+use futures::{
+    channel::mpsc,
+    future::{self, BoxFuture, Either},
+    stream::{BoxStream, FuturesUnordered},
+    FutureExt, SinkExt, Stream, StreamExt,
+};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+/// How many times we retry something that failed because the backend is reconnecting.
+const MAX_RECONNECT_RETRIES: u32 = 5;
+/// How long we wait before the first retry. This doubles with each retry.
+const RECONNECT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Configure and construct a [`TxQueue`] and the associated [`TxQueueDriver`].
+#[derive(Debug, Clone)]
+pub struct TxQueueBuilder {
+    max_in_flight: usize,
+    max_in_flight_per_signer: usize,
+    max_queued: usize,
+}
+
+impl Default for TxQueueBuilder {
+    fn default() -> Self {
+        TxQueueBuilder {
+            max_in_flight: 256,
+            max_in_flight_per_signer: 1,
+            max_queued: 1024,
+        }
+    }
+}
+
+impl TxQueueBuilder {
+    /// Create a new [`TxQueueBuilder`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The maximum number of transactions that will be in flight (ie submitted but not yet
+    /// finalized) at any one time across all signers. Defaults to 256.
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    /// The maximum number of transactions that will be in flight at any one time for a single
+    /// signer. Defaults to 1, meaning that each transaction for a signer is finalized before
+    /// the next is submitted.
+    ///
+    /// Transactions from a single signer are always submitted in order with increasing nonces.
+    /// If more than one can be in flight and one of them fails, then the later ones will be
+    /// waiting on a nonce that is never used and are likely to be dropped by the node.
+    pub fn max_in_flight_per_signer(mut self, max_in_flight_per_signer: usize) -> Self {
+        self.max_in_flight_per_signer = max_in_flight_per_signer.max(1);
+        self
+    }
+
+    /// The maximum number of jobs that will be waiting to be submitted at any one time across
+    /// all signers. Once this many are waiting, [`TxQueue::submit()`] waits for one of them to
+    /// be submitted before adding another. Defaults to 1024.
+    pub fn max_queued(mut self, max_queued: usize) -> Self {
+        self.max_queued = max_queued.max(1);
+        self
+    }
+
+    /// A low-level API to build the queue and driver which requires polling the driver for
+    /// transactions to be submitted. The driver is a [`Stream`] of the outcome of each job.
+    ///
+    /// If you just want to run the driver in the background on the default runtime and receive
+    /// the outcomes via a channel, use [`TxQueueBuilder::build_with_background_driver`] instead.
+    pub fn build<T, C>(self, client: C) -> (TxQueue<T>, TxQueueDriver<T, C>)
+    where
+        T: Config,
+        C: OnlineClientT<T>,
+        T::AccountId: Send + Sync,
+        <T::ExtrinsicParams as ExtrinsicParams<T>>::Params: Default + Send,
+    {
+        // The driver stops taking jobs once `max_queued` are waiting, so the channel itself
+        // only needs to hold the one job that each queue handle is always able to send.
+        let (jobs_tx, jobs_rx) = mpsc::channel(0);
+        let queue = TxQueue {
+            jobs_tx,
+            next_id: Arc::new(AtomicU64::new(0)),
+        };
+
+        let state = DriverState {
+            client,
+            config: self,
+            jobs_rx,
+            jobs_closed: false,
+            queued: 0,
+            signers: HashMap::new(),
+            tasks: FuturesUnordered::new(),
+            in_flight: 0,
+        };
+        let driver = TxQueueDriver {
+            inner: futures::stream::unfold(state, |mut state| async move {
+                let outcome = state.next_outcome().await?;
+                Some((outcome, state))
+            })
+            .boxed(),
+        };
+
+        (queue, driver)
+    }
+
+    /// An API to build the queue and run the driver in the background until every [`TxQueue`]
+    /// handle is dropped and all jobs are complete. The outcome of each job is sent to the
+    /// returned channel.
+    ///
+    /// - On non-wasm targets, this will spawn the driver on `tokio`.
+    /// - On wasm targets, this will spawn the driver on `wasm-bindgen-futures`.
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub fn build_with_background_driver<T, C>(
+        self,
+        client: C,
+    ) -> (TxQueue<T>, mpsc::UnboundedReceiver<TxQueueOutcome<T, C>>)
+    where
+        T: Config,
+        C: OnlineClientT<T>,
+        T::AccountId: Send + Sync,
+        <T::ExtrinsicParams as ExtrinsicParams<T>>::Params: Default + Send,
+    {
+        fn spawn<F: std::future::Future + Send + 'static>(future: F) {
+            #[cfg(not(target_family = "wasm"))]
+            tokio::spawn(async move {
+                future.await;
+            });
+            #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+            wasm_bindgen_futures::spawn_local(async move {
+                future.await;
+            });
+        }
+
+        let (queue, mut driver) = self.build(client);
+        let (outcomes_tx, outcomes_rx) = mpsc::unbounded();
+        spawn(async move {
+            while let Some(outcome) = driver.next().await {
+                // Keep driving the queue even if nobody is listening for outcomes.
+                let _ = outcomes_tx.unbounded_send(outcome);
+            }
+            tracing::debug!(target: "subxt", "TxQueue driver was closed");
+        });
+
+        (queue, outcomes_rx)
+    }
+}
+
+/// A queue which accepts `(payload, signer)` jobs and submits them on behalf of each
+/// signer in order, managing nonces and limiting how many transactions are in flight.
+///
+/// Create one with [`TxQueue::builder()`]. This handle is cheap to clone; the queue shuts
+/// down once every handle is dropped and the outstanding jobs are complete.
+///
+/// Nonces are fetched from the chain the first time a signer is seen and then tracked
+/// locally, and are re-fetched after a job for that signer fails. If the backend reports
+/// that it is reconnecting (see [`Error::is_disconnected_will_reconnect()`]), fetching the
+/// nonce or submitting the job is retried a few times, waiting longer before each retry,
+/// before the job fails with that error. Note that a retried transaction may be reported
+/// as invalid if the node had already received it before the connection was lost.
+#[derive_where(Clone)]
+pub struct TxQueue<T: Config> {
+    jobs_tx: mpsc::Sender<Job<T>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl<T: Config> TxQueue<T> {
+    /// Configure and construct a [`TxQueue`].
+    pub fn builder() -> TxQueueBuilder {
+        TxQueueBuilder::new()
+    }
+
+    /// Add a job to the queue, returning an ID which is used to report the outcome of it.
+    /// Jobs for the same signer are submitted in the order that they are added.
+    ///
+    /// This waits until the queue has room for the job (see [`TxQueueBuilder::max_queued()`]),
+    /// which requires the [`TxQueueDriver`] to be polled.
+    pub async fn submit<Call, Signer>(
+        &mut self,
+        call: Call,
+        signer: Signer,
+    ) -> Result<TxJobId, Error>
+    where
+        Call: Payload + Send + Sync + 'static,
+        Signer: SignerT<T> + Send + Sync + 'static,
+    {
+        let id = TxJobId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let job = Job {
+            id,
+            call: Box::new(call),
+            signer: DynSigner(Box::new(signer)),
+        };
+        self.jobs_tx
+            .send(job)
+            .await
+            .map_err(|_| TransactionError::QueueClosed)?;
+        Ok(id)
+    }
+}
+
+/// The ID of a job submitted to a [`TxQueue`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TxJobId(u64);
+
+/// The outcome of a job submitted to a [`TxQueue`].
+#[derive_where(Debug; C)]
+pub struct TxQueueOutcome<T: Config, C> {
+    /// The ID returned from [`TxQueue::submit()`] for this job.
+    pub id: TxJobId,
+    /// The finalized block that the transaction is in, or the error that prevented it from
+    /// being finalized. Use [`TxInBlock::wait_for_success()`] to see whether it succeeded.
+    pub result: Result<TxInBlock<T, C>, Error>,
+}
+
+/// Driver for the [`TxQueue`]. This must be polled in order for jobs to be submitted, and
+/// yields the outcome of each job as it completes.
+pub struct TxQueueDriver<T: Config, C> {
+    inner: BoxStream<'static, TxQueueOutcome<T, C>>,
+}
+
+impl<T: Config, C> std::fmt::Debug for TxQueueDriver<T, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TxQueueDriver")
+            .field("inner", &"<stream>")
+            .finish()
+    }
+}
+
+impl<T: Config, C> Stream for TxQueueDriver<T, C> {
+    type Item = TxQueueOutcome<T, C>;
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+struct Job<T: Config> {
+    id: TxJobId,
+    call: Box<dyn Payload + Send + Sync>,
+    signer: DynSigner<T>,
+}
+
+// Allows a boxed signer to be handed to functions expecting a `Signer`.
+struct DynSigner<T: Config>(Box<dyn SignerT<T> + Send + Sync>);
+
+impl<T: Config> SignerT<T> for DynSigner<T> {
+    fn account_id(&self) -> T::AccountId {
+        self.0.account_id()
+    }
+    fn address(&self) -> T::Address {
+        self.0.address()
+    }
+    fn sign(&self, signer_payload: &[u8]) -> T::Signature {
+        self.0.sign(signer_payload)
+    }
+}
+
+// Signers are keyed by their encoded account ID.
+type SignerKey = Vec<u8>;
+
+struct SignerState<T: Config> {
+    queue: VecDeque<Job<T>>,
+    in_flight: usize,
+    next_nonce: Option<u64>,
+    fetching_nonce: bool,
+}
+
+enum TaskOutput<T: Config, C> {
+    Nonce {
+        key: SignerKey,
+        result: Result<u64, Error>,
+    },
+    Job {
+        key: SignerKey,
+        outcome: TxQueueOutcome<T, C>,
+    },
+}
+
+struct DriverState<T: Config, C> {
+    client: C,
+    config: TxQueueBuilder,
+    jobs_rx: mpsc::Receiver<Job<T>>,
+    jobs_closed: bool,
+    // The number of jobs waiting in the signer queues.
+    queued: usize,
+    signers: HashMap<SignerKey, SignerState<T>>,
+    tasks: FuturesUnordered<BoxFuture<'static, TaskOutput<T, C>>>,
+    in_flight: usize,
+}
+
+impl<T, C> DriverState<T, C>
+where
+    T: Config,
+    C: OnlineClientT<T>,
+    T::AccountId: Send + Sync,
+    <T::ExtrinsicParams as ExtrinsicParams<T>>::Params: Default + Send,
+{
+    /// Drive the queue until the next job completes, returning `None` when all queue
+    /// handles have been dropped and there is nothing left to do.
+    async fn next_outcome(&mut self) -> Option<TxQueueOutcome<T, C>> {
+        loop {
+            self.start_tasks();
+
+            let output = if self.tasks.is_empty() {
+                if self.jobs_closed {
+                    return None;
+                }
+                match self.jobs_rx.next().await {
+                    Some(job) => self.enqueue(job),
+                    None => self.jobs_closed = true,
+                }
+                continue;
+            } else if self.jobs_closed || self.queued >= self.config.max_queued {
+                self.tasks.next().await.expect("tasks is not empty; qed")
+            } else {
+                match future::select(self.jobs_rx.next(), self.tasks.next()).await {
+                    Either::Left((Some(job), _)) => {
+                        self.enqueue(job);
+                        continue;
+                    }
+                    Either::Left((None, _)) => {
+                        self.jobs_closed = true;
+                        continue;
+                    }
+                    Either::Right((output, _)) => output.expect("tasks is not empty; qed"),
+                }
+            };
+
+            match output {
+                TaskOutput::Nonce { key, result } => {
+                    let signer = self.signers.get_mut(&key).expect("signer exists; qed");
+                    signer.fetching_nonce = false;
+                    match result {
+                        Ok(nonce) => signer.next_nonce = Some(nonce),
+                        // Fail the next job for this signer if we can't obtain a nonce for it.
+                        Err(e) => {
+                            let job = signer.queue.pop_front().expect("job is queued; qed");
+                            self.queued -= 1;
+                            self.remove_if_idle(&key);
+                            return Some(TxQueueOutcome {
+                                id: job.id,
+                                result: Err(e),
+                            });
+                        }
+                    }
+                }
+                TaskOutput::Job { key, outcome } => {
+                    self.in_flight -= 1;
+                    let signer = self.signers.get_mut(&key).expect("signer exists; qed");
+                    signer.in_flight -= 1;
+                    // Our nonce may be out of sync now, so fetch it again.
+                    if outcome.result.is_err() {
+                        signer.next_nonce = None;
+                    }
+                    self.remove_if_idle(&key);
+                    return Some(outcome);
+                }
+            }
+        }
+    }
+
+    fn enqueue(&mut self, job: Job<T>) {
+        let key = job.signer.account_id().encode();
+        self.queued += 1;
+        self.signers
+            .entry(key)
+            .or_insert_with(|| SignerState {
+                queue: VecDeque::new(),
+                in_flight: 0,
+                next_nonce: None,
+                fetching_nonce: false,
+            })
+            .queue
+            .push_back(job);
+    }
+
+    // Forget about signers with nothing to do, so that their nonce is fetched afresh next time.
+    fn remove_if_idle(&mut self, key: &SignerKey) {
+        if let Some(signer) = self.signers.get(key) {
+            if signer.queue.is_empty() && signer.in_flight == 0 && !signer.fetching_nonce {
+                self.signers.remove(key);
+            }
+        }
+    }
+
+    /// Start fetching nonces and submitting jobs for any signers that are able to.
+    fn start_tasks(&mut self) {
+        for (key, signer) in self.signers.iter_mut() {
+            if signer.queue.is_empty() || signer.fetching_nonce {
+                continue;
+            }
+
+            // Only fetch a nonce once nothing is in flight, so that it is up to date.
+            let Some(mut nonce) = signer.next_nonce else {
+                if signer.in_flight == 0 {
+                    let client = TxClient::<T, C>::new(self.client.clone());
+                    let account_id = signer.queue[0].signer.account_id();
+                    let key = key.clone();
+                    signer.fetching_nonce = true;
+                    self.tasks.push(
+                        async move {
+                            let result =
+                                retry_on_reconnect(|| client.account_nonce(&account_id)).await;
+                            TaskOutput::Nonce { key, result }
+                        }
+                        .boxed(),
+                    );
+                }
+                continue;
+            };
+
+            while !signer.queue.is_empty()
+                && signer.in_flight < self.config.max_in_flight_per_signer
+                && self.in_flight < self.config.max_in_flight
+            {
+                let job = signer.queue.pop_front().expect("queue is not empty; qed");
+                self.queued -= 1;
+                signer.in_flight += 1;
+                self.in_flight += 1;
+
+                let client = self.client.clone();
+                let key = key.clone();
+                self.tasks.push(
+                    async move {
+                        let result = run_job(client, &job, nonce).await;
+                        TaskOutput::Job {
+                            key,
+                            outcome: TxQueueOutcome { id: job.id, result },
+                        }
+                    }
+                    .boxed(),
+                );
+                nonce += 1;
+            }
+            signer.next_nonce = Some(nonce);
+        }
+    }
+}
+
+/// Sign and submit a job with the given nonce, waiting for it to be finalized and
+/// retrying if the backend is reconnecting.
+async fn run_job<T, C>(client: C, job: &Job<T>, nonce: u64) -> Result<TxInBlock<T, C>, Error>
+where
+    T: Config,
+    C: OnlineClientT<T>,
+    T::AccountId: Send + Sync,
+    <T::ExtrinsicParams as ExtrinsicParams<T>>::Params: Default + Send,
+{
+    let tx_client = TxClient::<T, C>::new(client);
+    let tx = retry_on_reconnect(|| {
+        tx_client.create_signed_with_nonce(&job.call, &job.signer, nonce, Default::default())
+    })
+    .await?;
+
+    retry_on_reconnect(|| async { tx.submit_and_watch().await?.wait_for_finalized().await }).await
+}
+
+/// Run the given operation, running it again after a short wait if it fails because the
+/// backend is reconnecting, up to [`MAX_RECONNECT_RETRIES`] times.
+async fn retry_on_reconnect<R, F, Fut>(mut op: F) -> Result<R, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<R, Error>>,
+{
+    let mut retries = 0;
+    loop {
+        match op().await {
+            Err(e) if e.is_disconnected_will_reconnect() && retries < MAX_RECONNECT_RETRIES => {
+                tracing::debug!(target: "subxt", "TxQueue retrying after reconnect: {e}");
+                futures_timer::Delay::new(RECONNECT_BACKOFF * 2u32.pow(retries)).await;
+                retries += 1;
+            }
+            res => return res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        backend::{mock::MockBackend, BlockRef, StreamOf, TransactionStatus},
+        client::OnlineClient,
+        dynamic::Value,
+        error::RpcError,
+        utils::{AccountId32, MultiAddress, MultiSignature},
+        SubstrateConfig,
+    };
+    use primitive_types::H256;
+    use std::sync::Mutex;
+
+    struct FakeSigner(AccountId32);
+
+    impl SignerT<SubstrateConfig> for FakeSigner {
+        fn account_id(&self) -> AccountId32 {
+            self.0.clone()
+        }
+        fn address(&self) -> MultiAddress<AccountId32, u32> {
+            self.0.clone().into()
+        }
+        fn sign(&self, _signer_payload: &[u8]) -> MultiSignature {
+            MultiSignature::Sr25519([0; 64])
+        }
+    }
+
+    fn remark() -> crate::tx::DynamicPayload {
+        crate::dynamic::tx("System", "remark", vec![Value::from_bytes([1])])
+    }
+
+    fn finalized() -> TransactionStatus<H256> {
+        TransactionStatus::InFinalizedBlock {
+            hash: BlockRef::from_hash(H256::zero()),
+        }
+    }
+
+    fn reconnecting() -> Error {
+        RpcError::DisconnectedWillReconnect("reconnecting".into()).into()
+    }
+
+    // Give each account a starting nonce equal to its first byte.
+    fn mock_nonces(backend: &MockBackend) {
+        backend.on_call("AccountNonceApi_account_nonce", |account_id| {
+            Ok((account_id.unwrap()[0] as u32).encode())
+        });
+    }
+
+    // Finalize every submitted transaction, recording the account and nonce of each.
+    fn mock_finalized(backend: &Arc<MockBackend>) -> Arc<Mutex<Vec<(u8, u64)>>> {
+        let metadata = backend.client().metadata();
+        let submitted = Arc::new(Mutex::new(Vec::new()));
+        let submitted2 = submitted.clone();
+        backend.on_submit(move |bytes| {
+            let extrinsics = subxt_core::blocks::decode_from::<SubstrateConfig>(
+                vec![bytes.to_vec()],
+                metadata.clone(),
+            )
+            .unwrap();
+            let ext = extrinsics.iter().next().unwrap();
+            // The address is a `MultiAddress::Id`, so skip the variant index.
+            let account = ext.address_bytes().unwrap()[1];
+            let nonce = ext.signed_extensions().unwrap().nonce().unwrap();
+            submitted2.lock().unwrap().push((account, nonce));
+            Ok(vec![finalized()])
+        });
+        submitted
+    }
+
+    // Run the driver in the background, returning the outcomes of all jobs once the queue
+    // has shut down.
+    fn spawn_driver(
+        driver: TxQueueDriver<SubstrateConfig, OnlineClient<SubstrateConfig>>,
+    ) -> tokio::task::JoinHandle<Vec<TxQueueOutcome<SubstrateConfig, OnlineClient<SubstrateConfig>>>>
+    {
+        tokio::spawn(driver.collect())
+    }
+
+    #[tokio::test]
+    async fn submits_jobs_for_each_signer_in_nonce_order() {
+        let backend = MockBackend::new();
+        let submitted = mock_finalized(&backend);
+
+        // Signers are forgotten when idle, so the nonce on chain must move on with each
+        // finalized transaction, starting from the first byte of the account.
+        let submitted2 = submitted.clone();
+        backend.on_call("AccountNonceApi_account_nonce", move |account_id| {
+            let account = account_id.unwrap()[0];
+            let finalized = submitted2
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(a, _)| *a == account)
+                .count();
+            Ok((account as u32 + finalized as u32).encode())
+        });
+
+        let (mut queue, driver) = TxQueueBuilder::new().build(backend.client());
+        let outcomes = spawn_driver(driver);
+        let mut ids = Vec::new();
+        for account in [5, 1, 5, 1, 5] {
+            let signer = FakeSigner(AccountId32([account; 32]));
+            ids.push((account, queue.submit(remark(), signer).await.unwrap()));
+        }
+        drop(queue);
+
+        let outcomes = outcomes.await.unwrap();
+        assert!(outcomes.iter().all(|outcome| outcome.result.is_ok()));
+
+        // Each signer's jobs are submitted with increasing nonces, and finish in order.
+        let submitted = submitted.lock().unwrap();
+        let account_of: HashMap<TxJobId, u8> = ids.iter().map(|(a, id)| (*id, *a)).collect();
+        for (account, nonces) in [(5, vec![5, 6, 7]), (1, vec![1, 2])] {
+            let submitted_nonces: Vec<_> = submitted
+                .iter()
+                .filter(|(a, _)| *a == account)
+                .map(|(_, nonce)| *nonce)
+                .collect();
+            assert_eq!(submitted_nonces, nonces);
+
+            let job_ids: Vec<_> = ids
+                .iter()
+                .filter(|(a, _)| *a == account)
+                .map(|(_, id)| *id)
+                .collect();
+            let outcome_ids: Vec<_> = outcomes
+                .iter()
+                .map(|outcome| outcome.id)
+                .filter(|id| account_of[id] == account)
+                .collect();
+            assert_eq!(outcome_ids, job_ids);
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_after_reconnect() {
+        let backend = MockBackend::new();
+
+        // Fetching the nonce fails once, and submitting the transaction fails twice.
+        let mut nonce_calls = 0;
+        backend.on_call("AccountNonceApi_account_nonce", move |_| {
+            nonce_calls += 1;
+            match nonce_calls {
+                1 => Err(reconnecting()),
+                _ => Ok(0u32.encode()),
+            }
+        });
+        let submissions = Arc::new(AtomicU64::new(0));
+        let submissions2 = submissions.clone();
+        backend.on_submit(
+            move |_| match submissions2.fetch_add(1, Ordering::Relaxed) {
+                0 | 1 => Err(reconnecting()),
+                _ => Ok(vec![finalized()]),
+            },
+        );
+
+        let (mut queue, driver) = TxQueueBuilder::new().build(backend.client());
+        let outcomes = spawn_driver(driver);
+        let id = queue
+            .submit(remark(), FakeSigner(AccountId32([0; 32])))
+            .await
+            .unwrap();
+        drop(queue);
+
+        let outcomes = outcomes.await.unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].id, id);
+        assert!(outcomes[0].result.is_ok());
+        assert_eq!(submissions.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_repeated_reconnects() {
+        let backend = MockBackend::new();
+        mock_nonces(&backend);
+        let mut attempts = 0;
+        backend.on_submit(move |_| {
+            attempts += 1;
+            assert!(attempts <= MAX_RECONNECT_RETRIES + 1, "too many retries");
+            Err(reconnecting())
+        });
+
+        let (mut queue, driver) = TxQueueBuilder::new().build(backend.client());
+        let outcomes = spawn_driver(driver);
+        queue
+            .submit(remark(), FakeSigner(AccountId32([0; 32])))
+            .await
+            .unwrap();
+        drop(queue);
+
+        let outcomes = outcomes.await.unwrap();
+        assert_eq!(outcomes.len(), 1);
+        let err = outcomes[0].result.as_ref().unwrap_err();
+        assert!(err.is_disconnected_will_reconnect());
+    }
+
+    #[tokio::test]
+    async fn submit_fails_once_driver_is_dropped() {
+        let backend = MockBackend::new();
+        let (mut queue, driver) =
+            TxQueueBuilder::new().build::<SubstrateConfig, _>(backend.client());
+        drop(driver);
+
+        let res = queue
+            .submit(remark(), FakeSigner(AccountId32([0; 32])))
+            .await;
+        assert!(matches!(
+            res,
+            Err(Error::Transaction(TransactionError::QueueClosed))
+        ));
+    }
+
+    #[tokio::test]
+    async fn submits_up_to_max_in_flight_per_signer_at_once() {
+        let backend = MockBackend::new();
+        mock_nonces(&backend);
+
+        // Hold on to the status sender of each submission, so that nothing is finalized
+        // until we say so.
+        let metadata = backend.client().metadata();
+        let submitted = Arc::new(Mutex::new(Vec::new()));
+        let submitted2 = submitted.clone();
+        backend.on_submit_stream(move |bytes| {
+            let extrinsics = subxt_core::blocks::decode_from::<SubstrateConfig>(
+                vec![bytes.to_vec()],
+                metadata.clone(),
+            )
+            .unwrap();
+            let nonce = extrinsics
+                .iter()
+                .next()
+                .unwrap()
+                .signed_extensions()
+                .unwrap()
+                .nonce()
+                .unwrap();
+            let (status_tx, status_rx) = mpsc::unbounded();
+            submitted2.lock().unwrap().push((nonce, status_tx));
+            Ok(StreamOf::new(Box::pin(status_rx)))
+        });
+        let submitted_nonces = || -> Vec<u64> {
+            let submitted = submitted.lock().unwrap();
+            submitted.iter().map(|(nonce, _)| *nonce).collect()
+        };
+
+        let wait_for_submissions = |n: usize| async move {
+            tokio::time::timeout(Duration::from_secs(5), async {
+                while submitted_nonces().len() < n {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            })
+            .await
+            .expect("jobs should be submitted");
+            // Give the driver a chance to submit more than it should.
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        };
+        let send_status = |range: std::ops::Range<usize>,
+                           status: fn() -> TransactionStatus<H256>| {
+            for (_, status_tx) in &submitted.lock().unwrap()[range] {
+                status_tx.unbounded_send(Ok(status())).unwrap();
+            }
+        };
+
+        let (mut queue, driver) = TxQueueBuilder::new()
+            .max_in_flight_per_signer(3)
+            .build(backend.client());
+        let outcomes = spawn_driver(driver);
+        for _ in 0..6 {
+            let signer = FakeSigner(AccountId32([0; 32]));
+            queue.submit(remark(), signer).await.unwrap();
+        }
+        drop(queue);
+
+        // Three jobs are in flight before any of them is finalized, and the rest wait.
+        wait_for_submissions(3).await;
+        assert_eq!(submitted_nonces(), vec![0, 1, 2]);
+
+        // Once they fail, the nonce is fetched again and the three waiting jobs are
+        // submitted together.
+        send_status(0..3, || TransactionStatus::Invalid {
+            message: "invalid".into(),
+        });
+        wait_for_submissions(6).await;
+        assert_eq!(submitted_nonces(), vec![0, 1, 2, 0, 1, 2]);
+
+        send_status(3..6, finalized);
+        let outcomes = outcomes.await.unwrap();
+        assert_eq!(outcomes.len(), 6);
+        assert_eq!(outcomes.iter().filter(|o| o.result.is_ok()).count(), 3);
+    }
+}
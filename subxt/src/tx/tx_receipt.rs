@@ -0,0 +1,377 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::tx_progress::find_dispatch_error;
+use crate::{
+    blocks::ExtrinsicEvents,
+    config::Config,
+    dynamic::{At, DecodedValue},
+    error::{DispatchError, Error},
+    Metadata,
+};
+use derive_where::derive_where;
+use scale_value::ValueDef;
+
+/// A summary of the outcome of a transaction which has made it into a block,
+/// obtained via [`crate::tx::TxInBlock::fetch_receipt()`].
+///
+/// The details are read from the `System.ExtrinsicSuccess`/`System.ExtrinsicFailed` and
+/// `TransactionPayment.TransactionFeePaid` events emitted by the transaction, or the
+/// `AssetTxFeePaid` event if the fee was paid in some other asset via the `AssetTxPayment`
+/// or `AssetConversionTxPayment` pallets. These are decoded dynamically, and so the
+/// corresponding fields are `None` on runtimes which don't emit these events.
+#[derive_where(Debug)]
+pub struct TxReceipt<T: Config> {
+    block_hash: T::Hash,
+    block_number: u64,
+    result: Result<(), DispatchError>,
+    actual_fee: Option<u128>,
+    tip: Option<u128>,
+    fee_asset_id: Option<DecodedValue>,
+    weight: Option<Weight>,
+    pays_fee: Option<bool>,
+    events: ExtrinsicEvents<T>,
+}
+
+/// The weight consumed by a transaction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Weight {
+    /// The computational time used.
+    pub ref_time: u64,
+    /// The size of the proof, in bytes. This is 0 on runtimes which predate proof size weights.
+    pub proof_size: u64,
+}
+
+impl<T: Config> TxReceipt<T> {
+    pub(crate) fn from_events(
+        block_hash: T::Hash,
+        block_number: u64,
+        events: ExtrinsicEvents<T>,
+        metadata: Metadata,
+        check_inner_result: bool,
+    ) -> Result<Self, Error> {
+        let result = match find_dispatch_error(&events, metadata, check_inner_result)? {
+            Some(dispatch_error) => Err(dispatch_error),
+            None => Ok(()),
+        };
+
+        let mut receipt = TxReceipt {
+            block_hash,
+            block_number,
+            result,
+            actual_fee: None,
+            tip: None,
+            fee_asset_id: None,
+            weight: None,
+            pays_fee: None,
+            events,
+        };
+
+        for ev in receipt.events.iter() {
+            let ev = ev?;
+            match (ev.pallet_name(), ev.variant_name()) {
+                ("System", "ExtrinsicSuccess" | "ExtrinsicFailed") => {
+                    let fields = ev.field_values()?;
+                    if let Some(dispatch_info) = fields.at("dispatch_info") {
+                        receipt.weight = dispatch_info.at("weight").and_then(decode_weight);
+                        receipt.pays_fee = dispatch_info.at("pays_fee").and_then(decode_pays_fee);
+                    }
+                }
+                ("TransactionPayment", "TransactionFeePaid") => {
+                    let fields = ev.field_values()?;
+                    receipt.actual_fee = fields.at("actual_fee").and_then(|v| v.as_u128());
+                    receipt.tip = fields.at("tip").and_then(|v| v.as_u128());
+                }
+                ("AssetTxPayment" | "AssetConversionTxPayment", "AssetTxFeePaid") => {
+                    let fields = ev.field_values()?;
+                    receipt.actual_fee = fields.at("actual_fee").and_then(|v| v.as_u128());
+                    receipt.tip = fields.at("tip").and_then(|v| v.as_u128());
+                    receipt.fee_asset_id = fields.at("asset_id").cloned();
+                }
+                _ => {}
+            }
+        }
+
+        Ok(receipt)
+    }
+
+    /// The hash of the block that the transaction is in.
+    pub fn block_hash(&self) -> T::Hash {
+        self.block_hash
+    }
+
+    /// The number of the block that the transaction is in.
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    /// The index of the transaction in the block.
+    pub fn extrinsic_index(&self) -> u32 {
+        self.events.extrinsic_index()
+    }
+
+    /// The hash of the transaction.
+    pub fn extrinsic_hash(&self) -> T::Hash {
+        self.events.extrinsic_hash()
+    }
+
+    /// Whether the transaction succeeded, or the error that it failed with. Like
+    /// [`crate::tx::TxInBlock::wait_for_success()`], this takes into account inner calls
+    /// wrapped via `Proxy.proxy` or `Sudo.sudo` if [`crate::tx::TxInBlock::check_inner_result()`]
+    /// applies.
+    pub fn result(&self) -> Result<(), &DispatchError> {
+        self.result.as_ref().map(|_| ())
+    }
+
+    /// Did the transaction succeed? See [`TxReceipt::result()`].
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// The fee that was actually paid for the transaction (including the tip). This is
+    /// denominated in the asset given by [`TxReceipt::fee_asset_id()`], if there is one.
+    pub fn actual_fee(&self) -> Option<u128> {
+        self.actual_fee
+    }
+
+    /// The tip that was paid for the transaction. Like [`TxReceipt::actual_fee()`], this is
+    /// denominated in the asset given by [`TxReceipt::fee_asset_id()`], if there is one.
+    pub fn tip(&self) -> Option<u128> {
+        self.tip
+    }
+
+    /// The ID of the asset that the fee was paid in, if it was paid in some asset other than
+    /// the native token via the `AssetTxPayment` or `AssetConversionTxPayment` pallets.
+    pub fn fee_asset_id(&self) -> Option<&DecodedValue> {
+        self.fee_asset_id.as_ref()
+    }
+
+    /// The weight that was consumed by the transaction.
+    pub fn weight(&self) -> Option<Weight> {
+        self.weight
+    }
+
+    /// Whether the transaction pays a fee. Some calls are free if they succeed.
+    pub fn pays_fee(&self) -> Option<bool> {
+        self.pays_fee
+    }
+
+    /// All of the events emitted by the transaction.
+    pub fn events(&self) -> &ExtrinsicEvents<T> {
+        &self.events
+    }
+
+    /// Consume the receipt, returning all of the events emitted by the transaction.
+    pub fn into_events(self) -> ExtrinsicEvents<T> {
+        self.events
+    }
+}
+
+// Weights are either a struct with `ref_time` and (from when it was introduced) `proof_size`
+// fields or, on older runtimes, a single number representing the ref time.
+fn decode_weight(value: &DecodedValue) -> Option<Weight> {
+    let as_u64 = |value: &DecodedValue| u64::try_from(value.as_u128()?).ok();
+    if let Some(ref_time) = as_u64(value) {
+        return Some(Weight {
+            ref_time,
+            proof_size: 0,
+        });
+    }
+    let proof_size = match value.at("proof_size") {
+        Some(proof_size) => as_u64(proof_size)?,
+        None => 0,
+    };
+    Some(Weight {
+        ref_time: as_u64(value.at("ref_time")?)?,
+        proof_size,
+    })
+}
+
+fn decode_pays_fee(value: &DecodedValue) -> Option<bool> {
+    match &value.value {
+        ValueDef::Variant(variant) => match variant.name.as_str() {
+            "Yes" => Some(true),
+            "No" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::events::test_utils::{events, metadata, module_error};
+    use crate::SubstrateConfig;
+    use scale_value::{Composite, Value};
+
+    fn value(value: Value) -> DecodedValue {
+        value.map_context(|_| 0)
+    }
+
+    fn dispatch_info(ref_time: u128, proof_size: u128) -> Value {
+        Value::named_composite([
+            (
+                "weight",
+                Value::named_composite([
+                    ("ref_time", Value::u128(ref_time)),
+                    ("proof_size", Value::u128(proof_size)),
+                ]),
+            ),
+            ("class", Value::unnamed_variant("Normal", [])),
+            ("pays_fee", Value::unnamed_variant("Yes", [])),
+        ])
+    }
+
+    fn fee_paid(actual_fee: u128, tip: u128) -> Value {
+        Value::unnamed_variant(
+            "TransactionPayment",
+            [Value::named_variant(
+                "TransactionFeePaid",
+                [
+                    ("who", Value::from_bytes([1; 32])),
+                    ("actual_fee", Value::u128(actual_fee)),
+                    ("tip", Value::u128(tip)),
+                ],
+            )],
+        )
+    }
+
+    #[test]
+    fn receipt_from_events() {
+        let metadata = metadata();
+        let success = Value::unnamed_variant(
+            "System",
+            [Value::named_variant(
+                "ExtrinsicSuccess",
+                [("dispatch_info", dispatch_info(1_000, 64))],
+            )],
+        );
+        let failed = Value::unnamed_variant(
+            "System",
+            [Value::named_variant(
+                "ExtrinsicFailed",
+                [
+                    (
+                        "dispatch_error",
+                        module_error(&metadata, "Balances", "InsufficientBalance"),
+                    ),
+                    ("dispatch_info", dispatch_info(2_000, 128)),
+                ],
+            )],
+        );
+        let events = events::<SubstrateConfig>(
+            &metadata,
+            vec![
+                (0, fee_paid(300, 20)),
+                (0, success),
+                (1, fee_paid(500, 0)),
+                (1, failed),
+            ],
+        );
+
+        let receipt = |index| {
+            let events = ExtrinsicEvents::new(Default::default(), index, events.clone());
+            TxReceipt::from_events(Default::default(), 10, events, metadata.clone(), false).unwrap()
+        };
+
+        let succeeded = receipt(0);
+        assert!(succeeded.is_success());
+        assert_eq!(succeeded.block_number(), 10);
+        assert_eq!(succeeded.extrinsic_index(), 0);
+        assert_eq!(succeeded.actual_fee(), Some(300));
+        assert_eq!(succeeded.tip(), Some(20));
+        assert_eq!(succeeded.fee_asset_id(), None);
+        assert_eq!(
+            succeeded.weight(),
+            Some(Weight {
+                ref_time: 1_000,
+                proof_size: 64
+            })
+        );
+        assert_eq!(succeeded.pays_fee(), Some(true));
+        assert_eq!(succeeded.events().iter().count(), 2);
+
+        let failed = receipt(1);
+        let Err(DispatchError::Module(module_error)) = failed.result() else {
+            panic!("expected a module error, got {:?}", failed.result());
+        };
+        assert_eq!(
+            module_error.details().unwrap().variant.name,
+            "InsufficientBalance"
+        );
+        assert_eq!(failed.actual_fee(), Some(500));
+        assert_eq!(failed.tip(), Some(0));
+        assert_eq!(
+            failed.weight(),
+            Some(Weight {
+                ref_time: 2_000,
+                proof_size: 128
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_two_dimensional_weight() {
+        let weight = value(Value::named_composite([
+            ("ref_time", Value::u128(1_000)),
+            ("proof_size", Value::u128(64)),
+        ]));
+        assert_eq!(
+            decode_weight(&weight),
+            Some(Weight {
+                ref_time: 1_000,
+                proof_size: 64
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_legacy_weight() {
+        let weight = value(Value::u128(1_000));
+        assert_eq!(
+            decode_weight(&weight),
+            Some(Weight {
+                ref_time: 1_000,
+                proof_size: 0
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_weight_without_proof_size() {
+        let weight = value(Value::named_composite([("ref_time", Value::u128(1))]));
+        assert_eq!(
+            decode_weight(&weight),
+            Some(Weight {
+                ref_time: 1,
+                proof_size: 0
+            })
+        );
+    }
+
+    #[test]
+    fn does_not_decode_malformed_weight() {
+        let not_a_number = value(Value::string("heavy"));
+        assert_eq!(decode_weight(&not_a_number), None);
+
+        let too_large = value(Value::u128(u64::MAX as u128 + 1));
+        assert_eq!(decode_weight(&too_large), None);
+
+        let proof_size_too_large = value(Value::named_composite([
+            ("ref_time", Value::u128(1)),
+            ("proof_size", Value::u128(u64::MAX as u128 + 1)),
+        ]));
+        assert_eq!(decode_weight(&proof_size_too_large), None);
+    }
+
+    #[test]
+    fn decodes_pays_fee() {
+        let pays = |name: &str| value(Value::variant(name, Composite::Unnamed(vec![])));
+        assert_eq!(decode_pays_fee(&pays("Yes")), Some(true));
+        assert_eq!(decode_pays_fee(&pays("No")), Some(false));
+        assert_eq!(decode_pays_fee(&pays("Maybe")), None);
+        assert_eq!(decode_pays_fee(&value(Value::bool(true))), None);
+    }
+}
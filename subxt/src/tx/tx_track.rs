@@ -0,0 +1,391 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Follow the progress of a transaction that was submitted elsewhere, given its hash.
+
+use super::tx_progress::find_extrinsic_index;
+use crate::{
+    backend::{BlockRef, StreamOf, StreamOfResults, TransactionStatus},
+    blocks::BlocksClient,
+    client::OnlineClientT,
+    config::Header,
+    error::{BlockError, Error},
+    utils::Era,
+    Config,
+};
+use codec::Decode;
+use futures::{future::Either, StreamExt};
+use std::collections::HashMap;
+
+/// The longest period that a mortal transaction can be valid for. We give up looking for a
+/// transaction once this many blocks have been finalized without finding it, unless we've
+/// seen it in a block and so know its actual mortality.
+const MAX_MORTAL_PERIOD: u64 = 1 << 16;
+
+/// Return a stream of transaction statuses for the transaction with the given hash, found by
+/// searching best and finalized blocks from the given block onwards.
+pub(crate) async fn track<T, C>(
+    client: C,
+    ext_hash: T::Hash,
+    search_from_block: BlockRef<T::Hash>,
+) -> Result<StreamOfResults<TransactionStatus<T::Hash>>, Error>
+where
+    T: Config,
+    C: OnlineClientT<T>,
+{
+    let backend = client.backend();
+    let start_number: u64 = backend
+        .block_header(search_from_block.hash())
+        .await?
+        .ok_or_else(|| Error::Block(BlockError::not_found(search_from_block.hash())))?
+        .number()
+        .into();
+
+    let best_blocks = backend.stream_best_block_headers().await?.map(Either::Left);
+    let finalized_blocks = backend
+        .stream_finalized_block_headers()
+        .await?
+        .map(Either::Right);
+    let blocks = futures::stream::select(best_blocks, finalized_blocks);
+
+    let tracker = Tracker {
+        client,
+        ext_hash,
+        start_number,
+        give_up_at: start_number.saturating_add(MAX_MORTAL_PERIOD),
+        blocks: HashMap::new(),
+        best_inclusion: None,
+        done: false,
+    };
+
+    let stream =
+        futures::stream::unfold((tracker, blocks), |(mut tracker, mut blocks)| async move {
+            let status = tracker.next_status(&mut blocks).await?;
+            Some((status, (tracker, blocks)))
+        });
+
+    Ok(StreamOf::new(Box::pin(stream)))
+}
+
+struct Tracker<T: Config, C> {
+    client: C,
+    ext_hash: T::Hash,
+    // We don't look at blocks below this number.
+    start_number: u64,
+    // Once a block with this number is finalized, we give up.
+    give_up_at: u64,
+    // Details about the blocks that we've looked at so far.
+    blocks: HashMap<T::Hash, TrackedBlock<T>>,
+    // The block on the current best chain containing our transaction, if any.
+    best_inclusion: Option<T::Hash>,
+    done: bool,
+}
+
+struct TrackedBlock<T: Config> {
+    block_ref: BlockRef<T::Hash>,
+    parent_hash: T::Hash,
+    number: u64,
+    contains_tx: bool,
+}
+
+type BlockEvent<T> = Either<
+    Result<(<T as Config>::Header, BlockRef<<T as Config>::Hash>), Error>,
+    Result<(<T as Config>::Header, BlockRef<<T as Config>::Hash>), Error>,
+>;
+
+impl<T: Config, C: OnlineClientT<T>> Tracker<T, C> {
+    async fn next_status<S>(
+        &mut self,
+        blocks: &mut S,
+    ) -> Option<Result<TransactionStatus<T::Hash>, Error>>
+    where
+        S: futures::Stream<Item = BlockEvent<T>> + Unpin,
+    {
+        while !self.done {
+            match blocks.next().await? {
+                Either::Left(best_block) => {
+                    let (header, block_ref) = match best_block {
+                        Ok(block) => block,
+                        Err(e) => return Some(Err(e)),
+                    };
+                    let hash = block_ref.hash();
+                    if let Err(e) = self.add_block(header, block_ref).await {
+                        return Some(Err(e));
+                    }
+
+                    // A reorg may have added or removed our transaction from the best chain.
+                    let inclusion = self.find_inclusion(hash);
+                    if inclusion == self.best_inclusion {
+                        continue;
+                    }
+                    self.best_inclusion = inclusion;
+                    return Some(Ok(match inclusion {
+                        Some(hash) => TransactionStatus::InBestBlock {
+                            hash: self.blocks[&hash].block_ref.clone(),
+                        },
+                        None => TransactionStatus::NoLongerInBestBlock,
+                    }));
+                }
+                Either::Right(finalized_block) => {
+                    let (header, block_ref) = match finalized_block {
+                        Ok(block) => block,
+                        Err(e) => return Some(Err(e)),
+                    };
+                    let hash = block_ref.hash();
+                    let number: u64 = header.number().into();
+                    if let Err(e) = self.add_block(header, block_ref).await {
+                        return Some(Err(e));
+                    }
+
+                    if let Some(hash) = self.find_inclusion(hash) {
+                        self.done = true;
+                        return Some(Ok(TransactionStatus::InFinalizedBlock {
+                            hash: self.blocks[&hash].block_ref.clone(),
+                        }));
+                    }
+                    if number >= self.give_up_at {
+                        self.done = true;
+                        return Some(Ok(TransactionStatus::Dropped {
+                            message:
+                                "The transaction was not finalized within its mortality period"
+                                    .into(),
+                        }));
+                    }
+
+                    // Blocks prior to this one are final and don't contain our transaction,
+                    // so we don't need to look at them again.
+                    self.start_number = number;
+                    self.blocks.retain(|_, block| block.number >= number);
+                }
+            }
+        }
+        None
+    }
+
+    /// Look at the given block and any ancestors of it that we haven't seen yet.
+    async fn add_block(
+        &mut self,
+        header: T::Header,
+        block_ref: BlockRef<T::Hash>,
+    ) -> Result<(), Error> {
+        let mut next = Some((header, block_ref));
+        while let Some((header, block_ref)) = next.take() {
+            let number: u64 = header.number().into();
+            if number < self.start_number || self.blocks.contains_key(&block_ref.hash()) {
+                break;
+            }
+
+            let parent_hash = header.parent_hash();
+            let contains_tx = self.check_block(block_ref.clone(), number).await?;
+            self.blocks.insert(
+                block_ref.hash(),
+                TrackedBlock {
+                    block_ref,
+                    parent_hash,
+                    number,
+                    contains_tx,
+                },
+            );
+
+            if number > self.start_number {
+                let parent_header = self
+                    .client
+                    .backend()
+                    .block_header(parent_hash)
+                    .await?
+                    .ok_or_else(|| Error::Block(BlockError::not_found(parent_hash)))?;
+                next = Some((parent_header, BlockRef::from_hash(parent_hash)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Does the given block contain our transaction? If so, use its mortality to work out
+    /// when to give up looking for it.
+    async fn check_block(
+        &mut self,
+        block_ref: BlockRef<T::Hash>,
+        number: u64,
+    ) -> Result<bool, Error> {
+        let block = BlocksClient::new(self.client.clone()).at(block_ref).await?;
+        let extrinsics = block.extrinsics().await?;
+        let Some(ext) = extrinsics.iter().find(|ext| ext.hash() == self.ext_hash) else {
+            return Ok(false);
+        };
+
+        // The runtime describes eras as an enum with a variant for each possible first byte,
+        // which doesn't line up with our `Era` type, so we decode the SCALE bytes directly.
+        let era = match ext.signed_extensions() {
+            Some(exts) => exts
+                .iter()
+                .find(|ext| ext.name() == "CheckMortality")
+                .map(|ext| Era::decode(&mut ext.bytes()))
+                .transpose()?,
+            None => None,
+        };
+        if let Some(era) = era {
+            self.give_up_at = era.death(number);
+        }
+        Ok(true)
+    }
+
+    /// Walk back from the given block to find the block containing our transaction, if any.
+    fn find_inclusion(&self, from: T::Hash) -> Option<T::Hash> {
+        let mut hash = from;
+        while let Some(block) = self.blocks.get(&hash) {
+            if block.contains_tx {
+                return Some(hash);
+            }
+            hash = block.parent_hash;
+        }
+        None
+    }
+}
+
+/// Walk back from the given block to the block numbered `stop_number`, returning the first
+/// block found to contain the extrinsic with the given hash, if any.
+pub(crate) async fn find_in_ancestors<T, C>(
+    client: &C,
+    ext_hash: T::Hash,
+    header: T::Header,
+    block_ref: BlockRef<T::Hash>,
+    stop_number: u64,
+) -> Result<Option<BlockRef<T::Hash>>, Error>
+where
+    T: Config,
+    C: OnlineClientT<T>,
+{
+    let backend = client.backend();
+    let (mut header, mut block_ref) = (header, block_ref);
+    loop {
+        let number: u64 = header.number().into();
+        if number < stop_number {
+            return Ok(None);
+        }
+
+        let block_body = backend
+            .block_body(block_ref.hash())
+            .await?
+            .ok_or_else(|| Error::Block(BlockError::not_found(block_ref.hash())))?;
+        if find_extrinsic_index::<T>(&block_body, ext_hash).is_some() {
+            return Ok(Some(block_ref));
+        }
+        if number == stop_number {
+            return Ok(None);
+        }
+
+        let parent_hash = header.parent_hash();
+        header = backend
+            .block_header(parent_hash)
+            .await?
+            .ok_or_else(|| Error::Block(BlockError::not_found(parent_hash)))?;
+        block_ref = BlockRef::from_hash(parent_hash);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        backend::mock::MockBackend,
+        config::{DefaultExtrinsicParamsBuilder, SubstrateConfig},
+        dynamic::Value,
+        tx::SubmittableExtrinsic,
+        OnlineClient,
+    };
+    use primitive_types::H256;
+
+    type Status = TransactionStatus<H256>;
+
+    fn remark() -> crate::tx::DynamicPayload {
+        crate::dynamic::tx("System", "remark", vec![Value::from_bytes([1, 2, 3])])
+    }
+
+    fn hash_of(status: Option<Result<Status, Error>>) -> (&'static str, Option<H256>) {
+        match status.expect("stream ended").expect("no error") {
+            Status::InBestBlock { hash } => ("best", Some(hash.hash())),
+            Status::InFinalizedBlock { hash } => ("finalized", Some(hash.hash())),
+            Status::NoLongerInBestBlock => ("no longer best", None),
+            Status::Dropped { .. } => ("dropped", None),
+            other => panic!("unexpected status {other:?}"),
+        }
+    }
+
+    async fn track_tx(
+        client: &OnlineClient<SubstrateConfig>,
+        tx: &SubmittableExtrinsic<SubstrateConfig, OnlineClient<SubstrateConfig>>,
+        backend: &MockBackend,
+    ) -> StreamOfResults<Status> {
+        track(
+            client.clone(),
+            tx.hash(),
+            BlockRef::from_hash(backend.genesis_hash()),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn follows_transaction_through_reorgs_until_finalized() {
+        let backend = MockBackend::new();
+        let client = backend.client();
+        let tx = client.tx().create_unsigned(&remark()).unwrap();
+        let mut statuses = track_tx(&client, &tx, &backend).await;
+
+        let genesis = backend.genesis_hash();
+        let block_1 = backend.add_block(genesis, vec![tx.encoded().to_vec()]);
+        backend.set_best(block_1);
+        assert_eq!(hash_of(statuses.next().await), ("best", Some(block_1)));
+
+        // A fork without the transaction becomes the best chain.
+        let fork_1 = backend.add_block(genesis, vec![]);
+        let fork_2 = backend.add_block(fork_1, vec![]);
+        backend.set_best(fork_2);
+        assert_eq!(hash_of(statuses.next().await), ("no longer best", None));
+
+        // A block on top of the original block becomes best again, and is finalized.
+        let block_2 = backend.add_block(block_1, vec![]);
+        backend.set_best(block_2);
+        assert_eq!(hash_of(statuses.next().await), ("best", Some(block_1)));
+        backend.finalize(block_2);
+        assert_eq!(hash_of(statuses.next().await), ("finalized", Some(block_1)));
+        assert!(statuses.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn gives_up_once_transaction_mortality_has_passed() {
+        let backend = MockBackend::new();
+        let client = backend.client();
+        let genesis = backend.genesis_hash();
+        // The transaction is valid from the genesis block until block 4.
+        let params = DefaultExtrinsicParamsBuilder::<SubstrateConfig>::new()
+            .mortal_unchecked(0, genesis, 4)
+            .build();
+        let signer = subxt_signer::sr25519::dev::alice();
+        let tx = client
+            .tx()
+            .create_signed_offline(&remark(), &signer, params)
+            .unwrap();
+        let mut statuses = track_tx(&client, &tx, &backend).await;
+
+        let block_1 = backend.add_block(genesis, vec![tx.encoded().to_vec()]);
+        backend.set_best(block_1);
+        assert_eq!(hash_of(statuses.next().await), ("best", Some(block_1)));
+
+        // A fork without the transaction becomes best and is finalized.
+        let mut fork = genesis;
+        for _ in 0..3 {
+            fork = backend.add_block(fork, vec![]);
+        }
+        backend.set_best(fork);
+        assert_eq!(hash_of(statuses.next().await), ("no longer best", None));
+        backend.finalize(fork);
+
+        // Once block 4 is finalized, the transaction can no longer be included.
+        fork = backend.add_block(fork, vec![]);
+        backend.finalize(fork);
+        assert_eq!(hash_of(statuses.next().await), ("dropped", None));
+        assert!(statuses.next().await.is_none());
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::Payload;
+use crate::{backend::BlockRef, client::OnlineClientT, error::Error, Config};
+use derive_where::derive_where;
+use std::{future::Future, marker::PhantomData};
+
+/// Execute pallet view functions.
+#[derive_where(Clone; Client)]
+pub struct ViewFunctionsApi<T: Config, Client> {
+    client: Client,
+    block_ref: BlockRef<T::Hash>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Config, Client> ViewFunctionsApi<T, Client> {
+    /// Create a new [`ViewFunctionsApi`]
+    pub(crate) fn new(client: Client, block_ref: BlockRef<T::Hash>) -> Self {
+        Self {
+            client,
+            block_ref,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, Client> ViewFunctionsApi<T, Client>
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    /// Run the validation logic against some view function payload you'd like to use. Returns `Ok(())`
+    /// if the payload is valid (or if it's not possible to check since the payload has no validation hash).
+    /// Return an error if the payload was not valid or something went wrong trying to validate it (ie
+    /// the view function in question does not exist at all)
+    pub fn validate<Call: Payload>(&self, payload: &Call) -> Result<(), Error> {
+        subxt_core::view_functions::validate(payload, &self.client.metadata()).map_err(Into::into)
+    }
+
+    /// Execute a pallet view function.
+    pub fn call<Call: Payload>(
+        &self,
+        payload: Call,
+    ) -> impl Future<Output = Result<Call::ReturnType, Error>> {
+        let client = self.client.clone();
+        let block_hash = self.block_ref.hash();
+        // Ensure that the returned future doesn't have a lifetime tied to api.view_functions(),
+        // which is a temporary thing we'll be throwing away quickly:
+        async move {
+            let metadata = client.metadata();
+
+            // Validate the view function payload hash against the compile hash from codegen.
+            subxt_core::view_functions::validate(&payload, &metadata)?;
+
+            // Encode the query ID and arguments of the view function.
+            let call_args = subxt_core::view_functions::call_args(&payload, &metadata)?;
+
+            // Make the call.
+            let bytes = client
+                .backend()
+                .call(
+                    subxt_core::view_functions::CALL_NAME,
+                    Some(call_args.as_slice()),
+                    block_hash,
+                )
+                .await?;
+
+            // Decode the response.
+            let value =
+                subxt_core::view_functions::decode_value(&mut &*bytes, &payload, &metadata)?;
+            Ok(value)
+        }
+    }
+}
@@ -83,7 +83,15 @@ async fn validate_not_possible_for_controller_account() -> Result<(), Error> {
         .wait_for_finalized_success()
         .await;
 
-    assert_matches!(announce_validator, Err(Error::Runtime(DispatchError::Module(err))) => {
+    let Err(err) = announce_validator else {
+        panic!("validating from a controller account should fail");
+    };
+    assert_matches!(
+        err.as_pallet_error::<staking::Error>(),
+        Some(staking::Error::NotController)
+    );
+    assert_matches!(&err, Error::Runtime(e) if e.is::<staking::Error>());
+    assert_matches!(err, Error::Runtime(DispatchError::Module(err)) => {
         let details = err.details().unwrap();
         assert_eq!(details.pallet.name(), "Staking");
         assert_eq!(&details.variant.name, "NotController");
@@ -104,14 +104,17 @@ async fn full_metadata_check() {
 
     // Runtime metadata is identical to the metadata used during API generation.
     assert!(node_runtime::is_codegen_valid_for(&api.metadata()));
+    assert!(node_runtime::compatibility_report(&api.metadata()).is_compatible());
 
     // Modify the metadata.
     let metadata = modified_metadata(api.metadata(), |md| {
         md.pallets[0].name = "NewPallet".to_string();
     });
 
-    // It should now be invalid:
+    // It should now be invalid, and the items in the renamed pallet are reported as removed:
     assert!(!node_runtime::is_codegen_valid_for(&metadata));
+    let report = node_runtime::compatibility_report(&metadata);
+    assert!(!report.removed.is_empty());
 }
 
 #[subxt_test]
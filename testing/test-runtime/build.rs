@@ -74,6 +74,7 @@ async fn run() {
         #[subxt::subxt(
             runtime_metadata_path = "{}",
             derive_for_all_types = "Eq, PartialEq",
+            compatibility_report,
         )]
         pub mod node_runtime {{}}
     "#,